@@ -3,13 +3,20 @@ pub const VM_OMNIBUS: &[u8]           = b"vm_omnibus";
 pub const VM_MEMORY_ACCOUNT: &[u8]    = b"vm_memory_account";
 pub const VM_STORAGE_ACCOUNT: &[u8]   = b"vm_storage_account";
 pub const VM_DURABLE_NONCE: &[u8]     = b"vm_durable_nonce";
+pub const VM_MULTISIG: &[u8]          = b"vm_multisig";
+pub const VM_FEE_CONFIG: &[u8]        = b"vm_fee_config";
+pub const VM_VELOCITY_LIMIT: &[u8]    = b"vm_velocity_limit";
+pub const VM_MINT: &[u8]              = b"vm_mint";
 pub const VM_UNLOCK_ACCOUNT: &[u8]    = b"vm_unlock_pda_account";
 pub const VM_WITHDRAW_RECEIPT: &[u8]  = b"vm_withdraw_receipt_account";
 pub const VM_DEPOSIT_PDA: &[u8]       = b"vm_deposit_pda";
+pub const VM_DEPOSIT_DELEGATE: &[u8]  = b"vm_deposit_delegate";
+pub const VM_DEPOSIT_RECEIPT: &[u8]   = b"vm_deposit_receipt";
 pub const VM_RELAY_ACCOUNT: &[u8]     = b"vm_relay_account";
 pub const VM_RELAY_PROOF: &[u8]       = b"vm_proof_account";
 pub const VM_RELAY_VAULT: &[u8]       = b"vm_relay_vault";
 pub const VM_RELAY_COMMITMENT: &[u8]  = b"relay_commitment";
+pub const VM_RELAY_NULLIFIER: &[u8]   = b"vm_relay_nullifier";
 pub const VM_SWAP_PDA: &[u8]          = b"vm_swap_pda";
 pub const VM_TIMELOCK_STATE: &[u8]    = b"timelock_state";
 pub const VM_TIMELOCK_VAULT: &[u8]    = b"timelock_vault";
@@ -26,3 +33,8 @@ pub const MAX_NUM_ACCOUNTS: usize = 320_000;
 pub const COMPRESSED_STATE_DEPTH: usize = 20;
 pub const RELAY_STATE_DEPTH: usize = 63;
 pub const RELAY_HISTORY_ITEMS: usize = 32;
+pub const RELAY_NULLIFIER_BYTES: usize = 4096;
+pub const RELAY_NULLIFIER_CAPACITY: u64 = 1024;
+pub const MAX_MULTISIG_SIGNERS: usize = 5;
+pub const MAX_FEE_BPS: u16 = 10_000;
+pub const VELOCITY_BUCKETS: usize = 8;
@@ -3,23 +3,46 @@ use solana_program::{
     system_instruction, 
     rent::Rent, 
 };
+use spl_token_2022::extension::{
+    BaseStateWithExtensions,
+    ExtensionType,
+    StateWithExtensions,
+};
 use steel::*;
 
-use crate::helpers::check_condition;
+use crate::{
+    consts::*,
+    cvm::{CodeVmAccount, MintPool, TokenProgram},
+    helpers::check_condition,
+};
 
 pub fn create_token_account<'info>(
     mint: &AccountInfo<'info>,
     target: &AccountInfo<'info>,
     seeds: &[&[u8]],
     payer: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
     system_program: &AccountInfo<'info>,
     rent_sysvar: &AccountInfo<'info>,
 ) -> ProgramResult {
+    // Token-2022 accounts need room for the extensions required by the mint.
+    let size = if token_program.key.eq(&spl_token_2022::id()) {
+        let mint_data = mint.try_borrow_data()?;
+        let mint_state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?;
+        let extensions = ExtensionType::get_required_init_account_extensions(
+            &mint_state.get_extension_types()?,
+        );
+
+        ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&extensions)?
+    } else {
+        spl_token::state::Account::LEN
+    };
+
     // Create the PDA.
     allocate_account(
         target,
-        &spl_token::id(),
-        spl_token::state::Account::LEN,
+        token_program.key,
+        size,
         seeds,
         system_program,
         payer,
@@ -27,12 +50,12 @@ pub fn create_token_account<'info>(
 
     // Initialize the PDA.
     solana_program::program::invoke_signed(
-        &spl_token::instruction::initialize_account(
-            &spl_token::id(),
+        &spl_token_2022::instruction::initialize_account(
+            token_program.key,
             target.key,
             mint.key,
             target.key,
-        ).unwrap(),
+        )?,
         &[
             target.clone(),
             mint.clone(),
@@ -43,6 +66,149 @@ pub fn create_token_account<'info>(
     )
 }
 
+/// Moves tokens for a VM. With the mint, this uses `TransferChecked`, which
+/// works with both token programs. Without it, this falls back to a plain
+/// `Transfer`, which is only allowed for SPL Token VMs.
+pub fn transfer_tokens_signed<'info>(
+    vm: &CodeVmAccount,
+    authority_info: &AccountInfo<'info>,
+    from_info: &AccountInfo<'info>,
+    to_info: &AccountInfo<'info>,
+    mint_info: Option<&AccountInfo<'info>>,
+    token_program: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    transfer_pool_tokens_signed(
+        &MintPool::from_vm(vm),
+        authority_info,
+        from_info,
+        to_info,
+        mint_info,
+        token_program,
+        amount,
+        signer_seeds,
+    )
+}
+
+/// Same as `transfer_tokens_signed`, for the mint of `pool`.
+pub fn transfer_pool_tokens_signed<'info>(
+    pool: &MintPool,
+    authority_info: &AccountInfo<'info>,
+    from_info: &AccountInfo<'info>,
+    to_info: &AccountInfo<'info>,
+    mint_info: Option<&AccountInfo<'info>>,
+    token_program: &AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    let mint_info = match mint_info {
+        Some(mint_info) => mint_info,
+        None => {
+            check_condition(
+                pool.token_program == TokenProgram::Token,
+                "the mint account must be provided for Token-2022 mints",
+            )?;
+
+            return transfer_signed(
+                authority_info,
+                from_info,
+                to_info,
+                token_program,
+                amount,
+                signer_seeds,
+            );
+        }
+    };
+
+    check_condition(
+        mint_info.key.eq(&pool.mint),
+        "mint account does not match VM instance",
+    )?;
+
+    let decimals = {
+        let mint_data = mint_info.try_borrow_data()?;
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)?
+            .base
+            .decimals
+    };
+
+    solana_program::program::invoke_signed(
+        &spl_token_2022::instruction::transfer_checked(
+            token_program.key,
+            from_info.key,
+            mint_info.key,
+            to_info.key,
+            authority_info.key,
+            &[],
+            amount,
+            decimals,
+        )?,
+        &[
+            token_program.clone(),
+            from_info.clone(),
+            mint_info.clone(),
+            to_info.clone(),
+            authority_info.clone(),
+        ],
+        signer_seeds,
+    )
+}
+
+/// Same as `transfer_tokens_signed`, for transfers authorized by a signer of
+/// the transaction.
+pub fn transfer_tokens<'info>(
+    vm: &CodeVmAccount,
+    authority_info: &AccountInfo<'info>,
+    from_info: &AccountInfo<'info>,
+    to_info: &AccountInfo<'info>,
+    mint_info: Option<&AccountInfo<'info>>,
+    token_program: &AccountInfo<'info>,
+    amount: u64,
+) -> ProgramResult {
+    transfer_tokens_signed(
+        vm,
+        authority_info,
+        from_info,
+        to_info,
+        mint_info,
+        token_program,
+        amount,
+        &[],
+    )
+}
+
+/// Moves tokens out of the omnibus of `pool`, signing for it.
+pub fn transfer_from_omnibus<'info>(
+    pool: &MintPool,
+    vm_info: &AccountInfo<'info>,
+    omnibus_info: &AccountInfo<'info>,
+    to_info: &AccountInfo<'info>,
+    mint_info: Option<&AccountInfo<'info>>,
+    token_program: &AccountInfo<'info>,
+    amount: u64,
+) -> ProgramResult {
+    let bump = [pool.omnibus.vault_bump];
+
+    // Registered mints include the mint in the omnibus seeds
+    let seeds: &[&[u8]] = if pool.is_registered {
+        &[CODE_VM, VM_OMNIBUS, vm_info.key.as_ref(), pool.mint.as_ref(), &bump]
+    } else {
+        &[CODE_VM, VM_OMNIBUS, vm_info.key.as_ref(), &bump]
+    };
+
+    transfer_pool_tokens_signed(
+        pool,
+        omnibus_info,
+        omnibus_info,
+        to_info,
+        mint_info,
+        token_program,
+        amount,
+        &[seeds],
+    )
+}
+
 pub fn create_account_with_size<'a, 'info, T: Discriminator + Pod>(
     target_account: &'a AccountInfo<'info>,
     size: usize,
@@ -102,4 +268,50 @@ pub fn resize_account<'info>(
     target_account.realloc(new_size, false)?;
 
     Ok(())
-}
\ No newline at end of file
+}
+
+pub fn close_token_account_signed<'info>(
+    authority_info: &AccountInfo<'info>,
+    token_account_info: &AccountInfo<'info>,
+    destination_info: &AccountInfo<'info>,
+    token_program: &AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> ProgramResult {
+    solana_program::program::invoke_signed(
+        &spl_token_2022::instruction::close_account(
+            token_program.key,
+            token_account_info.key,
+            destination_info.key,
+            authority_info.key,
+            &[authority_info.key],
+        )?,
+        &[
+            token_program.clone(),
+            token_account_info.clone(),
+            destination_info.clone(),
+            authority_info.clone(),
+        ],
+        signer_seeds,
+    )
+}
+
+pub fn close_account<'info>(
+    target_account: &AccountInfo<'info>,
+    destination: &AccountInfo<'info>,
+) -> ProgramResult {
+    // Move all lamports out of the account, this program owns the account so
+    // no CPI is required.
+    let lamports = target_account.lamports();
+    **destination.lamports.borrow_mut() = destination
+        .lamports()
+        .checked_add(lamports)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+    **target_account.lamports.borrow_mut() = 0;
+
+    // Hand the account back to the system program so that the address can be
+    // re-initialized later on.
+    target_account.assign(&system_program::ID);
+    target_account.realloc(0, false)?;
+
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use steel::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[repr(C)]
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Debug)]
+pub struct VirtualAllowanceAccount {
+    pub source: Pubkey,         // token address of the owner's timelock account
+    pub delegate: Pubkey,       // key allowed to spend from the source account
+    pub remaining: u64,         // amount the delegate can still spend
+    pub expires_at: i64,        // unix timestamp after which the allowance can't be used, 0 for never
+}
+
+impl VirtualAllowanceAccount {
+    pub const LEN: usize = // 80 bytes
+        32 + // source
+        32 + // delegate
+        8 +  // remaining
+        8;   // expires_at
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at != 0 && now >= self.expires_at
+    }
+
+    pub fn pack<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        BorshSerialize::serialize(self, &mut writer)
+    }
+
+    pub fn unpack(buf: &[u8]) -> std::io::Result<Self> {
+        let data = &buf[..VirtualAllowanceAccount::LEN];
+        BorshDeserialize::try_from_slice(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allowance_expiry() {
+        let mut allowance = VirtualAllowanceAccount {
+            source: Pubkey::new_unique(),
+            delegate: Pubkey::new_unique(),
+            remaining: 100,
+            expires_at: 0,
+        };
+
+        assert!(!allowance.is_expired(i64::MAX));
+
+        allowance.expires_at = 1_000;
+        assert!(!allowance.is_expired(999));
+        assert!(allowance.is_expired(1_000));
+    }
+}
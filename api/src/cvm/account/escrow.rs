@@ -0,0 +1,34 @@
+use steel::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[repr(C)]
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Debug)]
+pub struct VirtualEscrowAccount {
+    pub source: Pubkey,         // token address of the timelock account that funded the escrow
+    pub destination: Pubkey,    // token address of the timelock account paid on release
+    pub arbiter: Pubkey,        // key allowed to release the escrow
+    pub amount: u64,            // held by this account until it is released or refunded
+    pub deadline: i64,          // unix timestamp after which the escrow can be refunded
+}
+
+impl VirtualEscrowAccount {
+    pub const LEN: usize = // 112 bytes
+        32 + // source
+        32 + // destination
+        32 + // arbiter
+        8 +  // amount
+        8;   // deadline
+
+    pub fn is_refundable(&self, now: i64) -> bool {
+        now >= self.deadline
+    }
+
+    pub fn pack<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        BorshSerialize::serialize(self, &mut writer)
+    }
+
+    pub fn unpack(buf: &[u8]) -> std::io::Result<Self> {
+        let data = &buf[..VirtualEscrowAccount::LEN];
+        BorshDeserialize::try_from_slice(data)
+    }
+}
@@ -1,9 +1,21 @@
+mod allowance;
+mod escrow;
+mod multisig;
 mod nonce;
 mod relay;
+mod scheduled_transfer;
+mod subscription;
 mod timelock;
+mod vesting;
 mod virtual_account;
 
+pub use allowance::*;
+pub use escrow::*;
+pub use multisig::*;
 pub use nonce::*;
 pub use relay::*;
+pub use scheduled_transfer::*;
+pub use subscription::*;
 pub use timelock::*;
+pub use vesting::*;
 pub use virtual_account::*;
\ No newline at end of file
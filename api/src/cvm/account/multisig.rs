@@ -0,0 +1,102 @@
+use steel::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::{consts::MAX_MULTISIG_SIGNERS, utils, types::Hash};
+
+#[repr(C)]
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Debug)]
+pub struct VirtualMultisigAccount {
+    pub address: Pubkey,    // off-curve address used as the owner of the timelock accounts it controls
+    pub threshold: u8,      // number of signatures required to move funds
+    pub signer_count: u8,   // number of keys in use in the signers array
+    pub signers: [Pubkey; MAX_MULTISIG_SIGNERS],
+}
+
+impl VirtualMultisigAccount {
+    pub const LEN: usize = // 194 bytes
+        32 + // address
+        1 +  // threshold
+        1 +  // signer_count
+        32 * MAX_MULTISIG_SIGNERS; // signers
+
+    /// The hash of the threshold and signer keys, used as a seed for the
+    /// multisig address.
+    pub fn get_config_hash(threshold: u8, signers: &[Pubkey]) -> Hash {
+        let mut data = Vec::with_capacity(1 + 32 * signers.len());
+        data.push(threshold);
+        for signer in signers {
+            data.extend_from_slice(signer.as_ref());
+        }
+        utils::hash(&data)
+    }
+
+    pub fn get_signers(&self) -> &[Pubkey] {
+        &self.signers[..self.signer_count as usize]
+    }
+
+    /// A config is valid if it has between 1 and MAX_MULTISIG_SIGNERS unique
+    /// signers and `1 <= threshold <= signer_count`.
+    pub fn is_valid_config(threshold: u8, signers: &[Pubkey]) -> bool {
+        if signers.is_empty() || signers.len() > MAX_MULTISIG_SIGNERS {
+            return false;
+        }
+
+        if threshold == 0 || threshold as usize > signers.len() {
+            return false;
+        }
+
+        signers.iter().enumerate().all(|(i, signer)| {
+            !signers[..i].contains(signer)
+        })
+    }
+
+    pub fn pack<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        BorshSerialize::serialize(self, &mut writer)
+    }
+
+    pub fn unpack(buf: &[u8]) -> std::io::Result<Self> {
+        let data = &buf[..VirtualMultisigAccount::LEN];
+        BorshDeserialize::try_from_slice(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_valid_config() {
+        let a = Pubkey::new_unique();
+        let b = Pubkey::new_unique();
+
+        assert!(VirtualMultisigAccount::is_valid_config(1, &[a]));
+        assert!(VirtualMultisigAccount::is_valid_config(2, &[a, b]));
+
+        assert!(!VirtualMultisigAccount::is_valid_config(0, &[a, b]));
+        assert!(!VirtualMultisigAccount::is_valid_config(3, &[a, b]));
+        assert!(!VirtualMultisigAccount::is_valid_config(1, &[]));
+        assert!(!VirtualMultisigAccount::is_valid_config(2, &[a, a]));
+        assert!(!VirtualMultisigAccount::is_valid_config(1, &[a; MAX_MULTISIG_SIGNERS + 1]));
+    }
+
+    #[test]
+    fn test_pack_unpack() {
+        let mut signers = [Pubkey::default(); MAX_MULTISIG_SIGNERS];
+        signers[0] = Pubkey::new_unique();
+        signers[1] = Pubkey::new_unique();
+
+        let multisig = VirtualMultisigAccount {
+            address: Pubkey::new_unique(),
+            threshold: 2,
+            signer_count: 2,
+            signers,
+        };
+
+        let mut buf = vec![0u8; VirtualMultisigAccount::LEN];
+        multisig.pack(&mut buf[..]).unwrap();
+
+        let unpacked = VirtualMultisigAccount::unpack(&buf).unwrap();
+        assert_eq!(unpacked, multisig);
+        assert_eq!(unpacked.get_signers(), &signers[..2]);
+    }
+}
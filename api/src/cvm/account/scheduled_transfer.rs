@@ -0,0 +1,32 @@
+use steel::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[repr(C)]
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Debug)]
+pub struct VirtualScheduledTransfer {
+    pub source: Pubkey,         // token address of the source timelock account
+    pub destination: Pubkey,    // token address of the destination timelock account
+    pub amount: u64,            // held by this record until it is settled or cancelled
+    pub not_before: i64,        // unix timestamp before which the transfer can't settle
+}
+
+impl VirtualScheduledTransfer {
+    pub const LEN: usize = // 80 bytes
+        32 + // source
+        32 + // destination
+        8 +  // amount
+        8;   // not_before
+
+    pub fn is_due(&self, now: i64) -> bool {
+        now >= self.not_before
+    }
+
+    pub fn pack<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        BorshSerialize::serialize(self, &mut writer)
+    }
+
+    pub fn unpack(buf: &[u8]) -> std::io::Result<Self> {
+        let data = &buf[..VirtualScheduledTransfer::LEN];
+        BorshDeserialize::try_from_slice(data)
+    }
+}
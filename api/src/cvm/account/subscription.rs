@@ -0,0 +1,78 @@
+use steel::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[repr(C)]
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Debug)]
+pub struct VirtualSubscriptionAccount {
+    pub payer: Pubkey,          // token address of the timelock account that is charged
+    pub payee: Pubkey,          // token address of the timelock account that is paid
+    pub amount: u64,            // amount collected each period
+    pub period: i64,            // length of a period in seconds
+    pub next_due: i64,          // unix timestamp when the next payment can be collected
+    pub remaining: u64,         // number of payments left to collect
+}
+
+impl VirtualSubscriptionAccount {
+    pub const LEN: usize = // 96 bytes
+        32 + // payer
+        32 + // payee
+        8 +  // amount
+        8 +  // period
+        8 +  // next_due
+        8;   // remaining
+
+    pub fn is_due(&self, now: i64) -> bool {
+        self.remaining > 0 && now >= self.next_due
+    }
+
+    /// Moves the subscription to the next period after a payment has been
+    /// collected.
+    pub fn advance(&mut self) -> Option<()> {
+        self.next_due = self.next_due.checked_add(self.period)?;
+        self.remaining = self.remaining.checked_sub(1)?;
+        Some(())
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.remaining == 0
+    }
+
+    pub fn pack<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        BorshSerialize::serialize(self, &mut writer)
+    }
+
+    pub fn unpack(buf: &[u8]) -> std::io::Result<Self> {
+        let data = &buf[..VirtualSubscriptionAccount::LEN];
+        BorshDeserialize::try_from_slice(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subscription_advance() {
+        let mut subscription = VirtualSubscriptionAccount {
+            payer: Pubkey::new_unique(),
+            payee: Pubkey::new_unique(),
+            amount: 10,
+            period: 100,
+            next_due: 1_000,
+            remaining: 2,
+        };
+
+        assert!(!subscription.is_due(999));
+        assert!(subscription.is_due(1_000));
+
+        subscription.advance().unwrap();
+        assert_eq!(subscription.next_due, 1_100);
+        assert_eq!(subscription.remaining, 1);
+        assert!(!subscription.is_due(1_099));
+
+        subscription.advance().unwrap();
+        assert!(subscription.is_complete());
+        assert!(!subscription.is_due(i64::MAX));
+        assert!(subscription.advance().is_none());
+    }
+}
@@ -16,6 +16,9 @@ pub struct VirtualTimelockAccount {
 
     pub balance: u64,
     pub bump: u8,
+
+    pub lock_duration: u8,      // in days, zero if the VM lock duration applies
+    pub mint: Pubkey,           // a mint registered with the VM, zero if the VM mint applies
 }
 
 impl VirtualTimelockAccount {
@@ -28,6 +31,51 @@ impl VirtualTimelockAccount {
         8 +  // balance
         1;   // bump
 
+    /// Accounts with their own lock duration have one extra byte.
+    pub const LEN_WITH_LOCK_DURATION: usize = Self::LEN + 1;
+
+    /// Accounts for a registered mint have the lock duration byte (which can
+    /// be zero) followed by the mint.
+    pub const LEN_WITH_MINT: usize = Self::LEN_WITH_LOCK_DURATION + 32;
+
+    pub fn has_lock_duration(&self) -> bool {
+        self.lock_duration != 0
+    }
+
+    pub fn has_mint(&self) -> bool {
+        self.mint.ne(&Pubkey::default())
+    }
+
+    /// The mint held by this account, falling back to the VM mint when the
+    /// account doesn't have one.
+    pub fn get_mint(&self, vm_mint: &Pubkey) -> Pubkey {
+        if self.has_mint() {
+            self.mint
+        } else {
+            *vm_mint
+        }
+    }
+
+    /// The lock duration that applies to this account, falling back to the
+    /// VM lock duration when the account doesn't have one.
+    pub fn get_lock_duration(&self, vm_lock_duration: u8) -> u8 {
+        if self.has_lock_duration() {
+            self.lock_duration
+        } else {
+            vm_lock_duration
+        }
+    }
+
+    pub fn get_size(&self) -> usize {
+        if self.has_mint() {
+            Self::LEN_WITH_MINT
+        } else if self.has_lock_duration() {
+            Self::LEN_WITH_LOCK_DURATION
+        } else {
+            Self::LEN
+        }
+    }
+
     pub fn get_timelock_address(&self, mint: &Pubkey, authority: &Pubkey, lock_duration: u8) -> Pubkey {
         pdas::create_virtual_timelock_address(
             mint,
@@ -63,13 +111,105 @@ impl VirtualTimelockAccount {
         )
     }
 
+    /// Accounts without a lock duration or mint are packed using the
+    /// original layout, so their hash (and compressed state) is unchanged.
     pub fn pack<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
-        BorshSerialize::serialize(self, &mut writer)
+        let data = self.try_to_vec()?;
+        writer.write_all(&data[..self.get_size()])
     }
 
     pub fn unpack(buf: &[u8]) -> std::io::Result<Self> {
-        let data = &buf[..VirtualTimelockAccount::LEN];
-        BorshDeserialize::try_from_slice(data)
+        Self::unpack_with_len(buf, VirtualTimelockAccount::LEN)
+    }
+
+    pub fn unpack_with_lock_duration(buf: &[u8]) -> std::io::Result<Self> {
+        Self::unpack_with_len(buf, VirtualTimelockAccount::LEN_WITH_LOCK_DURATION)
+    }
+
+    pub fn unpack_with_mint(buf: &[u8]) -> std::io::Result<Self> {
+        Self::unpack_with_len(buf, VirtualTimelockAccount::LEN_WITH_MINT)
+    }
+
+    fn unpack_with_len(buf: &[u8], len: usize) -> std::io::Result<Self> {
+        let mut data = [0u8; VirtualTimelockAccount::LEN_WITH_MINT];
+        data[..len].copy_from_slice(&buf[..len]);
+        BorshDeserialize::try_from_slice(&data)
     }
 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cvm::VirtualAccount;
+
+    fn create_test_vta(lock_duration: u8) -> VirtualTimelockAccount {
+        create_test_vta_with_mint(lock_duration, Pubkey::default())
+    }
+
+    fn create_test_vta_with_mint(lock_duration: u8, mint: Pubkey) -> VirtualTimelockAccount {
+        VirtualTimelockAccount {
+            owner: Pubkey::new_unique(),
+            instance: Hash::new(&[7; 32]),
+            token_bump: 1,
+            unlock_bump: 2,
+            withdraw_bump: 3,
+            balance: 42,
+            bump: 4,
+            lock_duration,
+            mint,
+        }
+    }
+
+    #[test]
+    fn test_pack_without_lock_duration() {
+        let vta = create_test_vta(0);
+        let va = VirtualAccount::Timelock(vta);
+
+        let packed = va.pack();
+        assert_eq!(packed.len(), 1 + VirtualTimelockAccount::LEN);
+        assert_eq!(packed[0], 1);
+        assert_eq!(VirtualAccount::unpack(&packed).unwrap(), va);
+    }
+
+    #[test]
+    fn test_pack_with_lock_duration() {
+        let vta = create_test_vta(1);
+        let va = VirtualAccount::Timelock(vta);
+
+        let packed = va.pack();
+        assert_eq!(packed.len(), 1 + VirtualTimelockAccount::LEN_WITH_LOCK_DURATION);
+        assert_eq!(packed[0], 3);
+        assert_eq!(VirtualAccount::unpack(&packed).unwrap(), va);
+
+        // The lock duration is appended to the original layout
+        let legacy = VirtualTimelockAccount { lock_duration: 0, ..vta };
+        let legacy_packed = VirtualAccount::Timelock(legacy).pack();
+        assert_eq!(packed[1..=VirtualTimelockAccount::LEN], legacy_packed[1..]);
+        assert_eq!(packed[VirtualTimelockAccount::LEN + 1], 1);
+
+        assert_eq!(vta.get_lock_duration(21), 1);
+        assert_eq!(legacy.get_lock_duration(21), 21);
+    }
+
+    #[test]
+    fn test_pack_with_mint() {
+        let mint = Pubkey::new_unique();
+        let vta = create_test_vta_with_mint(0, mint);
+        let va = VirtualAccount::Timelock(vta);
+
+        let packed = va.pack();
+        assert_eq!(packed.len(), 1 + VirtualTimelockAccount::LEN_WITH_MINT);
+        assert_eq!(packed[0], 10);
+        assert_eq!(VirtualAccount::unpack(&packed).unwrap(), va);
+
+        // The mint comes after the lock duration byte
+        let with_duration = VirtualAccount::Timelock(create_test_vta_with_mint(1, mint));
+        assert_eq!(VirtualAccount::unpack(&with_duration.pack()).unwrap(), with_duration);
+        assert_eq!(packed[1 + VirtualTimelockAccount::LEN_WITH_LOCK_DURATION..], mint.to_bytes());
+
+        let vm_mint = Pubkey::new_unique();
+        assert_eq!(vta.get_mint(&vm_mint), mint);
+        assert_eq!(create_test_vta(0).get_mint(&vm_mint), vm_mint);
+    }
+}
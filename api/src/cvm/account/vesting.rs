@@ -0,0 +1,135 @@
+use steel::*;
+use borsh::{BorshDeserialize, BorshSerialize};
+
+#[repr(C)]
+#[derive(BorshDeserialize, BorshSerialize, Clone, Copy, PartialEq, Debug)]
+pub struct VirtualVestingAccount {
+    pub beneficiary: Pubkey,    // owner of the timelock account that vested tokens are claimed into
+    pub total: u64,             // total amount of tokens held by this account
+    pub claimed: u64,           // amount of tokens already claimed
+
+    pub start: i64,             // unix timestamp when vesting begins
+    pub cliff: i64,             // unix timestamp before which nothing can be claimed
+    pub end: i64,               // unix timestamp when the total amount has vested
+}
+
+impl VirtualVestingAccount {
+    pub const LEN: usize = // 72 bytes
+        32 + // beneficiary
+        8 +  // total
+        8 +  // claimed
+        8 +  // start
+        8 +  // cliff
+        8;   // end
+
+    /// A schedule is valid if `start <= cliff <= end`.
+    pub fn is_valid_schedule(&self) -> bool {
+        self.start <= self.cliff && self.cliff <= self.end
+    }
+
+    /// The amount that has vested at `now`, including anything already
+    /// claimed. Tokens vest linearly from `start` to `end`, but nothing is
+    /// vested before the `cliff`.
+    pub fn get_vested_amount(&self, now: i64) -> u64 {
+        if now < self.cliff {
+            return 0;
+        }
+
+        if now >= self.end {
+            return self.total;
+        }
+
+        let elapsed = (now as i128 - self.start as i128) as u128;
+        let duration = (self.end as i128 - self.start as i128) as u128;
+
+        (self.total as u128 * elapsed / duration) as u64
+    }
+
+    /// The amount that has vested at `now` but has not been claimed yet.
+    pub fn get_claimable_amount(&self, now: i64) -> u64 {
+        self.get_vested_amount(now).saturating_sub(self.claimed)
+    }
+
+    pub fn get_remaining_amount(&self) -> u64 {
+        self.total.saturating_sub(self.claimed)
+    }
+
+    pub fn is_fully_claimed(&self) -> bool {
+        self.claimed >= self.total
+    }
+
+    pub fn pack<W: std::io::Write>(&self, mut writer: W) -> std::io::Result<()> {
+        BorshSerialize::serialize(self, &mut writer)
+    }
+
+    pub fn unpack(buf: &[u8]) -> std::io::Result<Self> {
+        let data = &buf[..VirtualVestingAccount::LEN];
+        BorshDeserialize::try_from_slice(data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_vesting() -> VirtualVestingAccount {
+        VirtualVestingAccount {
+            beneficiary: Pubkey::new_unique(),
+            total: 1_000,
+            claimed: 0,
+            start: 1_000,
+            cliff: 1_250,
+            end: 2_000,
+        }
+    }
+
+    #[test]
+    fn test_vested_amount() {
+        let vesting = create_test_vesting();
+        assert!(vesting.is_valid_schedule());
+
+        assert_eq!(vesting.get_vested_amount(0), 0);
+        assert_eq!(vesting.get_vested_amount(1_249), 0);
+        assert_eq!(vesting.get_vested_amount(1_250), 250);
+        assert_eq!(vesting.get_vested_amount(1_500), 500);
+        assert_eq!(vesting.get_vested_amount(2_000), 1_000);
+        assert_eq!(vesting.get_vested_amount(i64::MAX), 1_000);
+    }
+
+    #[test]
+    fn test_claimable_amount() {
+        let mut vesting = create_test_vesting();
+
+        vesting.claimed = 250;
+        assert_eq!(vesting.get_claimable_amount(1_250), 0);
+        assert_eq!(vesting.get_claimable_amount(1_500), 250);
+        assert_eq!(vesting.get_remaining_amount(), 750);
+
+        vesting.claimed = 1_000;
+        assert_eq!(vesting.get_claimable_amount(2_000), 0);
+        assert!(vesting.is_fully_claimed());
+    }
+
+    #[test]
+    fn test_no_overflow() {
+        let vesting = VirtualVestingAccount {
+            total: u64::MAX,
+            start: 0,
+            cliff: 0,
+            end: 4,
+            ..create_test_vesting()
+        };
+
+        assert_eq!(vesting.get_vested_amount(2), u64::MAX / 2);
+    }
+
+    #[test]
+    fn test_invalid_schedule() {
+        let vesting = VirtualVestingAccount {
+            cliff: 2_500,
+            ..create_test_vesting()
+        };
+
+        assert!(!vesting.is_valid_schedule());
+    }
+}
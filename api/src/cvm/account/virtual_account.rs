@@ -7,6 +7,12 @@ use super::{
     VirtualDurableNonce,
     VirtualTimelockAccount,
     VirtualRelayAccount,
+    VirtualVestingAccount,
+    VirtualScheduledTransfer,
+    VirtualSubscriptionAccount,
+    VirtualAllowanceAccount,
+    VirtualEscrowAccount,
+    VirtualMultisigAccount,
 };
 
 
@@ -15,6 +21,12 @@ pub enum VirtualAccount {
     Nonce(VirtualDurableNonce),
     Timelock(VirtualTimelockAccount),
     Relay(VirtualRelayAccount),
+    Vesting(VirtualVestingAccount),
+    ScheduledTransfer(VirtualScheduledTransfer),
+    Subscription(VirtualSubscriptionAccount),
+    Allowance(VirtualAllowanceAccount),
+    Escrow(VirtualEscrowAccount),
+    Multisig(VirtualMultisigAccount),
 }
 
 impl VirtualAccount {
@@ -22,8 +34,14 @@ impl VirtualAccount {
     pub fn get_size(&self) -> usize {
         1 + (match self {
             VirtualAccount::Nonce(_) => VirtualDurableNonce::LEN,
-            VirtualAccount::Timelock(_) => VirtualTimelockAccount::LEN,
+            VirtualAccount::Timelock(account) => account.get_size(),
             VirtualAccount::Relay(_) => VirtualRelayAccount::LEN,
+            VirtualAccount::Vesting(_) => VirtualVestingAccount::LEN,
+            VirtualAccount::ScheduledTransfer(_) => VirtualScheduledTransfer::LEN,
+            VirtualAccount::Subscription(_) => VirtualSubscriptionAccount::LEN,
+            VirtualAccount::Allowance(_) => VirtualAllowanceAccount::LEN,
+            VirtualAccount::Escrow(_) => VirtualEscrowAccount::LEN,
+            VirtualAccount::Multisig(_) => VirtualMultisigAccount::LEN,
         })
     }
 
@@ -39,6 +57,30 @@ impl VirtualAccount {
         matches!(self, VirtualAccount::Nonce(_))
     }
 
+    pub fn is_vesting(&self) -> bool {
+        matches!(self, VirtualAccount::Vesting(_))
+    }
+
+    pub fn is_scheduled_transfer(&self) -> bool {
+        matches!(self, VirtualAccount::ScheduledTransfer(_))
+    }
+
+    pub fn is_subscription(&self) -> bool {
+        matches!(self, VirtualAccount::Subscription(_))
+    }
+
+    pub fn is_allowance(&self) -> bool {
+        matches!(self, VirtualAccount::Allowance(_))
+    }
+
+    pub fn is_escrow(&self) -> bool {
+        matches!(self, VirtualAccount::Escrow(_))
+    }
+
+    pub fn is_multisig(&self) -> bool {
+        matches!(self, VirtualAccount::Multisig(_))
+    }
+
     /// Get the hash of this VirtualAccount
     pub fn get_hash(&self) -> Hash {
         utils::hash(self.pack().as_ref())
@@ -46,13 +88,23 @@ impl VirtualAccount {
 
     /// Pack this VirtualAccount into a byte array
     pub fn pack(&self) -> Vec<u8> {
-        // The first byte is the variant, followed by the data
+        // The first byte is the variant, followed by the data. Timelock
+        // accounts with their own lock duration or mint use separate
+        // variants.
 
         let mut bytes = vec![0u8; self.get_size()];
         bytes[0] = match self {
             VirtualAccount::Nonce(_) => 0,
+            VirtualAccount::Timelock(account) if account.has_mint() => 10,
+            VirtualAccount::Timelock(account) if account.has_lock_duration() => 3,
             VirtualAccount::Timelock(_) => 1,
             VirtualAccount::Relay(_) => 2,
+            VirtualAccount::Vesting(_) => 4,
+            VirtualAccount::ScheduledTransfer(_) => 5,
+            VirtualAccount::Subscription(_) => 6,
+            VirtualAccount::Allowance(_) => 7,
+            VirtualAccount::Escrow(_) => 8,
+            VirtualAccount::Multisig(_) => 9,
         };
 
         match self {
@@ -65,6 +117,24 @@ impl VirtualAccount {
             VirtualAccount::Relay(account) => {
                 account.pack(&mut bytes[1..]).unwrap();
             },
+            VirtualAccount::Vesting(account) => {
+                account.pack(&mut bytes[1..]).unwrap();
+            },
+            VirtualAccount::ScheduledTransfer(account) => {
+                account.pack(&mut bytes[1..]).unwrap();
+            },
+            VirtualAccount::Subscription(account) => {
+                account.pack(&mut bytes[1..]).unwrap();
+            },
+            VirtualAccount::Allowance(account) => {
+                account.pack(&mut bytes[1..]).unwrap();
+            },
+            VirtualAccount::Escrow(account) => {
+                account.pack(&mut bytes[1..]).unwrap();
+            },
+            VirtualAccount::Multisig(account) => {
+                account.pack(&mut bytes[1..]).unwrap();
+            },
         }
         bytes
     }
@@ -95,6 +165,30 @@ impl VirtualAccount {
             2 => Ok(VirtualAccount::Relay(
                 VirtualRelayAccount::unpack(&data).unwrap()
             )),
+            3 => Ok(VirtualAccount::Timelock(
+                VirtualTimelockAccount::unpack_with_lock_duration(&data).unwrap()
+            )),
+            4 => Ok(VirtualAccount::Vesting(
+                VirtualVestingAccount::unpack(&data).unwrap()
+            )),
+            5 => Ok(VirtualAccount::ScheduledTransfer(
+                VirtualScheduledTransfer::unpack(&data).unwrap()
+            )),
+            6 => Ok(VirtualAccount::Subscription(
+                VirtualSubscriptionAccount::unpack(&data).unwrap()
+            )),
+            7 => Ok(VirtualAccount::Allowance(
+                VirtualAllowanceAccount::unpack(&data).unwrap()
+            )),
+            8 => Ok(VirtualAccount::Escrow(
+                VirtualEscrowAccount::unpack(&data).unwrap()
+            )),
+            9 => Ok(VirtualAccount::Multisig(
+                VirtualMultisigAccount::unpack(&data).unwrap()
+            )),
+            10 => Ok(VirtualAccount::Timelock(
+                VirtualTimelockAccount::unpack_with_mint(&data).unwrap()
+            )),
             _ => Err(ProgramError::InvalidAccountData)
         }
     }
@@ -124,6 +218,54 @@ impl VirtualAccount {
             None
         }
     }
+
+    pub fn into_inner_vesting(self) -> Option<VirtualVestingAccount> {
+        if let VirtualAccount::Vesting(inner) = self {
+            Some(inner)
+        } else {
+            None
+        }
+    }
+
+    pub fn into_inner_scheduled_transfer(self) -> Option<VirtualScheduledTransfer> {
+        if let VirtualAccount::ScheduledTransfer(inner) = self {
+            Some(inner)
+        } else {
+            None
+        }
+    }
+
+    pub fn into_inner_subscription(self) -> Option<VirtualSubscriptionAccount> {
+        if let VirtualAccount::Subscription(inner) = self {
+            Some(inner)
+        } else {
+            None
+        }
+    }
+
+    pub fn into_inner_allowance(self) -> Option<VirtualAllowanceAccount> {
+        if let VirtualAccount::Allowance(inner) = self {
+            Some(inner)
+        } else {
+            None
+        }
+    }
+
+    pub fn into_inner_escrow(self) -> Option<VirtualEscrowAccount> {
+        if let VirtualAccount::Escrow(inner) = self {
+            Some(inner)
+        } else {
+            None
+        }
+    }
+
+    pub fn into_inner_multisig(self) -> Option<VirtualMultisigAccount> {
+        if let VirtualAccount::Multisig(inner) = self {
+            Some(inner)
+        } else {
+            None
+        }
+    }
 }
 
 fn get_varient_size(variant: u8) -> usize {
@@ -131,6 +273,14 @@ fn get_varient_size(variant: u8) -> usize {
         0 => VirtualDurableNonce::LEN,
         1 => VirtualTimelockAccount::LEN,
         2 => VirtualRelayAccount::LEN,
+        3 => VirtualTimelockAccount::LEN_WITH_LOCK_DURATION,
+        4 => VirtualVestingAccount::LEN,
+        5 => VirtualScheduledTransfer::LEN,
+        6 => VirtualSubscriptionAccount::LEN,
+        7 => VirtualAllowanceAccount::LEN,
+        8 => VirtualEscrowAccount::LEN,
+        9 => VirtualMultisigAccount::LEN,
+        10 => VirtualTimelockAccount::LEN_WITH_MINT,
         _ => 0,
     }
 }
\ No newline at end of file
@@ -42,9 +42,9 @@ pub fn create_airdrop_message(
 ) -> Hash {
 
     let src_timelock_address = src_vta.get_timelock_address(
-        &vm.get_mint(),
+        &src_vta.get_mint(&vm.get_mint()),
         &vm.get_authority(),
-        vm.get_lock_duration(),
+        src_vta.get_lock_duration(vm.get_lock_duration()),
     );
 
     let src_token_address = src_vta.get_token_address(
@@ -57,4 +57,65 @@ pub fn create_airdrop_message(
         amount,
         vdn,
     )
-}
\ No newline at end of file
+}
+
+/// Like `compact_airdrop_message`, but also commits to the protocol fee the
+/// source pays on top of the airdropped total.
+pub fn compact_airdrop_message_with_fee(
+    src_timelock_address: &Pubkey,
+    dst_timelock_addresses: &[Pubkey],
+    amount: u64,
+    fee: u64,
+    vdn: &VirtualDurableNonce,
+) -> Hash {
+    let mut msg = Vec::new();
+
+    msg.push(b"airdrop_with_fee" as &[u8]);
+    msg.push(src_timelock_address.as_ref());
+    msg.push(vdn.address.as_ref());
+    msg.push(vdn.value.as_ref());
+
+    let amount_bytes = amount.to_le_bytes();
+    msg.push(&amount_bytes);
+
+    let fee_bytes = fee.to_le_bytes();
+    msg.push(&fee_bytes);
+
+    for dst_pubkey in dst_timelock_addresses {
+        msg.push(dst_pubkey.as_ref());
+    }
+
+    utils::hashv(&msg)
+}
+
+/// A zero fee gives the same message as `create_airdrop_message`.
+pub fn create_airdrop_message_with_fee(
+    vm: &CodeVmAccount,
+    src_vta: &VirtualTimelockAccount,
+    destinations: &[Pubkey],
+    amount: u64,
+    fee: u64,
+    vdn: &VirtualDurableNonce,
+) -> Hash {
+    if fee == 0 {
+        return create_airdrop_message(vm, src_vta, destinations, amount, vdn);
+    }
+
+    let src_timelock_address = src_vta.get_timelock_address(
+        &src_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        src_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    let src_token_address = src_vta.get_token_address(
+        &src_timelock_address,
+    );
+
+    compact_airdrop_message_with_fee(
+        &src_token_address,
+        destinations,
+        amount,
+        fee,
+        vdn,
+    )
+}
@@ -0,0 +1,120 @@
+use steel::*;
+
+use crate::utils;
+use crate::types::Hash;
+use crate::cvm::{
+    CodeVmAccount,
+    VirtualAllowanceAccount,
+    VirtualDurableNonce,
+    VirtualTimelockAccount
+};
+
+pub fn compact_approve_allowance_message(
+    src_token_address: &Pubkey,
+    delegate: &Pubkey,
+    amount: u64,
+    expires_at: i64,
+    vdn: &VirtualDurableNonce,
+) -> Hash {
+    let message = &[
+        b"approve_allowance",
+        src_token_address.as_ref(),
+        delegate.as_ref(),
+        &amount.to_le_bytes(),
+        &expires_at.to_le_bytes(),
+        vdn.address.as_ref(),
+        vdn.value.as_ref(), // this value is auto-advanced upon use
+    ];
+
+    utils::hashv(message)
+}
+
+pub fn compact_delegated_transfer_message(
+    src_token_address: &Pubkey,
+    dst_token_address: &Pubkey,
+    delegate: &Pubkey,
+    amount: u64,
+    vdn: &VirtualDurableNonce,
+) -> Hash {
+    let message = &[
+        b"delegated_transfer",
+        src_token_address.as_ref(),
+        dst_token_address.as_ref(),
+        delegate.as_ref(),
+        &amount.to_le_bytes(),
+        vdn.address.as_ref(),
+        vdn.value.as_ref(), // this value is auto-advanced upon use
+    ];
+
+    utils::hashv(message)
+}
+
+pub fn create_approve_allowance_message(
+    vm: &CodeVmAccount,
+    src_vta: &VirtualTimelockAccount,
+    delegate: &Pubkey,
+    vdn: &VirtualDurableNonce,
+    amount: u64,
+    expires_at: i64,
+) -> Hash {
+
+    let src_timelock_address = src_vta.get_timelock_address(
+        &src_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        src_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+    let src_token_address = src_vta.get_token_address(
+        &src_timelock_address,
+    );
+
+    compact_approve_allowance_message(
+        &src_token_address,
+        delegate,
+        amount,
+        expires_at,
+        vdn,
+    )
+}
+
+pub fn create_delegated_transfer_message(
+    vm: &CodeVmAccount,
+    allowance: &VirtualAllowanceAccount,
+    dst_vta: &VirtualTimelockAccount,
+    vdn: &VirtualDurableNonce,
+    amount: u64,
+) -> Hash {
+
+    let dst_timelock_address = dst_vta.get_timelock_address(
+        &dst_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        dst_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+    let dst_token_address = dst_vta.get_token_address(
+        &dst_timelock_address,
+    );
+
+    compact_delegated_transfer_message(
+        &allowance.source,
+        &dst_token_address,
+        &allowance.delegate,
+        amount,
+        vdn,
+    )
+}
+
+pub fn create_revoke_allowance_message(
+    allowance: &VirtualAllowanceAccount,
+    vdn: &VirtualDurableNonce,
+) -> Hash {
+    let message = &[
+        b"revoke_allowance",
+        allowance.source.as_ref(),
+        allowance.delegate.as_ref(),
+        &allowance.remaining.to_le_bytes(),
+        &allowance.expires_at.to_le_bytes(),
+        vdn.address.as_ref(),
+        vdn.value.as_ref(), // this value is auto-advanced upon use
+    ];
+
+    utils::hashv(message)
+}
@@ -0,0 +1,61 @@
+use steel::*;
+
+use crate::utils;
+use crate::types::Hash;
+use crate::cvm::{
+  CodeVmAccount,
+  VirtualTimelockAccount
+};
+
+/// The intent a user signs to deposit `amount` from their token account into
+/// a virtual account, see `DepositWithIntentIx`. The nonce is picked by the
+/// user, and each one can only be used once, until the intent expires.
+pub fn compact_deposit_intent_message(
+    vm_address: &Pubkey,
+    source: &Pubkey,
+    dst_timelock_address: &Pubkey,
+    amount: u64,
+    nonce: &Hash,
+    expires_at: i64,
+) -> Hash {
+    let message = &[
+        b"deposit_intent",
+        vm_address.as_ref(),
+        source.as_ref(),
+        dst_timelock_address.as_ref(),
+        &amount.to_le_bytes(),
+        nonce.as_ref(),
+        &expires_at.to_le_bytes(),
+    ];
+
+    utils::hashv(message)
+}
+
+pub fn create_deposit_intent_message(
+    vm: &CodeVmAccount,
+    vm_address: &Pubkey,
+    source: &Pubkey,
+    dst_vta: &VirtualTimelockAccount,
+    amount: u64,
+    nonce: &Hash,
+    expires_at: i64,
+) -> Hash {
+
+    let dst_timelock_address = dst_vta.get_timelock_address(
+        &dst_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        dst_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+    let dst_token_address = dst_vta.get_token_address(
+        &dst_timelock_address,
+    );
+
+    compact_deposit_intent_message(
+        vm_address,
+        source,
+        &dst_token_address,
+        amount,
+        nonce,
+        expires_at,
+    )
+}
@@ -0,0 +1,88 @@
+use steel::*;
+
+use crate::utils;
+use crate::types::Hash;
+use crate::cvm::{
+    CodeVmAccount,
+    VirtualDurableNonce,
+    VirtualEscrowAccount,
+    VirtualTimelockAccount
+};
+
+pub fn compact_escrow_deposit_message(
+    src_token_address: &Pubkey,
+    dst_token_address: &Pubkey,
+    arbiter: &Pubkey,
+    amount: u64,
+    deadline: i64,
+    vdn: &VirtualDurableNonce,
+) -> Hash {
+    let message = &[
+        b"escrow_deposit",
+        src_token_address.as_ref(),
+        dst_token_address.as_ref(),
+        arbiter.as_ref(),
+        &amount.to_le_bytes(),
+        &deadline.to_le_bytes(),
+        vdn.address.as_ref(),
+        vdn.value.as_ref(), // this value is auto-advanced upon use
+    ];
+
+    utils::hashv(message)
+}
+
+pub fn create_escrow_deposit_message(
+    vm: &CodeVmAccount,
+    src_vta: &VirtualTimelockAccount,
+    dst_vta: &VirtualTimelockAccount,
+    arbiter: &Pubkey,
+    vdn: &VirtualDurableNonce,
+    amount: u64,
+    deadline: i64,
+) -> Hash {
+
+    let src_timelock_address = src_vta.get_timelock_address(
+        &src_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        src_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+    let src_token_address = src_vta.get_token_address(
+        &src_timelock_address,
+    );
+
+    let dst_timelock_address = dst_vta.get_timelock_address(
+        &dst_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        dst_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+    let dst_token_address = dst_vta.get_token_address(
+        &dst_timelock_address,
+    );
+
+    compact_escrow_deposit_message(
+        &src_token_address,
+        &dst_token_address,
+        arbiter,
+        amount,
+        deadline,
+        vdn,
+    )
+}
+
+pub fn create_escrow_release_message(
+    escrow: &VirtualEscrowAccount,
+    vdn: &VirtualDurableNonce,
+) -> Hash {
+    let message = &[
+        b"escrow_release",
+        escrow.source.as_ref(),
+        escrow.destination.as_ref(),
+        escrow.arbiter.as_ref(),
+        &escrow.amount.to_le_bytes(),
+        &escrow.deadline.to_le_bytes(),
+        vdn.address.as_ref(),
+        vdn.value.as_ref(), // this value is auto-advanced upon use
+    ];
+
+    utils::hashv(message)
+}
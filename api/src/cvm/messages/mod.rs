@@ -1,7 +1,21 @@
 mod airdrop;
+mod allowance;
+mod deposit;
+mod escrow;
+mod payload;
+mod scheduled_transfer;
+mod subscription;
 mod transfer;
+mod version;
 mod withdraw;
 
 pub use airdrop::*;
+pub use allowance::*;
+pub use deposit::*;
+pub use escrow::*;
+pub use payload::*;
+pub use scheduled_transfer::*;
+pub use subscription::*;
 pub use transfer::*;
+pub use version::*;
 pub use withdraw::*;
\ No newline at end of file
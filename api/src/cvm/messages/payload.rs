@@ -0,0 +1,380 @@
+use steel::*;
+
+use crate::opcode::Opcode;
+use crate::types::Hash;
+use crate::cvm::{
+  CodeVmAccount,
+  VirtualDurableNonce,
+  VirtualTimelockAccount
+};
+use super::{sig_verify_message, sig_verify_with_mode};
+
+pub const PAYLOAD_HEADER: &str = "code-vm signing payload";
+pub const PAYLOAD_VERSION: u8 = 1;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PayloadAction {
+    Transfer,
+    Withdraw,
+    ExternalTransfer,
+    ExternalWithdraw,
+}
+
+impl PayloadAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PayloadAction::Transfer => "transfer",
+            PayloadAction::Withdraw => "withdraw",
+            PayloadAction::ExternalTransfer => "external_transfer",
+            PayloadAction::ExternalWithdraw => "external_withdraw",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "transfer" => Some(PayloadAction::Transfer),
+            "withdraw" => Some(PayloadAction::Withdraw),
+            "external_transfer" => Some(PayloadAction::ExternalTransfer),
+            "external_withdraw" => Some(PayloadAction::ExternalWithdraw),
+            _ => None,
+        }
+    }
+
+    /// The opcode that performs the action.
+    pub fn opcode(&self) -> Opcode {
+        match self {
+            PayloadAction::Transfer => Opcode::TransferOp,
+            PayloadAction::Withdraw => Opcode::WithdrawOp,
+            PayloadAction::ExternalTransfer => Opcode::ExternalTransferOp,
+            PayloadAction::ExternalWithdraw => Opcode::ExternalWithdrawOp,
+        }
+    }
+}
+
+/// A human-readable alternative to the hashed messages. A wallet can show
+/// the encoded payload to the user as-is, and the VM verifies the signature
+/// over the exact same bytes.
+///
+/// The encoding is UTF-8 text with one `key: value` field per line, in a
+/// fixed order. Addresses and the nonce value are base58. The `fee` line is
+/// only present when the VM charges a protocol fee.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SigningPayload {
+    pub program: Pubkey,
+    pub vm: Pubkey,
+    pub action: PayloadAction,
+    pub amount: u64,
+    pub fee: u64,
+    pub source: Pubkey,
+    pub destination: Pubkey,
+    pub nonce_account: Pubkey,
+    pub nonce: Hash,
+}
+
+impl SigningPayload {
+    pub fn with_fee(mut self, fee: u64) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut lines = vec![
+            PAYLOAD_HEADER.to_string(),
+            format!("version: {}", PAYLOAD_VERSION),
+            format!("program: {}", self.program),
+            format!("vm: {}", self.vm),
+            format!("action: {}", self.action.as_str()),
+            format!("amount: {}", self.amount),
+        ];
+
+        if self.fee > 0 {
+            lines.push(format!("fee: {}", self.fee));
+        }
+
+        lines.extend([
+            format!("source: {}", self.source),
+            format!("destination: {}", self.destination),
+            format!("nonce_account: {}", self.nonce_account),
+            format!("nonce: {}", self.nonce),
+        ]);
+
+        lines.join("\n").into_bytes()
+    }
+
+    pub fn decode(data: &[u8]) -> Option<Self> {
+        let text = std::str::from_utf8(data).ok()?;
+        let mut lines = text.split('\n').peekable();
+
+        if lines.next()? != PAYLOAD_HEADER {
+            return None;
+        }
+
+        let version: u8 = field(&mut lines, "version")?.parse().ok()?;
+        if version != PAYLOAD_VERSION {
+            return None;
+        }
+
+        let payload = Self {
+            program: decode_bytes(field(&mut lines, "program")?)?.into(),
+            vm: decode_bytes(field(&mut lines, "vm")?)?.into(),
+            action: PayloadAction::parse(field(&mut lines, "action")?)?,
+            amount: field(&mut lines, "amount")?.parse().ok()?,
+            fee: match lines.peek() {
+                Some(line) if line.starts_with("fee: ") => field(&mut lines, "fee")?.parse().ok()?,
+                _ => 0,
+            },
+            source: decode_bytes(field(&mut lines, "source")?)?.into(),
+            destination: decode_bytes(field(&mut lines, "destination")?)?.into(),
+            nonce_account: decode_bytes(field(&mut lines, "nonce_account")?)?.into(),
+            nonce: decode_bytes(field(&mut lines, "nonce")?)?.into(),
+        };
+
+        if lines.next().is_some() {
+            return None;
+        }
+
+        // Reject anything that wouldn't encode back to the same bytes (for
+        // example, leading zeros on the amount).
+        if payload.encode() != data {
+            return None;
+        }
+
+        Some(payload)
+    }
+}
+
+fn field<'a>(lines: &mut impl Iterator<Item = &'a str>, key: &str) -> Option<&'a str> {
+    let line = lines.next()?;
+    let (name, value) = line.split_once(": ")?;
+
+    if name != key {
+        return None;
+    }
+
+    Some(value)
+}
+
+fn decode_bytes(value: &str) -> Option<[u8; 32]> {
+    bs58::decode(value).into_vec().ok()?.try_into().ok()
+}
+
+fn get_token_address(
+    vm: &CodeVmAccount,
+    vta: &VirtualTimelockAccount,
+) -> Pubkey {
+    let timelock_address = vta.get_timelock_address(
+        &vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    vta.get_token_address(&timelock_address)
+}
+
+pub fn compact_payload(
+    vm_address: &Pubkey,
+    action: PayloadAction,
+    src_token_address: &Pubkey,
+    dst_token_address: &Pubkey,
+    amount: u64,
+    vdn: &VirtualDurableNonce,
+) -> SigningPayload {
+    SigningPayload {
+        program: crate::ID,
+        vm: *vm_address,
+        action,
+        amount,
+        fee: 0,
+        source: *src_token_address,
+        destination: *dst_token_address,
+        nonce_account: vdn.address,
+        nonce: vdn.value,
+    }
+}
+
+pub fn create_transfer_payload(
+    vm: &CodeVmAccount,
+    vm_address: &Pubkey,
+    src_vta: &VirtualTimelockAccount,
+    dst_vta: &VirtualTimelockAccount,
+    vdn: &VirtualDurableNonce,
+    amount: u64,
+) -> SigningPayload {
+    compact_payload(
+        vm_address,
+        PayloadAction::Transfer,
+        &get_token_address(vm, src_vta),
+        &get_token_address(vm, dst_vta),
+        amount,
+        vdn,
+    )
+}
+
+pub fn create_transfer_payload_to_external(
+    vm: &CodeVmAccount,
+    vm_address: &Pubkey,
+    src_vta: &VirtualTimelockAccount,
+    dst_pubkey: &Pubkey,
+    vdn: &VirtualDurableNonce,
+    amount: u64,
+) -> SigningPayload {
+    compact_payload(
+        vm_address,
+        PayloadAction::ExternalTransfer,
+        &get_token_address(vm, src_vta),
+        dst_pubkey,
+        amount,
+        vdn,
+    )
+}
+
+pub fn create_withdraw_payload(
+    vm: &CodeVmAccount,
+    vm_address: &Pubkey,
+    src_vta: &VirtualTimelockAccount,
+    dst_vta: &VirtualTimelockAccount,
+    vdn: &VirtualDurableNonce,
+) -> SigningPayload {
+    compact_payload(
+        vm_address,
+        PayloadAction::Withdraw,
+        &get_token_address(vm, src_vta),
+        &get_token_address(vm, dst_vta),
+        src_vta.balance,
+        vdn,
+    )
+}
+
+pub fn create_withdraw_payload_to_external(
+    vm: &CodeVmAccount,
+    vm_address: &Pubkey,
+    src_vta: &VirtualTimelockAccount,
+    dst_pubkey: &Pubkey,
+    vdn: &VirtualDurableNonce,
+) -> SigningPayload {
+    compact_payload(
+        vm_address,
+        PayloadAction::ExternalWithdraw,
+        &get_token_address(vm, src_vta),
+        dst_pubkey,
+        src_vta.balance,
+        vdn,
+    )
+}
+
+/// Verifies a signature over the hashed message, or over the structured
+/// payload describing the same action. Payloads commit to the program and
+/// VM address, so they're only accepted when the VM accepts v2 messages.
+///
+/// The opcode is the one that performs the payload action.
+pub fn sig_verify_message_or_payload(
+    vm: &CodeVmAccount,
+    vm_address: &Pubkey,
+    instructions_info: Option<&AccountInfo>,
+    pubkey: &[u8],
+    signature: &[u8],
+    message: &Hash,
+    payload: &SigningPayload,
+) -> Result<(), ProgramError> {
+    let opcode = payload.action.opcode();
+    let result = sig_verify_message(vm, vm_address, instructions_info, opcode, pubkey, signature, message);
+
+    if result.is_ok() || !vm.get_message_policy().accepts_v2() {
+        return result;
+    }
+
+    sig_verify_with_mode(vm, instructions_info, pubkey, signature, &payload.encode())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn golden_payload() -> SigningPayload {
+        SigningPayload {
+            program: crate::ID,
+            vm: Pubkey::new_from_array([1; 32]),
+            action: PayloadAction::Transfer,
+            amount: 1_000_000,
+            fee: 0,
+            source: Pubkey::new_from_array([2; 32]),
+            destination: Pubkey::new_from_array([3; 32]),
+            nonce_account: Pubkey::new_from_array([4; 32]),
+            nonce: Hash::new_from_array([5; 32]),
+        }
+    }
+
+    const GOLDEN_TRANSFER: &str = concat!(
+        "code-vm signing payload\n",
+        "version: 1\n",
+        "program: vmZ1WUq8SxjBWcaeTCvgJRZbS84R61uniFsQy5YMRTJ\n",
+        "vm: 4vJ9JU1bJJE96FWSJKvHsmmFADCg4gpZQff4P3bkLKi\n",
+        "action: transfer\n",
+        "amount: 1000000\n",
+        "source: 8qbHbw2BbbTHBW1sbeqakYXVKRQM8Ne7pLK7m6CVfeR\n",
+        "destination: CktRuQ2mttgRGkXJtyksdKHjUdc2C4TgDzyB98oEzy8\n",
+        "nonce_account: GgBaCs3NCBuZN12kCJgAW63ydqohFkHEdfdEXBPzLHq\n",
+        "nonce: LbUiWL3xVV8hTFYBVdbTNrpDo41NKS6o3LHHuDzjfcY",
+    );
+
+    #[test]
+    fn test_encode_golden_vector() {
+        let encoded = golden_payload().encode();
+        assert_eq!(std::str::from_utf8(&encoded).unwrap(), GOLDEN_TRANSFER);
+    }
+
+    #[test]
+    fn test_decode_golden_vector() {
+        let decoded = SigningPayload::decode(GOLDEN_TRANSFER.as_bytes()).unwrap();
+        assert_eq!(decoded, golden_payload());
+
+        for action in [
+            PayloadAction::Withdraw,
+            PayloadAction::ExternalTransfer,
+            PayloadAction::ExternalWithdraw,
+        ] {
+            let payload = SigningPayload { action, ..golden_payload() };
+            assert_eq!(SigningPayload::decode(&payload.encode()), Some(payload));
+        }
+    }
+
+    #[test]
+    fn test_external_actions_are_distinct() {
+        let external = GOLDEN_TRANSFER.replace("action: transfer", "action: external_transfer");
+        let decoded = SigningPayload::decode(external.as_bytes()).unwrap();
+
+        assert_eq!(decoded.action, PayloadAction::ExternalTransfer);
+        assert_eq!(decoded.action.opcode(), Opcode::ExternalTransferOp);
+        assert_ne!(decoded.encode(), golden_payload().encode());
+    }
+
+    #[test]
+    fn test_decode_rejects_malformed() {
+        let tampered = GOLDEN_TRANSFER.replace("amount: 1000000", "amount: 01000000");
+        assert!(SigningPayload::decode(tampered.as_bytes()).is_none());
+
+        let reordered = GOLDEN_TRANSFER.replace("action: transfer\namount: 1000000", "amount: 1000000\naction: transfer");
+        assert!(SigningPayload::decode(reordered.as_bytes()).is_none());
+
+        let unknown = GOLDEN_TRANSFER.replace("action: transfer", "action: burn");
+        assert!(SigningPayload::decode(unknown.as_bytes()).is_none());
+
+        let trailing = format!("{}\n", GOLDEN_TRANSFER);
+        assert!(SigningPayload::decode(trailing.as_bytes()).is_none());
+
+        let version = GOLDEN_TRANSFER.replace("version: 1", "version: 2");
+        assert!(SigningPayload::decode(version.as_bytes()).is_none());
+
+        let zero_fee = GOLDEN_TRANSFER.replace("amount: 1000000", "amount: 1000000\nfee: 0");
+        assert!(SigningPayload::decode(zero_fee.as_bytes()).is_none());
+    }
+
+    #[test]
+    fn test_fee_golden_vector() {
+        let payload = golden_payload().with_fee(2_500);
+        let expected = GOLDEN_TRANSFER.replace("amount: 1000000", "amount: 1000000\nfee: 2500");
+
+        assert_eq!(std::str::from_utf8(&payload.encode()).unwrap(), expected);
+        assert_eq!(SigningPayload::decode(expected.as_bytes()), Some(payload));
+    }
+}
@@ -0,0 +1,88 @@
+use steel::*;
+
+use crate::utils;
+use crate::types::Hash;
+use crate::cvm::{
+    CodeVmAccount,
+    VirtualDurableNonce,
+    VirtualScheduledTransfer,
+    VirtualTimelockAccount
+};
+
+pub fn compact_scheduled_transfer_message(
+    src_timelock_address: &Pubkey,
+    dst_timelock_address: &Pubkey,
+    amount: u64,
+    not_before: i64,
+    vdn: &VirtualDurableNonce,
+) -> Hash {
+    let message = &[
+        b"scheduled_transfer",
+        src_timelock_address.as_ref(),
+        dst_timelock_address.as_ref(),
+        &amount.to_le_bytes(),
+        &not_before.to_le_bytes(),
+        vdn.address.as_ref(),
+        vdn.value.as_ref(), // this value is auto-advanced upon use
+    ];
+
+    utils::hashv(message)
+}
+
+/// The record only holds token addresses, so the program and VM are part of
+/// the message to keep it from being used against a record in another VM.
+pub fn create_cancel_scheduled_transfer_message(
+    vm_address: &Pubkey,
+    scheduled: &VirtualScheduledTransfer,
+    vdn: &VirtualDurableNonce,
+) -> Hash {
+    let message = &[
+        b"cancel_scheduled_transfer",
+        crate::ID.as_ref(),
+        vm_address.as_ref(),
+        scheduled.source.as_ref(),
+        scheduled.destination.as_ref(),
+        &scheduled.amount.to_le_bytes(),
+        &scheduled.not_before.to_le_bytes(),
+        vdn.address.as_ref(),
+        vdn.value.as_ref(), // this value is auto-advanced upon use
+    ];
+
+    utils::hashv(message)
+}
+
+pub fn create_scheduled_transfer_message(
+    vm: &CodeVmAccount,
+    src_vta: &VirtualTimelockAccount,
+    dst_vta: &VirtualTimelockAccount,
+    vdn: &VirtualDurableNonce,
+    amount: u64,
+    not_before: i64,
+) -> Hash {
+
+    let src_timelock_address = src_vta.get_timelock_address(
+        &src_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        src_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+    let src_token_address = src_vta.get_token_address(
+        &src_timelock_address,
+    );
+
+    let dst_timelock_address = dst_vta.get_timelock_address(
+        &dst_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        dst_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+    let dst_token_address = dst_vta.get_token_address(
+        &dst_timelock_address,
+    );
+
+    compact_scheduled_transfer_message(
+        &src_token_address,
+        &dst_token_address,
+        amount,
+        not_before,
+        vdn,
+    )
+}
@@ -0,0 +1,96 @@
+use steel::*;
+
+use crate::utils;
+use crate::types::Hash;
+use crate::cvm::{
+    CodeVmAccount,
+    VirtualDurableNonce,
+    VirtualSubscriptionAccount,
+    VirtualTimelockAccount
+};
+
+pub fn compact_subscription_message(
+    payer_token_address: &Pubkey,
+    payee_token_address: &Pubkey,
+    amount: u64,
+    period: i64,
+    first_due: i64,
+    count: u64,
+    vdn: &VirtualDurableNonce,
+) -> Hash {
+    let message = &[
+        b"subscription",
+        payer_token_address.as_ref(),
+        payee_token_address.as_ref(),
+        &amount.to_le_bytes(),
+        &period.to_le_bytes(),
+        &first_due.to_le_bytes(),
+        &count.to_le_bytes(),
+        vdn.address.as_ref(),
+        vdn.value.as_ref(), // this value is auto-advanced upon use
+    ];
+
+    utils::hashv(message)
+}
+
+pub fn create_cancel_subscription_message(
+    vm_address: &Pubkey,
+    subscription: &VirtualSubscriptionAccount,
+    vdn: &VirtualDurableNonce,
+) -> Hash {
+    let message = &[
+        b"cancel_subscription",
+        crate::ID.as_ref(),
+        vm_address.as_ref(),
+        subscription.payer.as_ref(),
+        subscription.payee.as_ref(),
+        &subscription.amount.to_le_bytes(),
+        &subscription.period.to_le_bytes(),
+        &subscription.next_due.to_le_bytes(),
+        &subscription.remaining.to_le_bytes(),
+        vdn.address.as_ref(),
+        vdn.value.as_ref(), // this value is auto-advanced upon use
+    ];
+
+    utils::hashv(message)
+}
+
+pub fn create_subscription_message(
+    vm: &CodeVmAccount,
+    payer_vta: &VirtualTimelockAccount,
+    payee_vta: &VirtualTimelockAccount,
+    vdn: &VirtualDurableNonce,
+    amount: u64,
+    period: i64,
+    first_due: i64,
+    count: u64,
+) -> Hash {
+
+    let payer_timelock_address = payer_vta.get_timelock_address(
+        &payer_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        payer_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+    let payer_token_address = payer_vta.get_token_address(
+        &payer_timelock_address,
+    );
+
+    let payee_timelock_address = payee_vta.get_timelock_address(
+        &payee_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        payee_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+    let payee_token_address = payee_vta.get_token_address(
+        &payee_timelock_address,
+    );
+
+    compact_subscription_message(
+        &payer_token_address,
+        &payee_token_address,
+        amount,
+        period,
+        first_due,
+        count,
+        vdn,
+    )
+}
@@ -35,18 +35,18 @@ pub fn create_transfer_message(
 ) -> Hash {
 
     let src_timelock_address = src_vta.get_timelock_address(
-        &vm.get_mint(),
+        &src_vta.get_mint(&vm.get_mint()),
         &vm.get_authority(),
-        vm.get_lock_duration(),
+        src_vta.get_lock_duration(vm.get_lock_duration()),
     );
     let src_token_address = src_vta.get_token_address(
         &src_timelock_address,
     );
 
     let dst_timelock_address = dst_vta.get_timelock_address(
-        &vm.get_mint(),
+        &dst_vta.get_mint(&vm.get_mint()),
         &vm.get_authority(),
-        vm.get_lock_duration(),
+        dst_vta.get_lock_duration(vm.get_lock_duration()),
     );
     let dst_token_address = dst_vta.get_token_address(
         &dst_timelock_address,
@@ -69,9 +69,9 @@ pub fn create_transfer_message_to_external(
 ) -> Hash {
 
     let src_timelock_address = src_vta.get_timelock_address(
-        &vm.get_mint(),
+        &src_vta.get_mint(&vm.get_mint()),
         &vm.get_authority(),
-        vm.get_lock_duration(),
+        src_vta.get_lock_duration(vm.get_lock_duration()),
     );
     let src_token_address = src_vta.get_token_address(
         &src_timelock_address,
@@ -85,4 +85,97 @@ pub fn create_transfer_message_to_external(
     )
 }
 
+/// Like `compact_transfer_message`, but also commits to the protocol fee the
+/// source pays on top of the amount.
+pub fn compact_transfer_message_with_fee(
+    src_timelock_address: &Pubkey,
+    dst_timelock_address: &Pubkey,
+    amount: u64,
+    fee: u64,
+    vdn: &VirtualDurableNonce,
+) -> Hash {
+    let message = &[
+        b"transfer_with_fee",
+        src_timelock_address.as_ref(),
+        dst_timelock_address.as_ref(),
+        &amount.to_le_bytes(),
+        &fee.to_le_bytes(),
+        vdn.address.as_ref(),
+        vdn.value.as_ref(), // this value is auto-advanced upon use
+    ];
+
+    utils::hashv(message)
+}
+
+/// A zero fee gives the same message as `create_transfer_message`, so
+/// wallets don't need to change anything for VMs that don't charge fees.
+pub fn create_transfer_message_with_fee(
+    vm: &CodeVmAccount,
+    src_vta: &VirtualTimelockAccount,
+    dst_vta: &VirtualTimelockAccount,
+    vdn: &VirtualDurableNonce,
+    amount: u64,
+    fee: u64,
+) -> Hash {
+    if fee == 0 {
+        return create_transfer_message(vm, src_vta, dst_vta, vdn, amount);
+    }
+
+    let src_timelock_address = src_vta.get_timelock_address(
+        &src_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        src_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+    let src_token_address = src_vta.get_token_address(
+        &src_timelock_address,
+    );
 
+    let dst_timelock_address = dst_vta.get_timelock_address(
+        &dst_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        dst_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+    let dst_token_address = dst_vta.get_token_address(
+        &dst_timelock_address,
+    );
+
+    compact_transfer_message_with_fee(
+        &src_token_address,
+        &dst_token_address,
+        amount,
+        fee,
+        vdn,
+    )
+}
+
+/// A zero fee gives the same message as
+/// `create_transfer_message_to_external`.
+pub fn create_transfer_message_to_external_with_fee(
+    vm: &CodeVmAccount,
+    src_vta: &VirtualTimelockAccount,
+    dst_pubkey: &Pubkey,
+    vdn: &VirtualDurableNonce,
+    amount: u64,
+    fee: u64,
+) -> Hash {
+    if fee == 0 {
+        return create_transfer_message_to_external(vm, src_vta, dst_pubkey, vdn, amount);
+    }
+
+    let src_timelock_address = src_vta.get_timelock_address(
+        &src_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        src_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+    let src_token_address = src_vta.get_token_address(
+        &src_timelock_address,
+    );
+
+    compact_transfer_message_with_fee(
+        &src_token_address,
+        dst_pubkey,
+        amount,
+        fee,
+        vdn,
+    )
+}
@@ -0,0 +1,152 @@
+use steel::*;
+
+use crate::utils;
+use crate::opcode::Opcode;
+use crate::types::Hash;
+use crate::cvm::{CodeVmAccount, SignatureMode};
+
+pub const MESSAGE_DOMAIN: &[u8] = b"code_vm_message";
+
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MessageVersion {
+    V1 = 1,
+    V2 = 2,
+}
+
+/// Wraps a v1 message hash so that the signature also commits to the
+/// program ID, the VM address, the opcode and the message version. A v2
+/// signature can't be replayed against another deployment, another VM, or
+/// an opcode with a compatible layout.
+pub fn create_v2_message(
+    vm_address: &Pubkey,
+    opcode: Opcode,
+    message: &Hash,
+) -> Hash {
+    let message = &[
+        MESSAGE_DOMAIN,
+        &[MessageVersion::V2 as u8],
+        crate::ID.as_ref(),
+        vm_address.as_ref(),
+        &[opcode as u8],
+        message.as_ref(),
+    ];
+
+    utils::hashv(message)
+}
+
+/// Verifies a signature over raw message bytes, either with the curve25519
+/// syscalls or against an Ed25519 program instruction, depending on the VM's
+/// signature mode.
+pub fn sig_verify_with_mode(
+    vm: &CodeVmAccount,
+    instructions_info: Option<&AccountInfo>,
+    pubkey: &[u8],
+    signature: &[u8],
+    message: &[u8],
+) -> Result<(), ProgramError> {
+    match vm.get_signature_mode() {
+        SignatureMode::Syscall => {
+            if message.len() == 32 {
+                utils::sig_verify(pubkey, signature, message)
+            } else {
+                utils::sig_verify_bytes(pubkey, signature, message)
+            }
+        }
+        SignatureMode::Precompile => {
+            let instructions_info = instructions_info
+                .ok_or(ProgramError::NotEnoughAccountKeys)?;
+
+            utils::sig_verify_precompile(instructions_info, pubkey, signature, message)
+        }
+    }
+}
+
+/// Verifies a signature over a v1 message, or over its v2 wrapper, depending
+/// on which versions the VM accepts.
+pub fn sig_verify_message(
+    vm: &CodeVmAccount,
+    vm_address: &Pubkey,
+    instructions_info: Option<&AccountInfo>,
+    opcode: Opcode,
+    pubkey: &[u8],
+    signature: &[u8],
+    message: &Hash,
+) -> Result<(), ProgramError> {
+    let policy = vm.get_message_policy();
+
+    if policy.accepts_v2() {
+        let v2 = create_v2_message(vm_address, opcode, message);
+        let result = sig_verify_with_mode(vm, instructions_info, pubkey, signature, v2.as_ref());
+
+        if result.is_ok() || !policy.accepts_v1() {
+            return result;
+        }
+    }
+
+    sig_verify_with_mode(vm, instructions_info, pubkey, signature, message.as_ref())
+}
+
+/// Verifies several signatures over the same message with a single batched
+/// check. When the VM accepts both versions and neither batch verifies, the
+/// signatures are checked one at a time, since each signer may have picked a
+/// different version.
+pub fn sig_verify_message_batch(
+    vm: &CodeVmAccount,
+    vm_address: &Pubkey,
+    instructions_info: Option<&AccountInfo>,
+    opcode: Opcode,
+    pubkeys: &[&[u8]],
+    signatures: &[&[u8]],
+    message: &Hash,
+) -> Result<(), ProgramError> {
+    let policy = vm.get_message_policy();
+    let is_batched = vm.get_signature_mode() == SignatureMode::Syscall;
+
+    // Precompile signatures are already verified by the runtime, so there's
+    // nothing to batch.
+
+    if is_batched && policy.accepts_v2() {
+        let v2 = create_v2_message(vm_address, opcode, message);
+        let messages = vec![v2.as_ref(); pubkeys.len()];
+        let result = utils::sig_verify_batch(pubkeys, signatures, &messages);
+
+        if result.is_ok() || !policy.accepts_v1() {
+            return result;
+        }
+    }
+
+    if is_batched {
+        let messages = vec![message.as_ref(); pubkeys.len()];
+        let result = utils::sig_verify_batch(pubkeys, signatures, &messages);
+
+        if result.is_ok() || !policy.accepts_v2() {
+            return result;
+        }
+    }
+
+    for (pubkey, signature) in pubkeys.iter().zip(signatures.iter()) {
+        sig_verify_message(vm, vm_address, instructions_info, opcode, pubkey, signature, message)?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_v2_message_is_domain_separated() {
+        let vm_a = Pubkey::new_unique();
+        let vm_b = Pubkey::new_unique();
+        let message = utils::hash(b"transfer");
+
+        let v2 = create_v2_message(&vm_a, Opcode::TransferOp, &message);
+
+        assert_ne!(v2, message);
+        assert_ne!(v2, create_v2_message(&vm_b, Opcode::TransferOp, &message));
+        assert_ne!(v2, create_v2_message(&vm_a, Opcode::WithdrawOp, &message));
+        assert_eq!(v2, create_v2_message(&vm_a, Opcode::TransferOp, &message));
+    }
+}
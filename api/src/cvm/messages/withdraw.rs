@@ -32,18 +32,18 @@ pub fn create_withdraw_message(
 ) -> Hash {
 
     let src_timelock_address = src_vta.get_timelock_address(
-        &vm.get_mint(),
+        &src_vta.get_mint(&vm.get_mint()),
         &vm.get_authority(),
-        vm.get_lock_duration(),
+        src_vta.get_lock_duration(vm.get_lock_duration()),
     );
     let src_token_address = src_vta.get_token_address(
         &src_timelock_address,
     );
 
     let dst_timelock_address = dst_vta.get_timelock_address(
-        &vm.get_mint(),
+        &dst_vta.get_mint(&vm.get_mint()),
         &vm.get_authority(),
-        vm.get_lock_duration(),
+        dst_vta.get_lock_duration(vm.get_lock_duration()),
     );
     let dst_token_address = dst_vta.get_token_address(
         &dst_timelock_address,
@@ -64,9 +64,9 @@ pub fn create_withdraw_message_to_external(
 ) -> Hash {
 
     let src_timelock_address = src_vta.get_timelock_address(
-        &vm.get_mint(),
+        &src_vta.get_mint(&vm.get_mint()),
         &vm.get_authority(),
-        vm.get_lock_duration(),
+        src_vta.get_lock_duration(vm.get_lock_duration()),
     );
     let src_token_address = src_vta.get_token_address(
         &src_timelock_address,
@@ -1,8 +1,41 @@
 use steel::*;
 
+use crate::cvm::{CodeVmAccount, TokenProgram, VmMintAccount};
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
 pub struct TokenPool {
     pub vault: Pubkey,
     pub vault_bump: u8,
 }
+
+/// The mint and omnibus backing a virtual timelock account. This is the VM
+/// mint and omnibus, unless the account holds a mint registered with
+/// `InitMintIx`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MintPool {
+    pub mint: Pubkey,
+    pub token_program: TokenProgram,
+    pub omnibus: TokenPool,
+    pub is_registered: bool,
+}
+
+impl MintPool {
+    pub fn from_vm(vm: &CodeVmAccount) -> Self {
+        Self {
+            mint: vm.get_mint(),
+            token_program: vm.get_token_program(),
+            omnibus: vm.omnibus,
+            is_registered: false,
+        }
+    }
+
+    pub fn from_vm_mint(vm_mint: &VmMintAccount) -> Self {
+        Self {
+            mint: vm_mint.get_mint(),
+            token_program: vm_mint.get_token_program(),
+            omnibus: vm_mint.omnibus,
+            is_registered: true,
+        }
+    }
+}
@@ -0,0 +1,31 @@
+use steel::*;
+use crate::types::Hash;
+
+/// Records that a deposit intent was used, so the same signed intent can't
+/// pull in tokens twice. See `DepositWithIntentIx`. Once the intent has
+/// expired it can't be used anyway, so the receipt can be closed with
+/// `CloseDepositReceiptIx`.
+#[repr(C, align(8))]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct DepositReceiptAccount {
+    pub vm: Pubkey,
+    pub owner: Pubkey,  // The owner of the source token account
+    pub source: Pubkey, // The token account the deposit came from
+    pub nonce: Hash,
+    pub amount: u64,
+    pub expires_at: i64, // The unix timestamp the intent expires at
+    pub bump: u8,
+
+    _padding: [u8; 7],
+}
+
+impl DepositReceiptAccount {
+    pub const fn get_size() -> usize {
+        8 + std::mem::size_of::<Self>()
+    }
+
+    pub fn unpack(data: &[u8]) -> Self {
+        let data = &data[..Self::get_size()];
+        *Self::try_from_bytes(data).unwrap()
+    }
+}
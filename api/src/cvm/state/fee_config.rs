@@ -0,0 +1,97 @@
+use steel::*;
+
+use crate::consts::*;
+
+/// Where the protocol fee is paid to.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive)]
+pub enum FeeRecipient {
+    /// A virtual timelock account, identified by its token address.
+    Virtual = 0,
+
+    /// A real token account, paid out of the VM omnibus.
+    External,
+}
+
+/// The protocol fee charged by a VM on `TransferOp`, `ExternalTransferOp`
+/// and `AirdropOp`. The fee is paid by the source, on top of the amount, and
+/// is included in the signed message.
+#[repr(C, align(8))]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct FeeConfigAccount {
+    pub vm: Pubkey,
+    pub recipient: Pubkey,
+    pub minimum: u64,
+    pub bps: u16,
+    pub recipient_kind: u8, // see FeeRecipient
+    pub bump: u8,
+
+    _padding: [u8; 4],
+}
+
+impl FeeConfigAccount {
+    pub const fn get_size() -> usize {
+        8 + std::mem::size_of::<Self>()
+    }
+
+    pub fn unpack(data: &[u8]) -> Self {
+        let data = &data[..Self::get_size()];
+        *Self::try_from_bytes(data).unwrap()
+    }
+
+    /// Fees are turned off by setting both the bps and the minimum to zero.
+    pub fn is_enabled(&self) -> bool {
+        self.bps > 0 || self.minimum > 0
+    }
+
+    pub fn get_recipient_kind(&self) -> Option<FeeRecipient> {
+        FeeRecipient::try_from(self.recipient_kind).ok()
+    }
+
+    /// The fee owed on `amount`, which is `bps` of the amount (rounded
+    /// down), but no less than `minimum`.
+    pub fn get_fee(&self, amount: u64) -> Option<u64> {
+        let fee = (amount as u128)
+            .checked_mul(self.bps as u128)?
+            .checked_div(MAX_FEE_BPS as u128)?;
+
+        let fee = u64::try_from(fee).ok()?;
+
+        Some(fee.max(self.minimum))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_fee() {
+        let mut config = FeeConfigAccount::zeroed();
+        assert_eq!(config.get_fee(1_000_000), Some(0));
+
+        config.bps = 25; // 0.25%
+        assert_eq!(config.get_fee(1_000_000), Some(2_500));
+        assert_eq!(config.get_fee(399), Some(0));
+
+        config.minimum = 10;
+        assert_eq!(config.get_fee(399), Some(10));
+        assert_eq!(config.get_fee(1_000_000), Some(2_500));
+
+        config.bps = MAX_FEE_BPS;
+        assert_eq!(config.get_fee(u64::MAX), Some(u64::MAX));
+    }
+
+    #[test]
+    fn test_is_enabled() {
+        let mut config = FeeConfigAccount::zeroed();
+        assert!(!config.is_enabled());
+
+        config.minimum = 1;
+        assert!(config.is_enabled());
+
+        config.minimum = 0;
+        config.bps = 1;
+        assert!(config.is_enabled());
+    }
+}
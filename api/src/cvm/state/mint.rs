@@ -0,0 +1,44 @@
+use steel::*;
+
+use crate::cvm::{TokenPool, TokenProgram};
+
+/// A mint registered with a VM using `InitMintIx`, in addition to the VM
+/// mint. Each registered mint has its own omnibus, and virtual timelock
+/// accounts for it record the mint they hold.
+#[repr(C, align(8))]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct VmMintAccount {
+    pub vm: Pubkey,
+    pub mint: Pubkey,
+    pub omnibus: TokenPool,
+    pub token_program: u8, // see TokenProgram
+    pub bump: u8,
+
+    _padding: [u8; 5],
+}
+
+impl VmMintAccount {
+    pub const fn get_size() -> usize {
+        8 + std::mem::size_of::<Self>()
+    }
+
+    pub fn unpack(data: &[u8]) -> Self {
+        let data = &data[..Self::get_size()];
+        *Self::try_from_bytes(data).unwrap()
+    }
+
+    #[inline]
+    pub fn get_mint(&self) -> Pubkey {
+        self.mint
+    }
+
+    #[inline]
+    pub fn get_omnibus_bump(&self) -> u8 {
+        self.omnibus.vault_bump
+    }
+
+    #[inline]
+    pub fn get_token_program(&self) -> TokenProgram {
+        TokenProgram::try_from(self.token_program).unwrap_or(TokenProgram::Token)
+    }
+}
@@ -1,13 +1,23 @@
+mod deposit;
+mod fee_config;
 mod memory;
+mod mint;
 mod storage;
 mod relay;
+mod relay_nullifier;
 mod vm;
 mod unlock;
+mod velocity_limit;
 mod withdraw;
 
+pub use deposit::*;
+pub use fee_config::*;
 pub use memory::*;
+pub use mint::*;
 pub use storage::*;
 pub use relay::*;
+pub use relay_nullifier::*;
 pub use vm::*;
 pub use unlock::*;
+pub use velocity_limit::*;
 pub use withdraw::*;
\ No newline at end of file
@@ -0,0 +1,171 @@
+use steel::*;
+
+use crate::consts::*;
+use crate::helpers::check_condition;
+use crate::types::{BloomFilter, CircularBuffer, Hash};
+
+/// Tracks the commitments already used by a relay, so that a `RelayOp` or
+/// `ExternalRelayOp` can't be replayed with the same commitment.
+///
+/// This account is kept separate from the relay (and is not closed with it)
+/// so that commitments can't be re-used against a re-created relay either.
+///
+/// Commitments are recorded in two generations of bloom filters. Once the
+/// `current` generation holds `RELAY_NULLIFIER_CAPACITY` commitments it
+/// becomes the `previous` one, and the oldest generation is dropped. A
+/// commitment is bound to a recent root of the relay, so rather than
+/// remembering the dropped commitments, the roots they could have used are
+/// refused: only roots saved after `cutoff_root` are accepted. This requires a
+/// snapshot of the relay between rotations.
+///
+/// The capacity keeps false positives near 1 in 100k per payment. A false
+/// positive only refuses the payment, which can be made again against another
+/// recent root.
+#[repr(C, align(8))]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct RelayNullifierAccount {
+    pub relay: Pubkey,
+    pub num_items: u64, // commitments in the current generation
+    pub bump: u8,
+
+    _padding: [u8; 7],
+
+    pub start_root: Hash,  // latest relay root when `current` was started
+    pub cutoff_root: Hash, // latest relay root when `previous` was started
+
+    pub current: BloomFilter<{RELAY_NULLIFIER_BYTES}>,
+    pub previous: BloomFilter<{RELAY_NULLIFIER_BYTES}>,
+}
+
+impl RelayNullifierAccount {
+    pub const fn get_size() -> usize {
+        8 + std::mem::size_of::<Self>()
+    }
+
+    pub fn is_spent(&self, commitment: &Pubkey) -> bool {
+        let val = commitment.to_bytes();
+        self.current.contains(&val) || self.previous.contains(&val)
+    }
+
+    /// Returns true if commitments made against `recent_root` may have been
+    /// recorded in a generation that was already dropped.
+    pub fn is_expired(
+        &self,
+        recent_root: &Hash,
+        recent_roots: &CircularBuffer<{RELAY_HISTORY_ITEMS}, {Hash::LEN}>,
+    ) -> bool {
+        if self.cutoff_root == Hash::default() {
+            return false;
+        }
+
+        let cutoff = recent_roots.find_index(self.cutoff_root.as_ref());
+        let root = recent_roots.find_index(recent_root.as_ref());
+
+        match (cutoff, root) {
+            (Some(cutoff), Some(root)) => root <= cutoff,
+            // The cutoff is no longer a recent root, so all of them are newer
+            _ => false,
+        }
+    }
+
+    pub fn try_spend(
+        &mut self,
+        commitment: &Pubkey,
+        recent_root: &Hash,
+        recent_roots: &CircularBuffer<{RELAY_HISTORY_ITEMS}, {Hash::LEN}>,
+    ) -> ProgramResult {
+        if self.num_items >= RELAY_NULLIFIER_CAPACITY {
+            self.rotate(recent_roots);
+        }
+
+        check_condition(
+            !self.is_expired(recent_root, recent_roots),
+            "the recent_root is too old for the relay nullifier, a newer root is required",
+        )?;
+
+        check_condition(
+            !self.is_spent(commitment),
+            "the commitment has already been used",
+        )?;
+
+        self.current.insert(&commitment.to_bytes());
+        self.num_items += 1;
+
+        Ok(())
+    }
+
+    fn rotate(&mut self, recent_roots: &CircularBuffer<{RELAY_HISTORY_ITEMS}, {Hash::LEN}>) {
+        let latest = recent_roots.last().copied().unwrap_or_default();
+
+        self.previous = self.current;
+        self.current = BloomFilter::new();
+        self.cutoff_root = self.start_root;
+        self.start_root = latest.into();
+        self.num_items = 0;
+    }
+
+    pub fn unpack(data: &[u8]) -> Self {
+        let data = &data[..Self::get_size()];
+        *Self::try_from_bytes(data).unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash;
+
+    fn root(i: u32) -> Hash {
+        hash(&i.to_le_bytes())
+    }
+
+    fn commitment(i: u64) -> Pubkey {
+        Pubkey::new_from_array(hash(&i.to_le_bytes()).to_bytes())
+    }
+
+    #[test]
+    fn test_rejects_spent_commitment() {
+        let mut nullifier = RelayNullifierAccount::zeroed();
+        let mut roots = CircularBuffer::new();
+        roots.push(root(0).as_ref());
+
+        assert!(nullifier.try_spend(&commitment(0), &root(0), &roots).is_ok());
+        assert!(nullifier.try_spend(&commitment(0), &root(0), &roots).is_err());
+        assert_eq!(nullifier.num_items, 1);
+    }
+
+    #[test]
+    fn test_rotation_refuses_dropped_roots() {
+        let mut nullifier = RelayNullifierAccount::zeroed();
+        let mut roots = CircularBuffer::new();
+        let capacity = RELAY_NULLIFIER_CAPACITY;
+
+        // Generation 0, all made against root 0
+        roots.push(root(0).as_ref());
+        for i in 0..capacity {
+            nullifier.try_spend(&commitment(i), &root(0), &roots).unwrap();
+        }
+
+        // Generation 1 starts at root 1; generation 0 is still checked
+        roots.push(root(1).as_ref());
+        for i in capacity..capacity * 2 {
+            nullifier.try_spend(&commitment(i), &root(1), &roots).unwrap();
+        }
+        assert_eq!(nullifier.start_root, root(1));
+        assert!(nullifier.try_spend(&commitment(0), &root(0), &roots).is_err());
+
+        // Generation 0 gets dropped, so root 0 can no longer be used
+        roots.push(root(2).as_ref());
+        assert!(nullifier.try_spend(&commitment(capacity * 2), &root(2), &roots).is_ok());
+        assert_eq!(nullifier.cutoff_root, root(1));
+        assert_eq!(nullifier.num_items, 1);
+
+        assert!(nullifier.is_expired(&root(0), &roots));
+        assert!(nullifier.is_expired(&root(1), &roots));
+        assert!(!nullifier.is_expired(&root(2), &roots));
+        assert!(nullifier.try_spend(&commitment(0), &root(0), &roots).is_err());
+
+        // Generation 1 is still checked
+        assert!(nullifier.try_spend(&commitment(capacity), &root(2), &roots).is_err());
+    }
+}
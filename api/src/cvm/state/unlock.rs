@@ -17,8 +17,9 @@ pub struct UnlockStateAccount {
     pub unlock_at: i64,
     pub bump: u8,
     pub state: u8,
+    pub has_receipt: u8, // set once a withdraw receipt is created
 
-    _padding: [u8; 6],
+    _padding: [u8; 5],
 }
 
 impl UnlockStateAccount {
@@ -44,4 +45,8 @@ impl UnlockStateAccount {
         self.state == TimelockState::WaitingForTimeout as u8
     }
 
+    pub fn has_receipt(&self) -> bool {
+        self.has_receipt != 0
+    }
+
 }
\ No newline at end of file
@@ -0,0 +1,186 @@
+use steel::*;
+
+use crate::consts::VELOCITY_BUCKETS;
+
+/// Caps how much an owner's virtual accounts can send in any window of time.
+///
+/// Each owner has their own record per mint, which tracks their recent usage
+/// in units of that mint. Records of the VM mint have a zero `mint`. The VM
+/// default is a record of the VM mint with `Pubkey::default()` as the owner;
+/// owners with a zero `window` use the limits of the VM default, or aren't
+/// limited for registered mints, which have no default.
+///
+/// The window is a rolling one. Usage is recorded in `VELOCITY_BUCKETS`
+/// buckets that each cover part of the window, and a bucket counts in full
+/// for as long as any part of it is in the window. So no more than `amount`
+/// can be sent in any `window` seconds, and usage is freed at most one bucket
+/// length after it leaves the window.
+#[repr(C, align(8))]
+#[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
+pub struct VelocityLimitAccount {
+    pub vm: Pubkey,
+    pub owner: Pubkey,
+    pub mint: Pubkey,      // a mint registered with the VM, zero for the VM mint
+    pub amount: u64,       // The most that can be sent per window, in quarks
+    pub window: i64,       // The window length, in seconds
+    pub bucket_len: i64,   // The bucket length the buckets were recorded with
+    pub last_bucket: i64,  // The newest bucket, as a count of bucket_len since the epoch
+    pub buckets: [u64; VELOCITY_BUCKETS], // The amount sent in each recent bucket
+    pub bump: u8,
+
+    _padding: [u8; 7],
+}
+
+impl VelocityLimitAccount {
+    pub const fn get_size() -> usize {
+        8 + std::mem::size_of::<Self>()
+    }
+
+    pub fn unpack(data: &[u8]) -> Self {
+        let data = &data[..Self::get_size()];
+        *Self::try_from_bytes(data).unwrap()
+    }
+
+    pub fn is_default(&self) -> bool {
+        self.owner.eq(&Pubkey::default())
+    }
+
+    pub fn has_mint(&self) -> bool {
+        self.mint.ne(&Pubkey::default())
+    }
+
+    pub fn has_limit(&self) -> bool {
+        self.window > 0
+    }
+
+    /// The bucket length for `window`, chosen so that a window overlaps at
+    /// most `VELOCITY_BUCKETS` buckets.
+    pub fn get_bucket_len(window: i64) -> i64 {
+        let n = VELOCITY_BUCKETS as i64 - 1;
+        (window.max(1) + n - 1) / n
+    }
+
+    /// The amount sent in the window that ends at `now`.
+    pub fn get_used(&self, window: i64, now: i64) -> u64 {
+        if self.bucket_len == 0 {
+            return 0;
+        }
+
+        let first = now.saturating_sub(window).div_euclid(self.bucket_len);
+        let oldest = self.last_bucket - (VELOCITY_BUCKETS as i64 - 1);
+
+        (first.max(oldest)..=self.last_bucket)
+            .map(|bucket| self.buckets[Self::get_slot(bucket)])
+            .fold(0u64, |total, used| total.saturating_add(used))
+    }
+
+    /// How much more can be sent at `now` under the given limit.
+    pub fn get_remaining(&self, amount: u64, window: i64, now: i64) -> u64 {
+        amount.saturating_sub(self.get_used(window, now))
+    }
+
+    /// Records `value` as sent at `now`.
+    pub fn record(&mut self, window: i64, value: u64, now: i64) -> Option<()> {
+        let bucket_len = Self::get_bucket_len(window);
+
+        if self.bucket_len != bucket_len {
+            // The window changed, so keep the usage that is still in it as
+            // if it was all sent now
+            let used = self.get_used(window, now);
+
+            self.buckets = [0; VELOCITY_BUCKETS];
+            self.bucket_len = bucket_len;
+            self.last_bucket = now.div_euclid(bucket_len);
+            self.buckets[Self::get_slot(self.last_bucket)] = used;
+        }
+
+        let bucket = now.div_euclid(bucket_len);
+
+        if bucket > self.last_bucket {
+            let start = self.last_bucket
+                .saturating_add(1)
+                .max(bucket - (VELOCITY_BUCKETS as i64 - 1));
+
+            for old in start..=bucket {
+                self.buckets[Self::get_slot(old)] = 0;
+            }
+            self.last_bucket = bucket;
+        }
+
+        let slot = Self::get_slot(self.last_bucket);
+        self.buckets[slot] = self.buckets[slot].checked_add(value)?;
+        Some(())
+    }
+
+    fn get_slot(bucket: i64) -> usize {
+        bucket.rem_euclid(VELOCITY_BUCKETS as i64) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_velocity_window() {
+        let mut limit = VelocityLimitAccount::zeroed();
+        limit.amount = 100;
+        limit.window = 70; // buckets of 10 seconds
+        assert!(limit.has_limit());
+        assert_eq!(VelocityLimitAccount::get_bucket_len(70), 10);
+
+        assert_eq!(limit.get_remaining(100, 70, 1_000), 100);
+        limit.record(70, 70, 1_000).unwrap();
+        assert_eq!(limit.get_remaining(100, 70, 1_030), 30);
+
+        limit.record(70, 30, 1_069).unwrap();
+        assert_eq!(limit.get_remaining(100, 70, 1_069), 0);
+
+        // The first 70 leaves the window, but not the 30 sent at 1_069
+        assert_eq!(limit.get_remaining(100, 70, 1_080), 70);
+        limit.record(70, 70, 1_080).unwrap();
+        assert_eq!(limit.get_remaining(100, 70, 1_080), 0);
+
+        // Everything is freed once a window and a bucket have passed
+        assert_eq!(limit.get_remaining(100, 70, 1_160), 100);
+    }
+
+    #[test]
+    fn test_velocity_window_is_rolling() {
+        let mut limit = VelocityLimitAccount::zeroed();
+
+        // Sending the limit right before a fixed window would reset doesn't
+        // allow sending it again right after
+        limit.record(60, 100, 1_059).unwrap();
+        assert_eq!(limit.get_remaining(100, 60, 1_060), 0);
+        assert_eq!(limit.get_remaining(100, 60, 1_118), 0);
+
+        // Never more than the limit in any window
+        let mut sent = vec![];
+        let mut limit = VelocityLimitAccount::zeroed();
+        for now in (0..1_000).step_by(7) {
+            let value = limit.get_remaining(100, 60, now).min(13);
+            limit.record(60, value, now).unwrap();
+            sent.push((now, value));
+        }
+        for (start, _) in &sent {
+            let total: u64 = sent.iter()
+                .filter(|(t, _)| *t > start - 60 && *t <= *start)
+                .map(|(_, value)| value)
+                .sum();
+            assert!(total <= 100);
+        }
+    }
+
+    #[test]
+    fn test_velocity_window_changed() {
+        let mut limit = VelocityLimitAccount::zeroed();
+        limit.record(60, 80, 1_000).unwrap();
+
+        // Usage still in the window is kept when the window changes
+        assert_eq!(limit.get_remaining(100, 3_600, 1_010), 20);
+        limit.record(3_600, 10, 1_010).unwrap();
+        assert_eq!(limit.get_remaining(100, 3_600, 1_010), 10);
+        assert_eq!(limit.get_remaining(100, 3_600, 5_000), 100);
+    }
+}
@@ -7,6 +7,71 @@ use crate::{
     utils
 };
 
+/// Which signed message versions a VM accepts. Existing VMs have this byte
+/// zeroed, so they default to v1 only.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive)]
+pub enum MessagePolicy {
+    V1Only = 0,
+    V1AndV2,
+    V2Only,
+}
+
+impl MessagePolicy {
+    pub fn accepts_v1(&self) -> bool {
+        matches!(self, MessagePolicy::V1Only | MessagePolicy::V1AndV2)
+    }
+
+    pub fn accepts_v2(&self) -> bool {
+        matches!(self, MessagePolicy::V1AndV2 | MessagePolicy::V2Only)
+    }
+}
+
+/// How a VM verifies opcode signatures. Existing VMs have this byte zeroed,
+/// so they keep using the curve25519 syscalls.
+///
+/// With `Precompile`, the transaction must carry an Ed25519 program
+/// instruction for each signature, and the opcode only checks that it's
+/// there through the instructions sysvar. This costs transaction space, but
+/// far fewer CUs, and relies on the runtime's audited verification.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive)]
+pub enum SignatureMode {
+    Syscall = 0,
+    Precompile,
+}
+
+/// The token program of the VM's mint, chosen at `InitVmIx`. Existing VMs
+/// have this byte zeroed, so they use the SPL Token program.
+///
+/// Token-2022 VMs need the mint passed in to every instruction that moves
+/// tokens, so that transfers can go through `TransferChecked`.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromPrimitive)]
+pub enum TokenProgram {
+    Token = 0,
+    Token2022,
+}
+
+impl TokenProgram {
+    pub fn id(&self) -> Pubkey {
+        match self {
+            TokenProgram::Token => spl_token::id(),
+            TokenProgram::Token2022 => spl_token_2022::id(),
+        }
+    }
+
+    pub fn from_id(program_id: &Pubkey) -> Option<Self> {
+        if program_id.eq(&spl_token::id()) {
+            Some(TokenProgram::Token)
+        } else if program_id.eq(&spl_token_2022::id()) {
+            Some(TokenProgram::Token2022)
+        } else {
+            None
+        }
+    }
+}
+
 #[repr(C, align(8))]
 #[derive(Clone, Copy, Debug, PartialEq, Pod, Zeroable)]
 pub struct CodeVmAccount {
@@ -17,8 +82,11 @@ pub struct CodeVmAccount {
     pub omnibus: TokenPool,
     pub lock_duration: u8,  // in days
     pub bump: u8,
-
-    _padding: [u8; 5],
+    pub message_policy: u8, // see MessagePolicy
+    pub signature_mode: u8, // see SignatureMode
+    pub token_program: u8,  // see TokenProgram
+    pub velocity_default: u8, // 1 when the VM default velocity limit is on
+    pub velocity_limits: u8,  // 1 once any velocity limit has been set
 }
 
 impl CodeVmAccount {
@@ -96,5 +164,34 @@ impl CodeVmAccount {
         self.slot
     }
 
+    #[inline]
+    pub fn get_message_policy(&self) -> MessagePolicy {
+        MessagePolicy::try_from(self.message_policy).unwrap_or(MessagePolicy::V1Only)
+    }
+
+    #[inline]
+    pub fn get_signature_mode(&self) -> SignatureMode {
+        SignatureMode::try_from(self.signature_mode).unwrap_or(SignatureMode::Syscall)
+    }
+
+    #[inline]
+    pub fn get_token_program(&self) -> TokenProgram {
+        TokenProgram::try_from(self.token_program).unwrap_or(TokenProgram::Token)
+    }
+
+    /// When the VM default velocity limit is on, every owner's transfers
+    /// are limited, so their velocity limit record is required.
+    #[inline]
+    pub fn has_velocity_default(&self) -> bool {
+        self.velocity_default != 0
+    }
+
+    /// Once any velocity limit has been set, every transfer out of a virtual
+    /// account needs the velocity limit record of its owner.
+    #[inline]
+    pub fn has_velocity_limits(&self) -> bool {
+        self.velocity_limits != 0
+    }
+
 }
 
@@ -10,4 +10,15 @@ pub struct WithdrawReceiptAccount {
     pub bump: u8,
 
     _padding: [u8; 7],
-}
\ No newline at end of file
+}
+
+impl WithdrawReceiptAccount {
+    pub const fn get_size() -> usize {
+        8 + std::mem::size_of::<Self>()
+    }
+
+    pub fn unpack(data: &[u8]) -> Self {
+        let data = &data[..Self::get_size()];
+        *Self::try_from_bytes(data).unwrap()
+    }
+}
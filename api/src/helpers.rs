@@ -1,11 +1,15 @@
 use steel::*;
 use solana_program::msg;
+use spl_token_2022::extension::{BaseStateWithExtensions, ExtensionType};
 
 use crate::{
     consts::*, 
     cvm::{
-        CodeVmAccount, MemoryAccount, RelayAccount, StorageAccount, VirtualAccount 
+        CodeVmAccount, FeeConfigAccount, FeeRecipient, MemoryAccount, RelayAccount, RelayNullifierAccount, StorageAccount,
+        MintPool, VelocityLimitAccount, VirtualAccount, VirtualMultisigAccount, VirtualTimelockAccount,
+        VmMintAccount 
     },
+    pdas::find_virtual_multisig_pda,
     types::{Hash, SliceAllocator, SliceAllocatorMut},
 };
 
@@ -44,6 +48,57 @@ pub fn check_signer(account: &AccountInfo) -> ProgramResult {
     Ok(())
 }
 
+/// Checks that the owner of a timelock account approved the instruction, and
+/// returns the instruction data without the multisig config.
+///
+/// A multisig address can't sign, so a timelock account owned by a multisig
+/// is approved by `threshold` of the multisig signers instead. The signer keys
+/// are given, in order, as the last accounts of the instruction, and the
+/// threshold and number of signers are the last two bytes of the instruction
+/// data (see `with_multisig_signers`), so the multisig address is derived
+/// once from them.
+pub fn check_owner_signer<'a>(
+    owner_info: &AccountInfo,
+    vm_info: &AccountInfo,
+    accounts: &[AccountInfo],
+    data: &'a [u8],
+) -> Result<&'a [u8], ProgramError> {
+    if owner_info.is_signer {
+        check_signer(owner_info)?;
+        return Ok(data);
+    }
+
+    let [data @ .., threshold, signer_count] = data else {
+        return Err(ProgramError::MissingRequiredSignature);
+    };
+
+    let signer_count = *signer_count as usize;
+    if signer_count > accounts.len().min(MAX_MULTISIG_SIGNERS) {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let signer_infos = &accounts[accounts.len() - signer_count..];
+    let keys: Vec<Pubkey> = signer_infos.iter().map(|info| *info.key).collect();
+
+    let config = VirtualMultisigAccount::get_config_hash(*threshold, &keys);
+    let (address, _) = find_virtual_multisig_pda(vm_info.key, &config);
+
+    if address.ne(owner_info.key) {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let approvals = signer_infos.iter()
+        .filter(|info| info.is_signer)
+        .count();
+
+    check_condition(
+        approvals >= *threshold as usize,
+        "not enough multisig signers approved the instruction",
+    )?;
+
+    Ok(data)
+}
+
 pub fn check_mut(account: &AccountInfo) -> ProgramResult {
     account.is_writable()?;
     Ok(())
@@ -262,6 +317,313 @@ pub fn check_relay(
     Ok(())
 }
 
+pub fn load_relay_nullifier<'a>(
+    relay_nullifier_info: &'a AccountInfo<'_>,
+    relay_info: &'a AccountInfo<'_>
+) -> Result<&'a mut RelayNullifierAccount, ProgramError> {
+    let nullifier = 
+        relay_nullifier_info.to_account_mut::<RelayNullifierAccount>(&crate::ID)?;
+
+    check_seeds(
+        relay_nullifier_info, 
+        &[
+            CODE_VM, 
+            VM_RELAY_NULLIFIER,
+            relay_info.key.as_ref()
+        ],
+        nullifier.bump, 
+        &crate::ID
+    )?;
+
+    check_condition(
+        nullifier.relay.eq(relay_info.key),
+        "relay does not match the relay nullifier account",
+    )?;
+
+    Ok(nullifier)
+}
+
+pub fn load_fee_config<'a>(
+    fee_config_info: &'a AccountInfo<'_>,
+    vm_info: &'a AccountInfo<'_>
+) -> Result<&'a FeeConfigAccount, ProgramError> {
+    let fee_config = 
+        fee_config_info.to_account::<FeeConfigAccount>(&crate::ID)?;
+
+    check_seeds(
+        fee_config_info, 
+        &[
+            CODE_VM, 
+            VM_FEE_CONFIG,
+            vm_info.key.as_ref()
+        ],
+        fee_config.bump, 
+        &crate::ID
+    )?;
+
+    check_condition(
+        fee_config.vm.eq(vm_info.key),
+        "vm does not match the fee config account",
+    )?;
+
+    Ok(fee_config)
+}
+
+/// Whether an account looks like a fee config. Used to pick it out of the
+/// trailing accounts of an instruction; `load_fee_config` does the real
+/// checks.
+pub fn is_fee_config(account: &AccountInfo<'_>) -> bool {
+    if account.owner.ne(&crate::ID) {
+        return false;
+    }
+
+    match account.try_borrow_data() {
+        Ok(data) => data.first() == Some(&(FeeConfigAccount::discriminator())),
+        Err(_) => false,
+    }
+}
+
+/// Returns true for a fee config that pays fees to an external token account,
+/// which then has to follow it in the account list.
+pub fn has_fee_account(fee_config_info: &AccountInfo<'_>) -> bool {
+    match fee_config_info.try_borrow_data() {
+        Ok(data) => FeeConfigAccount::try_from_bytes(&data)
+            .map(|config| config.get_recipient_kind() == Some(FeeRecipient::External))
+            .unwrap_or(false),
+        Err(_) => false,
+    }
+}
+
+pub fn load_velocity_limit<'a>(
+    velocity_limit_info: &'a AccountInfo<'_>,
+    vm_info: &'a AccountInfo<'_>
+) -> Result<&'a VelocityLimitAccount, ProgramError> {
+    let velocity_limit = 
+        velocity_limit_info.to_account::<VelocityLimitAccount>(&crate::ID)?;
+
+    let mut seeds = vec![
+        CODE_VM, 
+        VM_VELOCITY_LIMIT,
+        vm_info.key.as_ref(),
+        velocity_limit.owner.as_ref()
+    ];
+    if velocity_limit.has_mint() {
+        seeds.push(velocity_limit.mint.as_ref());
+    }
+
+    check_seeds(
+        velocity_limit_info, 
+        &seeds,
+        velocity_limit.bump, 
+        &crate::ID
+    )?;
+
+    check_condition(
+        velocity_limit.vm.eq(vm_info.key),
+        "vm does not match the velocity limit account",
+    )?;
+
+    Ok(velocity_limit)
+}
+
+/// Whether an account looks like a velocity limit, see `is_fee_config`.
+pub fn is_velocity_limit(account: &AccountInfo<'_>) -> bool {
+    if account.owner.ne(&crate::ID) {
+        return false;
+    }
+
+    match account.try_borrow_data() {
+        Ok(data) => data.first() == Some(&(VelocityLimitAccount::discriminator())),
+        Err(_) => false,
+    }
+}
+
+pub fn load_vm_mint<'a>(
+    vm_mint_info: &'a AccountInfo<'_>,
+    vm_info: &'a AccountInfo<'_>
+) -> Result<&'a VmMintAccount, ProgramError> {
+    let vm_mint = 
+        vm_mint_info.to_account::<VmMintAccount>(&crate::ID)?;
+
+    check_seeds(
+        vm_mint_info, 
+        &[
+            CODE_VM, 
+            VM_MINT,
+            vm_info.key.as_ref(),
+            vm_mint.mint.as_ref()
+        ],
+        vm_mint.bump, 
+        &crate::ID
+    )?;
+
+    check_condition(
+        vm_mint.vm.eq(vm_info.key),
+        "vm does not match the registered mint account",
+    )?;
+
+    Ok(vm_mint)
+}
+
+/// Whether an account looks like a registered mint, see `is_fee_config`.
+pub fn is_vm_mint(account: &AccountInfo<'_>) -> bool {
+    if account.owner.ne(&crate::ID) {
+        return false;
+    }
+
+    match account.try_borrow_data() {
+        Ok(data) => data.first() == Some(&(VmMintAccount::discriminator())),
+        Err(_) => false,
+    }
+}
+
+/// Loads the registered mint account from `accounts`, if there is one.
+pub fn find_vm_mint<'a>(
+    vm_info: &'a AccountInfo<'_>,
+    accounts: &'a [AccountInfo<'_>],
+) -> Result<Option<&'a VmMintAccount>, ProgramError> {
+    match accounts.iter().find(|account| is_vm_mint(account)) {
+        Some(vm_mint_info) => Ok(Some(load_vm_mint(vm_mint_info, vm_info)?)),
+        None => Ok(None),
+    }
+}
+
+/// The pool backing accounts that hold `mint`, where `Pubkey::default()` is
+/// the VM mint. Registered mints need their `VmMintAccount`.
+pub fn get_mint_pool(
+    vm: &CodeVmAccount,
+    vm_mint: Option<&VmMintAccount>,
+    mint: &Pubkey,
+) -> Result<MintPool, ProgramError> {
+    if mint.eq(&Pubkey::default()) {
+        return Ok(MintPool::from_vm(vm));
+    }
+
+    check_condition(
+        vm_mint.is_some(),
+        "the registered mint account must be provided",
+    )?;
+
+    let vm_mint = vm_mint.unwrap();
+
+    check_condition(
+        vm_mint.mint.eq(mint),
+        "the registered mint account is for a different mint",
+    )?;
+
+    Ok(MintPool::from_vm_mint(vm_mint))
+}
+
+/// Checks that `omnibus_info` is the omnibus of `pool`.
+pub fn check_pool_omnibus(
+    omnibus_info: &AccountInfo<'_>,
+    pool: &MintPool,
+) -> ProgramResult {
+    check_condition(
+        omnibus_info.key.eq(&pool.omnibus.vault),
+        "omnibus does not match the mint pool",
+    )
+}
+
+/// Checks that two virtual timelock accounts hold the same mint, which
+/// every opcode that moves balances between them requires.
+pub fn check_same_mint(
+    src_vta: &VirtualTimelockAccount,
+    dst_vta: &VirtualTimelockAccount,
+) -> ProgramResult {
+    check_condition(
+        src_vta.mint.eq(&dst_vta.mint),
+        "the source and destination accounts hold different mints",
+    )
+}
+
+/// Checks that a virtual timelock account holds the VM mint. Relays, swaps
+/// and vesting only support the VM mint.
+pub fn check_holds_vm_mint(vta: &VirtualTimelockAccount) -> ProgramResult {
+    check_condition(
+        !vta.has_mint(),
+        "only accounts for the VM mint are supported",
+    )
+}
+
+/// The Token-2022 mint extensions the VM supports. Others are rejected, since
+/// they either let someone other than the VM move or freeze the omnibus
+/// tokens (e.g. a permanent delegate, a frozen default account state or a
+/// pausable mint), or need accounts and instructions the VM doesn't pass to
+/// the token program (e.g. transfer hooks and confidential transfers).
+const SUPPORTED_MINT_EXTENSIONS: [ExtensionType; 5] = [
+    ExtensionType::TransferFeeConfig,
+    ExtensionType::InterestBearingConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+    ExtensionType::MintCloseAuthority,
+];
+
+/// Checks that the VM can move tokens of `mint_info`, which must only use
+/// the extensions in `SUPPORTED_MINT_EXTENSIONS`.
+pub fn check_supported_mint(mint_info: &AccountInfo<'_>) -> ProgramResult {
+    let data = mint_info.try_borrow_data()?;
+    let mint = spl_token_2022::extension::StateWithExtensions::<
+        spl_token_2022::state::Mint
+    >::unpack(&data)?;
+    let extensions = mint.get_extension_types()?;
+
+    check_condition(
+        extensions.iter().all(|extension| SUPPORTED_MINT_EXTENSIONS.contains(extension)),
+        "the mint uses an unsupported Token-2022 extension",
+    )
+}
+
+/// Checks that `token_program_info` is the token program of the VM's mint.
+pub fn check_token_program(
+    token_program_info: &AccountInfo<'_>,
+    vm: &CodeVmAccount,
+) -> ProgramResult {
+    check_program(token_program_info, &vm.get_token_program().id())
+}
+
+/// Returns `mint` if it's one of `accounts`. Instructions that move tokens
+/// take the mint as an optional extra account, which Token-2022 mints must
+/// provide.
+pub fn get_optional_mint<'a, 'info>(
+    mint: &Pubkey,
+    accounts: &'a [AccountInfo<'info>],
+) -> Option<&'a AccountInfo<'info>> {
+    accounts.iter().find(|account| account.key.eq(mint))
+}
+
+/// The balance of a token account of either token program.
+pub fn get_token_amount(token_account_info: &AccountInfo<'_>) -> Result<u64, ProgramError> {
+    check_condition(
+        token_account_info.owner.eq(&spl_token::id()) ||
+        token_account_info.owner.eq(&spl_token_2022::id()),
+        "the token account is not owned by a token program",
+    )?;
+
+    let data = token_account_info.try_borrow_data()?;
+    let account = spl_token_2022::extension::StateWithExtensions::<
+        spl_token_2022::state::Account
+    >::unpack(&data)?;
+
+    Ok(account.base.amount)
+}
+
+/// The owner of a token account of either token program.
+pub fn get_token_owner(token_account_info: &AccountInfo<'_>) -> Result<Pubkey, ProgramError> {
+    check_condition(
+        token_account_info.owner.eq(&spl_token::id()) ||
+        token_account_info.owner.eq(&spl_token_2022::id()),
+        "the token account is not owned by a token program",
+    )?;
+
+    let data = token_account_info.try_borrow_data()?;
+    let account = spl_token_2022::extension::StateWithExtensions::<
+        spl_token_2022::state::Account
+    >::unpack(&data)?;
+
+    Ok(account.base.owner)
+}
+
 pub fn check_omnibus(
     omnibus_info: &AccountInfo<'_>, 
     vm_info: &AccountInfo<'_>
@@ -29,6 +29,19 @@ pub enum CodeInstruction {
     TransferForSwapIx,
     CancelSwapIx,
     CloseSwapAccountIfEmptyIx,
+    CloseRelayIx,
+    InitRelayNullifierIx,
+    CancelUnlockIx,
+    InitVestingIx,
+    InitMultisigIx,
+    SetMessagePolicyIx,
+    SetSignatureModeIx,
+    SetFeeConfigIx,
+    SetVelocityLimitIx,
+    InitMintIx,
+    DepositWithIntentIx,
+    SweepDepositIx,
+    CloseDepositReceiptIx,
 }
 
 instruction!(CodeInstruction, InitVmIx);
@@ -50,6 +63,19 @@ instruction!(CodeInstruction, DepositWithAuthorityIx);
 instruction!(CodeInstruction, TransferForSwapIx);
 instruction!(CodeInstruction, CancelSwapIx);
 instruction!(CodeInstruction, CloseSwapAccountIfEmptyIx);
+instruction!(CodeInstruction, CloseRelayIx);
+instruction!(CodeInstruction, InitRelayNullifierIx);
+instruction!(CodeInstruction, CancelUnlockIx);
+instruction!(CodeInstruction, InitVestingIx);
+instruction!(CodeInstruction, InitMultisigIx);
+instruction!(CodeInstruction, SetMessagePolicyIx);
+instruction!(CodeInstruction, SetSignatureModeIx);
+instruction!(CodeInstruction, SetFeeConfigIx);
+instruction!(CodeInstruction, SetVelocityLimitIx);
+instruction!(CodeInstruction, InitMintIx);
+instruction!(CodeInstruction, DepositWithIntentIx);
+instruction!(CodeInstruction, SweepDepositIx);
+instruction!(CodeInstruction, CloseDepositReceiptIx);
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
@@ -192,6 +218,7 @@ pub struct InitTimelockIx {
     pub virtual_timelock_bump: u8,
     pub virtual_vault_bump: u8,
     pub unlock_pda_bump: u8,
+    pub lock_duration: u8,      // zero to use the VM lock duration
 }
 
 impl InitTimelockIx {
@@ -201,6 +228,7 @@ impl InitTimelockIx {
             virtual_timelock_bump: self.virtual_timelock_bump,
             virtual_vault_bump: self.virtual_vault_bump,
             unlock_pda_bump: self.unlock_pda_bump,
+            lock_duration: self.lock_duration,
         })
     }
 
@@ -210,6 +238,7 @@ impl InitTimelockIx {
             virtual_timelock_bump: parsed.virtual_timelock_bump,
             virtual_vault_bump: parsed.virtual_vault_bump,
             unlock_pda_bump: parsed.unlock_pda_bump,
+            lock_duration: parsed.lock_duration,
         }
     }
 }
@@ -219,6 +248,7 @@ pub struct ParsedInitTimelockIx {
     pub virtual_timelock_bump: u8,
     pub virtual_vault_bump: u8,
     pub unlock_pda_bump: u8,
+    pub lock_duration: u8,
 }
 
 #[repr(C)]
@@ -286,6 +316,7 @@ pub struct InitRelayIx {
     pub name: [u8; MAX_NAME_LEN],
     pub relay_bump: u8,
     pub relay_vault_bump: u8,
+    pub relay_nullifier_bump: u8,
 }
 
 #[repr(C)]
@@ -293,6 +324,17 @@ pub struct InitRelayIx {
 pub struct SnapshotIx { // SaveRecentRoot
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct CloseRelayIx {
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct InitRelayNullifierIx {
+    pub relay_nullifier_bump: u8,
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
 pub struct DepositFromPdaIx {
@@ -356,6 +398,7 @@ pub struct ParsedDepositWithAuthorityIx {
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
 pub struct InitUnlockIx {
+    pub lock_duration: u8,      // zero to use the VM lock duration
 }
 
 #[repr(C)]
@@ -363,6 +406,292 @@ pub struct InitUnlockIx {
 pub struct UnlockIx {
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct CancelUnlockIx {
+    _data: PhantomData<CancelUnlockIxData>,
+}
+
+impl CancelUnlockIx {
+    pub fn try_from_slice(data: &[u8]) -> Result<CancelUnlockIxData, std::io::Error> {
+        CancelUnlockIxData::try_from_slice(data)
+    }
+
+    pub fn try_to_bytes(args: CancelUnlockIxData) -> Result<Vec<u8>, std::io::Error> {
+        let discriminator = CodeInstruction::CancelUnlockIx as u8;
+        let data = args.try_to_vec()?;
+        let mut result = Vec::with_capacity(1 + data.len());
+        result.push(discriminator);
+        result.extend_from_slice(&data);
+        Ok(result)
+    }
+}
+
+#[repr(u8)]
+#[derive(BorshSerialize, BorshDeserialize, Clone, PartialEq, Debug)]
+pub enum CancelUnlockIxData {
+    FromMemory {
+        account_index: u16,
+    } = 0,
+    FromStorage {
+        packed_va: Vec<u8>,
+        proof: Vec<Hash>,
+        signature: Signature,
+    } = 1,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct InitVestingIx {
+    pub account_index: [u8; 2], // Pack u16 as [u8; 2]
+    pub total: [u8; 8],         // Pack u64 as [u8; 8]
+    pub start: [u8; 8],         // Pack i64 as [u8; 8]
+    pub cliff: [u8; 8],         // Pack i64 as [u8; 8]
+    pub end: [u8; 8],           // Pack i64 as [u8; 8]
+}
+
+impl InitVestingIx {
+    pub fn to_struct(&self) -> Result<ParsedInitVestingIx, std::io::Error> {
+        Ok(ParsedInitVestingIx {
+            account_index: u16::from_le_bytes(self.account_index),
+            total: u64::from_le_bytes(self.total),
+            start: i64::from_le_bytes(self.start),
+            cliff: i64::from_le_bytes(self.cliff),
+            end: i64::from_le_bytes(self.end),
+        })
+    }
+
+    pub fn from_struct(parsed: ParsedInitVestingIx) -> Self {
+        InitVestingIx {
+            account_index: parsed.account_index.to_le_bytes(),
+            total: parsed.total.to_le_bytes(),
+            start: parsed.start.to_le_bytes(),
+            cliff: parsed.cliff.to_le_bytes(),
+            end: parsed.end.to_le_bytes(),
+        }
+    }
+}
+
+pub struct ParsedInitVestingIx {
+    pub account_index: u16,
+    pub total: u64,
+    pub start: i64,
+    pub cliff: i64,
+    pub end: i64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct InitMultisigIx {
+    pub account_index: [u8; 2], // Pack u16 as [u8; 2]
+    pub threshold: u8,
+}
+
+impl InitMultisigIx {
+    pub fn to_struct(&self) -> Result<ParsedInitMultisigIx, std::io::Error> {
+        Ok(ParsedInitMultisigIx {
+            account_index: u16::from_le_bytes(self.account_index),
+            threshold: self.threshold,
+        })
+    }
+
+    pub fn from_struct(parsed: ParsedInitMultisigIx) -> Self {
+        InitMultisigIx {
+            account_index: parsed.account_index.to_le_bytes(),
+            threshold: parsed.threshold,
+        }
+    }
+}
+
+pub struct ParsedInitMultisigIx {
+    pub account_index: u16,
+    pub threshold: u8,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct SetMessagePolicyIx {
+    pub message_policy: u8,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct SetSignatureModeIx {
+    pub signature_mode: u8,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct SetFeeConfigIx {
+    pub recipient: Pubkey,
+    pub minimum: [u8; 8], // Pack u64 as [u8; 8]
+    pub bps: [u8; 2],     // Pack u16 as [u8; 2]
+    pub recipient_kind: u8,
+    pub fee_config_bump: u8,
+}
+
+impl SetFeeConfigIx {
+    pub fn to_struct(&self) -> Result<ParsedSetFeeConfigIx, std::io::Error> {
+        Ok(ParsedSetFeeConfigIx {
+            recipient: self.recipient,
+            minimum: u64::from_le_bytes(self.minimum),
+            bps: u16::from_le_bytes(self.bps),
+            recipient_kind: self.recipient_kind,
+            fee_config_bump: self.fee_config_bump,
+        })
+    }
+
+    pub fn from_struct(parsed: ParsedSetFeeConfigIx) -> Self {
+        SetFeeConfigIx {
+            recipient: parsed.recipient,
+            minimum: parsed.minimum.to_le_bytes(),
+            bps: parsed.bps.to_le_bytes(),
+            recipient_kind: parsed.recipient_kind,
+            fee_config_bump: parsed.fee_config_bump,
+        }
+    }
+}
+
+pub struct ParsedSetFeeConfigIx {
+    pub recipient: Pubkey,
+    pub minimum: u64,
+    pub bps: u16,
+    pub recipient_kind: u8,
+    pub fee_config_bump: u8,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct SetVelocityLimitIx {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub amount: [u8; 8], // Pack u64 as [u8; 8]
+    pub window: [u8; 8], // Pack i64 as [u8; 8]
+    pub velocity_limit_bump: u8,
+}
+
+impl SetVelocityLimitIx {
+    pub fn to_struct(&self) -> Result<ParsedSetVelocityLimitIx, std::io::Error> {
+        Ok(ParsedSetVelocityLimitIx {
+            owner: self.owner,
+            mint: self.mint,
+            amount: u64::from_le_bytes(self.amount),
+            window: i64::from_le_bytes(self.window),
+            velocity_limit_bump: self.velocity_limit_bump,
+        })
+    }
+
+    pub fn from_struct(parsed: ParsedSetVelocityLimitIx) -> Self {
+        SetVelocityLimitIx {
+            owner: parsed.owner,
+            mint: parsed.mint,
+            amount: parsed.amount.to_le_bytes(),
+            window: parsed.window.to_le_bytes(),
+            velocity_limit_bump: parsed.velocity_limit_bump,
+        }
+    }
+}
+
+pub struct ParsedSetVelocityLimitIx {
+    pub owner: Pubkey,
+    pub mint: Pubkey,
+    pub amount: u64,
+    pub window: i64,
+    pub velocity_limit_bump: u8,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct InitMintIx {
+    pub vm_mint_bump: u8,
+    pub vm_mint_omnibus_bump: u8,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct DepositWithIntentIx {
+    pub account_index: [u8; 2], // Pack u16 as [u8; 2]
+    pub amount: [u8; 8],        // Pack u64 as [u8; 8]
+    pub nonce: Hash,
+    pub expires_at: [u8; 8],    // Pack i64 as [u8; 8]
+    pub signature: Signature,
+    pub delegate_bump: u8,
+    pub receipt_bump: u8,
+}
+
+impl DepositWithIntentIx {
+    pub fn to_struct(&self) -> Result<ParsedDepositWithIntentIx, std::io::Error> {
+        Ok(ParsedDepositWithIntentIx {
+            account_index: u16::from_le_bytes(self.account_index),
+            amount: u64::from_le_bytes(self.amount),
+            nonce: self.nonce,
+            expires_at: i64::from_le_bytes(self.expires_at),
+            signature: self.signature,
+            delegate_bump: self.delegate_bump,
+            receipt_bump: self.receipt_bump,
+        })
+    }
+
+    pub fn from_struct(parsed: ParsedDepositWithIntentIx) -> Self {
+        DepositWithIntentIx {
+            account_index: parsed.account_index.to_le_bytes(),
+            amount: parsed.amount.to_le_bytes(),
+            nonce: parsed.nonce,
+            expires_at: parsed.expires_at.to_le_bytes(),
+            signature: parsed.signature,
+            delegate_bump: parsed.delegate_bump,
+            receipt_bump: parsed.receipt_bump,
+        }
+    }
+}
+
+pub struct ParsedDepositWithIntentIx {
+    pub account_index: u16,
+    pub amount: u64,
+    pub nonce: Hash,
+    pub expires_at: i64,
+    pub signature: Signature,
+    pub delegate_bump: u8,
+    pub receipt_bump: u8,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct SweepDepositIx {
+    pub account_index: [u8; 2], // Pack u16 as [u8; 2]
+    pub bump: u8,
+    pub close: u8,              // Pack bool as u8
+}
+
+impl SweepDepositIx {
+    pub fn to_struct(&self) -> Result<ParsedSweepDepositIx, std::io::Error> {
+        Ok(ParsedSweepDepositIx {
+            account_index: u16::from_le_bytes(self.account_index),
+            bump: self.bump,
+            close: self.close != 0,
+        })
+    }
+
+    pub fn from_struct(parsed: ParsedSweepDepositIx) -> Self {
+        SweepDepositIx {
+            account_index: parsed.account_index.to_le_bytes(),
+            bump: parsed.bump,
+            close: parsed.close as u8,
+        }
+    }
+}
+
+pub struct ParsedSweepDepositIx {
+    pub account_index: u16,
+    pub bump: u8,
+    pub close: bool,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct CloseDepositReceiptIx {
+}
+
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
 pub struct WithdrawIx {
@@ -401,6 +730,10 @@ pub enum WithdrawIxData {
     FromSwap {
         bump: u8,
     } = 3,
+    PartialFromMemory {
+        account_index: u16,
+        amount: u64,
+    } = 4,
 }
 
 
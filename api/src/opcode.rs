@@ -1,3 +1,4 @@
+use crate::consts::MAX_MULTISIG_SIGNERS;
 use crate::types::Hash;
 use steel::*;
 
@@ -16,7 +17,29 @@ pub enum Opcode {
 
   ConditionalTransferOp = 12,
 
+  MergeOp = 15,
+
   AirdropOp = 30,
+
+  ClaimVestedOp = 40,
+
+  ScheduledTransferOp = 50,
+  SettleScheduledTransferOp = 51,
+  CancelScheduledTransferOp = 52,
+
+  CreateSubscriptionOp = 60,
+  CollectSubscriptionOp = 61,
+  CancelSubscriptionOp = 62,
+
+  ApproveAllowanceOp = 70,
+  DelegatedTransferOp = 71,
+  RevokeAllowanceOp = 72,
+
+  EscrowDepositOp = 80,
+  EscrowReleaseOp = 81,
+  EscrowRefundOp = 82,
+
+  MultisigTransferOp = 90,
 }
 
 instruction!(Opcode, TransferOp);
@@ -26,7 +49,22 @@ instruction!(Opcode, ExternalTransferOp);
 instruction!(Opcode, ExternalWithdrawOp);
 instruction!(Opcode, ExternalRelayOp);
 instruction!(Opcode, ConditionalTransferOp);
+instruction!(Opcode, MergeOp);
 instruction!(Opcode, AirdropOp);
+instruction!(Opcode, ClaimVestedOp);
+instruction!(Opcode, ScheduledTransferOp);
+instruction!(Opcode, SettleScheduledTransferOp);
+instruction!(Opcode, CancelScheduledTransferOp);
+instruction!(Opcode, CreateSubscriptionOp);
+instruction!(Opcode, CollectSubscriptionOp);
+instruction!(Opcode, CancelSubscriptionOp);
+instruction!(Opcode, ApproveAllowanceOp);
+instruction!(Opcode, DelegatedTransferOp);
+instruction!(Opcode, RevokeAllowanceOp);
+instruction!(Opcode, EscrowDepositOp);
+instruction!(Opcode, EscrowReleaseOp);
+instruction!(Opcode, EscrowRefundOp);
+instruction!(Opcode, MultisigTransferOp);
 
 #[repr(C)]
 #[derive(Clone, Copy, Debug, Pod, Zeroable)]
@@ -75,9 +113,24 @@ pub struct RelayOp { // relay_to_internal
     pub transcript: Hash,      // no packing needed
     pub recent_root: Hash,     // no packing needed
     pub commitment: Pubkey,    // no packing needed
+    pub relay_index: u8,       // index into the exec relay set
 }
 
 impl RelayOp {
+    /// The length of the original layout, which had no `relay_index`.
+    pub const LEGACY_LEN: usize = std::mem::size_of::<Self>() - 1;
+
+    /// Parses the opcode data. The original layout is still accepted and
+    /// pays from the first relay of the exec relay set.
+    pub fn unpack(data: &[u8]) -> Result<ParsedRelayOp, ProgramError> {
+        if data.len() == Self::LEGACY_LEN {
+            let mut padded = [0u8; std::mem::size_of::<Self>()];
+            padded[..Self::LEGACY_LEN].copy_from_slice(data);
+            return Ok(Self::try_from_bytes(&padded)?.to_struct()?);
+        }
+        Ok(Self::try_from_bytes(data)?.to_struct()?)
+    }
+
     /// Converts the byte array `amount` to `u64`.
     pub fn to_struct(&self) -> Result<ParsedRelayOp, std::io::Error> {
         Ok(ParsedRelayOp {
@@ -85,6 +138,7 @@ impl RelayOp {
             transcript: self.transcript,
             recent_root: self.recent_root,
             commitment: self.commitment,
+            relay_index: self.relay_index,
         })
     }
 
@@ -95,6 +149,7 @@ impl RelayOp {
             transcript: parsed.transcript,
             recent_root: parsed.recent_root,
             commitment: parsed.commitment,
+            relay_index: parsed.relay_index,
         }
     }
 }
@@ -104,6 +159,7 @@ pub struct ParsedRelayOp {
     pub transcript: Hash,
     pub recent_root: Hash,
     pub commitment: Pubkey,
+    pub relay_index: u8,
 }
 
 #[repr(C)]
@@ -153,9 +209,24 @@ pub struct ExternalRelayOp { // relay_to_external
     pub transcript: Hash,      // Assuming Hash is [u8; 32], no change needed
     pub recent_root: Hash,     // Assuming Hash is [u8; 32], no change needed
     pub commitment: Pubkey,    // Assuming Pubkey is [u8; 32], no change needed
+    pub relay_index: u8,       // index into the exec relay set
 }
 
 impl ExternalRelayOp {
+    /// The length of the original layout, which had no `relay_index`.
+    pub const LEGACY_LEN: usize = std::mem::size_of::<Self>() - 1;
+
+    /// Parses the opcode data. The original layout is still accepted and
+    /// pays from the first relay of the exec relay set.
+    pub fn unpack(data: &[u8]) -> Result<ParsedExternalRelayOp, ProgramError> {
+        if data.len() == Self::LEGACY_LEN {
+            let mut padded = [0u8; std::mem::size_of::<Self>()];
+            padded[..Self::LEGACY_LEN].copy_from_slice(data);
+            return Ok(Self::try_from_bytes(&padded)?.to_struct()?);
+        }
+        Ok(Self::try_from_bytes(data)?.to_struct()?)
+    }
+
     /// Converts the byte array `amount` to `u64`.
     pub fn to_struct(&self) -> Result<ParsedExternalRelayOp, std::io::Error> {
         Ok(ParsedExternalRelayOp {
@@ -163,6 +234,7 @@ impl ExternalRelayOp {
             transcript: self.transcript,
             recent_root: self.recent_root,
             commitment: self.commitment,
+            relay_index: self.relay_index,
         })
     }
 
@@ -173,6 +245,7 @@ impl ExternalRelayOp {
             transcript: parsed.transcript,
             recent_root: parsed.recent_root,
             commitment: parsed.commitment,
+            relay_index: parsed.relay_index,
         }
     }
 }
@@ -182,6 +255,7 @@ pub struct ParsedExternalRelayOp {
     pub transcript: Hash,
     pub recent_root: Hash,
     pub commitment: Pubkey,
+    pub relay_index: u8,
 }
 
 #[repr(C)]
@@ -247,3 +321,282 @@ pub struct ParsedAirdropOp {
     pub amount: u64,
     pub count: u8,
 }
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct ClaimVestedOp {
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct ScheduledTransferOp {
+    pub signature: [u8; 64],
+    pub amount: [u8; 8],     // Pack u64 as [u8; 8]
+    pub not_before: [u8; 8], // Pack i64 as [u8; 8]
+}
+
+impl ScheduledTransferOp {
+    /// Converts the byte arrays `amount` and `not_before` to `u64` and `i64`.
+    pub fn to_struct(&self) -> Result<ParsedScheduledTransferOp, std::io::Error> {
+        Ok(ParsedScheduledTransferOp {
+            signature: self.signature,
+            amount: u64::from_le_bytes(self.amount),
+            not_before: i64::from_le_bytes(self.not_before),
+        })
+    }
+
+    /// Creates `ScheduledTransferOp` from the parsed struct by converting the values back to byte arrays.
+    pub fn from_struct(parsed: ParsedScheduledTransferOp) -> Self {
+        ScheduledTransferOp {
+            signature: parsed.signature,
+            amount: parsed.amount.to_le_bytes(),
+            not_before: parsed.not_before.to_le_bytes(),
+        }
+    }
+}
+
+pub struct ParsedScheduledTransferOp {
+    pub signature: [u8; 64],
+    pub amount: u64,
+    pub not_before: i64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct SettleScheduledTransferOp {
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct CancelScheduledTransferOp {
+    pub signature: [u8; 64],
+}
+
+impl CancelScheduledTransferOp {
+    // Since CancelScheduledTransferOp only contains byte arrays, no conversion methods are necessary.
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct CreateSubscriptionOp {
+    pub signature: [u8; 64],
+    pub amount: [u8; 8],     // Pack u64 as [u8; 8]
+    pub period: [u8; 8],     // Pack i64 as [u8; 8]
+    pub first_due: [u8; 8],  // Pack i64 as [u8; 8]
+    pub count: [u8; 8],      // Pack u64 as [u8; 8]
+}
+
+impl CreateSubscriptionOp {
+    /// Converts the byte arrays to their `u64` and `i64` values.
+    pub fn to_struct(&self) -> Result<ParsedCreateSubscriptionOp, std::io::Error> {
+        Ok(ParsedCreateSubscriptionOp {
+            signature: self.signature,
+            amount: u64::from_le_bytes(self.amount),
+            period: i64::from_le_bytes(self.period),
+            first_due: i64::from_le_bytes(self.first_due),
+            count: u64::from_le_bytes(self.count),
+        })
+    }
+
+    /// Creates `CreateSubscriptionOp` from the parsed struct by converting the values back to byte arrays.
+    pub fn from_struct(parsed: ParsedCreateSubscriptionOp) -> Self {
+        CreateSubscriptionOp {
+            signature: parsed.signature,
+            amount: parsed.amount.to_le_bytes(),
+            period: parsed.period.to_le_bytes(),
+            first_due: parsed.first_due.to_le_bytes(),
+            count: parsed.count.to_le_bytes(),
+        }
+    }
+}
+
+pub struct ParsedCreateSubscriptionOp {
+    pub signature: [u8; 64],
+    pub amount: u64,
+    pub period: i64,
+    pub first_due: i64,
+    pub count: u64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct CollectSubscriptionOp {
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct CancelSubscriptionOp {
+    pub signature: [u8; 64],
+}
+
+impl CancelSubscriptionOp {
+    // Since CancelSubscriptionOp only contains byte arrays, no conversion methods are necessary.
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct ApproveAllowanceOp {
+    pub signature: [u8; 64],
+    pub delegate: Pubkey,
+    pub amount: [u8; 8],     // Pack u64 as [u8; 8]
+    pub expires_at: [u8; 8], // Pack i64 as [u8; 8]
+}
+
+impl ApproveAllowanceOp {
+    /// Converts the byte arrays `amount` and `expires_at` to `u64` and `i64`.
+    pub fn to_struct(&self) -> Result<ParsedApproveAllowanceOp, std::io::Error> {
+        Ok(ParsedApproveAllowanceOp {
+            signature: self.signature,
+            delegate: self.delegate,
+            amount: u64::from_le_bytes(self.amount),
+            expires_at: i64::from_le_bytes(self.expires_at),
+        })
+    }
+
+    /// Creates `ApproveAllowanceOp` from the parsed struct by converting the values back to byte arrays.
+    pub fn from_struct(parsed: ParsedApproveAllowanceOp) -> Self {
+        ApproveAllowanceOp {
+            signature: parsed.signature,
+            delegate: parsed.delegate,
+            amount: parsed.amount.to_le_bytes(),
+            expires_at: parsed.expires_at.to_le_bytes(),
+        }
+    }
+}
+
+pub struct ParsedApproveAllowanceOp {
+    pub signature: [u8; 64],
+    pub delegate: Pubkey,
+    pub amount: u64,
+    pub expires_at: i64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct DelegatedTransferOp {
+    pub signature: [u8; 64],
+    pub amount: [u8; 8], // Pack u64 as [u8; 8]
+}
+
+impl DelegatedTransferOp {
+    /// Converts the byte array `amount` to `u64`.
+    pub fn to_struct(&self) -> Result<ParsedDelegatedTransferOp, std::io::Error> {
+        Ok(ParsedDelegatedTransferOp {
+            signature: self.signature,
+            amount: u64::from_le_bytes(self.amount),
+        })
+    }
+
+    /// Creates `DelegatedTransferOp` from the parsed struct by converting `u64` back to byte array.
+    pub fn from_struct(parsed: ParsedDelegatedTransferOp) -> Self {
+        DelegatedTransferOp {
+            signature: parsed.signature,
+            amount: parsed.amount.to_le_bytes(),
+        }
+    }
+}
+
+pub struct ParsedDelegatedTransferOp {
+    pub signature: [u8; 64],
+    pub amount: u64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct RevokeAllowanceOp {
+    pub signature: [u8; 64],
+}
+
+impl RevokeAllowanceOp {
+    // Since RevokeAllowanceOp only contains byte arrays, no conversion methods are necessary.
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct EscrowDepositOp {
+    pub signature: [u8; 64],
+    pub arbiter: Pubkey,
+    pub amount: [u8; 8],     // Pack u64 as [u8; 8]
+    pub deadline: [u8; 8],   // Pack i64 as [u8; 8]
+}
+
+impl EscrowDepositOp {
+    /// Converts the byte arrays `amount` and `deadline` to `u64` and `i64`.
+    pub fn to_struct(&self) -> Result<ParsedEscrowDepositOp, std::io::Error> {
+        Ok(ParsedEscrowDepositOp {
+            signature: self.signature,
+            arbiter: self.arbiter,
+            amount: u64::from_le_bytes(self.amount),
+            deadline: i64::from_le_bytes(self.deadline),
+        })
+    }
+
+    /// Creates `EscrowDepositOp` from the parsed struct by converting the values back to byte arrays.
+    pub fn from_struct(parsed: ParsedEscrowDepositOp) -> Self {
+        EscrowDepositOp {
+            signature: parsed.signature,
+            arbiter: parsed.arbiter,
+            amount: parsed.amount.to_le_bytes(),
+            deadline: parsed.deadline.to_le_bytes(),
+        }
+    }
+}
+
+pub struct ParsedEscrowDepositOp {
+    pub signature: [u8; 64],
+    pub arbiter: Pubkey,
+    pub amount: u64,
+    pub deadline: i64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct EscrowReleaseOp {
+    pub signature: [u8; 64],
+}
+
+impl EscrowReleaseOp {
+    // Since EscrowReleaseOp only contains byte arrays, no conversion methods are necessary.
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct EscrowRefundOp {
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct MultisigTransferOp {
+    pub amount: [u8; 8], // Pack u64 as [u8; 8]
+    pub signatures: [[u8; 64]; MAX_MULTISIG_SIGNERS],
+}
+
+impl MultisigTransferOp {
+    /// Converts the byte array `amount` to `u64`.
+    pub fn to_struct(&self) -> Result<ParsedMultisigTransferOp, std::io::Error> {
+        Ok(ParsedMultisigTransferOp {
+            amount: u64::from_le_bytes(self.amount),
+            signatures: self.signatures,
+        })
+    }
+
+    /// Creates `MultisigTransferOp` from the parsed struct by converting `u64` back to byte array.
+    pub fn from_struct(parsed: ParsedMultisigTransferOp) -> Self {
+        MultisigTransferOp {
+            amount: parsed.amount.to_le_bytes(),
+            signatures: parsed.signatures,
+        }
+    }
+}
+
+/// The signatures are in the same order as the multisig signers. Signers
+/// that did not sign leave their slot zeroed.
+pub struct ParsedMultisigTransferOp {
+    pub amount: u64,
+    pub signatures: [[u8; 64]; MAX_MULTISIG_SIGNERS],
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod, Zeroable)]
+pub struct MergeOp {
+}
@@ -45,6 +45,54 @@ pub fn find_vm_relay_vault_pda(relay: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(&[CODE_VM, VM_RELAY_VAULT, relay.as_ref()], &crate::id())
 }
 
+#[cfg(not(target_os = "solana"))]
+pub fn find_vm_relay_nullifier_pda(relay: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CODE_VM, VM_RELAY_NULLIFIER, relay.as_ref()], &crate::id())
+}
+
+#[cfg(not(target_os = "solana"))]
+pub fn find_vm_fee_config_pda(vm: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[CODE_VM, VM_FEE_CONFIG, vm.as_ref()], &crate::id())
+}
+
+/// The velocity limit of `owner`. The VM default uses `Pubkey::default()`
+/// as the owner.
+#[cfg(not(target_os = "solana"))]
+pub fn find_vm_velocity_limit_pda(vm: &Pubkey, owner: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[CODE_VM, VM_VELOCITY_LIMIT, vm.as_ref(), owner.as_ref()],
+        &crate::id(),
+    )
+}
+
+/// The velocity limit of `owner` for a mint registered with the VM.
+#[cfg(not(target_os = "solana"))]
+pub fn find_vm_mint_velocity_limit_pda(vm: &Pubkey, owner: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[CODE_VM, VM_VELOCITY_LIMIT, vm.as_ref(), owner.as_ref(), mint.as_ref()],
+        &crate::id(),
+    )
+}
+
+/// The registration of `mint` with the VM, see `InitMintIx`.
+#[cfg(not(target_os = "solana"))]
+pub fn find_vm_mint_pda(vm: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[CODE_VM, VM_MINT, vm.as_ref(), mint.as_ref()],
+        &crate::id(),
+    )
+}
+
+/// The omnibus of a mint registered with the VM. The VM mint uses
+/// `find_vm_omnibus_pda`.
+#[cfg(not(target_os = "solana"))]
+pub fn find_vm_mint_omnibus_pda(vm: &Pubkey, mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[CODE_VM, VM_OMNIBUS, vm.as_ref(), mint.as_ref()],
+        &crate::id(),
+    )
+}
+
 #[cfg(not(target_os = "solana"))]
 pub fn find_timelock_deposit_pda(vm: &Pubkey, depositor: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(
@@ -53,6 +101,25 @@ pub fn find_timelock_deposit_pda(vm: &Pubkey, depositor: &Pubkey) -> (Pubkey, u8
     )
 }
 
+/// The delegate that users approve on their token account to deposit with a
+/// signed intent, see `DepositWithIntentIx`.
+#[cfg(not(target_os = "solana"))]
+pub fn find_vm_deposit_delegate_pda(vm: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[CODE_VM, VM_DEPOSIT_DELEGATE, vm.as_ref()],
+        &crate::id(),
+    )
+}
+
+/// The receipt of a deposit intent, which stops it from being replayed.
+#[cfg(not(target_os = "solana"))]
+pub fn find_vm_deposit_receipt_pda(vm: &Pubkey, owner: &Pubkey, nonce: &Hash) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[CODE_VM, VM_DEPOSIT_RECEIPT, vm.as_ref(), owner.as_ref(), nonce.as_ref()],
+        &crate::id(),
+    )
+}
+
 #[cfg(not(target_os = "solana"))]
 pub fn find_timelock_swap_pda(vm: &Pubkey, swapper: &Pubkey) -> (Pubkey, u8) {
     Pubkey::find_program_address(
@@ -88,6 +155,18 @@ pub fn find_virtual_nonce_pda(vm: &Pubkey, seed: &Pubkey, poh: &Hash) -> (Pubkey
     )
 }
 
+pub fn find_virtual_multisig_pda(vm: &Pubkey, config: &Hash) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            CODE_VM,
+            VM_MULTISIG,
+            config.as_ref(),
+            vm.as_ref(),
+        ],
+        &crate::id(),
+    )
+}
+
 pub fn create_virtual_nonce_pda(vm: &Pubkey, seed: &Pubkey, poh: &Hash, bump: u8) -> Pubkey {
     Pubkey::create_program_address(
         &[
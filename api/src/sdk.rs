@@ -3,7 +3,19 @@
 use steel::*;
 use crate::prelude::*;
 
-pub fn vm_init(vm_authority: Pubkey, mint: Pubkey, lock_duration: u8) -> Instruction {
+mod reconcile;
+mod relay;
+mod vesting;
+pub use reconcile::*;
+pub use relay::*;
+pub use vesting::*;
+
+pub fn vm_init(
+    vm_authority: Pubkey,
+    mint: Pubkey,
+    token_program: TokenProgram,
+    lock_duration: u8,
+) -> Instruction {
 
     let (vm, vm_bump) = find_vm_pda(&mint, &vm_authority, lock_duration);
     let (omnibus, vm_omnibus_bump) = find_vm_omnibus_pda(&vm);
@@ -15,7 +27,7 @@ pub fn vm_init(vm_authority: Pubkey, mint: Pubkey, lock_duration: u8) -> Instruc
             AccountMeta::new(vm, false),
             AccountMeta::new(omnibus, false),
             AccountMeta::new_readonly(mint, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(token_program.id(), false),
             AccountMeta::new_readonly(system_program::id(), false),
             AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false),
         ],
@@ -80,6 +92,171 @@ pub fn vm_memory_resize(
     }
 }
 
+pub fn vm_set_message_policy(
+    vm_authority: Pubkey,
+    vm: Pubkey,
+    message_policy: MessagePolicy,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(vm_authority, true),
+            AccountMeta::new(vm, false),
+        ],
+        data: SetMessagePolicyIx {
+            message_policy: message_policy as u8,
+        }
+        .to_bytes(),
+    }
+}
+
+pub fn vm_set_signature_mode(
+    vm_authority: Pubkey,
+    vm: Pubkey,
+    signature_mode: SignatureMode,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(vm_authority, true),
+            AccountMeta::new(vm, false),
+        ],
+        data: SetSignatureModeIx {
+            signature_mode: signature_mode as u8,
+        }
+        .to_bytes(),
+    }
+}
+
+pub fn vm_set_fee_config(
+    vm_authority: Pubkey,
+    vm: Pubkey,
+    recipient_kind: FeeRecipient,
+    recipient: Pubkey,
+    bps: u16,
+    minimum: u64,
+) -> Instruction {
+    let (fee_config, fee_config_bump) = find_vm_fee_config_pda(&vm);
+
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(vm_authority, true),
+            AccountMeta::new(vm, false),
+            AccountMeta::new(fee_config, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SetFeeConfigIx::from_struct(
+            ParsedSetFeeConfigIx {
+            recipient,
+            minimum,
+            bps,
+            recipient_kind: recipient_kind as u8,
+            fee_config_bump,
+        }).to_bytes(),
+    }
+}
+
+/// Sets the velocity limit of `owner`, or the VM default when `owner` is
+/// `Pubkey::default()`.
+pub fn vm_set_velocity_limit(
+    vm_authority: Pubkey,
+    vm: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+    window: i64,
+) -> Instruction {
+    let (velocity_limit, velocity_limit_bump) = find_vm_velocity_limit_pda(&vm, &owner);
+
+    set_velocity_limit(
+        vm_authority,
+        vm,
+        velocity_limit,
+        ParsedSetVelocityLimitIx {
+            owner,
+            mint: Pubkey::default(),
+            amount,
+            window,
+            velocity_limit_bump,
+        },
+    )
+}
+
+/// Sets the velocity limit of `owner` for a mint registered with the VM, in
+/// units of that mint.
+pub fn vm_set_mint_velocity_limit(
+    vm_authority: Pubkey,
+    vm: Pubkey,
+    owner: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+    window: i64,
+) -> Instruction {
+    let (velocity_limit, velocity_limit_bump) =
+        find_vm_mint_velocity_limit_pda(&vm, &owner, &mint);
+
+    set_velocity_limit(
+        vm_authority,
+        vm,
+        velocity_limit,
+        ParsedSetVelocityLimitIx {
+            owner,
+            mint,
+            amount,
+            window,
+            velocity_limit_bump,
+        },
+    )
+}
+
+fn set_velocity_limit(
+    vm_authority: Pubkey,
+    vm: Pubkey,
+    velocity_limit: Pubkey,
+    args: ParsedSetVelocityLimitIx,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(vm_authority, true),
+            AccountMeta::new(vm, false),
+            AccountMeta::new(velocity_limit, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: SetVelocityLimitIx::from_struct(args).to_bytes(),
+    }
+}
+
+/// Registers `mint` with the VM, creating its omnibus.
+pub fn vm_init_mint(
+    vm_authority: Pubkey,
+    vm: Pubkey,
+    mint: Pubkey,
+    token_program: TokenProgram,
+) -> Instruction {
+    let (vm_mint, vm_mint_bump) = find_vm_mint_pda(&vm, &mint);
+    let (omnibus, vm_mint_omnibus_bump) = find_vm_mint_omnibus_pda(&vm, &mint);
+
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(vm_authority, true),
+            AccountMeta::new(vm, false),
+            AccountMeta::new(vm_mint, false),
+            AccountMeta::new(omnibus, false),
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(token_program.id(), false),
+            AccountMeta::new_readonly(system_program::id(), false),
+            AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false),
+        ],
+        data: InitMintIx {
+            vm_mint_bump,
+            vm_mint_omnibus_bump,
+        }
+        .to_bytes(),
+    }
+}
+
 pub fn vm_storage_init(vm_authority: Pubkey, vm: Pubkey, name: &str) -> Instruction {
     let name = create_name(name);
     let (vm_storage, vm_storage_bump) = find_vm_storage_pda(&vm, &name);
@@ -132,6 +309,31 @@ pub fn system_timelock_init(
     virtual_timelock_bump: u8,
     virtual_vault_bump: u8,
     unlock_pda_bump: u8,
+) -> Instruction {
+    system_timelock_init_with_lock_duration(
+        vm_authority,
+        vm,
+        vm_memory,
+        virtual_account_owner,
+        account_index,
+        virtual_timelock_bump,
+        virtual_vault_bump,
+        unlock_pda_bump,
+        0, // use the VM lock duration
+    )
+}
+
+/// The bumps must be derived from the timelock address for `lock_duration`.
+pub fn system_timelock_init_with_lock_duration(
+    vm_authority: Pubkey,
+    vm: Pubkey,
+    vm_memory: Pubkey,
+    virtual_account_owner: Pubkey,
+    account_index: u16,
+    virtual_timelock_bump: u8,
+    virtual_vault_bump: u8,
+    unlock_pda_bump: u8,
+    lock_duration: u8,
 ) -> Instruction {
     Instruction {
         program_id: crate::ID,
@@ -147,6 +349,7 @@ pub fn system_timelock_init(
             virtual_timelock_bump,
             virtual_vault_bump,
             unlock_pda_bump,
+            lock_duration,
         }).to_bytes(),
     }
 }
@@ -258,10 +461,172 @@ pub fn vm_exec(
     }
 }
 
-pub fn relay_init(vm_authority: Pubkey, vm: Pubkey, mint: Pubkey, name: &str) -> Instruction {
+/// Appends extra (relay, relay_vault) pairs to a `vm_exec` instruction. The
+/// relay passed to `vm_exec` is index 0 of the relay set, these follow it.
+/// The relay nullifiers are derived from the relay addresses and appended
+/// along with them.
+pub fn with_exec_relays(mut ix: Instruction, relays: &[(Pubkey, Pubkey)]) -> Instruction {
+    let relay = ix.accounts[7].pubkey;
+    let relay_nullifier = match relay {
+        crate::ID => None,
+        _ => Some(find_vm_relay_nullifier_pda(&relay).0),
+    };
+    ix.accounts.push(optional_meta(relay_nullifier, false));
+
+    for (relay, relay_vault) in relays {
+        let (relay_nullifier, _) = find_vm_relay_nullifier_pda(relay);
+
+        ix.accounts.push(AccountMeta::new(*relay, false));
+        ix.accounts.push(AccountMeta::new(*relay_vault, false));
+        ix.accounts.push(AccountMeta::new(relay_nullifier, false));
+    }
+    ix
+}
+
+/// The same as `with_exec_relays`, except that the relay vaults are derived
+/// from the relay addresses.
+pub fn with_exec_relay_set(ix: Instruction, extra_relays: &[Pubkey]) -> Instruction {
+    let relays = extra_relays
+        .iter()
+        .map(|relay| (*relay, find_vm_relay_vault_pda(relay).0))
+        .collect::<Vec<_>>();
+
+    with_exec_relays(ix, &relays)
+}
+
+/// Appends the VM fee config to a `vm_exec` instruction, along with the fee
+/// token account when fees are paid to an external recipient. Call it after
+/// `with_exec_relays`.
+pub fn with_exec_fee_config(
+    mut ix: Instruction,
+    vm: Pubkey,
+    fee_account: Option<Pubkey>,
+) -> Instruction {
+    let (fee_config, _) = find_vm_fee_config_pda(&vm);
+    ix.accounts.push(AccountMeta::new_readonly(fee_config, false));
+
+    if let Some(fee_account) = fee_account {
+        ix.accounts.push(AccountMeta::new(fee_account, false));
+    }
+    ix
+}
+
+/// Appends the velocity limit of the source `owner` to a `vm_exec`
+/// instruction, along with the VM default when the owner's record uses the
+/// default limits. Call it after `with_exec_fee_config`.
+pub fn with_exec_velocity_limit(
+    mut ix: Instruction,
+    vm: Pubkey,
+    owner: Pubkey,
+    use_default: bool,
+) -> Instruction {
+    let (velocity_limit, _) = find_vm_velocity_limit_pda(&vm, &owner);
+    ix.accounts.push(AccountMeta::new(velocity_limit, false));
+
+    if use_default {
+        let (velocity_default, _) = find_vm_velocity_limit_pda(&vm, &Pubkey::default());
+        ix.accounts.push(AccountMeta::new_readonly(velocity_default, false));
+    }
+    ix
+}
+
+/// Appends the velocity limit of the source `owner` for a registered `mint`
+/// to a `vm_exec` instruction. Registered mints have no VM default. Call it
+/// after `with_exec_fee_config`.
+pub fn with_exec_mint_velocity_limit(
+    mut ix: Instruction,
+    vm: Pubkey,
+    owner: Pubkey,
+    mint: Pubkey,
+) -> Instruction {
+    let (velocity_limit, _) = find_vm_mint_velocity_limit_pda(&vm, &owner, &mint);
+    ix.accounts.push(AccountMeta::new(velocity_limit, false));
+    ix
+}
+
+/// The accounts that instructions moving tokens of `pool` take after their
+/// fixed accounts. The mint lets the VM use `TransferChecked`, which
+/// Token-2022 mints require, and registered mints need their `VmMintAccount`.
+fn pool_accounts(vm: &Pubkey, pool: &MintPool) -> Vec<AccountMeta> {
+    let mut accounts = vec![AccountMeta::new_readonly(pool.mint, false)];
+    if pool.is_registered {
+        let (vm_mint, _) = find_vm_mint_pda(vm, &pool.mint);
+        accounts.push(AccountMeta::new_readonly(vm_mint, false));
+    }
+    accounts
+}
+
+/// Appends the VM's mint to a `vm_exec` instruction that moves tokens, which
+/// lets the VM use `TransferChecked`. Token-2022 VMs require it. Call it after
+/// the other `with_exec_*` helpers, except for `with_exec_instructions_sysvar`.
+/// The other instructions that move tokens take the `MintPool` and add the
+/// mint themselves.
+pub fn with_mint(mut ix: Instruction, mint: Pubkey) -> Instruction {
+    ix.accounts.push(AccountMeta::new_readonly(mint, false));
+    ix
+}
+
+/// Appends the account of a mint registered with `vm_init_mint`, which
+/// `vm_exec` needs for virtual accounts that hold it. Call it right after
+/// `with_mint`.
+pub fn with_vm_mint(mut ix: Instruction, vm: Pubkey, mint: Pubkey) -> Instruction {
+    let (vm_mint, _) = find_vm_mint_pda(&vm, &mint);
+    ix.accounts.push(AccountMeta::new_readonly(vm_mint, false));
+    ix
+}
+
+/// Lets a multisig approve an unlock or withdraw instruction for the timelock
+/// accounts it owns. The multisig address no longer signs, and the signer keys
+/// are appended as signers, so call it after every other `with_*` helper.
+/// Only `threshold` of the signers need to sign the transaction, the others
+/// are appended as read-only accounts. The threshold and number of signers
+/// are appended to the instruction data.
+pub fn with_multisig_signers(
+    mut ix: Instruction,
+    vm: Pubkey,
+    threshold: u8,
+    signers: &[Pubkey],
+    approvers: &[Pubkey],
+) -> Instruction {
+    let config = VirtualMultisigAccount::get_config_hash(threshold, signers);
+    let (multisig, _) = find_virtual_multisig_pda(&vm, &config);
+
+    for meta in ix.accounts.iter_mut() {
+        if meta.pubkey.eq(&multisig) {
+            meta.is_signer = false;
+        }
+    }
+
+    for signer in signers {
+        ix.accounts.push(AccountMeta::new_readonly(*signer, approvers.contains(signer)));
+    }
+
+    ix.data.push(threshold);
+    ix.data.push(signers.len() as u8);
+    ix
+}
+
+/// Appends the instructions sysvar to a `vm_exec` instruction, which VMs in
+/// the `SignatureMode::Precompile` mode need to find the Ed25519 program
+/// instructions. This must be the last account, so call it after
+/// `with_exec_relays`, `with_exec_fee_config`, `with_exec_velocity_limit`,
+/// `with_mint` and `with_vm_mint`.
+pub fn with_exec_instructions_sysvar(mut ix: Instruction) -> Instruction {
+    ix.accounts.push(AccountMeta::new_readonly(sysvar::instructions::ID, false));
+    ix
+}
+
+pub fn relay_init(
+    vm_authority: Pubkey,
+    vm: Pubkey,
+    mint: Pubkey,
+    token_program: TokenProgram,
+    name: &str,
+) -> Instruction {
     let name = create_name(name);
     let (relay, relay_bump) = find_vm_relay_pda(&vm, &name);
     let (relay_vault, relay_vault_bump) = find_vm_relay_vault_pda(&relay);
+    let (relay_nullifier, relay_nullifier_bump) = find_vm_relay_nullifier_pda(&relay);
 
     Instruction {
         program_id: crate::ID,
@@ -271,14 +636,36 @@ pub fn relay_init(vm_authority: Pubkey, vm: Pubkey, mint: Pubkey, name: &str) ->
             AccountMeta::new(relay, false),
             AccountMeta::new(relay_vault, false),
             AccountMeta::new_readonly(mint, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
+            AccountMeta::new_readonly(token_program.id(), false),
             AccountMeta::new_readonly(system_program::id(), false),
             AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false),
+            AccountMeta::new(relay_nullifier, false),
         ],
         data: InitRelayIx {
             name,
             relay_bump,
             relay_vault_bump,
+            relay_nullifier_bump,
+        }
+        .to_bytes(),
+    }
+}
+
+/// Creates the nullifier of a relay created before `relay_init` created it.
+pub fn relay_init_nullifier(vm_authority: Pubkey, vm: Pubkey, relay: Pubkey) -> Instruction {
+    let (relay_nullifier, relay_nullifier_bump) = find_vm_relay_nullifier_pda(&relay);
+
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(vm_authority, true),
+            AccountMeta::new(vm, false),
+            AccountMeta::new_readonly(relay, false),
+            AccountMeta::new(relay_nullifier, false),
+            AccountMeta::new_readonly(system_program::id(), false),
+        ],
+        data: InitRelayNullifierIx {
+            relay_nullifier_bump,
         }
         .to_bytes(),
     }
@@ -296,6 +683,30 @@ pub fn relay_save_root(vm_authority: Pubkey, vm: Pubkey, relay: Pubkey) -> Instr
     }
 }
 
+pub fn relay_close(
+    vm_authority: Pubkey,
+    vm: Pubkey,
+    relay: Pubkey,
+    relay_vault: Pubkey,
+    pool: &MintPool,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(vm_authority, true),
+        AccountMeta::new(vm, false),
+        AccountMeta::new(relay, false),
+        AccountMeta::new(relay_vault, false),
+        AccountMeta::new(pool.omnibus.vault, false),
+        AccountMeta::new_readonly(pool.token_program.id(), false),
+    ];
+    accounts.extend(pool_accounts(&vm, pool));
+
+    Instruction {
+        program_id: crate::ID,
+        accounts,
+        data: CloseRelayIx {}.to_bytes(),
+    }
+}
+
 pub fn timelock_deposit_from_pda(
     vm_authority: Pubkey,
     vm: Pubkey,
@@ -303,23 +714,26 @@ pub fn timelock_deposit_from_pda(
     depositor: Pubkey,
     deposit_pda: Pubkey,
     deposit_ata: Pubkey,
-    omnibus: Pubkey,
+    pool: &MintPool,
     account_index: u16,
     amount: u64,
     bump: u8,
 ) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(vm_authority, true),
+        AccountMeta::new(vm, false),
+        AccountMeta::new(vm_memory, false),
+        AccountMeta::new_readonly(depositor, false),
+        AccountMeta::new_readonly(deposit_pda, false),
+        AccountMeta::new(deposit_ata, false),
+        AccountMeta::new(pool.omnibus.vault, false),
+        AccountMeta::new_readonly(pool.token_program.id(), false),
+    ];
+    accounts.extend(pool_accounts(&vm, pool));
+
     Instruction {
         program_id: crate::ID,
-        accounts: vec![
-            AccountMeta::new(vm_authority, true),
-            AccountMeta::new(vm, false),
-            AccountMeta::new(vm_memory, false),
-            AccountMeta::new_readonly(depositor, false),
-            AccountMeta::new_readonly(deposit_pda, false),
-            AccountMeta::new(deposit_ata, false),
-            AccountMeta::new(omnibus, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
+        accounts,
         data: DepositFromPdaIx::from_struct(
             ParsedDepositFromPdaIx{
             account_index,
@@ -329,6 +743,111 @@ pub fn timelock_deposit_from_pda(
     }
 }
 
+/// Deposits the whole balance of the deposit ATA, optionally closing it and
+/// returning its rent to `vm_authority`.
+#[allow(clippy::too_many_arguments)]
+pub fn timelock_sweep_deposit(
+    vm_authority: Pubkey,
+    vm: Pubkey,
+    vm_memory: Pubkey,
+    depositor: Pubkey,
+    deposit_pda: Pubkey,
+    deposit_ata: Pubkey,
+    pool: &MintPool,
+    account_index: u16,
+    bump: u8,
+    close: bool,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(vm_authority, true),
+        AccountMeta::new(vm, false),
+        AccountMeta::new(vm_memory, false),
+        AccountMeta::new_readonly(depositor, false),
+        AccountMeta::new_readonly(deposit_pda, false),
+        AccountMeta::new(deposit_ata, false),
+        AccountMeta::new(pool.omnibus.vault, false),
+        AccountMeta::new_readonly(pool.token_program.id(), false),
+    ];
+    accounts.extend(pool_accounts(&vm, pool));
+
+    Instruction {
+        program_id: crate::ID,
+        accounts,
+        data: SweepDepositIx::from_struct(
+            ParsedSweepDepositIx{
+            account_index,
+            bump,
+            close,
+        }).to_bytes(),
+    }
+}
+
+pub fn system_vesting_init(
+    vm_authority: Pubkey,
+    vm: Pubkey,
+    vm_memory: Pubkey,
+    source_authority: Pubkey,
+    source_ata: Pubkey,
+    beneficiary: Pubkey,
+    pool: &MintPool,
+    account_index: u16,
+    total: u64,
+    start: i64,
+    cliff: i64,
+    end: i64,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(vm_authority, true),
+        AccountMeta::new(vm, false),
+        AccountMeta::new(vm_memory, false),
+        AccountMeta::new(source_authority, true),
+        AccountMeta::new(source_ata, false),
+        AccountMeta::new_readonly(beneficiary, false),
+        AccountMeta::new(pool.omnibus.vault, false),
+        AccountMeta::new_readonly(pool.token_program.id(), false),
+    ];
+    accounts.extend(pool_accounts(&vm, pool));
+
+    Instruction {
+        program_id: crate::ID,
+        accounts,
+        data: InitVestingIx::from_struct(
+            ParsedInitVestingIx {
+            account_index,
+            total,
+            start,
+            cliff,
+            end,
+        }).to_bytes(),
+    }
+}
+
+pub fn system_multisig_init(
+    vm_authority: Pubkey,
+    vm: Pubkey,
+    vm_memory: Pubkey,
+    signers: &[Pubkey],
+    account_index: u16,
+    threshold: u8,
+) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(vm_authority, true),
+        AccountMeta::new(vm, false),
+        AccountMeta::new(vm_memory, false),
+    ];
+    accounts.extend(signers.iter().map(|signer| AccountMeta::new_readonly(*signer, false)));
+
+    Instruction {
+        program_id: crate::ID,
+        accounts,
+        data: InitMultisigIx::from_struct(
+            ParsedInitMultisigIx {
+            account_index,
+            threshold,
+        }).to_bytes(),
+    }
+}
+
 pub fn timelock_deposit_with_authority(
     vm_authority: Pubkey,
     vm: Pubkey,
@@ -336,22 +855,25 @@ pub fn timelock_deposit_with_authority(
     source_authority: Pubkey,
     source_ata: Pubkey,
     destination: Pubkey,
-    omnibus: Pubkey,
+    pool: &MintPool,
     account_index: u16,
     amount: u64,
 ) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(vm_authority, true),
+        AccountMeta::new(vm, false),
+        AccountMeta::new(vm_memory, false),
+        AccountMeta::new(source_authority, true),
+        AccountMeta::new(source_ata, false),
+        AccountMeta::new_readonly(destination, false),
+        AccountMeta::new(pool.omnibus.vault, false),
+        AccountMeta::new_readonly(pool.token_program.id(), false),
+    ];
+    accounts.extend(pool_accounts(&vm, pool));
+
     Instruction {
         program_id: crate::ID,
-        accounts: vec![
-            AccountMeta::new(vm_authority, true),
-            AccountMeta::new(vm, false),
-            AccountMeta::new(vm_memory, false),
-            AccountMeta::new(source_authority, true),
-            AccountMeta::new(source_ata, false),
-            AccountMeta::new_readonly(destination, false),
-            AccountMeta::new(omnibus, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
+        accounts,
         data: DepositWithAuthorityIx::from_struct(
             ParsedDepositWithAuthorityIx{
             account_index,
@@ -360,11 +882,106 @@ pub fn timelock_deposit_with_authority(
     }
 }
 
+/// A deposit intent signed by `source_authority`, see
+/// `create_deposit_intent_message`.
+#[derive(Clone, Copy)]
+pub struct DepositIntent {
+    pub source_authority: Pubkey,
+    pub source: Pubkey,
+    pub account_index: u16,
+    pub amount: u64,
+    pub nonce: Hash,
+    pub expires_at: i64,
+    pub signature: Signature,
+}
+
+/// Pulls in a deposit the source authority signed an intent for. The source
+/// authority must first approve the delegate from
+/// `find_vm_deposit_delegate_pda` on the source account.
+pub fn timelock_deposit_with_intent(
+    vm_authority: Pubkey,
+    vm: Pubkey,
+    vm_memory: Pubkey,
+    pool: &MintPool,
+    intent: &DepositIntent,
+) -> Instruction {
+    let (deposit_delegate, delegate_bump) = find_vm_deposit_delegate_pda(&vm);
+    let (deposit_receipt, receipt_bump) =
+        find_vm_deposit_receipt_pda(&vm, &intent.source_authority, &intent.nonce);
+
+    let mut accounts = vec![
+        AccountMeta::new(vm_authority, true),
+        AccountMeta::new(vm, false),
+        AccountMeta::new(vm_memory, false),
+        AccountMeta::new_readonly(intent.source_authority, false),
+        AccountMeta::new(intent.source, false),
+        AccountMeta::new_readonly(deposit_delegate, false),
+        AccountMeta::new(deposit_receipt, false),
+        AccountMeta::new(pool.omnibus.vault, false),
+        AccountMeta::new_readonly(pool.token_program.id(), false),
+        AccountMeta::new_readonly(system_program::id(), false),
+    ];
+    accounts.extend(pool_accounts(&vm, pool));
+
+    Instruction {
+        program_id: crate::ID,
+        accounts,
+        data: DepositWithIntentIx::from_struct(
+            ParsedDepositWithIntentIx{
+            account_index: intent.account_index,
+            amount: intent.amount,
+            nonce: intent.nonce,
+            expires_at: intent.expires_at,
+            signature: intent.signature,
+            delegate_bump,
+            receipt_bump,
+        }).to_bytes(),
+    }
+}
+
+/// Closes the receipt of an expired deposit intent, refunding its rent to
+/// the VM authority.
+pub fn deposit_receipt_close(
+    vm_authority: Pubkey,
+    vm: Pubkey,
+    source_authority: Pubkey,
+    nonce: Hash,
+) -> Instruction {
+    let (deposit_receipt, _) =
+        find_vm_deposit_receipt_pda(&vm, &source_authority, &nonce);
+
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(vm_authority, true),
+            AccountMeta::new(vm, false),
+            AccountMeta::new(deposit_receipt, false),
+        ],
+        data: CloseDepositReceiptIx {}.to_bytes(),
+    }
+}
+
 pub fn timelock_unlock_init(
     account_owner: Pubkey,
     payer: Pubkey,
     vm: Pubkey,
     unlock_pda: Pubkey,
+) -> Instruction {
+    timelock_unlock_init_with_lock_duration(
+        account_owner,
+        payer,
+        vm,
+        unlock_pda,
+        0, // use the VM lock duration
+    )
+}
+
+pub fn timelock_unlock_init_with_lock_duration(
+    account_owner: Pubkey,
+    payer: Pubkey,
+    vm: Pubkey,
+    unlock_pda: Pubkey,
+    lock_duration: u8,
 ) -> Instruction {
     Instruction {
         program_id: crate::ID,
@@ -376,7 +993,7 @@ pub fn timelock_unlock_init(
             AccountMeta::new_readonly(system_program::id(), false),
             AccountMeta::new_readonly(solana_program::sysvar::rent::id(), false),
         ],
-        data: InitUnlockIx {}.to_bytes(),
+        data: InitUnlockIx { lock_duration }.to_bytes(),
     }
 }
 
@@ -398,11 +1015,37 @@ pub fn timelock_unlock_finalize(
     }
 }
 
+/// Cancels an unlock. `vm_memory_or_storage` is the memory account for
+/// `CancelUnlockIxData::FromMemory`, or the storage account for
+/// `CancelUnlockIxData::FromStorage`.
+pub fn timelock_unlock_cancel(
+    account_owner: Pubkey,
+    payer: Pubkey,
+    vm: Pubkey,
+    unlock_pda: Pubkey,
+    vm_memory_or_storage: Pubkey,
+    withdraw_receipt: Pubkey,
+    data: CancelUnlockIxData,
+) -> Instruction {
+    Instruction {
+        program_id: crate::ID,
+        accounts: vec![
+            AccountMeta::new(account_owner, true),
+            AccountMeta::new(payer, true),
+            AccountMeta::new(vm, false),
+            AccountMeta::new(unlock_pda, false),
+            AccountMeta::new_readonly(vm_memory_or_storage, false),
+            AccountMeta::new_readonly(withdraw_receipt, false),
+        ],
+        data: CancelUnlockIx::try_to_bytes(data).unwrap(),
+    }
+}
+
 pub fn timelock_withdraw(
     depositor: Pubkey,
     payer: Pubkey,
     vm: Pubkey,
-    vm_omnibus: Option<Pubkey>,
+    pool: &MintPool,
     vm_memory: Option<Pubkey>,
     vm_storage: Option<Pubkey>,
     deposit_or_swap_pda: Option<Pubkey>,
@@ -413,26 +1056,28 @@ pub fn timelock_withdraw(
     data: WithdrawIxData,
 ) -> Instruction {
 
-    // This instruction has 3 variants, each with a slightly different set of
+    // This instruction has 4 variants, each with a slightly different set of
     // accounts.
 
-    let accounts = match data {
+    let mut accounts = match data {
         WithdrawIxData::FromDeposit { .. } => 
             withdraw_from_deposit(
-                depositor, payer, vm, deposit_or_swap_pda, deposit_or_swap_ata, unlock_pda, external_address),
+                depositor, payer, vm, pool, deposit_or_swap_pda, deposit_or_swap_ata, unlock_pda, external_address),
 
         WithdrawIxData::FromSwap { .. } => 
             withdraw_from_swap(
-                depositor, payer, vm, deposit_or_swap_pda, deposit_or_swap_ata, unlock_pda, external_address),
+                depositor, payer, vm, pool, deposit_or_swap_pda, deposit_or_swap_ata, unlock_pda, external_address),
 
-        WithdrawIxData::FromMemory { .. } => 
+        WithdrawIxData::FromMemory { .. } |
+        WithdrawIxData::PartialFromMemory { .. } => 
             withdraw_from_memory(
-                depositor, payer, vm, vm_omnibus, vm_memory, unlock_pda, withdraw_receipt, external_address),
+                depositor, payer, vm, pool, vm_memory, unlock_pda, withdraw_receipt, external_address),
 
         WithdrawIxData::FromStorage { .. } => 
             withdraw_from_storage(
-                depositor, payer, vm, vm_omnibus, vm_storage, unlock_pda, withdraw_receipt, external_address),
+                depositor, payer, vm, pool, vm_storage, unlock_pda, withdraw_receipt, external_address),
     };
+    accounts.extend(pool_accounts(&vm, pool));
 
     let data = WithdrawIx::try_to_bytes(data).unwrap();
 
@@ -443,10 +1088,12 @@ pub fn timelock_withdraw(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn withdraw_from_deposit(
     depositor: Pubkey,
     payer: Pubkey,
     vm: Pubkey,
+    pool: &MintPool,
     deposit_pda: Option<Pubkey>,
     deposit_ata: Option<Pubkey>,
     unlock_pda: Pubkey,
@@ -464,16 +1111,18 @@ fn withdraw_from_deposit(
         AccountMeta::new(unlock_pda, false),
         optional_meta(None, false), // withdraw_receipt
         AccountMeta::new(external_address, false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(pool.token_program.id(), false),
         optional_meta(None, false), // system_program
         optional_meta(None, false), // rent_sysvar
     ]
 }
 
+#[allow(clippy::too_many_arguments)]
 fn withdraw_from_swap(
     depositor: Pubkey,
     payer: Pubkey,
     vm: Pubkey,
+    pool: &MintPool,
     swap_pda: Option<Pubkey>,
     swap_ata: Option<Pubkey>,
     unlock_pda: Pubkey,
@@ -491,7 +1140,7 @@ fn withdraw_from_swap(
         AccountMeta::new(unlock_pda, false),
         optional_meta(None, false), // withdraw_receipt
         AccountMeta::new(external_address, false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(pool.token_program.id(), false),
         optional_meta(None, false), // system_program
         optional_meta(None, false), // rent_sysvar
     ]
@@ -501,7 +1150,7 @@ pub fn withdraw_from_memory(
     depositor: Pubkey,
     payer: Pubkey,
     vm: Pubkey,
-    vm_omnibus: Option<Pubkey>,
+    pool: &MintPool,
     vm_memory: Option<Pubkey>,
     unlock_pda: Pubkey,
     withdraw_receipt: Option<Pubkey>,
@@ -511,7 +1160,7 @@ pub fn withdraw_from_memory(
         AccountMeta::new(depositor, true),
         AccountMeta::new(payer, true),
         AccountMeta::new(vm, false),
-        optional_meta(vm_omnibus, false),
+        AccountMeta::new(pool.omnibus.vault, false),
         optional_meta(vm_memory, false),
         optional_meta(None, false), // vm_storage
         optional_meta(None, false), // deposit_pda
@@ -519,7 +1168,7 @@ pub fn withdraw_from_memory(
         AccountMeta::new(unlock_pda, false),
         optional_meta(withdraw_receipt, false),
         AccountMeta::new(external_address, false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(pool.token_program.id(), false),
         optional_readonly_meta(Some(system_program::id()), false),
         optional_readonly_meta(Some(solana_program::sysvar::rent::id()), false),
     ]
@@ -529,7 +1178,7 @@ fn withdraw_from_storage(
     depositor: Pubkey,
     payer: Pubkey,
     vm: Pubkey,
-    vm_omnibus: Option<Pubkey>,
+    pool: &MintPool,
     vm_storage: Option<Pubkey>,
     unlock_pda: Pubkey,
     withdraw_receipt: Option<Pubkey>,
@@ -539,7 +1188,7 @@ fn withdraw_from_storage(
         AccountMeta::new(depositor, true),
         AccountMeta::new(payer, true),
         AccountMeta::new(vm, false),
-        optional_meta(vm_omnibus, false),
+        AccountMeta::new(pool.omnibus.vault, false),
         optional_meta(None, false), // vm_memory
         optional_meta(vm_storage, false),
         optional_meta(None, false), // deposit_pda
@@ -547,7 +1196,7 @@ fn withdraw_from_storage(
         AccountMeta::new(unlock_pda, false),
         optional_meta(withdraw_receipt, false),
         AccountMeta::new(external_address, false),
-        AccountMeta::new_readonly(spl_token::id(), false),
+        AccountMeta::new_readonly(pool.token_program.id(), false),
         optional_readonly_meta(Some(system_program::id()), false),
         optional_readonly_meta(Some(solana_program::sysvar::rent::id()), false),
     ]
@@ -560,20 +1209,24 @@ pub fn transfer_for_swap(
     swap_pda: Pubkey,
     swap_ata: Pubkey,
     destination: Pubkey,
+    pool: &MintPool,
     amount: u64,
     bump: u8,
 ) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(vm_authority, true),
+        AccountMeta::new(vm, false),
+        AccountMeta::new(swapper, true),
+        AccountMeta::new_readonly(swap_pda, false),
+        AccountMeta::new(swap_ata, false),
+        AccountMeta::new(destination, false),
+        AccountMeta::new_readonly(pool.token_program.id(), false),
+    ];
+    accounts.extend(pool_accounts(&vm, pool));
+
     Instruction {
         program_id: crate::ID,
-        accounts: vec![
-            AccountMeta::new(vm_authority, true),
-            AccountMeta::new(vm, false),
-            AccountMeta::new(swapper, true),
-            AccountMeta::new_readonly(swap_pda, false),
-            AccountMeta::new(swap_ata, false),
-            AccountMeta::new(destination, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
+        accounts,
         data: TransferForSwapIx::from_struct(
             ParsedTransferForSwapIx{
             amount,
@@ -589,23 +1242,26 @@ pub fn cancel_swap(
     swapper: Pubkey,
     swap_pda: Pubkey,
     swap_ata: Pubkey,
-    omnibus: Pubkey,
+    pool: &MintPool,
     account_index: u16,
     amount: u64,
     bump: u8,
 ) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(vm_authority, true),
+        AccountMeta::new(vm, false),
+        AccountMeta::new(vm_memory, false),
+        AccountMeta::new_readonly(swapper, false),
+        AccountMeta::new_readonly(swap_pda, false),
+        AccountMeta::new(swap_ata, false),
+        AccountMeta::new(pool.omnibus.vault, false),
+        AccountMeta::new_readonly(pool.token_program.id(), false),
+    ];
+    accounts.extend(pool_accounts(&vm, pool));
+
     Instruction {
         program_id: crate::ID,
-        accounts: vec![
-            AccountMeta::new(vm_authority, true),
-            AccountMeta::new(vm, false),
-            AccountMeta::new(vm_memory, false),
-            AccountMeta::new_readonly(swapper, false),
-            AccountMeta::new_readonly(swap_pda, false),
-            AccountMeta::new(swap_ata, false),
-            AccountMeta::new(omnibus, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
+        accounts,
         data: CancelSwapIx::from_struct(
             ParsedCancelSwapIx{
             account_index,
@@ -615,6 +1271,7 @@ pub fn cancel_swap(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn close_swap_account_if_empty(
     vm_authority: Pubkey,
     vm: Pubkey,
@@ -622,19 +1279,23 @@ pub fn close_swap_account_if_empty(
     swap_pda: Pubkey,
     swap_ata: Pubkey,
     destination: Pubkey,
+    pool: &MintPool,
     bump: u8,
 ) -> Instruction {
+    let mut accounts = vec![
+        AccountMeta::new(vm_authority, true),
+        AccountMeta::new(vm, false),
+        AccountMeta::new_readonly(swapper, false),
+        AccountMeta::new_readonly(swap_pda, false),
+        AccountMeta::new(swap_ata, false),
+        AccountMeta::new(destination, false),
+        AccountMeta::new_readonly(pool.token_program.id(), false),
+    ];
+    accounts.extend(pool_accounts(&vm, pool));
+
     Instruction {
         program_id: crate::ID,
-        accounts: vec![
-            AccountMeta::new(vm_authority, true),
-            AccountMeta::new(vm, false),
-            AccountMeta::new_readonly(swapper, false),
-            AccountMeta::new_readonly(swap_pda, false),
-            AccountMeta::new(swap_ata, false),
-            AccountMeta::new(destination, false),
-            AccountMeta::new_readonly(spl_token::id(), false),
-        ],
+        accounts,
         data: CloseSwapAccountIfEmptyIx::from_struct(
             ParsedCloseSwapAccountIfEmptyIx{
             bump,
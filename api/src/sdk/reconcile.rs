@@ -0,0 +1,764 @@
+use steel::*;
+use spl_token::instruction::TokenInstruction;
+use spl_token_2022::instruction::TokenInstruction as Token2022Instruction;
+use spl_token_2022::extension::transfer_fee::{
+    instruction::TransferFeeInstruction,
+    TransferFee,
+    TransferFeeConfig,
+};
+use spl_token_2022::extension::{BaseStateWithExtensions, StateWithExtensions};
+
+use crate::prelude::*;
+
+/// The direction tokens moved in or out of a relay vault.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelayFlow {
+    In,
+    Out,
+}
+
+/// A single movement of tokens in or out of a relay vault, recovered from
+/// instruction data.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RelayLedgerEntry {
+    pub flow: RelayFlow,
+    pub amount: u64,
+    pub source: RelayLedgerSource,
+}
+
+/// Where a ledger entry came from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RelayLedgerSource {
+    Opcode(Opcode),
+    Instruction(CodeInstruction),
+    Token,
+}
+
+/// The result of comparing the expected relay vault balance against the
+/// actual vault balance.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RelayReconciliationReport {
+    pub relay: Pubkey,
+    pub vault: Pubkey,
+    pub opening_balance: u64,
+    pub total_in: u128,
+    pub total_out: u128,
+    pub expected_balance: i128,
+    pub actual_balance: u64,
+
+    /// The actual balance minus the expected balance.
+    pub discrepancy: i128,
+
+    /// Number of instructions that touched the vault with an amount that
+    /// can't be recovered from instruction data (e.g. `ExternalWithdrawOp`,
+    /// or `CloseRelayIx` without a swept balance).
+    pub num_unresolved: usize,
+
+    /// Number of token program instructions that couldn't be decoded, and
+    /// were skipped.
+    pub num_unknown: usize,
+
+    /// Whether the relay was closed by one of the processed instructions.
+    pub closed: bool,
+}
+
+impl RelayReconciliationReport {
+    pub fn is_balanced(&self) -> bool {
+        self.discrepancy == 0 && self.num_unresolved == 0
+    }
+}
+
+/// Walks instructions that touched a relay and keeps a ledger of the tokens
+/// that moved in and out of its vault.
+///
+/// Instructions must be provided in execution order and should only include
+/// instructions that succeeded. Instructions that don't involve the relay
+/// vault are ignored.
+///
+/// Relays hold the VM mint, so only transfers of the VM mint are recorded.
+/// Opcodes that pay out of the omnibus of a registered mint, and token
+/// instructions for other mints, are ignored.
+pub struct RelayReconciliation {
+    pub relay: Pubkey,
+    pub vault: Pubkey,
+    pub pool: MintPool,
+    pub opening_balance: u64,
+    pub entries: Vec<RelayLedgerEntry>,
+    pub num_unresolved: usize,
+    pub num_unknown: usize,
+    pub closed: bool,
+
+    /// The vault balance observed right before `CloseRelayIx`, which is the
+    /// amount the close swept out of the vault.
+    pub swept_balance: Option<u64>,
+
+    /// The transfer fee of a Token-2022 mint, which is withheld from
+    /// transfers into the vault.
+    pub transfer_fee: Option<TransferFee>,
+}
+
+impl RelayReconciliation {
+    /// Creates a ledger for `relay`, where `pool` is the VM mint pool from
+    /// `MintPool::from_vm`.
+    pub fn new(
+        relay_address: &Pubkey,
+        relay: &RelayAccount,
+        pool: &MintPool,
+        opening_balance: u64,
+    ) -> Self {
+        Self {
+            relay: *relay_address,
+            vault: relay.treasury.vault,
+            pool: *pool,
+            opening_balance,
+            entries: vec![],
+            num_unresolved: 0,
+            num_unknown: 0,
+            closed: false,
+            swept_balance: None,
+            transfer_fee: None,
+        }
+    }
+
+    /// Sets the vault balance observed right before the relay was closed.
+    /// Without it, a `CloseRelayIx` is counted as unresolved.
+    pub fn with_swept_balance(mut self, swept_balance: u64) -> Self {
+        self.swept_balance = Some(swept_balance);
+        self
+    }
+
+    /// Reads the transfer fee in effect at `epoch` from the data of a
+    /// Token-2022 mint. Without it, transfers into the vault are recorded at
+    /// the amount sent, unless the instruction carries the fee.
+    pub fn with_transfer_fee(mut self, mint_data: &[u8], epoch: u64) -> Result<Self, ProgramError> {
+        let mint = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(mint_data)?;
+        self.transfer_fee = mint
+            .get_extension::<TransferFeeConfig>()
+            .ok()
+            .map(|config| *config.get_epoch_fee(epoch));
+        Ok(self)
+    }
+
+    pub fn total_in(&self) -> u128 {
+        self.sum(RelayFlow::In)
+    }
+
+    pub fn total_out(&self) -> u128 {
+        self.sum(RelayFlow::Out)
+    }
+
+    pub fn expected_balance(&self) -> i128 {
+        self.opening_balance as i128
+            + self.total_in() as i128
+            - self.total_out() as i128
+    }
+
+    /// Records a movement that isn't visible in VM or token program
+    /// instructions, for example a transfer made by another program.
+    pub fn record(&mut self, entry: RelayLedgerEntry) {
+        self.entries.push(entry);
+    }
+
+    pub fn process_instructions(&mut self, ixs: &[Instruction]) -> Result<(), ProgramError> {
+        for ix in ixs {
+            self.process_instruction(ix)?;
+        }
+        Ok(())
+    }
+
+    /// Processes a single instruction. Token program instructions that
+    /// can't be decoded are skipped and counted in `num_unknown`.
+    pub fn process_instruction(&mut self, ix: &Instruction) -> Result<(), ProgramError> {
+        if ix.program_id.eq(&crate::ID) {
+            self.process_vm_instruction(ix)
+        } else if ix.program_id.eq(&spl_token::id()) {
+            self.process_token_instruction(ix)
+        } else if ix.program_id.eq(&spl_token_2022::id()) {
+            self.process_token_2022_instruction(ix)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Compares the ledger against the actual vault balance. Use a balance of
+    /// zero if the vault was closed.
+    pub fn report(&self, actual_balance: u64) -> RelayReconciliationReport {
+        let expected_balance = self.expected_balance();
+
+        RelayReconciliationReport {
+            relay: self.relay,
+            vault: self.vault,
+            opening_balance: self.opening_balance,
+            total_in: self.total_in(),
+            total_out: self.total_out(),
+            expected_balance,
+            actual_balance,
+            discrepancy: actual_balance as i128 - expected_balance,
+            num_unresolved: self.num_unresolved,
+            num_unknown: self.num_unknown,
+            closed: self.closed,
+        }
+    }
+
+    fn sum(&self, flow: RelayFlow) -> u128 {
+        self.entries
+            .iter()
+            .filter(|e| e.flow == flow)
+            .map(|e| e.amount as u128)
+            .sum()
+    }
+
+    fn process_vm_instruction(&mut self, ix: &Instruction) -> Result<(), ProgramError> {
+        let (tag, data) = ix.data
+            .split_first()
+            .ok_or(ProgramError::InvalidInstructionData)?;
+
+        let ix_type = CodeInstruction::try_from(*tag)
+            .or(Err(ProgramError::InvalidInstructionData))?;
+
+        match ix_type {
+            CodeInstruction::ExecIx => {
+                let args = ExecIx::try_from_slice(data)
+                    .or(Err(ProgramError::InvalidInstructionData))?;
+                self.process_exec(ix, &args)
+            }
+            CodeInstruction::CloseRelayIx => {
+                if get_key(ix, 2).eq(&Some(self.relay)) {
+                    // The whole vault balance is swept, which is not part of
+                    // the instruction data
+                    match self.swept_balance {
+                        Some(swept) => self.push(
+                            RelayFlow::Out, swept, RelayLedgerSource::Instruction(ix_type)),
+                        None => self.num_unresolved += 1,
+                    }
+                    self.closed = true;
+                }
+                Ok(())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn process_exec(&mut self, ix: &Instruction, args: &ExecIxData) -> Result<(), ProgramError> {
+        let opcode = Opcode::try_from(args.opcode)
+            .or(Err(ProgramError::InvalidInstructionData))?;
+        let source = RelayLedgerSource::Opcode(opcode);
+
+        // Transfers out of the omnibus of a registered mint can't be for
+        // the relay
+        let external_address = get_key(ix, 9);
+        let to_vault = external_address.eq(&Some(self.vault)) &&
+            get_key(ix, 6).eq(&Some(self.pool.omnibus.vault));
+
+        match opcode {
+            Opcode::RelayOp => {
+                let op = RelayOp::unpack(&args.data)?;
+                if get_exec_relay(ix, op.relay_index).eq(&Some(self.relay)) {
+                    self.push(RelayFlow::Out, op.amount, source);
+                }
+            }
+            Opcode::ExternalRelayOp => {
+                let op = ExternalRelayOp::unpack(&args.data)?;
+                if get_exec_relay(ix, op.relay_index).eq(&Some(self.relay)) {
+                    self.push(RelayFlow::Out, op.amount, source);
+                }
+            }
+            Opcode::ConditionalTransferOp if to_vault => {
+                let op = ConditionalTransferOp::try_from_bytes(&args.data)?.to_struct()
+                    .or(Err(ProgramError::InvalidInstructionData))?;
+                let fee = self.get_transfer_fee(op.amount);
+                self.push(RelayFlow::In, op.amount.saturating_sub(fee), source);
+            }
+            Opcode::ExternalTransferOp if to_vault => {
+                let op = ExternalTransferOp::try_from_bytes(&args.data)?.to_struct()
+                    .or(Err(ProgramError::InvalidInstructionData))?;
+                let fee = self.get_transfer_fee(op.amount);
+                self.push(RelayFlow::In, op.amount.saturating_sub(fee), source);
+            }
+            Opcode::ExternalWithdrawOp if to_vault => {
+                // The amount is the full virtual account balance, which is
+                // not part of the instruction data
+                self.num_unresolved += 1;
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn process_token_instruction(&mut self, ix: &Instruction) -> Result<(), ProgramError> {
+        let source = RelayLedgerSource::Token;
+
+        let Ok(token_ix) = TokenInstruction::unpack(&ix.data) else {
+            self.num_unknown += 1;
+            return Ok(());
+        };
+
+        match token_ix {
+            TokenInstruction::Transfer { amount } => {
+                self.process_token_transfer(ix, 0, 1, amount, source);
+            }
+            TokenInstruction::TransferChecked { amount, .. } if self.is_pool_mint(ix, 1) => {
+                self.process_token_transfer(ix, 0, 2, amount, source);
+            }
+            TokenInstruction::MintTo { amount } |
+            TokenInstruction::MintToChecked { amount, .. }
+                if get_key(ix, 1).eq(&Some(self.vault)) => {
+                self.push(RelayFlow::In, amount, source);
+            }
+            TokenInstruction::Burn { amount } |
+            TokenInstruction::BurnChecked { amount, .. }
+                if get_key(ix, 0).eq(&Some(self.vault)) => {
+                self.push(RelayFlow::Out, amount, source);
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn process_token_2022_instruction(&mut self, ix: &Instruction) -> Result<(), ProgramError> {
+        let source = RelayLedgerSource::Token;
+
+        let Ok(token_ix) = Token2022Instruction::unpack(&ix.data) else {
+            self.num_unknown += 1;
+            return Ok(());
+        };
+
+        // The fee is withheld in the destination account
+        match token_ix {
+            #[allow(deprecated)]
+            Token2022Instruction::Transfer { amount } => {
+                let fee = self.get_transfer_fee(amount);
+                self.process_token_transfer_with_fee(ix, 0, 1, amount, fee, source);
+            }
+            Token2022Instruction::TransferChecked { amount, .. } if self.is_pool_mint(ix, 1) => {
+                let fee = self.get_transfer_fee(amount);
+                self.process_token_transfer_with_fee(ix, 0, 2, amount, fee, source);
+            }
+            Token2022Instruction::TransferFeeExtension if self.is_pool_mint(ix, 1) => {
+                let Ok(fee_ix) = TransferFeeInstruction::unpack(&ix.data[1..]) else {
+                    self.num_unknown += 1;
+                    return Ok(());
+                };
+
+                if let TransferFeeInstruction::TransferCheckedWithFee { amount, fee, .. } = fee_ix {
+                    self.process_token_transfer_with_fee(ix, 0, 2, amount, fee, source);
+                }
+            }
+            Token2022Instruction::MintTo { amount } |
+            Token2022Instruction::MintToChecked { amount, .. }
+                if get_key(ix, 1).eq(&Some(self.vault)) => {
+                self.push(RelayFlow::In, amount, source);
+            }
+            Token2022Instruction::Burn { amount } |
+            Token2022Instruction::BurnChecked { amount, .. }
+                if get_key(ix, 0).eq(&Some(self.vault)) => {
+                self.push(RelayFlow::Out, amount, source);
+            }
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    fn process_token_transfer(
+        &mut self,
+        ix: &Instruction,
+        src: usize,
+        dst: usize,
+        amount: u64,
+        source: RelayLedgerSource,
+    ) {
+        self.process_token_transfer_with_fee(ix, src, dst, amount, 0, source);
+    }
+
+    fn process_token_transfer_with_fee(
+        &mut self,
+        ix: &Instruction,
+        src: usize,
+        dst: usize,
+        amount: u64,
+        fee: u64,
+        source: RelayLedgerSource,
+    ) {
+        let from_vault = get_key(ix, src).eq(&Some(self.vault));
+        let to_vault = get_key(ix, dst).eq(&Some(self.vault));
+
+        if from_vault && !to_vault {
+            self.push(RelayFlow::Out, amount, source);
+        } else if to_vault && !from_vault {
+            self.push(RelayFlow::In, amount.saturating_sub(fee), source);
+        }
+    }
+
+    fn get_transfer_fee(&self, amount: u64) -> u64 {
+        self.transfer_fee
+            .and_then(|transfer_fee| transfer_fee.calculate_fee(amount))
+            .unwrap_or(0)
+    }
+
+    fn is_pool_mint(&self, ix: &Instruction, index: usize) -> bool {
+        get_key(ix, index).eq(&Some(self.pool.mint))
+    }
+
+    fn push(&mut self, flow: RelayFlow, amount: u64, source: RelayLedgerSource) {
+        self.entries.push(RelayLedgerEntry { flow, amount, source });
+    }
+}
+
+fn get_key(ix: &Instruction, index: usize) -> Option<Pubkey> {
+    ix.accounts
+        .get(index)
+        .map(|meta| meta.pubkey)
+        .filter(|key| key.ne(&crate::ID))
+}
+
+/// Returns the relay at `index` in the relay set of a `vm_exec` instruction.
+/// See `with_exec_relays` for the account layout.
+fn get_exec_relay(ix: &Instruction, index: u8) -> Option<Pubkey> {
+    match index {
+        0 => get_key(ix, 7),
+        _ => get_key(ix, 12 + (index as usize - 1) * 3),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_test_relay() -> (Pubkey, RelayAccount, MintPool) {
+        let relay_address = Pubkey::new_unique();
+        let mut relay = RelayAccount::zeroed();
+        relay.treasury.vault = Pubkey::new_unique();
+        (relay_address, relay, create_test_pool())
+    }
+
+    fn create_test_pool() -> MintPool {
+        MintPool {
+            mint: Pubkey::new_unique(),
+            token_program: TokenProgram::Token,
+            omnibus: TokenPool {
+                vault: Pubkey::new_unique(),
+                vault_bump: 0,
+            },
+            is_registered: false,
+        }
+    }
+
+    fn create_exec(
+        pool: &MintPool,
+        relay: Option<Pubkey>,
+        external: Option<Pubkey>,
+        data: Vec<u8>,
+    ) -> Instruction {
+        vm_exec(
+            Pubkey::new_unique(),
+            Pubkey::new_unique(),
+            None,
+            None,
+            None,
+            None,
+            Some(pool.omnibus.vault),
+            relay,
+            None,
+            external,
+            Some(spl_token::id()),
+            data[0],
+            vec![],
+            vec![],
+            data[1..].to_vec(),
+        )
+    }
+
+    fn create_relay_op(amount: u64, relay_index: u8) -> Vec<u8> {
+        RelayOp::from_struct(ParsedRelayOp {
+            amount,
+            transcript: Hash::default(),
+            recent_root: Hash::default(),
+            commitment: Pubkey::default(),
+            relay_index,
+        }).to_bytes()
+    }
+
+    fn create_conditional_transfer(amount: u64) -> Vec<u8> {
+        ConditionalTransferOp::from_struct(ParsedConditionalTransferOp {
+            signature: [0; 64],
+            amount,
+        }).to_bytes()
+    }
+
+    #[test]
+    fn test_balanced_relay() {
+        let (relay_address, relay, pool) = create_test_relay();
+        let vault = relay.treasury.vault;
+
+        let mut recon = RelayReconciliation::new(&relay_address, &relay, &pool, 1000);
+
+        recon.process_instructions(&[
+            create_exec(&pool, Some(relay_address), None, create_relay_op(42, 0)),
+            create_exec(&pool, None, Some(vault), create_conditional_transfer(42)),
+            create_exec(&pool, Some(relay_address), None, create_relay_op(100, 0)),
+        ]).unwrap();
+
+        assert_eq!(recon.total_out(), 142);
+        assert_eq!(recon.total_in(), 42);
+
+        let report = recon.report(900);
+        assert_eq!(report.expected_balance, 900);
+        assert!(report.is_balanced());
+    }
+
+    #[test]
+    fn test_legacy_relay_op() {
+        let (relay_address, relay, pool) = create_test_relay();
+        let mut data = create_relay_op(42, 0);
+        data.pop();
+
+        let mut recon = RelayReconciliation::new(&relay_address, &relay, &pool, 1000);
+        recon.process_instruction(&create_exec(&pool, Some(relay_address), None, data)).unwrap();
+
+        assert_eq!(recon.total_out(), 42);
+    }
+
+    #[test]
+    fn test_discrepancy() {
+        let (relay_address, relay, pool) = create_test_relay();
+
+        let mut recon = RelayReconciliation::new(&relay_address, &relay, &pool, 1000);
+        recon.process_instruction(
+            &create_exec(&pool, Some(relay_address), None, create_relay_op(42, 0))
+        ).unwrap();
+
+        let report = recon.report(900);
+        assert!(!report.is_balanced());
+        assert_eq!(report.discrepancy, -58);
+    }
+
+    #[test]
+    fn test_ignores_other_relays() {
+        let (relay_address, relay, pool) = create_test_relay();
+        let other_relay = Pubkey::new_unique();
+
+        let mut recon = RelayReconciliation::new(&relay_address, &relay, &pool, 1000);
+        recon.process_instructions(&[
+            // Another relay at index 0
+            create_exec(&pool, Some(other_relay), None, create_relay_op(42, 0)),
+
+            // This relay as an extra relay at index 1
+            with_exec_relay_set(
+                create_exec(&pool, Some(other_relay), None, create_relay_op(7, 1)),
+                &[relay_address],
+            ),
+
+            // A conditional transfer to some other account
+            create_exec(&pool, None, Some(Pubkey::new_unique()), create_conditional_transfer(5)),
+        ]).unwrap();
+
+        assert_eq!(recon.total_out(), 7);
+        assert_eq!(recon.total_in(), 0);
+        assert!(recon.report(993).is_balanced());
+    }
+
+    #[test]
+    fn test_token_instructions() {
+        let (relay_address, relay, pool) = create_test_relay();
+        let vault = relay.treasury.vault;
+        let mint = pool.mint;
+        let owner = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        let mut recon = RelayReconciliation::new(&relay_address, &relay, &pool, 0);
+        recon.process_instructions(&[
+            spl_token::instruction::mint_to(
+                &spl_token::id(), &mint, &vault, &owner, &[], 500).unwrap(),
+            spl_token::instruction::transfer(
+                &spl_token::id(), &other, &vault, &owner, &[], 25).unwrap(),
+            spl_token::instruction::transfer(
+                &spl_token::id(), &vault, &other, &owner, &[], 10).unwrap(),
+        ]).unwrap();
+
+        assert_eq!(recon.total_in(), 525);
+        assert_eq!(recon.total_out(), 10);
+        assert!(recon.report(515).is_balanced());
+    }
+
+    #[test]
+    fn test_closed_relay() {
+        let (relay_address, relay, pool) = create_test_relay();
+        let vault = relay.treasury.vault;
+
+        let mut recon = RelayReconciliation::new(&relay_address, &relay, &pool, 1000);
+        recon.process_instructions(&[
+            create_exec(&pool, Some(relay_address), None, create_relay_op(42, 0)),
+            relay_close(
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                relay_address,
+                vault,
+                &pool,
+            ),
+        ]).unwrap();
+
+        // The swept amount is unknown without the observed balance
+        let report = recon.report(0);
+        assert!(report.closed);
+        assert_eq!(report.num_unresolved, 1);
+        assert!(!report.is_balanced());
+    }
+
+    #[test]
+    fn test_closed_relay_with_swept_balance() {
+        let (relay_address, relay, pool) = create_test_relay();
+        let vault = relay.treasury.vault;
+
+        // The vault held more than the ledger expected when it was closed
+        let mut recon = RelayReconciliation::new(&relay_address, &relay, &pool, 1000)
+            .with_swept_balance(960);
+        recon.process_instructions(&[
+            create_exec(&pool, Some(relay_address), None, create_relay_op(42, 0)),
+            relay_close(
+                Pubkey::new_unique(),
+                Pubkey::new_unique(),
+                relay_address,
+                vault,
+                &pool,
+            ),
+        ]).unwrap();
+
+        let report = recon.report(0);
+        assert!(report.closed);
+        assert_eq!(report.total_out, 1002);
+        assert_eq!(report.discrepancy, 2);
+    }
+
+    #[test]
+    fn test_token_2022_instructions() {
+        let (relay_address, relay, pool) = create_test_relay();
+        let vault = relay.treasury.vault;
+        let mint = pool.mint;
+        let owner = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let token_program = spl_token_2022::id();
+
+        let mut recon = RelayReconciliation::new(&relay_address, &relay, &pool, 0);
+        recon.process_instructions(&[
+            spl_token_2022::instruction::mint_to(
+                &token_program, &mint, &vault, &owner, &[], 500).unwrap(),
+            spl_token_2022::instruction::transfer_checked(
+                &token_program, &vault, &mint, &other, &owner, &[], 10, 6).unwrap(),
+            spl_token_2022::extension::transfer_fee::instruction::transfer_checked_with_fee(
+                &token_program, &other, &mint, &vault, &owner, &[], 100, 6, 3).unwrap(),
+        ]).unwrap();
+
+        assert_eq!(recon.total_in(), 597);
+        assert_eq!(recon.total_out(), 10);
+        assert!(recon.report(587).is_balanced());
+    }
+
+    #[test]
+    fn test_totals_do_not_overflow() {
+        let (relay_address, relay, pool) = create_test_relay();
+
+        let mut recon = RelayReconciliation::new(&relay_address, &relay, &pool, 0);
+        recon.process_instructions(&[
+            create_exec(&pool, Some(relay_address), None, create_relay_op(u64::MAX, 0)),
+            create_exec(&pool, Some(relay_address), None, create_relay_op(u64::MAX, 0)),
+        ]).unwrap();
+
+        assert_eq!(recon.total_out(), u64::MAX as u128 * 2);
+        assert_eq!(recon.report(0).expected_balance, -(u64::MAX as i128 * 2));
+    }
+
+    #[test]
+    fn test_ignores_other_pools() {
+        let (relay_address, relay, pool) = create_test_relay();
+        let vault = relay.treasury.vault;
+        let other_pool = create_test_pool();
+        let owner = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+
+        let mut recon = RelayReconciliation::new(&relay_address, &relay, &pool, 0);
+        recon.process_instructions(&[
+            // Paid out of the omnibus of a registered mint
+            create_exec(&other_pool, None, Some(vault), create_conditional_transfer(5)),
+
+            // A transfer of another mint
+            spl_token::instruction::transfer_checked(
+                &spl_token::id(), &other, &other_pool.mint, &vault, &owner, &[], 25, 6).unwrap(),
+
+            create_exec(&pool, None, Some(vault), create_conditional_transfer(42)),
+        ]).unwrap();
+
+        assert_eq!(recon.total_in(), 42);
+        assert!(recon.report(42).is_balanced());
+    }
+
+    #[test]
+    fn test_skips_unknown_token_instructions() {
+        let (relay_address, relay, pool) = create_test_relay();
+        let unknown = |program_id: Pubkey| Instruction {
+            program_id,
+            accounts: vec![],
+            data: vec![255],
+        };
+
+        let mut recon = RelayReconciliation::new(&relay_address, &relay, &pool, 1000);
+        recon.process_instructions(&[
+            unknown(spl_token::id()),
+            unknown(spl_token_2022::id()),
+            create_exec(&pool, Some(relay_address), None, create_relay_op(42, 0)),
+        ]).unwrap();
+
+        let report = recon.report(958);
+        assert_eq!(report.num_unknown, 2);
+        assert_eq!(report.total_out, 42);
+        assert!(report.is_balanced());
+    }
+
+    #[test]
+    fn test_token_2022_transfer_fee() {
+        use spl_token_2022::extension::{
+            BaseStateWithExtensionsMut,
+            ExtensionType,
+            StateWithExtensionsMut,
+        };
+        use spl_token_2022::state::Mint;
+
+        let (relay_address, relay, pool) = create_test_relay();
+        let vault = relay.treasury.vault;
+        let mint = pool.mint;
+        let owner = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let token_program = spl_token_2022::id();
+
+        // A mint that charges 1% on transfers
+        let len = ExtensionType::try_calculate_account_len::<Mint>(
+            &[ExtensionType::TransferFeeConfig]).unwrap();
+        let mut mint_data = vec![0; len];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut mint_data).unwrap();
+        let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        config.newer_transfer_fee.transfer_fee_basis_points = 100.into();
+        config.newer_transfer_fee.maximum_fee = u64::MAX.into();
+        state.base = Mint { decimals: 6, is_initialized: true, ..Default::default() };
+        state.pack_base();
+        state.init_account_type().unwrap();
+
+        let mut recon = RelayReconciliation::new(&relay_address, &relay, &pool, 0)
+            .with_transfer_fee(&mint_data, 0)
+            .unwrap();
+        recon.process_instructions(&[
+            spl_token_2022::instruction::transfer_checked(
+                &token_program, &other, &mint, &vault, &owner, &[], 1000, 6).unwrap(),
+            create_exec(&pool, None, Some(vault), create_conditional_transfer(500)),
+
+            // The sender pays the fee on the way out
+            spl_token_2022::instruction::transfer_checked(
+                &token_program, &vault, &mint, &other, &owner, &[], 100, 6).unwrap(),
+        ]).unwrap();
+
+        assert_eq!(recon.total_in(), 990 + 495);
+        assert_eq!(recon.total_out(), 100);
+        assert!(recon.report(1385).is_balanced());
+    }
+}
@@ -0,0 +1,264 @@
+use steel::*;
+
+use crate::prelude::*;
+
+/// Everything a client needs to make a private payment through a relay.
+///
+/// The relay pays `destination` out of its treasury, and the `source` then
+/// repays the relay with a conditional transfer to `target`. The program
+/// writes `vra` into memory once the `RelayOp`/`ExternalRelayOp` executes.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RelayPayment {
+    pub amount: u64,
+    pub transcript: Hash,
+    pub recent_root: Hash,
+    pub commitment: Pubkey,
+    pub proof: Pubkey,
+    pub target: Pubkey,
+    pub vra: VirtualRelayAccount,
+}
+
+impl RelayPayment {
+    /// Builds a payment against the most recent root saved by the relay.
+    pub fn new(
+        relay_address: &Pubkey,
+        relay: &RelayAccount,
+        source: &Pubkey,
+        destination: &Pubkey,
+        amount: u64,
+    ) -> Self {
+        Self::new_with_root(
+            relay_address,
+            relay,
+            &relay.get_recent_root(),
+            source,
+            destination,
+            amount,
+        )
+    }
+
+    /// Builds a payment against a specific root. The root must still be in
+    /// the relay `recent_roots` list when the opcode is executed.
+    pub fn new_with_root(
+        relay_address: &Pubkey,
+        relay: &RelayAccount,
+        recent_root: &Hash,
+        source: &Pubkey,
+        destination: &Pubkey,
+        amount: u64,
+    ) -> Self {
+        let transcript = create_relay_transcript(source, destination, amount);
+
+        let (commitment, _) = find_relay_commitment_address(
+            relay_address,
+            recent_root,
+            &transcript,
+            destination,
+            amount,
+        );
+
+        let (proof, _) = find_relay_proof_address(
+            relay_address,
+            recent_root,
+            &commitment,
+        );
+
+        let (target, _) = find_relay_destination(&proof);
+
+        Self {
+            amount,
+            transcript,
+            recent_root: *recent_root,
+            commitment,
+            proof,
+            target,
+            vra: VirtualRelayAccount {
+                target,
+                destination: relay.treasury.vault,
+            },
+        }
+    }
+
+    /// The `relay_index` selects this relay from the exec relay set.
+    pub fn to_relay_op(&self, relay_index: u8) -> RelayOp {
+        RelayOp::from_struct(ParsedRelayOp {
+            amount: self.amount,
+            transcript: self.transcript,
+            recent_root: self.recent_root,
+            commitment: self.commitment,
+            relay_index,
+        })
+    }
+
+    pub fn to_external_relay_op(&self, relay_index: u8) -> ExternalRelayOp {
+        ExternalRelayOp::from_struct(ParsedExternalRelayOp {
+            amount: self.amount,
+            transcript: self.transcript,
+            recent_root: self.recent_root,
+            commitment: self.commitment,
+            relay_index,
+        })
+    }
+}
+
+/// The transcript binds the (hidden) source to the payment. Only its hash is
+/// revealed on-chain as part of the commitment seeds.
+pub fn create_relay_transcript(
+    source: &Pubkey,
+    destination: &Pubkey,
+    amount: u64,
+) -> Hash {
+    hashv(&[
+        b"transfer",
+        source.as_ref(),
+        destination.as_ref(),
+        &amount.to_le_bytes(),
+    ])
+}
+
+/// Creates a relay payment from `src_vta` to the virtual account `dst_vta`,
+/// to be executed with a `RelayOp`.
+pub fn create_relay_payment(
+    vm: &CodeVmAccount,
+    relay_address: &Pubkey,
+    relay: &RelayAccount,
+    src_vta: &VirtualTimelockAccount,
+    dst_vta: &VirtualTimelockAccount,
+    amount: u64,
+) -> RelayPayment {
+    let source = get_vta_token_address(vm, src_vta);
+    let destination = get_vta_token_address(vm, dst_vta);
+
+    RelayPayment::new(relay_address, relay, &source, &destination, amount)
+}
+
+/// Creates a relay payment from `src_vta` to a real token account, to be
+/// executed with an `ExternalRelayOp`.
+pub fn create_external_relay_payment(
+    vm: &CodeVmAccount,
+    relay_address: &Pubkey,
+    relay: &RelayAccount,
+    src_vta: &VirtualTimelockAccount,
+    destination: &Pubkey,
+    amount: u64,
+) -> RelayPayment {
+    let source = get_vta_token_address(vm, src_vta);
+
+    RelayPayment::new(relay_address, relay, &source, destination, amount)
+}
+
+fn get_vta_token_address(vm: &CodeVmAccount, vta: &VirtualTimelockAccount) -> Pubkey {
+    let timelock_address = vta.get_timelock_address(
+        &vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        vta.get_lock_duration(vm.get_lock_duration()),
+    );
+    vta.get_token_address(&timelock_address)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    fn create_test_relay() -> (Pubkey, RelayAccount) {
+        let relay_address = Pubkey::new_unique();
+        let mut relay = RelayAccount::zeroed();
+        relay.treasury.vault = Pubkey::new_unique();
+        relay.history.init(&[b"relay_test"]);
+        relay.save_recent_root();
+        (relay_address, relay)
+    }
+
+    #[test]
+    fn test_relay_payment_matches_program_derivation() {
+        // Fixed vectors; a change to any seed or to the transcript layout
+        // must show up here, since the program derives the same addresses.
+        let relay_address = Pubkey::new_from_array([1; 32]);
+        let mut relay = RelayAccount::zeroed();
+        relay.treasury.vault = Pubkey::new_from_array([2; 32]);
+        let recent_root = Hash::new_from_array([3; 32]);
+        let source = Pubkey::new_from_array([4; 32]);
+        let destination = Pubkey::new_from_array([5; 32]);
+
+        let payment = RelayPayment::new_with_root(
+            &relay_address, &relay, &recent_root, &source, &destination, 42);
+
+        let expected = |s: &str| Pubkey::from_str(s).unwrap();
+
+        assert_eq!(payment.recent_root, recent_root);
+        assert_eq!(
+            Pubkey::new_from_array(payment.transcript.to_bytes()),
+            expected("41ZfotzwtTfUk3bNWbkEEvPca47G8WRihRMQ9LQK4Gwu"));
+        assert_eq!(
+            payment.commitment,
+            expected("5FQc3XB7HekDYo23qdCPiVwxmPgoTiwMoatL5LpkAzTy"));
+        assert_eq!(
+            payment.proof,
+            expected("AvtopdxjQGJuS6ceQZ6pTodTQGrrXbySiyZ3U7BUevZ"));
+        assert_eq!(
+            payment.vra.target,
+            expected("BayDiGHGax2hMSiuug4Nh2JiBUzV1caAJ8wpghZg1rGm"));
+        assert_eq!(payment.vra.destination, relay.treasury.vault);
+    }
+
+    #[test]
+    fn test_relay_payment_ops() {
+        let (relay_address, relay) = create_test_relay();
+        let payment = RelayPayment::new(
+            &relay_address,
+            &relay,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            1_000,
+        );
+
+        let op = payment.to_relay_op(0).to_struct().unwrap();
+        assert_eq!(op.amount, payment.amount);
+        assert_eq!(op.transcript, payment.transcript);
+        assert_eq!(op.recent_root, payment.recent_root);
+        assert_eq!(op.commitment, payment.commitment);
+        assert_eq!(op.relay_index, 0);
+
+        let op = payment.to_external_relay_op(2).to_struct().unwrap();
+        assert_eq!(op.amount, payment.amount);
+        assert_eq!(op.commitment, payment.commitment);
+        assert_eq!(op.relay_index, 2);
+    }
+
+    #[test]
+    fn test_relay_op_accepts_legacy_layout() {
+        let (relay_address, relay) = create_test_relay();
+        let payment = RelayPayment::new(
+            &relay_address,
+            &relay,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            1_000,
+        );
+
+        let data = payment.to_relay_op(3).to_bytes();
+        let op = RelayOp::unpack(&data[1..]).unwrap();
+        assert_eq!(op.relay_index, 3);
+
+        // Without the trailing relay_index the first relay is used
+        let op = RelayOp::unpack(&data[1..data.len() - 1]).unwrap();
+        assert_eq!(op.commitment, payment.commitment);
+        assert_eq!(op.relay_index, 0);
+
+        let data = payment.to_external_relay_op(3).to_bytes();
+        let op = ExternalRelayOp::unpack(&data[1..data.len() - 1]).unwrap();
+        assert_eq!(op.amount, payment.amount);
+        assert_eq!(op.relay_index, 0);
+
+        assert!(RelayOp::unpack(&data[1..data.len() - 2]).is_err());
+    }
+
+    #[test]
+    fn test_relay_transcript_hides_source() {
+        let destination = Pubkey::new_unique();
+        let a = create_relay_transcript(&Pubkey::new_unique(), &destination, 5);
+        let b = create_relay_transcript(&Pubkey::new_unique(), &destination, 5);
+        assert_ne!(a, b);
+    }
+}
@@ -0,0 +1,75 @@
+use crate::prelude::*;
+
+/// A snapshot of a vesting account at a point in time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VestingStatus {
+    pub total: u64,
+    pub vested: u64,
+    pub claimed: u64,
+    pub claimable: u64,
+    pub remaining: u64,
+}
+
+impl VestingStatus {
+    pub fn new(vesting: &VirtualVestingAccount, now: i64) -> Self {
+        Self {
+            total: vesting.total,
+            vested: vesting.get_vested_amount(now),
+            claimed: vesting.claimed,
+            claimable: vesting.get_claimable_amount(now),
+            remaining: vesting.get_remaining_amount(),
+        }
+    }
+}
+
+/// Builds the `ClaimVestedOp` opcode data. The `mem_indicies` for the exec
+/// are the vesting account followed by the beneficiary's timelock account.
+pub fn create_claim_vested_op() -> Vec<u8> {
+    ClaimVestedOp {}.to_bytes()
+}
+
+/// Reads a vesting account out of raw `MemoryAccount` data, as returned by an
+/// RPC node. Returns `None` if the slot is empty or holds another kind of
+/// virtual account.
+pub fn read_virtual_vesting(
+    memory_data: &[u8],
+    account_index: u16,
+) -> Option<VirtualVestingAccount> {
+    let memory = MemoryAccount::unpack(memory_data);
+    let capacity = memory.get_capacity();
+    let account_size = memory.get_account_size();
+
+    let data = memory_data.get(MemoryAccount::get_size()..)?;
+    let mem = SliceAllocator::try_from_slice(data, capacity, account_size).ok()?;
+    let va = VirtualAccount::unpack(&mem.read_item(account_index)?).ok()?;
+
+    va.into_inner_vesting()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use steel::Pubkey;
+
+    #[test]
+    fn test_vesting_status() {
+        let vesting = VirtualVestingAccount {
+            beneficiary: Pubkey::new_unique(),
+            total: 1_000,
+            claimed: 100,
+            start: 0,
+            cliff: 100,
+            end: 1_000,
+        };
+
+        let status = VestingStatus::new(&vesting, 500);
+
+        assert_eq!(status, VestingStatus {
+            total: 1_000,
+            vested: 500,
+            claimed: 100,
+            claimable: 400,
+            remaining: 900,
+        });
+    }
+}
@@ -2,10 +2,15 @@
 use steel::*;
 use crate::cvm::{
     CodeVmAccount, 
+    DepositReceiptAccount, 
+    FeeConfigAccount, 
     MemoryAccount, 
     RelayAccount, 
+    RelayNullifierAccount, 
     StorageAccount, 
     UnlockStateAccount, 
+    VelocityLimitAccount, 
+    VmMintAccount, 
     WithdrawReceiptAccount
 };
 
@@ -19,6 +24,11 @@ pub enum AccountType {
     RelayAccount,
     UnlockStateAccount,
     WithdrawReceiptAccount,
+    RelayNullifierAccount,
+    FeeConfigAccount,
+    VelocityLimitAccount,
+    VmMintAccount,
+    DepositReceiptAccount,
 }
 
 
@@ -27,4 +37,9 @@ account!(AccountType, MemoryAccount);
 account!(AccountType, StorageAccount);
 account!(AccountType, RelayAccount);
 account!(AccountType, UnlockStateAccount);
-account!(AccountType, WithdrawReceiptAccount);
\ No newline at end of file
+account!(AccountType, WithdrawReceiptAccount);
+account!(AccountType, RelayNullifierAccount);
+account!(AccountType, FeeConfigAccount);
+account!(AccountType, VelocityLimitAccount);
+account!(AccountType, VmMintAccount);
+account!(AccountType, DepositReceiptAccount);
\ No newline at end of file
@@ -0,0 +1,112 @@
+use bytemuck::{Pod, Zeroable};
+
+/// Number of bit positions checked per value.
+const NUM_HASHES: usize = 8;
+
+/// A fixed size bloom filter over 32 byte values.
+///
+/// The values inserted are expected to already be uniformly distributed (for
+/// example, PDAs or hashes), so the bit positions are taken directly from
+/// the value instead of re-hashing it. This keeps lookups cheap on-chain.
+#[repr(C, align(8))]
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct BloomFilter<const N: usize> {
+    pub bits: [u8; N],
+}
+
+unsafe impl<const N: usize> Zeroable for BloomFilter<N> {}
+unsafe impl<const N: usize> Pod for BloomFilter<N> {}
+
+impl<const N: usize> BloomFilter<N> {
+    pub fn new() -> Self {
+        Self { bits: [0; N] }
+    }
+
+    pub const fn get_size() -> usize {
+        std::mem::size_of::<Self>()
+    }
+
+    pub const fn num_bits() -> usize {
+        N * 8
+    }
+
+    fn positions(val: &[u8; 32]) -> [usize; NUM_HASHES] {
+        let mut positions = [0usize; NUM_HASHES];
+        for (i, position) in positions.iter_mut().enumerate() {
+            let chunk = &val[i * 4..(i + 1) * 4];
+            let x = u32::from_le_bytes(chunk.try_into().unwrap());
+            *position = x as usize % Self::num_bits();
+        }
+        positions
+    }
+
+    /// Returns true if `val` may have been inserted. False positives are
+    /// possible, false negatives are not.
+    pub fn contains(&self, val: &[u8; 32]) -> bool {
+        Self::positions(val)
+            .iter()
+            .all(|&pos| self.bits[pos / 8] & (1 << (pos % 8)) != 0)
+    }
+
+    pub fn insert(&mut self, val: &[u8; 32]) {
+        for pos in Self::positions(val) {
+            self.bits[pos / 8] |= 1 << (pos % 8);
+        }
+    }
+}
+
+impl<const N: usize> Default for BloomFilter<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utils::hash;
+
+    type TestFilter = BloomFilter<1024>;
+
+    #[test]
+    fn test_empty_filter() {
+        let filter = TestFilter::new();
+        assert!(!filter.contains(&[1; 32]));
+        assert!(!filter.contains(&[0; 32]));
+    }
+
+    #[test]
+    fn test_insert_and_contains() {
+        let mut filter = TestFilter::new();
+
+        let item1 = hash(b"item1").to_bytes();
+        let item2 = hash(b"item2").to_bytes();
+
+        filter.insert(&item1);
+
+        assert!(filter.contains(&item1));
+        assert!(!filter.contains(&item2));
+
+        filter.insert(&item2);
+
+        assert!(filter.contains(&item1));
+        assert!(filter.contains(&item2));
+    }
+
+    #[test]
+    fn test_no_false_negatives() {
+        let mut filter = TestFilter::new();
+
+        let items: Vec<[u8; 32]> = (0u32..200)
+            .map(|i| hash(&i.to_le_bytes()).to_bytes())
+            .collect();
+
+        for item in items.iter() {
+            filter.insert(item);
+        }
+
+        for item in items.iter() {
+            assert!(filter.contains(item));
+        }
+    }
+}
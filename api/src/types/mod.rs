@@ -1,9 +1,11 @@
+pub mod bloom_filter;
 pub mod circular_buffer;
 pub mod merkle_tree;
 pub mod signature;
 pub mod slice_allocator;
 pub mod hash;
 
+pub use bloom_filter::*;
 pub use circular_buffer::*;
 pub use merkle_tree::*;
 pub use signature::*;
@@ -1,5 +1,7 @@
 mod hash;
+mod precompile;
 mod signature;
 
 pub use hash::*;
+pub use precompile::*;
 pub use signature::*;
\ No newline at end of file
@@ -0,0 +1,145 @@
+use steel::*;
+use solana_program::{
+    ed25519_program,
+    sysvar::instructions::{load_current_index_checked, load_instruction_at_checked},
+};
+
+const ED25519_SIG_LEN: usize = 64;
+const ED25519_PUBKEY_LEN: usize = 32;
+
+/// Header is the signature count followed by a padding byte.
+const SIGNATURE_OFFSETS_START: usize = 2;
+const SIGNATURE_OFFSETS_LEN: usize = 14;
+
+/// Instruction index the Ed25519 program uses to refer to its own data.
+const CURRENT_INSTRUCTION: u16 = u16::MAX;
+
+/// Verify an ed25519 signature by finding an Ed25519 program instruction
+/// earlier in the transaction that covers it.
+///
+/// The runtime verifies every precompile instruction before the transaction
+/// executes, so the signature is valid if one of them references exactly
+/// this pubkey, signature and message. Only entries that keep their data
+/// inside the Ed25519 instruction itself are considered.
+pub fn sig_verify_precompile(
+    instructions_info: &AccountInfo,
+    pubkey: &[u8],
+    sig: &[u8],
+    message: &[u8],
+) -> Result<(), ProgramError> {
+    if pubkey.len() != ED25519_PUBKEY_LEN {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if sig.len() != ED25519_SIG_LEN {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let current_index = load_current_index_checked(instructions_info)?;
+
+    for index in 0..current_index {
+        let ix = load_instruction_at_checked(index as usize, instructions_info)?;
+
+        if ix.program_id != ed25519_program::ID {
+            continue;
+        }
+
+        if has_signature(&ix.data, pubkey, sig, message) {
+            return Ok(());
+        }
+    }
+
+    Err(ProgramError::MissingRequiredSignature)
+}
+
+/// Check whether Ed25519 program instruction data contains an entry for the
+/// given pubkey, signature and message.
+fn has_signature(data: &[u8], pubkey: &[u8], sig: &[u8], message: &[u8]) -> bool {
+    let count = match data.first() {
+        Some(count) => *count as usize,
+        None => return false,
+    };
+
+    for i in 0..count {
+        let start = SIGNATURE_OFFSETS_START + i * SIGNATURE_OFFSETS_LEN;
+        let offsets = match data.get(start..start + SIGNATURE_OFFSETS_LEN) {
+            Some(offsets) => offsets,
+            None => return false,
+        };
+
+        let read = |at: usize| u16::from_le_bytes([offsets[at], offsets[at + 1]]);
+
+        let sig_offset = read(0) as usize;
+        let sig_ix = read(2);
+        let pubkey_offset = read(4) as usize;
+        let pubkey_ix = read(6);
+        let message_offset = read(8) as usize;
+        let message_size = read(10) as usize;
+        let message_ix = read(12);
+
+        if sig_ix != CURRENT_INSTRUCTION
+            || pubkey_ix != CURRENT_INSTRUCTION
+            || message_ix != CURRENT_INSTRUCTION
+        {
+            continue;
+        }
+
+        let found_sig = data.get(sig_offset..sig_offset + ED25519_SIG_LEN);
+        let found_pubkey = data.get(pubkey_offset..pubkey_offset + ED25519_PUBKEY_LEN);
+        let found_message = data.get(message_offset..message_offset + message_size);
+
+        if found_sig == Some(sig)
+            && found_pubkey == Some(pubkey)
+            && found_message == Some(message)
+        {
+            return true;
+        }
+    }
+
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::{
+        ed25519_instruction::new_ed25519_instruction,
+        signature::{Keypair, Signer},
+    };
+
+    #[test]
+    fn test_has_signature() {
+        let keypair = Keypair::new();
+        let dalek = ed25519_dalek::Keypair::from_bytes(&keypair.to_bytes()).unwrap();
+
+        let message = [7u8; 32];
+        let ix = new_ed25519_instruction(&dalek, &message);
+
+        let pubkey = keypair.pubkey().to_bytes();
+        let sig = keypair.sign_message(&message);
+
+        assert!(has_signature(&ix.data, &pubkey, sig.as_ref(), &message));
+        assert!(!has_signature(&ix.data, &pubkey, sig.as_ref(), &[8u8; 32]));
+        assert!(!has_signature(&ix.data, &Keypair::new().pubkey().to_bytes(), sig.as_ref(), &message));
+        assert!(!has_signature(&ix.data[..20], &pubkey, sig.as_ref(), &message));
+        assert!(!has_signature(&[], &pubkey, sig.as_ref(), &message));
+    }
+
+    #[test]
+    fn test_has_signature_rejects_external_data() {
+        let keypair = Keypair::new();
+        let dalek = ed25519_dalek::Keypair::from_bytes(&keypair.to_bytes()).unwrap();
+
+        let message = [7u8; 32];
+        let mut ix = new_ed25519_instruction(&dalek, &message);
+
+        // Point the message at another instruction
+        ix.data[SIGNATURE_OFFSETS_START + 12..SIGNATURE_OFFSETS_START + 14]
+            .copy_from_slice(&0u16.to_le_bytes());
+
+        let pubkey = keypair.pubkey().to_bytes();
+        let sig = keypair.sign_message(&message);
+
+        assert!(!has_signature(&ix.data, &pubkey, sig.as_ref(), &message));
+    }
+}
@@ -2,11 +2,21 @@ use steel::*;
 use std::mem::MaybeUninit;
 use curve25519_dalek::scalar::Scalar;
 use solana_ed25519_sha512::hash;
+use sha2::{Digest, Sha512};
 use solana_curve25519::{
-    edwards::{ subtract_edwards, multiply_edwards, validate_edwards, PodEdwardsPoint },
+    edwards::{
+        subtract_edwards,
+        multiply_edwards,
+        multiscalar_multiply_edwards,
+        validate_edwards,
+        PodEdwardsPoint,
+    },
     scalar::PodScalar,
 };
 
+use super::hashv;
+use crate::types::Hash;
+
 const ED25519_SIG_LEN: usize = 64;
 const ED25519_PUBKEY_LEN: usize = 32;
 
@@ -16,9 +26,37 @@ const G: [u8; 32] = [
     102, 102, 102, 102, 102, 102, 102, 102, 102, 102, 102, 102, 102, 102, 102, 102
 ];
 
-/// Verify an ed25519 signature.
-#[allow(non_snake_case)]
+/// Verify an ed25519 signature over a 32-byte message.
 pub fn sig_verify(pubkey: &[u8], sig: &[u8], message: &[u8]) -> Result<(), ProgramError> {
+    if message.len() != 32 {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Optimized single-round SHA-512, only possible for 32-byte messages.
+    verify(pubkey, sig, |r, pubkey| {
+        hash(r, pubkey, message.try_into().unwrap())
+    })
+}
+
+/// Verify an ed25519 signature over a message of any length.
+///
+/// The SHA-512 is computed in software, so this is far more expensive than
+/// `sig_verify`. Only use it when the signed message can't be a hash.
+pub fn sig_verify_bytes(pubkey: &[u8], sig: &[u8], message: &[u8]) -> Result<(), ProgramError> {
+    verify(pubkey, sig, |r, pubkey| {
+        let mut h = Sha512::new();
+        h.update(r);
+        h.update(pubkey);
+        h.update(message);
+        h.finalize().into()
+    })
+}
+
+#[allow(non_snake_case)]
+fn verify<F>(pubkey: &[u8], sig: &[u8], challenge: F) -> Result<(), ProgramError>
+where
+    F: FnOnce(&[u8; 32], &[u8; 32]) -> [u8; 64],
+{
     // Normally, we could verify the signature using the Solana SDK or
     // dalek_ed25519, but those are too compute, stack, and heap heavy for the
     // SVM.
@@ -30,6 +68,121 @@ pub fn sig_verify(pubkey: &[u8], sig: &[u8], message: &[u8]) -> Result<(), Progr
     // SVM. Refer to 
     // https://github.com/dalek-cryptography/curve25519-dalek/blob/0964f800ab2114a862543ca000291a6e3531c203/ed25519-dalek/src/verifying.rs#L401
 
+    let (pubkey_point, sig_R, sig_s) = decode_signature(pubkey, sig)?;
+
+    // k = SHA-512(R || A || M)
+    let f = challenge(&sig_R.0, &pubkey_point.0);
+
+    let k = Scalar::from_bytes_mod_order_wide(&f);
+
+    let k_bytes = k.to_bytes();
+    let pubkey_bytes = pubkey_point.0;
+    let sig_s_bytes = sig_s.to_bytes();
+
+    let a = PodScalar(k_bytes);
+    let b = PodScalar(sig_s_bytes);
+    let B = PodEdwardsPoint(G);
+
+    // R = sB - kA
+
+    let sB = multiply_edwards(&b, &B).unwrap();
+    let kA = multiply_edwards(&a, &PodEdwardsPoint(pubkey_bytes)).unwrap();
+    let R = subtract_edwards(&sB, &kA).unwrap();
+
+    let expected_R = sig_R.0;
+    let computed_R = R.0;
+
+    if expected_R == computed_R {
+        Ok(())
+    } else {
+        Err(ProgramError::InvalidAccountOwner)
+    }
+}
+
+/// Verify a batch of ed25519 signatures over 32-byte messages.
+///
+/// Instead of checking `R_i = s_i * B - k_i * A_i` for each signature (two
+/// scalar multiplications each), this checks a random linear combination of
+/// all of them with a single multiscalar multiplication:
+///
+///     sum(z_i * R_i) + sum(z_i * k_i * A_i) - sum(z_i * s_i) * B == 0
+///
+/// The `z_i` are 128-bit coefficients derived from a hash of the entire
+/// batch, so signatures can't be crafted to cancel each other out. The batch
+/// succeeds or fails as a whole and doesn't report which signature is bad.
+///
+/// The check is cofactored (each `z_i` is multiplied by 8), otherwise a
+/// small-order component in `R_i` or `A_i` would be cancelled only for some
+/// coefficients, and the result would depend on the rest of the batch. Unlike
+/// `sig_verify`, this accepts signatures whose `R` has a small-order
+/// component, which honest signers never produce.
+#[allow(non_snake_case)]
+pub fn sig_verify_batch(
+    pubkeys: &[&[u8]],
+    sigs: &[&[u8]],
+    messages: &[&[u8]],
+) -> Result<(), ProgramError> {
+    let n = pubkeys.len();
+
+    if n == 0 || sigs.len() != n || messages.len() != n {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if messages.iter().any(|message| message.len() != 32) {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Bind the coefficients to every input in the batch
+    let mut transcript: Vec<&[u8]> = Vec::with_capacity(3 * n + 1);
+    transcript.push(b"code_vm_sig_verify_batch");
+    for i in 0..n {
+        transcript.push(pubkeys[i]);
+        transcript.push(sigs[i]);
+        transcript.push(messages[i]);
+    }
+    let seed = hashv(&transcript);
+
+    let mut scalars = Vec::with_capacity(2 * n + 1);
+    let mut points = Vec::with_capacity(2 * n + 1);
+    let mut B_coefficient = Scalar::ZERO;
+    let cofactor = Scalar::from(8u64);
+
+    for i in 0..n {
+        let (pubkey_point, sig_R, sig_s) = decode_signature(pubkeys[i], sigs[i])?;
+
+        let f = hash(&sig_R.0, &pubkey_point.0, messages[i].try_into().unwrap());
+        let k = Scalar::from_bytes_mod_order_wide(&f);
+        let z = batch_coefficient(&seed, i) * cofactor;
+
+        B_coefficient += z * sig_s;
+
+        scalars.push(PodScalar(z.to_bytes()));
+        points.push(sig_R);
+
+        scalars.push(PodScalar((z * k).to_bytes()));
+        points.push(pubkey_point);
+    }
+
+    scalars.push(PodScalar((-B_coefficient).to_bytes()));
+    points.push(PodEdwardsPoint(G));
+
+    let result = multiscalar_multiply_edwards(&scalars, &points)
+        .ok_or(ProgramError::InvalidAccountOwner)?;
+
+    if result == identity() {
+        Ok(())
+    } else {
+        Err(ProgramError::InvalidAccountOwner)
+    }
+}
+
+/// Parse and sanity check a public key and signature, returning `A`, `R`
+/// and `s`.
+#[allow(non_snake_case)]
+fn decode_signature(
+    pubkey: &[u8],
+    sig: &[u8],
+) -> Result<(PodEdwardsPoint, PodEdwardsPoint, Scalar), ProgramError> {
     if pubkey.len() != ED25519_PUBKEY_LEN {
         return Err(ProgramError::InvalidArgument);
     }
@@ -42,7 +195,10 @@ pub fn sig_verify(pubkey: &[u8], sig: &[u8], message: &[u8]) -> Result<(), Progr
     let (sig_lower, sig_upper) = split_signature(sig.try_into().unwrap());
 
     let sig_R = PodEdwardsPoint(sig_lower);
-    let sig_s = Scalar::from_canonical_bytes(sig_upper).unwrap();
+    // A non-canonical s (s >= l) is malleable and must be rejected, not
+    // unwrapped.
+    let sig_s = Option::<Scalar>::from(Scalar::from_canonical_bytes(sig_upper))
+        .ok_or(ProgramError::InvalidAccountOwner)?;
 
     if is_small_order(&sig_R) || is_small_order(&pubkey_point) {
         return Err(ProgramError::InvalidAccountOwner);
@@ -59,43 +215,16 @@ pub fn sig_verify(pubkey: &[u8], sig: &[u8], message: &[u8]) -> Result<(), Progr
         return Err(ProgramError::InvalidAccountOwner);
     }
 
-    // let mut h: Sha512 = Sha512::new(); // <- Expensive, no system calls available yet.
-    // h.update(sig_R.0);
-    // h.update(&pubkey);
-    // h.update(&message);
-    // let f = h.finalize();
-
-    // Optimized version of the above (single-round SHA-512)
-    let f = hash(
-        &sig_R.0,
-        pubkey.try_into().unwrap(),
-        message.try_into().unwrap()
-    );
-
-    let k = Scalar::from_bytes_mod_order_wide(&f);
-
-    let k_bytes = k.to_bytes();
-    let pubkey_bytes = pubkey_point.0;
-    let sig_s_bytes = sig_s.to_bytes();
-
-    let a = PodScalar(k_bytes);
-    let b = PodScalar(sig_s_bytes);
-    let B = PodEdwardsPoint(G);
-
-    // R = sB - kA
-
-    let sB = multiply_edwards(&b, &B).unwrap();
-    let kA = multiply_edwards(&a, &PodEdwardsPoint(pubkey_bytes)).unwrap();
-    let R = subtract_edwards(&sB, &kA).unwrap();
+    Ok((pubkey_point, sig_R, sig_s))
+}
 
-    let expected_R = sig_R.0;
-    let computed_R = R.0;
+/// Derive the 128-bit coefficient for the signature at `index` in a batch.
+fn batch_coefficient(seed: &Hash, index: usize) -> Scalar {
+    let h = hashv(&[seed.as_ref(), &(index as u64).to_le_bytes()]);
 
-    if expected_R == computed_R {
-        Ok(())
-    } else {
-        Err(ProgramError::InvalidAccountOwner)
-    }
+    let mut bytes = [0u8; 32];
+    bytes[..16].copy_from_slice(&h.as_ref()[..16]);
+    Scalar::from_bytes_mod_order(bytes)
 }
 
 
@@ -269,4 +398,211 @@ mod tests {
         assert!(sig_verify(&pubkey, &sig, "not the right message".as_bytes()).is_err());
     }
 
+    #[test]
+    fn test_sig_verify_bytes() {
+        use solana_sdk::signature::{Keypair, Signer};
+
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey().to_bytes();
+
+        let message = "a message that is longer than thirty-two bytes".as_bytes();
+        let sig = keypair.sign_message(message);
+
+        assert!(sig_verify_bytes(&pubkey, sig.as_ref(), message).is_ok());
+        assert!(sig_verify_bytes(&pubkey, sig.as_ref(), "not the right message".as_bytes()).is_err());
+
+        // Both paths agree on 32-byte messages
+        let digest = [7u8; 32];
+        let sig = keypair.sign_message(&digest);
+
+        assert!(sig_verify(&pubkey, sig.as_ref(), &digest).is_ok());
+        assert!(sig_verify_bytes(&pubkey, sig.as_ref(), &digest).is_ok());
+    }
+
+    #[test]
+    fn test_non_canonical_s() {
+        use solana_sdk::signature::{Keypair, Signer};
+
+        let keypair = Keypair::new();
+        let pubkey = keypair.pubkey().to_bytes();
+        let message = [7u8; 32];
+
+        let mut sig: [u8; 64] = keypair.sign_message(&message).as_ref().try_into().unwrap();
+        assert!(sig_verify(&pubkey, &sig, &message).is_ok());
+
+        // s + l is an equivalent, but non-canonical, encoding of s
+        let l = curve25519_dalek::constants::BASEPOINT_ORDER.to_bytes();
+        let mut carry = 0u16;
+        for i in 0..32 {
+            let sum = sig[32 + i] as u16 + l[i] as u16 + carry;
+            sig[32 + i] = sum as u8;
+            carry = sum >> 8;
+        }
+
+        assert!(sig_verify(&pubkey, &sig, &message).is_err());
+        assert!(sig_verify_batch(&[&pubkey, &pubkey], &[&sig, &sig], &[&message, &message]).is_err());
+    }
+
+    mod batch {
+        use super::*;
+        use ed25519_dalek::{Keypair, PublicKey, Signature, Signer};
+
+        struct Batch {
+            pubkeys: Vec<[u8; 32]>,
+            sigs: Vec<[u8; 64]>,
+            messages: Vec<[u8; 32]>,
+        }
+
+        impl Batch {
+            fn new(n: usize) -> Self {
+                let mut batch = Batch { pubkeys: vec![], sigs: vec![], messages: vec![] };
+
+                for i in 0..n {
+                    let keypair = solana_sdk::signature::Keypair::new();
+                    let keypair = Keypair::from_bytes(&keypair.to_bytes()).unwrap();
+                    let message: [u8; 32] = hashv(&[b"message", &i.to_le_bytes()]).into();
+
+                    batch.pubkeys.push(keypair.public.to_bytes());
+                    batch.sigs.push(keypair.sign(&message).to_bytes());
+                    batch.messages.push(message);
+                }
+
+                batch
+            }
+
+            fn ours(&self) -> bool {
+                let pubkeys: Vec<&[u8]> = self.pubkeys.iter().map(|k| k.as_ref()).collect();
+                let sigs: Vec<&[u8]> = self.sigs.iter().map(|s| s.as_ref()).collect();
+                let messages: Vec<&[u8]> = self.messages.iter().map(|m| m.as_ref()).collect();
+
+                sig_verify_batch(&pubkeys, &sigs, &messages).is_ok()
+            }
+
+            fn dalek(&self) -> bool {
+                let pubkeys: Vec<PublicKey> = self.pubkeys.iter()
+                    .map(|k| PublicKey::from_bytes(k).unwrap())
+                    .collect();
+                let sigs: Vec<Signature> = self.sigs.iter()
+                    .map(|s| Signature::from_bytes(s).unwrap())
+                    .collect();
+                let messages: Vec<&[u8]> = self.messages.iter().map(|m| m.as_ref()).collect();
+
+                ed25519_dalek::verify_batch(&messages, &sigs, &pubkeys).is_ok()
+            }
+
+            fn assert_agrees(&self, expected: bool) {
+                assert_eq!(self.dalek(), expected);
+                assert_eq!(self.ours(), expected);
+            }
+        }
+
+        #[test]
+        fn test_valid_batches() {
+            for n in 1..=8 {
+                Batch::new(n).assert_agrees(true);
+            }
+        }
+
+        #[test]
+        fn test_tampered_message() {
+            for n in 2..=6 {
+                for i in 0..n {
+                    let mut batch = Batch::new(n);
+                    batch.messages[i][0] ^= 1;
+                    batch.assert_agrees(false);
+                }
+            }
+        }
+
+        #[test]
+        fn test_tampered_signature() {
+            for n in 2..=6 {
+                let mut batch = Batch::new(n);
+                batch.sigs[n - 1][0] ^= 1;
+                batch.assert_agrees(false);
+            }
+        }
+
+        #[test]
+        fn test_swapped_signatures() {
+            let mut batch = Batch::new(4);
+            batch.sigs.swap(0, 1);
+            batch.assert_agrees(false);
+        }
+
+        #[test]
+        fn test_wrong_signer() {
+            let mut batch = Batch::new(4);
+            batch.pubkeys[2] = Batch::new(1).pubkeys[0];
+            batch.assert_agrees(false);
+        }
+
+        #[test]
+        fn test_duplicate_entries() {
+            let mut batch = Batch::new(3);
+            batch.pubkeys.push(batch.pubkeys[0]);
+            batch.sigs.push(batch.sigs[0]);
+            batch.messages.push(batch.messages[0]);
+            batch.assert_agrees(true);
+        }
+
+        /// Signs `message` with a nonce point `R + T`, where `T` has small
+        /// order. The signature passes a cofactored check but not the
+        /// cofactorless one.
+        #[allow(non_snake_case)]
+        fn sign_with_torsion(message: &[u8; 32]) -> ([u8; 32], [u8; 64]) {
+            let seed = solana_sdk::signature::Keypair::new().secret().to_bytes();
+            let h = Sha512::digest(seed);
+
+            let mut a_bytes = [0u8; 32];
+            a_bytes.copy_from_slice(&h[..32]);
+            a_bytes[0] &= 248;
+            a_bytes[31] &= 127;
+            a_bytes[31] |= 64;
+            let a = Scalar::from_bytes_mod_order(a_bytes);
+            let A = (constants::ED25519_BASEPOINT_POINT * a).compress().to_bytes();
+
+            let r = Sha512::new().chain_update(&h[32..]).chain_update(message).finalize();
+            let r = Scalar::from_bytes_mod_order_wide(&r.into());
+            let R = constants::ED25519_BASEPOINT_POINT * r + constants::EIGHT_TORSION[1];
+            let R = R.compress().to_bytes();
+
+            let k = Sha512::new().chain_update(R).chain_update(A).chain_update(message).finalize();
+            let k = Scalar::from_bytes_mod_order_wide(&k.into());
+            let s = r + k * a;
+
+            let mut sig = [0u8; 64];
+            sig[..32].copy_from_slice(&R);
+            sig[32..].copy_from_slice(s.as_bytes());
+            (A, sig)
+        }
+
+        #[test]
+        fn test_torsioned_r() {
+            for n in 1..=4 {
+                let mut batch = Batch::new(n);
+                let (pubkey, sig) = sign_with_torsion(&batch.messages[0]);
+                batch.pubkeys[0] = pubkey;
+                batch.sigs[0] = sig;
+
+                // Rejected by the single check, but always accepted by the
+                // cofactored batch, no matter the coefficients
+                assert!(sig_verify(&pubkey, &sig, &batch.messages[0]).is_err());
+                assert!(batch.ours());
+
+                // The torsion doesn't hide a forged signature
+                batch.messages[0][0] ^= 1;
+                assert!(!batch.ours());
+            }
+        }
+
+        #[test]
+        fn test_mismatched_lengths() {
+            let mut batch = Batch::new(3);
+            batch.sigs.pop();
+            assert!(!batch.ours());
+
+            assert!(sig_verify_batch(&[], &[], &[]).is_err());
+        }
+    }
 }
@@ -0,0 +1,187 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+/*
+    This instruction is used to cancel an unlock that was started with the
+    InitUnlockIx instruction. The unlock state account is closed, which puts
+    the timelock back into the locked state. The rent is refunded to the payer.
+
+    An unlock can be cancelled while waiting for the timeout or after it was
+    finalized, but not once a withdraw receipt was created for it. At that
+    point funds may have left the VM non-custodially.
+
+    The receipt is checked using the owner's virtual account, which is read
+    from memory, or from storage if it was compressed. Compressed accounts
+    can't be decompressed while an unlock is in progress, so the account
+    state and a proof are given instead, like when withdrawing from storage.
+    Unlocks from before the has_receipt flag existed don't have it set, so
+    the receipt account for the virtual account must be empty too.
+
+    An owner that is a multisig address doesn't sign. Instead, the multisig
+    signer keys are given as the last accounts, and `threshold` of them sign.
+    The threshold and number of signers are appended to the instruction data.
+
+    Accounts expected by this instruction:
+    
+    | # | R/W | Type              | PDA | Name                 | Description                               |
+    |---|-----|-------------------|-----|----------------------|-------------------------------------------|
+    | 0 | mut | Signer            |     | account_owner        | The virtual account owner.                |
+    | 1 | mut | Signer            |     | payer                | The transaction fee payer.                |
+    | 2 | mut | Vm                | PDA | vm                   | The VM instance state account.            |
+    | 3 | mut | UnlockState       | PDA | unlock_pda           | Account to close.                         |
+    | 4 |     | Memory or Storage | PDA | vm_memory_or_storage | Where the virtual account is held.        |
+    | 5 |     | Receipt           | PDA | withdraw_receipt     | Must be empty.                            |
+    |...|     | Signer            |     | multisig_signers     | (optional) For a multisig owner.          |
+
+
+    Derived account seeds:
+
+    2. vm:               [ "code_vm", <mint>, <vm_authority>, <lock_duration> ]
+    3. unlock_pda:       [ "code_vm", "vm_unlock_pda_account", <account_owner>, <timelock_address>, <vm> ]
+    4. vm_memory:        [ "code_vm", "vm_memory_account", <self.name>, <vm> ]
+       vm_storage:       [ "code_vm", "vm_storage_account", <self.name>, <vm> ]
+    5. withdraw_receipt: [ "code_vm", "vm_withdraw_receipt_account", <unlock_pda>, <nonce>, <vm> ]
+
+
+    Instruction data:
+
+    The instruction data is a CancelUnlockIxData enum, one of:
+
+    FromMemory:
+    0. account_index: u16     - The index of the virtual account in vm_memory.
+
+    FromStorage:
+    0. packed_va: Vec<u8>     - The packed virtual account.
+    1. proof: Vec<Hash>       - The proof that the account is in vm_storage.
+    2. signature: Signature   - The VM authority's signature from compression.
+*/
+pub fn process_cancel_unlock(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let [
+        account_owner_info,
+        payer_info,
+        vm_info,
+        unlock_pda_info,
+        vm_memory_or_storage_info,
+        withdraw_receipt_info,
+        remaining @ ..
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);        
+    };
+
+    let ix_data = check_owner_signer(account_owner_info, vm_info, remaining, data)?;
+    let args = CancelUnlockIx::try_from_slice(ix_data)?;
+
+    check_signer(payer_info)?;
+    check_mut(payer_info)?;
+    check_mut(vm_info)?;
+    check_mut(unlock_pda_info)?;
+
+    let vm = load_vm(vm_info)?;
+
+    let unlock_pda = unlock_pda_info.to_account::<UnlockStateAccount>(&code_vm_api::ID)?;
+
+    check_seeds(
+        unlock_pda_info, 
+        &[
+            CODE_VM,
+            VM_UNLOCK_ACCOUNT,
+            account_owner_info.key.as_ref(),
+            unlock_pda.address.as_ref(),
+            vm_info.key.as_ref(),
+        ],
+        unlock_pda.bump, 
+        &code_vm_api::id()
+    )?;
+
+    check_condition(
+        unlock_pda.owner.eq(account_owner_info.key),
+        "unlock_pda owner does not match the account owner",
+    )?;
+
+    check_condition(
+        unlock_pda.vm.eq(vm_info.key),
+        "unlock_pda vm does not match the expected vm",
+    )?;
+
+    check_condition(
+        unlock_pda.is_waiting() || unlock_pda.is_unlocked(),
+        "invalid unlock state",
+    )?;
+
+    check_condition(
+        !unlock_pda.has_receipt(),
+        "a withdraw receipt exists for this unlock",
+    )?;
+
+    let vta = read_timelock(vm, vm_info, vm_memory_or_storage_info, &args)?;
+
+    check_condition(
+        vta.owner.eq(account_owner_info.key),
+        "the virtual account owner does not match the account owner",
+    )?;
+
+    let timelock_address = vta.get_timelock_address(
+        &vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    check_condition(
+        unlock_pda.address.eq(&timelock_address),
+        "unlock_pda does not belong to the virtual account",
+    )?;
+
+    let (receipt_address, _) =
+        find_withdraw_receipt_address(unlock_pda_info.key, &vta.instance, vm_info.key);
+
+    check_condition(
+        withdraw_receipt_info.key.eq(&receipt_address),
+        "withdraw_receipt does not match the expected receipt address",
+    )?;
+
+    check_condition(
+        withdraw_receipt_info.data_is_empty(),
+        "a withdraw receipt exists for this unlock",
+    )?;
+
+    close_account(unlock_pda_info, payer_info)?;
+
+    vm.advance_poh(CodeInstruction::CancelUnlockIx, accounts, data);
+
+    Ok(())
+}
+
+/// Reads the virtual account from memory, or checks that the given account
+/// state is held in storage.
+fn read_timelock(
+    vm: &CodeVmAccount,
+    vm_info: &AccountInfo<'_>,
+    vm_memory_or_storage_info: &AccountInfo<'_>,
+    args: &CancelUnlockIxData,
+) -> Result<VirtualTimelockAccount, ProgramError> {
+    let va = match args {
+        CancelUnlockIxData::FromMemory { account_index } => {
+            check_memory(vm_memory_or_storage_info, vm_info)?;
+            try_read(vm_memory_or_storage_info, *account_index)?
+        },
+        CancelUnlockIxData::FromStorage { packed_va, proof, signature } => {
+            let storage = load_storage(vm_memory_or_storage_info, vm_info)?;
+
+            let va = VirtualAccount::unpack(packed_va)?;
+            let va_hash = va.get_hash();
+            let sig_hash = hashv(&[signature.as_ref(), va_hash.as_ref()]);
+
+            sig_verify(vm.authority.as_ref(), signature.as_ref(), va_hash.as_ref())?;
+
+            check_condition(
+                storage.compressed_state.contains(proof, sig_hash),
+                "the virtual account is not in vm_storage",
+            )?;
+
+            va
+        },
+    };
+
+    va.into_inner_timelock()
+        .ok_or(ProgramError::InvalidAccountData)
+}
@@ -0,0 +1,79 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+/*
+    This instruction closes the receipt of a deposit intent, see
+    DepositWithIntentIx. The rent is refunded to the VM authority, which paid
+    for the receipt when the deposit was pulled in.
+
+    A receipt can only be closed once its intent has expired. Expired intents
+    are rejected by DepositWithIntentIx, so closing the receipt doesn't allow
+    the intent to be replayed.
+
+    Accounts expected by this instruction:
+
+    | # | R/W | Type           | PDA | Name            | Description                      |
+    |---|-----|----------------|-----|-----------------|----------------------------------|
+    | 0 | mut | Signer         |     | vm_authority    | The authority of the VM.         |
+    | 1 | mut | Vm             | PDA | vm              | The VM instance state account.   |
+    | 2 | mut | DepositReceipt | PDA | deposit_receipt | The receipt to close.            |
+
+
+    Derived account seeds:
+
+    1. vm:              [ "code_vm", <mint>, <vm_authority>, <lock_duration> ]
+    2. deposit_receipt: [ "code_vm", "vm_deposit_receipt", <vm>, <owner>, <nonce> ]
+
+    Instruction data:
+
+    <none>
+*/
+pub fn process_close_deposit_receipt(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+
+    let [
+        vm_authority_info,
+        vm_info,
+        deposit_receipt_info,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    check_signer(vm_authority_info)?;
+    check_mut(vm_info)?;
+    check_mut(deposit_receipt_info)?;
+
+    let vm = load_vm_checked(vm_info, vm_authority_info)?;
+
+    let receipt = deposit_receipt_info
+        .to_account::<DepositReceiptAccount>(&code_vm_api::ID)?;
+
+    check_seeds(
+        deposit_receipt_info,
+        &[
+            CODE_VM,
+            VM_DEPOSIT_RECEIPT,
+            vm_info.key.as_ref(),
+            receipt.owner.as_ref(),
+            receipt.nonce.as_ref(),
+        ],
+        receipt.bump,
+        &code_vm_api::ID,
+    )?;
+
+    check_condition(
+        receipt.vm.eq(vm_info.key),
+        "the deposit receipt does not belong to this VM",
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    check_condition(
+        now >= receipt.expires_at,
+        "the deposit intent has not expired yet",
+    )?;
+
+    close_account(deposit_receipt_info, vm_authority_info)?;
+
+    vm.advance_poh(CodeInstruction::CloseDepositReceiptIx, accounts, data);
+
+    Ok(())
+}
@@ -0,0 +1,111 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+/*
+    This instruction closes a relay account and its token vault. Any tokens
+    left in the relay vault are swept into the VM omnibus before the vault is
+    closed. The rent from both accounts is refunded to the VM authority.
+
+    Note, virtual relay accounts that were written by this relay point at the
+    relay vault. Conditional transfers against those accounts will fail once
+    the relay is closed.
+
+    The relay nullifier account is intentionally left open, so that used
+    commitments can't be replayed against a relay re-created with the same
+    name.
+
+    Accounts expected by this instruction:
+
+    | # | R/W | Type         | PDA | Name           | Description                              |
+    |---|-----|--------------|-----|----------------|------------------------------------------|
+    | 0 | mut | Signer       |     | vm_authority   | The authority of the VM.                 |
+    | 1 | mut | Vm           | PDA | vm             | The VM instance state account.           |
+    | 2 | mut | Relay        | PDA | relay          | The relay account to close.              |
+    | 3 | mut | TokenAccount | PDA | relay_vault    | The relay token account to close.        |
+    | 4 | mut | TokenAccount | PDA | omnibus        | A derived token account owned by the VM. |
+    | 5 |     | Program      |     | token_program  | The VM's token program.                  |
+    | 6 |     | TokenMint    |     | mint           | The VM's mint, for Token-2022 VMs.       |
+
+
+    Derived account seeds:
+
+    1. vm:           [ "code_vm", <mint>, <vm_authority>, <lock_duration> ]
+    2. relay:        [ "code_vm", "vm_relay_account", <self.name>, <vm> ]
+    3. relay_vault:  [ "code_vm", "vm_relay_vault", <relay> ]
+    4. omnibus:      [ "code_vm", "vm_omnibus", <vm> ]
+
+    Instruction data:
+
+    <none>
+*/
+pub fn process_close_relay(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+
+    let [
+        vm_authority_info,
+        vm_info,
+        relay_info,
+        relay_vault_info,
+        omnibus_info,
+        token_program_info,
+        remaining @ ..
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    check_signer(vm_authority_info)?;
+    check_mut(vm_info)?;
+    check_mut(relay_info)?;
+    check_mut(relay_vault_info)?;
+    check_mut(omnibus_info)?;
+
+    let vm = load_vm_checked(vm_info, vm_authority_info)?;
+
+    check_token_program(token_program_info, vm)?;
+
+    check_omnibus(omnibus_info, vm_info)?;
+    check_relay(relay_info, vm_info)?;
+
+    let relay = relay_info.to_account::<RelayAccount>(&code_vm_api::ID)?;
+    let vault_bump = relay.treasury.vault_bump;
+
+    check_condition(
+        relay.treasury.vault.eq(relay_vault_info.key),
+        "relay_vault does not match the relay treasury",
+    )?;
+
+    let vault_seeds: &[&[u8]] = &[
+        CODE_VM,
+        VM_RELAY_VAULT,
+        relay_info.key.as_ref(),
+        &[vault_bump],
+    ];
+
+    // Sweep whatever is left in the vault back into the omnibus
+    let amount = get_token_amount(relay_vault_info)?;
+    if amount > 0 {
+        transfer_tokens_signed(
+            vm,
+            relay_vault_info,
+            relay_vault_info,
+            omnibus_info,
+            get_optional_mint(&vm.get_mint(), remaining),
+            token_program_info,
+            amount,
+            &[vault_seeds],
+        )?;
+    }
+
+    close_token_account_signed(
+        relay_vault_info,
+        relay_vault_info,
+        vm_authority_info,
+        token_program_info,
+        &[vault_seeds],
+    )?;
+
+    close_account(relay_info, vm_authority_info)?;
+
+    vm.advance_poh(CodeInstruction::CloseRelayIx, accounts, data);
+
+    Ok(())
+}
@@ -97,6 +97,24 @@ pub fn process_decompress(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramR
         VirtualAccount::Relay(_) => {
             // Relay accounts are not timelocked
         }
+        VirtualAccount::Vesting(_) => {
+            // Vesting accounts are not timelocked
+        }
+        VirtualAccount::ScheduledTransfer(_) => {
+            // Scheduled transfers are not timelocked
+        }
+        VirtualAccount::Subscription(_) => {
+            // Subscriptions are not timelocked
+        }
+        VirtualAccount::Allowance(_) => {
+            // Allowances are not timelocked
+        }
+        VirtualAccount::Escrow(_) => {
+            // Escrows are not timelocked
+        }
+        VirtualAccount::Multisig(_) => {
+            // Multisig accounts are not timelocked
+        }
     }
 
     let va = unchecked_va;
@@ -124,8 +142,11 @@ fn check_timelock_state(
     unlock_pda_info: &AccountInfo<'_>,
     withdraw_receipt_info: &AccountInfo<'_>,
 ) -> ProgramResult {
-    let timelock_address =
-        vta.get_timelock_address(&vm.get_mint(), &vm.get_authority(), vm.get_lock_duration());
+    let timelock_address = vta.get_timelock_address(
+        &vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        vta.get_lock_duration(vm.get_lock_duration()),
+    );
 
     let unlock_address = vta.get_unlock_address(&timelock_address, &vm_info.key);
 
@@ -14,6 +14,9 @@ use steel::*;
     Once they have done this, we can call this instruction to pull in the
     deposit and update the user's virtual account.
 
+    Accounts for a registered mint are deposited into the omnibus of that
+    mint, using its token program.
+
     Accounts expected by this instruction:
 
     | # | R/W | Type         | PDA | Name          | Description                                   |
@@ -25,7 +28,9 @@ use steel::*;
     | 4 |     | Address      | PDA | deposit_pda   | A derived account within the VM address space.|
     | 5 | mut | TokenAccount | PDA | deposit_ata   | A derived token account owned by deposit_pda. |
     | 6 | mut | TokenAccount | PDA | omnibus       | A derived token account owned by vm.          |
-    | 7 |     | Program      |     | token_program | The SPL token program.                        |
+    | 7 |     | Program      |     | token_program | The token program of the account's mint.      |
+    | 8 |     | TokenMint    |     | mint          | The account's mint, for Token-2022 mints.     |
+    | 9 |     | VmMint       | PDA | vm_mint       | Required for accounts of a registered mint.   |
 
 
     Derived account seeds:
@@ -35,6 +40,9 @@ use steel::*;
     3. deposit_pda: [ "code_vm", "vm_deposit_pda", <depositor>, <vm> ]
     4. deposit_ata: <standard ATA seed>
     5. omnibus:     [ "code_vm", "vm_omnibus", <vm> ]
+    6. vm_mint:     [ "code_vm", "vm_mint", <vm>, <mint> ]
+
+    For registered mints, the omnibus is [ "code_vm", "vm_omnibus", <vm>, <mint> ].
 
     Instruction data:
 
@@ -53,6 +61,7 @@ pub fn process_deposit_from_pda(accounts: &[AccountInfo<'_>], data: &[u8]) -> Pr
         deposit_ata_info,
         omnibus_info,
         token_program_info,
+        remaining @ ..
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
@@ -62,11 +71,9 @@ pub fn process_deposit_from_pda(accounts: &[AccountInfo<'_>], data: &[u8]) -> Pr
     check_mut(vm_memory_info)?;
     check_mut(deposit_ata_info)?;
     check_mut(omnibus_info)?;
-    check_program(token_program_info, &spl_token::id())?;
 
     let vm = load_vm_checked(vm_info, vm_authority_info)?;
 
-    check_omnibus(omnibus_info, vm_info)?;
     check_memory(vm_memory_info, vm_info)?;
 
     let va = try_read(&vm_memory_info, args.account_index)?;
@@ -77,10 +84,21 @@ pub fn process_deposit_from_pda(accounts: &[AccountInfo<'_>], data: &[u8]) -> Pr
         "The depositor does not own this account",
     )?;
 
-    transfer_signed(
+    // Accounts for a registered mint deposit into that mint's omnibus
+    let pool = get_mint_pool(vm, find_vm_mint(vm_info, remaining)?, &vta.mint)?;
+
+    check_program(token_program_info, &pool.token_program.id())?;
+    check_pool_omnibus(omnibus_info, &pool)?;
+
+    let mint_info = get_optional_mint(&pool.mint, remaining);
+    let omnibus_balance = get_token_amount(omnibus_info)?;
+
+    transfer_pool_tokens_signed(
+        &pool,
         deposit_pda_info,
         deposit_ata_info,
         omnibus_info,
+        mint_info,
         token_program_info,
         args.amount,
         &[&[
@@ -92,9 +110,15 @@ pub fn process_deposit_from_pda(accounts: &[AccountInfo<'_>], data: &[u8]) -> Pr
         ]],
     )?;
 
+    // Only credit what the omnibus received, which is less than the amount
+    // when the mint charges transfer fees.
+    let received = get_token_amount(omnibus_info)?
+        .checked_sub(omnibus_balance)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
     vta.balance = vta
         .balance
-        .checked_add(args.amount)
+        .checked_add(received)
         .ok_or(ProgramError::ArithmeticOverflow)?;
 
     try_write(
@@ -124,7 +148,9 @@ pub fn process_deposit_from_pda(accounts: &[AccountInfo<'_>], data: &[u8]) -> Pr
     | 4 | mut | TokenAccount | PDA | source_ata       | A derived account within the VM address space.|
     | 5 |     | Address      |     | destination      | Destination VTA owner                         |
     | 6 | mut | TokenAccount | PDA | omnibus          | A derived token account owned by vm.          |
-    | 7 |     | Program      |     | token_program    | The SPL token program.                        |
+    | 7 |     | Program      |     | token_program    | The token program of the account's mint.      |
+    | 8 |     | TokenMint    |     | mint             | The account's mint, for Token-2022 mints.     |
+    | 9 |     | VmMint       | PDA | vm_mint          | Required for accounts of a registered mint.   |
 
 
     Derived account seeds:
@@ -133,6 +159,9 @@ pub fn process_deposit_from_pda(accounts: &[AccountInfo<'_>], data: &[u8]) -> Pr
     2. vm_memory:   [ "code_vm", "vm_memory_account", <self.name>, <vm> ]
     3. source_ata:  <standard ATA seed>
     4. omnibus:     [ "code_vm", "vm_omnibus", <vm> ]
+    5. vm_mint:     [ "code_vm", "vm_mint", <vm>, <mint> ]
+
+    For registered mints, the omnibus is [ "code_vm", "vm_omnibus", <vm>, <mint> ].
 
     Instruction data:
 
@@ -150,6 +179,7 @@ pub fn process_deposit_with_authority(accounts: &[AccountInfo<'_>], data: &[u8])
         destination_info,
         omnibus_info,
         token_program_info,
+        remaining @ ..
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);        
     };
@@ -160,11 +190,9 @@ pub fn process_deposit_with_authority(accounts: &[AccountInfo<'_>], data: &[u8])
     check_mut(vm_memory_info)?;
     check_mut(source_ata_info)?;
     check_mut(omnibus_info)?;
-    check_program(token_program_info, &spl_token::id())?;
 
     let vm = load_vm_checked(vm_info, vm_authority_info)?;
 
-    check_omnibus(omnibus_info, vm_info)?;
     check_memory(vm_memory_info, vm_info)?;
 
     let va = try_read(&vm_memory_info, args.account_index)?;
@@ -175,17 +203,35 @@ pub fn process_deposit_with_authority(accounts: &[AccountInfo<'_>], data: &[u8])
         "The destination does not match the vta owner",
     )?;
 
-    transfer(
+    // Accounts for a registered mint deposit into that mint's omnibus
+    let pool = get_mint_pool(vm, find_vm_mint(vm_info, remaining)?, &vta.mint)?;
+
+    check_program(token_program_info, &pool.token_program.id())?;
+    check_pool_omnibus(omnibus_info, &pool)?;
+
+    let mint_info = get_optional_mint(&pool.mint, remaining);
+    let omnibus_balance = get_token_amount(omnibus_info)?;
+
+    transfer_pool_tokens_signed(
+        &pool,
         source_authority_info,
         source_ata_info,
         omnibus_info,
+        mint_info,
         token_program_info,
         args.amount,
+        &[],
     )?;
 
+    // Only credit what the omnibus received, which is less than the amount
+    // when the mint charges transfer fees.
+    let received = get_token_amount(omnibus_info)?
+        .checked_sub(omnibus_balance)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
     vta.balance = vta
         .balance
-        .checked_add(args.amount)
+        .checked_add(received)
         .ok_or(ProgramError::ArithmeticOverflow)?;
 
     try_write(
@@ -198,3 +244,362 @@ pub fn process_deposit_with_authority(accounts: &[AccountInfo<'_>], data: &[u8])
 
     Ok(())
 }
+
+/*
+    This instruction deposits tokens from any user token account, without the
+    user having to co-sign the transaction. Instead, the user signs a deposit
+    intent off-chain and approves the VM's deposit delegate on their token
+    account for at least the amount. The VM authority can then pull in the
+    deposit whenever it likes, and credit the virtual account in the intent.
+
+    The intent commits to the VM, the source token account, the destination
+    virtual account, the amount, a nonce picked by the user and an expiry
+    time. A receipt is created for each (owner, nonce) pair, so an intent can
+    only be used once. Expired intents are rejected, after which the receipt
+    can be closed with CloseDepositReceiptIx to refund its rent.
+
+    Accounts expected by this instruction:
+
+    | # | R/W | Type           | PDA | Name             | Description                                   |
+    |---|-----|----------------|-----|------------------|-----------------------------------------------|
+    | 0 | mut | Signer         |     | vm_authority     | The authority of the VM.                      |
+    | 1 | mut | Vm             | PDA | vm               | The VM instance state account.                |
+    | 2 | mut | Memory         | PDA | vm_memory        | The memory account to pull from.              |
+    | 3 |     | Address        |     | source_authority | The owner of the source token account.        |
+    | 4 | mut | TokenAccount   |     | source           | The token account to deposit from.            |
+    | 5 |     | Address        | PDA | deposit_delegate | The delegate approved on the source account.  |
+    | 6 | mut | DepositReceipt | PDA | deposit_receipt  | The receipt for this intent, to create.       |
+    | 7 | mut | TokenAccount   | PDA | omnibus          | A derived token account owned by vm.          |
+    | 8 |     | Program        |     | token_program    | The token program of the account's mint.      |
+    | 9 |     | Program        |     | system_program   | The system program.                           |
+    |10 |     | TokenMint      |     | mint             | The account's mint, for Token-2022 mints.     |
+    |11 |     | VmMint         | PDA | vm_mint          | Required for accounts of a registered mint.   |
+    |12 |     | Sysvar         |     | instructions     | Required when the VM verifies signatures      |
+    |   |     |                |     |                  | with the Ed25519 precompile.                  |
+
+
+    Derived account seeds:
+
+    1. vm:               [ "code_vm", <mint>, <vm_authority>, <lock_duration> ]
+    2. vm_memory:        [ "code_vm", "vm_memory_account", <self.name>, <vm> ]
+    3. deposit_delegate: [ "code_vm", "vm_deposit_delegate", <vm> ]
+    4. deposit_receipt:  [ "code_vm", "vm_deposit_receipt", <vm>, <source_authority>, <nonce> ]
+    5. omnibus:          [ "code_vm", "vm_omnibus", <vm> ]
+    6. vm_mint:          [ "code_vm", "vm_mint", <vm>, <mint> ]
+
+    For registered mints, the omnibus is [ "code_vm", "vm_omnibus", <vm>, <mint> ].
+
+    Instruction data:
+
+    0. account_index: u16     - The index of the account in the VM's paged memory.
+    1. amount: u64            - Amount to deposit
+    2. nonce: Hash            - The nonce of the intent.
+    3. expires_at: i64        - The unix timestamp the intent expires at.
+    4. signature: Signature   - The source authority's signature over the intent.
+    5. delegate_bump: u8      - Deposit delegate bump
+    6. receipt_bump: u8       - Deposit receipt bump
+*/
+pub fn process_deposit_with_intent(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let args = DepositWithIntentIx::try_from_bytes(data)?.to_struct()?;
+    let [
+        vm_authority_info,
+        vm_info,
+        vm_memory_info,
+        source_authority_info,
+        source_info,
+        deposit_delegate_info,
+        deposit_receipt_info,
+        omnibus_info,
+        token_program_info,
+        system_program_info,
+        remaining @ ..
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    check_signer(vm_authority_info)?;
+    check_mut(vm_info)?;
+    check_mut(vm_memory_info)?;
+    check_mut(source_info)?;
+    check_mut(deposit_receipt_info)?;
+    check_mut(omnibus_info)?;
+    check_program(system_program_info, &system_program::id())?;
+
+    let vm = load_vm_checked(vm_info, vm_authority_info)?;
+
+    check_memory(vm_memory_info, vm_info)?;
+
+    let now = Clock::get()?.unix_timestamp;
+    check_condition(
+        now < args.expires_at,
+        "the deposit intent has expired",
+    )?;
+
+    check_seeds(
+        deposit_delegate_info,
+        &[
+            CODE_VM,
+            VM_DEPOSIT_DELEGATE,
+            vm_info.key.as_ref(),
+        ],
+        args.delegate_bump,
+        &code_vm_api::ID,
+    )?;
+
+    check_condition(
+        get_token_owner(source_info)?.eq(source_authority_info.key),
+        "the source authority does not own the source account",
+    )?;
+
+    let va = try_read(&vm_memory_info, args.account_index)?;
+    let mut vta = va.into_inner_timelock().unwrap();
+
+    let hash = create_deposit_intent_message(
+        vm,
+        vm_info.key,
+        source_info.key,
+        &vta,
+        args.amount,
+        &args.nonce,
+        args.expires_at,
+    );
+
+    let instructions_info = remaining
+        .iter()
+        .find(|account| sysvar::instructions::check_id(account.key));
+
+    sig_verify_with_mode(
+        vm,
+        instructions_info,
+        source_authority_info.key.as_ref(),
+        args.signature.as_ref(),
+        hash.as_ref(),
+    )?;
+
+    // The receipt can only be created once, which stops the intent from
+    // being replayed
+    check_uninitialized_pda(
+        deposit_receipt_info,
+        &[
+            CODE_VM,
+            VM_DEPOSIT_RECEIPT,
+            vm_info.key.as_ref(),
+            source_authority_info.key.as_ref(),
+            args.nonce.as_ref(),
+        ],
+        args.receipt_bump,
+        &code_vm_api::id(),
+    )?;
+
+    create_account::<DepositReceiptAccount>(
+        deposit_receipt_info,
+        &code_vm_api::ID,
+        &[
+            CODE_VM,
+            VM_DEPOSIT_RECEIPT,
+            vm_info.key.as_ref(),
+            source_authority_info.key.as_ref(),
+            args.nonce.as_ref(),
+            &[args.receipt_bump],
+        ],
+        system_program_info,
+        vm_authority_info,
+    )?;
+
+    let receipt = deposit_receipt_info
+        .to_account_mut::<DepositReceiptAccount>(&code_vm_api::ID)?;
+
+    receipt.vm = *vm_info.key;
+    receipt.owner = *source_authority_info.key;
+    receipt.source = *source_info.key;
+    receipt.nonce = args.nonce;
+    receipt.amount = args.amount;
+    receipt.expires_at = args.expires_at;
+    receipt.bump = args.receipt_bump;
+
+    // Accounts for a registered mint deposit into that mint's omnibus
+    let pool = get_mint_pool(vm, find_vm_mint(vm_info, remaining)?, &vta.mint)?;
+
+    check_program(token_program_info, &pool.token_program.id())?;
+    check_pool_omnibus(omnibus_info, &pool)?;
+
+    let mint_info = get_optional_mint(&pool.mint, remaining);
+    let omnibus_balance = get_token_amount(omnibus_info)?;
+
+    transfer_pool_tokens_signed(
+        &pool,
+        deposit_delegate_info,
+        source_info,
+        omnibus_info,
+        mint_info,
+        token_program_info,
+        args.amount,
+        &[&[
+            CODE_VM,
+            VM_DEPOSIT_DELEGATE,
+            vm_info.key.as_ref(),
+            &[args.delegate_bump],
+        ]],
+    )?;
+
+    // Only credit what the omnibus received, which is less than the amount
+    // when the mint charges transfer fees.
+    let received = get_token_amount(omnibus_info)?
+        .checked_sub(omnibus_balance)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    vta.balance = vta
+        .balance
+        .checked_add(received)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    try_write(
+        vm_memory_info,
+        args.account_index,
+        &VirtualAccount::Timelock(vta),
+    )?;
+
+    vm.advance_poh(CodeInstruction::DepositWithIntentIx, accounts, data);
+
+    Ok(())
+}
+
+/*
+    This instruction is the same as DepositFromPdaIx, except that it pulls in
+    the whole balance of the deposit_ata, so the caller doesn't need to know
+    the amount. Optionally, the deposit_ata is closed afterwards and its rent
+    is returned to the VM authority. The next deposit to the same deposit PDA
+    will need the ATA to be created again.
+
+    Token-2022 accounts that hold withheld transfer fees can't be closed
+    until the fees are harvested.
+
+    Accounts expected by this instruction:
+
+    | # | R/W | Type         | PDA | Name          | Description                                   |
+    |---|-----|--------------|-----|---------------|-----------------------------------------------|
+    | 0 | mut | Signer       |     | vm_authority  | The authority of the VM.                      |
+    | 1 | mut | Vm           | PDA | vm            | The VM instance state account.                |
+    | 2 | mut | Memory       | PDA | vm_memory     | The memory account to pull from.              |
+    | 3 |     | Address      |     | depositor     | The owner of this deposit.                    |
+    | 4 |     | Address      | PDA | deposit_pda   | A derived account within the VM address space.|
+    | 5 | mut | TokenAccount | PDA | deposit_ata   | A derived token account owned by deposit_pda. |
+    | 6 | mut | TokenAccount | PDA | omnibus       | A derived token account owned by vm.          |
+    | 7 |     | Program      |     | token_program | The token program of the account's mint.      |
+    | 8 |     | TokenMint    |     | mint          | The account's mint, for Token-2022 mints.     |
+    | 9 |     | VmMint       | PDA | vm_mint       | Required for accounts of a registered mint.   |
+
+
+    Derived account seeds:
+
+    1. vm:          [ "code_vm", <mint>, <vm_authority>, <lock_duration> ]
+    2. vm_memory:   [ "code_vm", "vm_memory_account", <self.name>, <vm> ]
+    3. deposit_pda: [ "code_vm", "vm_deposit_pda", <depositor>, <vm> ]
+    4. deposit_ata: <standard ATA seed>
+    5. omnibus:     [ "code_vm", "vm_omnibus", <vm> ]
+    6. vm_mint:     [ "code_vm", "vm_mint", <vm>, <mint> ]
+
+    For registered mints, the omnibus is [ "code_vm", "vm_omnibus", <vm>, <mint> ].
+
+    Instruction data:
+
+    0. account_index: u16 - The index of the account in the VM's paged memory.
+    1. bump: u8           - Deposit PDA bump
+    2. close: bool        - Whether to close the deposit_ata afterwards.
+*/
+pub fn process_sweep_deposit(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let args = SweepDepositIx::try_from_bytes(data)?.to_struct()?;
+    let [
+        vm_authority_info,
+        vm_info,
+        vm_memory_info,
+        depositor_info,
+        deposit_pda_info,
+        deposit_ata_info,
+        omnibus_info,
+        token_program_info,
+        remaining @ ..
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    check_signer(vm_authority_info)?;
+    check_mut(vm_info)?;
+    check_mut(vm_memory_info)?;
+    check_mut(deposit_ata_info)?;
+    check_mut(omnibus_info)?;
+
+    let vm = load_vm_checked(vm_info, vm_authority_info)?;
+
+    check_memory(vm_memory_info, vm_info)?;
+
+    let va = try_read(vm_memory_info, args.account_index)?;
+    let mut vta = va.into_inner_timelock().unwrap();
+
+    check_condition(
+        vta.owner.eq(depositor_info.key),
+        "The depositor does not own this account",
+    )?;
+
+    // Accounts for a registered mint deposit into that mint's omnibus
+    let pool = get_mint_pool(vm, find_vm_mint(vm_info, remaining)?, &vta.mint)?;
+
+    check_program(token_program_info, &pool.token_program.id())?;
+    check_pool_omnibus(omnibus_info, &pool)?;
+
+    let mint_info = get_optional_mint(&pool.mint, remaining);
+    let amount = get_token_amount(deposit_ata_info)?;
+
+    let signer_seeds: &[&[u8]] = &[
+        CODE_VM,
+        VM_DEPOSIT_PDA,
+        depositor_info.key.as_ref(),
+        vm_info.key.as_ref(),
+        &[args.bump],
+    ];
+
+    if amount > 0 {
+        let omnibus_balance = get_token_amount(omnibus_info)?;
+
+        transfer_pool_tokens_signed(
+            &pool,
+            deposit_pda_info,
+            deposit_ata_info,
+            omnibus_info,
+            mint_info,
+            token_program_info,
+            amount,
+            &[signer_seeds],
+        )?;
+
+        // Only credit what the omnibus received, see DepositFromPdaIx
+        let received = get_token_amount(omnibus_info)?
+            .checked_sub(omnibus_balance)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        vta.balance = vta
+            .balance
+            .checked_add(received)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        try_write(
+            vm_memory_info,
+            args.account_index,
+            &VirtualAccount::Timelock(vta),
+        )?;
+    }
+
+    if args.close {
+        close_token_account_signed(
+            deposit_pda_info,
+            deposit_ata_info,
+            vm_authority_info,
+            token_program_info,
+            &[signer_seeds],
+        )?;
+    }
+
+    vm.advance_poh(CodeInstruction::SweepDepositIx, accounts, data);
+
+    Ok(())
+}
@@ -24,6 +24,20 @@ use crate::opcode::*;
     | 8 | mut | TokenAccount |     | PDA | relay_vault      | A derived token account owned by the relay.  |
     | 9 | mut | TokenAccount |     |     | external_address | Required when making external transfers.     |
     | 10|     | Program      |     |     | token_program    | Required when making token transfers.        |
+    | 11| mut | Nullifier    |     | PDA | relay_nullifier  | The nullifier of the relay at position 7.    |
+    |...| mut | Relay        |     | PDA | relay            | Additional relays, as (relay, relay_vault,   |
+    |...| mut | TokenAccount |     | PDA | relay_vault      | relay_nullifier) triples. Selected by index  |
+    |...| mut | Nullifier    |     | PDA | relay_nullifier  | in relay opcodes.                            |
+    |...|     | FeeConfig    |     | PDA | fee_config       | Required to charge the VM's protocol fee.    |
+    |...| mut | TokenAccount |     |     | fee_account      | The external fee recipient, if any.          |
+    |...| mut | VelocityLimit|     | PDA | velocity_limit   | The source owner's velocity limit, if any.   |
+    |...|     | VelocityLimit|     | PDA | velocity_default | The VM default velocity limit, if needed.    |
+    |...|     | TokenMint    |     |     | mint             | The VM's mint. Required for token transfers  |
+    |   |     |              |     |     |                  | by Token-2022 VMs.                           |
+    |...|     | VmMint       |     | PDA | vm_mint          | Required by external opcodes for accounts of |
+    |   |     |              |     |     |                  | a registered mint.                           |
+    |last|    | Sysvar       |     |     | instructions     | Required when the VM verifies signatures     |
+    |   |     |              |     |     |                  | with the Ed25519 precompile.                 |
 
 
     Derived account seeds:
@@ -36,6 +50,41 @@ use crate::opcode::*;
     6. omnibus:       [ "code_vm", "vm_omnibus", <vm> ]
     7. relay:         [ "code_vm", "vm_relay_account", <self.name>, <vm> ]
     8. relay_vault:   [ "code_vm", "vm_relay_vault", <relay> ]
+    11. relay_nullifier: [ "code_vm", "vm_relay_nullifier", <relay> ]
+    ... fee_config:      [ "code_vm", "vm_fee_config", <vm> ]
+    ... velocity_limit:  [ "code_vm", "vm_velocity_limit", <vm>, <owner> ]
+                         [ "code_vm", "vm_velocity_limit", <vm>, <owner>, <mint> ]
+    ... vm_mint:         [ "code_vm", "vm_mint", <vm>, <mint> ]
+
+    Note, the relay set is made up of the relay at position 7 (index 0)
+    followed by any additional (relay, relay_vault, relay_nullifier) triples
+    at the end of the account list (index 1, 2, ...). The relay nullifiers
+    are only required when executing relay opcodes.
+
+    The fee config, when given, comes after the relays and is followed by the
+    fee token account if, and only if, the fee config pays an external
+    recipient. Opcodes that charge fees to a virtual recipient take its memory
+    location as an extra, last entry in mem_indicies and mem_banks. When fees
+    are turned off, no extra entry is needed.
+
+    The velocity limit of the source owner, when given, comes next. It's
+    followed by the VM default (owner Pubkey::default()) when the owner's
+    record uses the default limits. Once any velocity limit has been set on
+    the VM, the owner's record for the mint of the source account is required
+    by every opcode that moves funds out of a virtual timelock account.
+
+    The VM's mint, when given, comes right before the instructions sysvar.
+    Opcodes that move real tokens for accounts of a registered mint also need
+    the registered mint account, which comes right after the mint. In that
+    case, the mint and omnibus are the ones of the registered mint, and the
+    omnibus is [ "code_vm", "vm_omnibus", <vm>, <mint> ].
+
+    The instructions sysvar, when given, is always the last account.
+
+    The optional accounts after the relays must be given in the order listed
+    above. They are found from the end of the account list, each by its key
+    or, for program accounts, by its owner and discriminator, so an account
+    given out of order is read as a relay account and the instruction fails.
 
     Instruction data:
 
@@ -54,6 +103,7 @@ pub fn process_exec(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult
     let vm = load_vm_checked(ctx.vm_info, ctx.vm_authority_info)?;
 
     ctx.check_memory_banks()?;
+    ctx.check_relays()?;
 
     let ix = Opcode::try_from(args.opcode).unwrap();
 
@@ -69,8 +119,30 @@ pub fn process_exec(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult
 
         Opcode::ConditionalTransferOp  => process_conditional_transfer(&ctx, &args),
 
+        Opcode::MergeOp                => process_merge(&ctx, &args),
+
         Opcode::AirdropOp              => process_airdrop(&ctx, &args),
 
+        Opcode::ClaimVestedOp          => process_claim_vested(&ctx, &args),
+
+        Opcode::ScheduledTransferOp       => process_scheduled_transfer(&ctx, &args),
+        Opcode::SettleScheduledTransferOp => process_settle_scheduled_transfer(&ctx, &args),
+        Opcode::CancelScheduledTransferOp => process_cancel_scheduled_transfer(&ctx, &args),
+
+        Opcode::CreateSubscriptionOp   => process_create_subscription(&ctx, &args),
+        Opcode::CollectSubscriptionOp  => process_collect_subscription(&ctx, &args),
+        Opcode::CancelSubscriptionOp   => process_cancel_subscription(&ctx, &args),
+
+        Opcode::ApproveAllowanceOp     => process_approve_allowance(&ctx, &args),
+        Opcode::DelegatedTransferOp    => process_delegated_transfer(&ctx, &args),
+        Opcode::RevokeAllowanceOp      => process_revoke_allowance(&ctx, &args),
+
+        Opcode::EscrowDepositOp        => process_escrow_deposit(&ctx, &args),
+        Opcode::EscrowReleaseOp        => process_escrow_release(&ctx, &args),
+        Opcode::EscrowRefundOp         => process_escrow_refund(&ctx, &args),
+
+        Opcode::MultisigTransferOp     => process_multisig_transfer(&ctx, &args),
+
         _ => Err(ProgramError::InvalidInstructionData),
     }?;
 
@@ -87,10 +159,18 @@ pub struct ExecContext<'a, 'b> {
     pub mem_c_info: Option<&'a AccountInfo<'b>>,
     pub mem_d_info: Option<&'a AccountInfo<'b>>,
     pub omnibus_info: Option<&'a AccountInfo<'b>>,
-    pub relay_info: Option<&'a AccountInfo<'b>>,
-    pub relay_vault_info: Option<&'a AccountInfo<'b>>,
+    pub relay_infos: Vec<Option<&'a AccountInfo<'b>>>,
+    pub relay_vault_infos: Vec<Option<&'a AccountInfo<'b>>>,
+    pub relay_nullifier_infos: Vec<Option<&'a AccountInfo<'b>>>,
     pub external_address_info: Option<&'a AccountInfo<'b>>,
     pub token_program_info: Option<&'a AccountInfo<'b>>,
+    pub fee_config_info: Option<&'a AccountInfo<'b>>,
+    pub fee_account_info: Option<&'a AccountInfo<'b>>,
+    pub velocity_limit_info: Option<&'a AccountInfo<'b>>,
+    pub velocity_default_info: Option<&'a AccountInfo<'b>>,
+    pub mint_info: Option<&'a AccountInfo<'b>>,
+    pub vm_mint_info: Option<&'a AccountInfo<'b>>,
+    pub instructions_info: Option<&'a AccountInfo<'b>>,
 }
 
 impl<'a, 'b> ExecContext<'a, 'b> {
@@ -107,8 +187,9 @@ impl<'a, 'b> ExecContext<'a, 'b> {
             relay_vault_info,
             external_address_info,
             token_program_info,
+            remaining,
         ) = match accounts {
-            [ a0, a1, a2, a3, a4, a5, a6, a7, a8, a9, a10 ] => (
+            [ a0, a1, a2, a3, a4, a5, a6, a7, a8, a9, a10, remaining @ .. ] => (
                 a0,
                 a1,
                 get_optional(a2),
@@ -120,10 +201,70 @@ impl<'a, 'b> ExecContext<'a, 'b> {
                 get_optional(a8),
                 get_optional(a9),
                 get_optional(a10),
+                remaining,
             ),
             _ => return Err(ProgramError::NotEnoughAccountKeys),
         };
 
+        let (instructions_info, remaining) = match remaining {
+            [rest @ .., last] if sysvar::instructions::check_id(last.key) => (Some(last), rest),
+            _ => (None, remaining),
+        };
+
+        let (vm_mint_info, remaining) = match remaining {
+            [rest @ .., last] if is_vm_mint(last) => (Some(last), rest),
+            _ => (None, remaining),
+        };
+
+        let mint = match vm_mint_info {
+            Some(vm_mint_info) => load_vm_mint(vm_mint_info, vm_info)?.get_mint(),
+            None => vm_info.to_account::<CodeVmAccount>(&code_vm_api::ID)?.get_mint(),
+        };
+        let (mint_info, remaining) = match remaining {
+            [rest @ .., last] if last.key.eq(&mint) => (Some(last), rest),
+            _ => (None, remaining),
+        };
+
+        let (velocity_limit_info, velocity_default_info, remaining) = match remaining {
+            [rest @ .., velocity_limit, velocity_default]
+                if is_velocity_limit(velocity_limit) && is_velocity_limit(velocity_default) =>
+                (Some(velocity_limit), Some(velocity_default), rest),
+            [rest @ .., velocity_limit] if is_velocity_limit(velocity_limit) =>
+                (Some(velocity_limit), None, rest),
+            _ => (None, None, remaining),
+        };
+
+        let (fee_config_info, fee_account_info, remaining) = match remaining {
+            [rest @ .., fee_config, fee_account]
+                if is_fee_config(fee_config) && has_fee_account(fee_config) =>
+                (Some(fee_config), Some(fee_account), rest),
+            [rest @ .., fee_config] if is_fee_config(fee_config) =>
+                (Some(fee_config), None, rest),
+            _ => (None, None, remaining),
+        };
+
+        // Any accounts after the fixed set are the nullifier for the first
+        // relay, followed by extra relays given as (relay, relay_vault,
+        // relay_nullifier) triples
+        let (relay_nullifier_info, remaining) = match remaining {
+            [] => (None, remaining),
+            [first, rest @ ..] => (get_optional(first), rest),
+        };
+
+        if remaining.len() % 3 != 0 {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        }
+
+        let mut relay_infos = vec![relay_info];
+        let mut relay_vault_infos = vec![relay_vault_info];
+        let mut relay_nullifier_infos = vec![relay_nullifier_info];
+
+        for triple in remaining.chunks_exact(3) {
+            relay_infos.push(get_optional(&triple[0]));
+            relay_vault_infos.push(get_optional(&triple[1]));
+            relay_nullifier_infos.push(get_optional(&triple[2]));
+        }
+
         Ok(Self {
             vm_authority_info,
             vm_info,
@@ -132,10 +273,18 @@ impl<'a, 'b> ExecContext<'a, 'b> {
             mem_c_info,
             mem_d_info,
             omnibus_info,
-            relay_info,
-            relay_vault_info,
+            relay_infos,
+            relay_vault_infos,
+            relay_nullifier_infos,
             external_address_info,
             token_program_info,
+            fee_config_info,
+            fee_account_info,
+            velocity_limit_info,
+            velocity_default_info,
+            mint_info,
+            vm_mint_info,
+            instructions_info,
         })
     }
 
@@ -174,6 +323,318 @@ impl<'a, 'b> ExecContext<'a, 'b> {
         Ok(())
     }
 
+    pub fn check_relays(&self) -> Result<(), ProgramError> {
+        let mut provided = Vec::with_capacity(self.relay_infos.len());
+
+        for relay_info in self.relay_infos.iter().flatten() {
+            check_mut(relay_info)?;
+            check_relay(relay_info, self.vm_info)?;
+            provided.push(*relay_info);
+        }
+
+        for relay_vault_info in self.relay_vault_infos.iter().flatten() {
+            check_mut(relay_vault_info)?;
+        }
+
+        for relay_nullifier_info in self.relay_nullifier_infos.iter().flatten() {
+            check_mut(relay_nullifier_info)?;
+        }
+
+        check_unique(
+            &provided,
+            "provided relays must be unique",
+        )?;
+
+        Ok(())
+    }
+
+    /// Returns the (relay, relay_vault, relay_nullifier) accounts at `index`
+    /// in the relay set.
+    pub fn get_relay(&self, index: u8) -> Result<(
+        &'a AccountInfo<'b>,
+        &'a AccountInfo<'b>,
+        &'a AccountInfo<'b>,
+    ), ProgramError> {
+        let index = index as usize;
+
+        check_condition(
+            index < self.relay_infos.len(),
+            "the relay index is out of range",
+        )?;
+
+        let relay_info = self.relay_infos[index];
+        let relay_vault_info = self.relay_vault_infos[index];
+        let relay_nullifier_info = self.relay_nullifier_infos[index];
+
+        check_condition(
+            relay_info.is_some(),
+            "the relay account must be provided",
+        )?;
+
+        check_condition(
+            relay_vault_info.is_some(),
+            "the relay_vault account must be provided",
+        )?;
+
+        check_condition(
+            relay_nullifier_info.is_some(),
+            "the relay_nullifier account must be provided",
+        )?;
+
+        let relay_info = relay_info.unwrap();
+        let relay_vault_info = relay_vault_info.unwrap();
+        let relay_nullifier_info = relay_nullifier_info.unwrap();
+
+        let relay = relay_info.to_account::<RelayAccount>(&code_vm_api::ID)?;
+
+        check_condition(
+            relay.treasury.vault.eq(relay_vault_info.key),
+            "relay_vault does not match the relay treasury",
+        )?;
+
+        load_relay_nullifier(relay_nullifier_info, relay_info)?;
+
+        Ok((relay_info, relay_vault_info, relay_nullifier_info))
+    }
+
+    /// The pool backing `vta`, which needs the registered mint account when
+    /// the account holds a registered mint.
+    pub fn get_pool(
+        &self,
+        vm: &CodeVmAccount,
+        vta: &VirtualTimelockAccount,
+    ) -> Result<MintPool, ProgramError> {
+        let vm_mint = match self.vm_mint_info {
+            Some(vm_mint_info) => Some(load_vm_mint(vm_mint_info, self.vm_info)?),
+            None => None,
+        };
+
+        get_mint_pool(vm, vm_mint, &vta.mint)
+    }
+
+    /// Returns the VM's fee config, if it was given with the instruction and
+    /// fees are turned on. A config with fees turned off is treated as if it
+    /// wasn't given.
+    pub fn get_fee_config(&self) -> Result<Option<FeeConfigAccount>, ProgramError> {
+        match self.fee_config_info {
+            Some(fee_config_info) => {
+                let fee_config = *load_fee_config(fee_config_info, self.vm_info)?;
+                Ok(Some(fee_config).filter(|config| config.is_enabled()))
+            },
+            None => Ok(None),
+        }
+    }
+
+    /// The number of extra memory indicies needed to pay the fee, which is
+    /// one for virtual fee recipients and zero otherwise, or when fees are
+    /// turned off.
+    pub fn get_fee_slots(&self, fee_config: &Option<FeeConfigAccount>) -> usize {
+        match fee_config.and_then(|config| config.get_recipient_kind()) {
+            Some(FeeRecipient::Virtual) => 1,
+            _ => 0,
+        }
+    }
+
+    /// Pays `fee` to the fee recipient. A virtual recipient is read from the
+    /// last memory index, which can't be used by the opcode for anything
+    /// else.
+    pub fn pay_fee(
+        &self,
+        vm: &CodeVmAccount,
+        fee_config: &FeeConfigAccount,
+        fee: u64,
+        data: &ExecIxData,
+    ) -> ProgramResult {
+        if fee == 0 {
+            return Ok(());
+        }
+
+        match fee_config.get_recipient_kind() {
+            Some(FeeRecipient::Virtual) => {
+                let mem_indicies = &data.mem_indicies;
+                let mem_banks = &data.mem_banks;
+
+                let slot = mem_indicies.len() - 1;
+                let fee_index = mem_indicies[slot];
+                let fee_mem = mem_banks[slot];
+
+                let is_used = mem_indicies[..slot].iter()
+                    .zip(mem_banks[..slot].iter())
+                    .any(|(index, mem)| *index == fee_index && *mem == fee_mem);
+
+                check_condition(
+                    !is_used,
+                    "the fee recipient can't be used by the opcode",
+                )?;
+
+                let vm_mem = self.get_banks();
+
+                check_condition(
+                    vm_mem[fee_mem as usize].is_some(),
+                    "the fee memory account must be provided",
+                )?;
+
+                let fee_mem_info = vm_mem[fee_mem as usize].unwrap();
+
+                let va = try_read(fee_mem_info, fee_index)?;
+                let mut fee_vta = va.into_inner_timelock().ok_or(ProgramError::InvalidAccountData)?;
+
+                // Fees are charged in the VM mint
+                check_holds_vm_mint(&fee_vta)?;
+
+                let fee_timelock_address = fee_vta.get_timelock_address(
+                    &vm.get_mint(),
+                    &vm.get_authority(),
+                    fee_vta.get_lock_duration(vm.get_lock_duration()),
+                );
+
+                check_condition(
+                    fee_vta.get_token_address(&fee_timelock_address).eq(&fee_config.recipient),
+                    "the fee account does not match the fee recipient",
+                )?;
+
+                fee_vta.balance = fee_vta.balance
+                    .checked_add(fee)
+                    .ok_or(ProgramError::ArithmeticOverflow)?;
+
+                try_write(
+                    fee_mem_info,
+                    fee_index,
+                    &VirtualAccount::Timelock(fee_vta)
+                )
+            }
+            Some(FeeRecipient::External) => {
+                check_condition(
+                    self.omnibus_info.is_some(),
+                    "the omnibus account must be provided",
+                )?;
+
+                check_condition(
+                    self.token_program_info.is_some(),
+                    "the token program account must be provided",
+                )?;
+
+                check_condition(
+                    self.fee_account_info.is_some(),
+                    "the fee account must be provided",
+                )?;
+
+                let omnibus_info = self.omnibus_info.unwrap();
+                let token_program_info = self.token_program_info.unwrap();
+                let fee_account_info = self.fee_account_info.unwrap();
+
+                check_mut(omnibus_info)?;
+                check_mut(fee_account_info)?;
+                check_token_program(token_program_info, vm)?;
+
+                check_condition(
+                    fee_account_info.key.eq(&fee_config.recipient),
+                    "the fee account does not match the fee recipient",
+                )?;
+
+                transfer_tokens_signed(
+                    vm,
+                    omnibus_info,
+                    omnibus_info,
+                    fee_account_info,
+                    self.mint_info,
+                    token_program_info,
+                    fee,
+                    &[&[
+                        CODE_VM,
+                        VM_OMNIBUS,
+                        self.vm_info.key.as_ref(),
+                        &[vm.get_omnibus_bump()],
+                    ]]
+                )
+            }
+            None => Err(ProgramError::InvalidAccountData),
+        }
+    }
+
+    /// Records `amount` as sent from `src_vta` against its owner's velocity
+    /// limit for the mint it holds. Once any velocity limit has been set on
+    /// the VM, the owner's record is required. Fails if it wasn't given, or
+    /// if it would take the owner over the limit for the current window.
+    ///
+    /// Owners whose record has a zero window use the VM default, which only
+    /// exists for the VM mint.
+    pub fn spend_velocity(
+        &self,
+        vm: &CodeVmAccount,
+        src_vta: &VirtualTimelockAccount,
+        amount: u64,
+    ) -> ProgramResult {
+        if !vm.has_velocity_limits() {
+            return Ok(());
+        }
+
+        check_condition(
+            self.velocity_limit_info.is_some(),
+            "the velocity limit of the source owner must be provided",
+        )?;
+
+        let velocity_limit_info = self.velocity_limit_info.unwrap();
+
+        check_mut(velocity_limit_info)?;
+
+        let velocity_limit = load_velocity_limit(velocity_limit_info, self.vm_info)?;
+
+        check_condition(
+            velocity_limit.owner.eq(&src_vta.owner),
+            "the velocity limit is for a different owner",
+        )?;
+
+        check_condition(
+            velocity_limit.mint.eq(&src_vta.mint),
+            "the velocity limit is for a different mint",
+        )?;
+
+        if !velocity_limit.has_limit() && (src_vta.has_mint() || !vm.has_velocity_default()) {
+            return Ok(());
+        }
+
+        let (limit, window) = if velocity_limit.has_limit() {
+            (velocity_limit.amount, velocity_limit.window)
+        } else {
+            check_condition(
+                self.velocity_default_info.is_some(),
+                "the VM default velocity limit must be provided",
+            )?;
+
+            let velocity_default = load_velocity_limit(
+                self.velocity_default_info.unwrap(),
+                self.vm_info,
+            )?;
+
+            check_condition(
+                velocity_default.is_default(),
+                "the velocity default is not the VM default",
+            )?;
+
+            if !velocity_default.has_limit() {
+                return Ok(());
+            }
+
+            (velocity_default.amount, velocity_default.window)
+        };
+
+        let now = Clock::get()?.unix_timestamp;
+
+        check_condition(
+            amount <= velocity_limit.get_remaining(limit, window, now),
+            "the amount exceeds the owner's velocity limit for this window",
+        )?;
+
+        let velocity_limit = velocity_limit_info
+            .to_account_mut::<VelocityLimitAccount>(&code_vm_api::ID)?;
+
+        velocity_limit.record(window, amount, now)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+
     pub fn get_banks(&self) -> [Option<&AccountInfo<'b>>; 4] {
         [
             self.mem_a_info,
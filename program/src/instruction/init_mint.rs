@@ -0,0 +1,152 @@
+use code_vm_api::prelude::*;
+use solana_program::{
+    system_program,
+    sysvar,
+};
+use steel::*;
+
+/*
+    This instruction registers an additional mint with a VM. The mint gets its
+    own omnibus, and virtual timelock accounts created for it record the mint
+    they hold. Accounts for different mints share the VM's memory, storage and
+    nonces, but opcodes only move balances between accounts of the same mint.
+
+    The mint can use the SPL Token program or Token-2022, independently of
+    the VM mint. Only the Token-2022 extensions that `InitVmIx` supports are
+    allowed, and memo transfers are out of scope in the same way.
+
+    Accounts expected by this instruction:
+    
+    | # | R/W | Type         | PDA | Name           | Description                              |
+    |---|-----|------------- |-----|----------------|------------------------------------------|
+    | 0 | mut | Signer       |     | vm_authority   | The authority of the VM.                 |
+    | 1 | mut | Vm           | PDA | vm             | The VM instance state account.           |
+    | 2 | mut | VmMint       | PDA | vm_mint        | The registration of the mint.            |
+    | 3 | mut | TokenAccount | PDA | omnibus        | A derived token account for the mint.    |
+    | 4 |     | TokenMint    |     | mint           | The mint to register.                    |
+    | 5 |     | Program      |     | token_program  | The SPL token or Token-2022 program.     |
+    | 6 |     | Program      |     | system_program | The system program.                      |
+    | 7 |     | Sysvar       |     | rent_sysvar    | The rent sysvar.                         |
+
+
+    Derived account seeds:
+
+    1. vm:      [ "code_vm", <mint>, <vm_authority>, <lock_duration> ]
+    2. vm_mint: [ "code_vm", "vm_mint", <vm>, <mint> ]
+    3. omnibus: [ "code_vm", "vm_omnibus", <vm>, <mint> ]
+
+
+    Instruction data:
+
+    0. vm_mint_bump: u8          - The bump seed for the registered mint account.
+    1. vm_mint_omnibus_bump: u8  - The bump seed for the mint's derived token account.
+*/
+pub fn process_init_mint(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+
+    let args = InitMintIx::try_from_bytes(data)?;
+    let [
+        vm_authority_info,
+        vm_info,
+        vm_mint_info,
+        omnibus_info,
+        mint_info,
+        token_program_info,
+        system_program_info,
+        rent_sysvar_info 
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);        
+    };
+
+    check_signer(vm_authority_info)?;
+    check_mut(vm_info)?;
+    check_mut(vm_mint_info)?;
+    check_mut(omnibus_info)?;
+    check_program(system_program_info, &system_program::id())?;
+    check_sysvar(rent_sysvar_info, &sysvar::rent::id())?;
+
+    let vm = load_vm_checked(vm_info, vm_authority_info)?;
+
+    check_condition(
+        mint_info.key.ne(&vm.get_mint()),
+        "the VM mint can't be registered",
+    )?;
+
+    let token_program = TokenProgram::from_id(token_program_info.key)
+        .ok_or(ProgramError::IncorrectProgramId)?;
+
+    check_program(token_program_info, &token_program.id())?;
+
+    check_condition(
+        mint_info.owner.eq(token_program_info.key),
+        "the mint is not owned by the token program",
+    )?;
+
+    check_supported_mint(mint_info)?;
+
+    check_uninitialized_pda(
+        vm_mint_info, 
+        &[
+            CODE_VM, 
+            VM_MINT,
+            vm_info.key.as_ref(),
+            mint_info.key.as_ref()
+        ],
+        args.vm_mint_bump,
+        &code_vm_api::id()
+    )?;
+    check_uninitialized_pda(
+        omnibus_info, 
+        &[
+            CODE_VM, 
+            VM_OMNIBUS,
+            vm_info.key.as_ref(),
+            mint_info.key.as_ref()
+        ],
+        args.vm_mint_omnibus_bump, 
+        &code_vm_api::id()
+    )?;
+
+    create_account::<VmMintAccount>(
+        vm_mint_info,
+        &code_vm_api::ID,
+        &[
+            CODE_VM, 
+            VM_MINT,
+            vm_info.key.as_ref(),
+            mint_info.key.as_ref(),
+            &[args.vm_mint_bump]
+        ],
+        system_program_info,
+        vm_authority_info,
+    )?;
+
+    // Create the mint's derived token account.
+    create_token_account(
+        mint_info,
+        omnibus_info,
+        &[
+            CODE_VM, 
+            VM_OMNIBUS, 
+            vm_info.key.as_ref(),
+            mint_info.key.as_ref(),
+            &[args.vm_mint_omnibus_bump]
+        ],
+        vm_authority_info,
+        token_program_info,
+        system_program_info,
+        rent_sysvar_info,
+    )?;
+
+    let vm_mint = vm_mint_info.to_account_mut::<VmMintAccount>(&code_vm_api::ID)?;
+
+    vm_mint.vm = *vm_info.key;
+    vm_mint.mint = *mint_info.key;
+    vm_mint.omnibus.vault = *omnibus_info.key;
+    vm_mint.omnibus.vault_bump = args.vm_mint_omnibus_bump;
+    vm_mint.token_program = token_program as u8;
+    vm_mint.bump = args.vm_mint_bump;
+
+    vm.advance_poh(CodeInstruction::InitMintIx, accounts, data);
+
+    Ok(())
+}
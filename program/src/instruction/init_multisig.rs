@@ -0,0 +1,87 @@
+use code_vm_api::prelude::*;
+use steel::*;
+use solana_program::msg;
+
+/*
+    This instruction initializes a virtual multisig account. The multisig is
+    given an off-curve address derived from its threshold and signer keys,
+    which is then used as the owner of the virtual timelock accounts it
+    controls. Funds in those accounts can only be moved with the
+    MultisigTransferOp opcode, using signatures from at least `threshold` of
+    the signers. The signers can also unlock and withdraw those accounts
+    non-custodially, see InitUnlockIx.
+
+    Accounts expected by this instruction:
+
+    | # | R/W | Type    | PDA | Name         | Description                              |
+    |---|-----|---------|-----|--------------|------------------------------------------|
+    | 0 | mut | Signer  |     | vm_authority | The authority of the VM.                 |
+    | 1 | mut | Vm      | PDA | vm           | The VM instance state account.           |
+    | 2 | mut | Memory  | PDA | vm_memory    | Where to create the virtual account.     |
+    | 3.. |   | Address |     | signers      | The signer keys of the multisig.         |
+
+
+    Derived account seeds:
+
+    1. vm:         [ "code_vm", <mint>, <vm_authority>, <lock_duration> ]
+    2. vm_memory:  [ "code_vm", "vm_memory_account", <self.name>, <vm> ]
+
+
+    Instruction data:
+
+    0. account_index: u16  - The location in the VM's paged memory to create the account.
+    1. threshold: u8       - The number of signatures required to move funds.
+*/
+pub fn process_init_multisig(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let args = InitMultisigIx::try_from_bytes(data)?.to_struct()?;
+    let [
+        vm_authority_info,
+        vm_info,
+        vm_memory_info,
+        signer_infos @ ..
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    check_signer(vm_authority_info)?;
+    check_mut(vm_info)?;
+    check_mut(vm_memory_info)?;
+
+    let vm = load_vm_checked(vm_info, vm_authority_info)?;
+
+    check_memory(vm_memory_info, vm_info)?;
+    check_is_empty(vm_memory_info, args.account_index)?;
+
+    let keys: Vec<Pubkey> = signer_infos.iter().map(|info| *info.key).collect();
+
+    if !VirtualMultisigAccount::is_valid_config(args.threshold, &keys) {
+        msg!(
+            "Failed condition: the multisig must have 1 to {} unique signers and 1 <= threshold <= signers",
+            MAX_MULTISIG_SIGNERS,
+        );
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    let config = VirtualMultisigAccount::get_config_hash(args.threshold, &keys);
+    let (address, _) = find_virtual_multisig_pda(vm_info.key, &config);
+
+    let mut signers = [Pubkey::default(); MAX_MULTISIG_SIGNERS];
+    signers[..keys.len()].copy_from_slice(&keys);
+
+    let multisig = VirtualMultisigAccount {
+        address,
+        threshold: args.threshold,
+        signer_count: keys.len() as u8,
+        signers,
+    };
+
+    try_write(
+        vm_memory_info,
+        args.account_index,
+        &VirtualAccount::Multisig(multisig),
+    )?;
+
+    vm.advance_poh(CodeInstruction::InitMultisigIx, accounts, data);
+
+    Ok(())
+}
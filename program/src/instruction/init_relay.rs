@@ -2,36 +2,44 @@ use code_vm_api::prelude::*;
 use solana_program::{system_program, sysvar};
 use steel::*;
 
+use crate::create_relay_nullifier;
+
 /*
     This instruction creates a new relay account and treasury. Relay accounts
     are used to facilitate private transfers using the Code privacy protocol.
 
+    The relay nullifier is created along with the relay. It outlives the
+    relay, so a relay re-created with the same name re-uses its nullifier.
+
     Accounts expected by this instruction:
 
-    | # | R/W | Type         | PDA | Name           | Description                              |
-    |---|-----|--------------|-----|----------------|------------------------------------------|
-    | 0 | mut | Signer       |     | vm_authority   | The authority of the VM.                 |
-    | 1 | mut | Vm           | PDA | vm             | The VM instance state account.           |
-    | 2 | mut | Relay        | PDA | vm_relay       | The relay account to create.             |
-    | 3 | mut | TokenAccount | PDA | vm_relay_vault | The relay token account to create.       |
-    | 4 |     | TokenMint    |     | mint           | The mint to use for this relay.          |
-    | 5 |     | Program      |     | token_program  | The SPL token program.                   |
-    | 6 |     | Program      |     | system_program | The system program.                      |
-    | 7 |     | Sysvar       |     | rent_sysvar    | The rent sysvar.                         |
+    | # | R/W | Type           | PDA | Name            | Description                              |
+    |---|-----|----------------|-----|-----------------|------------------------------------------|
+    | 0 | mut | Signer         |     | vm_authority    | The authority of the VM.                 |
+    | 1 | mut | Vm             | PDA | vm              | The VM instance state account.           |
+    | 2 | mut | Relay          | PDA | vm_relay        | The relay account to create.             |
+    | 3 | mut | TokenAccount   | PDA | vm_relay_vault  | The relay token account to create.       |
+    | 4 |     | TokenMint      |     | mint            | The mint to use for this relay.          |
+    | 5 |     | Program        |     | token_program   | The VM's token program.                  |
+    | 6 |     | Program        |     | system_program  | The system program.                      |
+    | 7 |     | Sysvar         |     | rent_sysvar     | The rent sysvar.                         |
+    | 8 | mut | RelayNullifier | PDA | relay_nullifier | The relay nullifier account to create.   |
 
 
     Derived account seeds:
 
-    1. vm:           [ "code_vm", <mint>, <vm_authority>, <lock_duration> ]
-    2. relay:        [ "code_vm", "vm_relay_account", <data.name>, <vm> ]
-    2. relay_vault:  [ "code_vm", "vm_relay_vault", <relay> ]
+    1. vm:              [ "code_vm", <mint>, <vm_authority>, <lock_duration> ]
+    2. relay:           [ "code_vm", "vm_relay_account", <data.name>, <vm> ]
+    3. relay_vault:     [ "code_vm", "vm_relay_vault", <relay> ]
+    4. relay_nullifier: [ "code_vm", "vm_relay_nullifier", <relay> ]
 
 
     Instruction data:
 
-    0. name: [u8; 32]        - The name of this storage module.
-    1. relay_bump: u8        - The bump seed for the this relay account.
-    2. relay_vault_bump: u8  - The bump seed for the relay token account.
+    0. name: [u8; 32]           - The name of this storage module.
+    1. relay_bump: u8           - The bump seed for the this relay account.
+    2. relay_vault_bump: u8     - The bump seed for the relay token account.
+    3. relay_nullifier_bump: u8 - The bump seed for the relay nullifier account.
 */
 pub fn process_init_relay(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
     let args = InitRelayIx::try_from_bytes(data)?;
@@ -43,7 +51,8 @@ pub fn process_init_relay(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramR
         mint_info,
         token_program_info,
         system_program_info,
-        rent_sysvar_info 
+        rent_sysvar_info,
+        relay_nullifier_info,
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);        
     };
@@ -52,12 +61,14 @@ pub fn process_init_relay(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramR
     check_mut(vm_info)?;
     check_mut(relay_info)?;
     check_mut(relay_vault_info)?;
-    check_program(token_program_info, &spl_token::id())?;
+    check_mut(relay_nullifier_info)?;
     check_program(system_program_info, &system_program::id())?;
     check_sysvar(rent_sysvar_info, &sysvar::rent::id())?;
 
     let vm = load_vm_checked(vm_info, vm_authority_info)?;
 
+    check_token_program(token_program_info, vm)?;
+
     check_condition(
         mint_info.key == &vm.mint,
         "mint account does not match VM instance",
@@ -109,6 +120,7 @@ pub fn process_init_relay(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramR
             &[args.relay_vault_bump],
         ],
         vm_authority_info,
+        token_program_info,
         system_program_info,
         rent_sysvar_info,
     )?;
@@ -130,6 +142,18 @@ pub fn process_init_relay(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramR
 
     relay.recent_roots.push(relay.history.get_root().as_ref());
 
+    if relay_nullifier_info.data_is_empty() {
+        create_relay_nullifier(
+            vm_authority_info,
+            relay_info,
+            relay_nullifier_info,
+            system_program_info,
+            args.relay_nullifier_bump,
+        )?;
+    } else {
+        load_relay_nullifier(relay_nullifier_info, relay_info)?;
+    }
+
     vm.advance_poh(CodeInstruction::InitRelayIx, accounts, data);
 
     Ok(())
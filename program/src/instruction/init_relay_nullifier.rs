@@ -0,0 +1,114 @@
+use code_vm_api::prelude::*;
+use solana_program::system_program;
+use steel::*;
+
+/*
+    This instruction creates the nullifier account for a relay that was
+    created before InitRelayIx created the nullifier itself. The nullifier
+    keeps track of the commitments that were already used by the relay, and
+    must be provided alongside the relay to make private payments.
+
+    Relays created before the nullifier existed can't make private payments
+    until this instruction has been run for them, so the VM authority must
+    run it once for each existing relay when upgrading.
+
+    Note, the nullifier is derived from the relay address and is not closed
+    with the relay. A relay re-created with the same name will re-use it.
+
+    Accounts expected by this instruction:
+
+    | # | R/W | Type             | PDA | Name            | Description                              |
+    |---|-----|------------------|-----|-----------------|------------------------------------------|
+    | 0 | mut | Signer           |     | vm_authority    | The authority of the VM.                 |
+    | 1 | mut | Vm               | PDA | vm              | The VM instance state account.           |
+    | 2 |     | Relay            | PDA | relay           | The relay to create a nullifier for.     |
+    | 3 | mut | RelayNullifier   | PDA | relay_nullifier | The relay nullifier account to create.   |
+    | 4 |     | Program          |     | system_program  | The system program.                      |
+
+
+    Derived account seeds:
+
+    1. vm:               [ "code_vm", <mint>, <vm_authority>, <lock_duration> ]
+    2. relay:            [ "code_vm", "vm_relay_account", <self.name>, <vm> ]
+    3. relay_nullifier:  [ "code_vm", "vm_relay_nullifier", <relay> ]
+
+
+    Instruction data:
+
+    0. relay_nullifier_bump: u8  - The bump seed for the relay nullifier account.
+*/
+pub fn process_init_relay_nullifier(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let args = InitRelayNullifierIx::try_from_bytes(data)?;
+    let [
+        vm_authority_info,
+        vm_info,
+        relay_info,
+        relay_nullifier_info,
+        system_program_info,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    check_signer(vm_authority_info)?;
+    check_mut(vm_info)?;
+    check_mut(relay_nullifier_info)?;
+    check_program(system_program_info, &system_program::id())?;
+
+    let vm = load_vm_checked(vm_info, vm_authority_info)?;
+
+    check_relay(relay_info, vm_info)?;
+
+    create_relay_nullifier(
+        vm_authority_info,
+        relay_info,
+        relay_nullifier_info,
+        system_program_info,
+        args.relay_nullifier_bump,
+    )?;
+
+    vm.advance_poh(CodeInstruction::InitRelayNullifierIx, accounts, data);
+
+    Ok(())
+}
+
+/// Creates the nullifier account of a relay, see InitRelayIx and
+/// InitRelayNullifierIx.
+pub fn create_relay_nullifier<'a>(
+    vm_authority_info: &AccountInfo<'a>,
+    relay_info: &AccountInfo<'a>,
+    relay_nullifier_info: &AccountInfo<'a>,
+    system_program_info: &AccountInfo<'a>,
+    relay_nullifier_bump: u8,
+) -> ProgramResult {
+    check_uninitialized_pda(
+        relay_nullifier_info,
+        &[
+            CODE_VM,
+            VM_RELAY_NULLIFIER,
+            relay_info.key.as_ref(),
+        ],
+        relay_nullifier_bump,
+        &code_vm_api::id(),
+    )?;
+
+    create_account::<RelayNullifierAccount>(
+        relay_nullifier_info,
+        &code_vm_api::ID,
+        &[
+            CODE_VM,
+            VM_RELAY_NULLIFIER,
+            relay_info.key.as_ref(),
+            &[relay_nullifier_bump],
+        ],
+        system_program_info,
+        vm_authority_info,
+    )?;
+
+    let nullifier = relay_nullifier_info
+        .to_account_mut::<RelayNullifierAccount>(&code_vm_api::ID)?;
+
+    nullifier.relay = *relay_info.key;
+    nullifier.bump = relay_nullifier_bump;
+
+    Ok(())
+}
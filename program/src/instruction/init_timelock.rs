@@ -1,4 +1,5 @@
 use code_vm_api::{prelude::*, pdas};
+use solana_program::msg;
 use steel::*;
 
 /*
@@ -9,6 +10,23 @@ use steel::*;
     A timelock account can be non-custodially unlocked by the owner using the
     init_unlock and unlock instructions.
 
+    The account uses the VM lock duration unless a different lock_duration is
+    provided, in which case the account carries its own. This allows a single
+    VM to offer multiple lock durations.
+
+    Similarly, the account holds the VM mint unless a registered mint account
+    is provided, in which case it holds that mint. The mint is part of the
+    timelock address, so the bumps must be derived with it.
+
+    Note, these accounts are larger than the original layout, so they don't
+    fit the standard VirtualTimelockAccount::LEN + 1 memory slots. The memory
+    account size must be at least:
+
+    - LEN_WITH_LOCK_DURATION + 1 for accounts with their own lock duration.
+    - LEN_WITH_MINT + 1 for accounts holding a registered mint.
+
+    Smaller slots are rejected with AccountDataTooSmall.
+
     Accounts expected by this instruction:
     
     | # | R/W | Type    | PDA | Name                   | Description                              |
@@ -17,12 +35,14 @@ use steel::*;
     | 1 | mut | Vm      | PDA | vm                     | The VM instance state account.           |
     | 2 | mut | Memory  | PDA | vm_memory              | Where to create the virtual account.     |
     | 3 |     | Address |     | virtual_account_owner  | The virtual account owner.               |
+    | 4 |     | VmMint  | PDA | vm_mint                | (optional) A mint registered with the VM.|
 
 
     Derived account seeds:
 
     1. vm:         [ "code_vm", <mint>, <vm_authority>, <lock_duration> ]
     2. vm_memory:  [ "code_vm", "vm_memory_account", <self.name>, <vm> ]
+    3. vm_mint:    [ "code_vm", "vm_mint", <vm>, <mint> ]
 
     Instruction data:
 
@@ -30,6 +50,7 @@ use steel::*;
     1. virtual_timelock_bump: u8 - The bump seed for the virtual timelock account.
     2. virtual_vault_bump: u8    - The bump seed for the virtual token account.
     3. unlock_pda_bump: u8       - The bump seed for the unlock PDA address.
+    4. lock_duration: u8         - The lock duration in days, or zero to use the VM's.
 
 */
 pub fn process_init_timelock(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
@@ -40,6 +61,7 @@ pub fn process_init_timelock(accounts: &[AccountInfo<'_>], data: &[u8]) -> Progr
         vm_info,
         vm_memory_info,
         virtual_account_owner_info,
+        remaining @ ..
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);        
     };
@@ -53,14 +75,26 @@ pub fn process_init_timelock(accounts: &[AccountInfo<'_>], data: &[u8]) -> Progr
     check_memory(vm_memory_info, vm_info)?;
     check_is_empty(vm_memory_info, args.account_index)?;
 
+    // Accounts for the VM mint keep a zero mint, and the original layout
+    let (mint, timelock_mint) = match find_vm_mint(vm_info, remaining)? {
+        Some(vm_mint) => (vm_mint.get_mint(), vm_mint.get_mint()),
+        None => (Pubkey::default(), vm.get_mint()),
+    };
+
     let owner = virtual_account_owner_info.key.clone();
     let nonce = vm.get_current_poh();
 
+    let lock_duration = if args.lock_duration == 0 {
+        vm.get_lock_duration()
+    } else {
+        args.lock_duration
+    };
+
     let (timelock_address, timelock_bump) = pdas::find_virtual_timelock_address(
-        &vm.get_mint(), 
+        &timelock_mint, 
         &vm.get_authority(), 
         &owner, 
-        vm.get_lock_duration(), 
+        lock_duration, 
     );
 
     if args.virtual_timelock_bump != timelock_bump {
@@ -92,9 +126,27 @@ pub fn process_init_timelock(accounts: &[AccountInfo<'_>], data: &[u8]) -> Progr
         unlock_bump: args.unlock_pda_bump,
         withdraw_bump,
         balance: 0,
+
+        // Accounts using the VM lock duration keep the original layout
+        lock_duration: if lock_duration == vm.get_lock_duration() {
+            0
+        } else {
+            lock_duration
+        },
+        mint,
     };
     let va = VirtualAccount::Timelock(vta);
 
+    let (_, account_size) = MemoryAccount::get_capacity_and_size(vm_memory_info);
+    if va.get_size() > account_size {
+        msg!(
+            "The virtual account needs {} bytes, but vm_memory only has {} byte slots",
+            va.get_size(),
+            account_size,
+        );
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+
     try_write(vm_memory_info, args.account_index, &va)?;
 
     vm.advance_poh(CodeInstruction::InitTimelockIx, accounts, data);
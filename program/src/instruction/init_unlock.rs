@@ -4,9 +4,18 @@ use solana_program::msg;
 
 /*
     This instruction is used to begin the unlock process for a timelocked
-    account. Once the VM lockduration has passed, the owner can finalize the
+    account. Once the lock duration has passed, the owner can finalize the
     unlock process and withdraw their funds non-custodially.
 
+    Accounts created with their own lock duration must provide it here, since
+    it is part of the timelock address. Otherwise, the VM lock duration is used.
+    For the same reason, accounts for a registered mint must provide the
+    registered mint account.
+
+    An owner that is a multisig address doesn't sign. Instead, the multisig
+    signer keys are given as the last accounts, and `threshold` of them sign.
+    The threshold and number of signers are appended to the instruction data.
+
     Accounts expected by this instruction:
     
     | # | R/W | Type        | PDA | Name           | Description                       |
@@ -17,12 +26,19 @@ use solana_program::msg;
     | 3 | mut | UnlockState | PDA | unlock_pda     | Account to create.                |
     | 4 |     | Program     |     | system_program | The system program.               |
     | 5 |     | Sysvar      |     | rent_sysvar    | The rent sysvar.                  |
+    | 6 |     | VmMint      | PDA | vm_mint        | (optional) The registered mint.   |
+    |...|     | Signer      |     | multisig_signers | (optional) For a multisig owner.  |
 
 
     Derived account seeds:
 
     2. vm:          [ "code_vm", <mint>, <vm_authority>, <lock_duration> ]
     3. unlock_pda:  [ "code_vm", "vm_unlock_pda_account", <account_owner>, <timelock_address>, <vm> ]
+    6. vm_mint:     [ "code_vm", "vm_mint", <vm>, <mint> ]
+
+    Instruction data:
+
+    0. lock_duration: u8  - (optional) The lock duration in days, or zero to use the VM's.
 
 */
 pub fn process_init_unlock(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
@@ -34,11 +50,20 @@ pub fn process_init_unlock(accounts: &[AccountInfo<'_>], data: &[u8]) -> Program
         unlock_pda_info,
         system_program_info,
         rent_sysvar_info,
+        remaining @ ..
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);        
     };
 
-    check_signer(account_owner_info)?;
+    let ix_data = check_owner_signer(account_owner_info, vm_info, remaining, data)?;
+
+    // Older clients don't send any instruction data
+    let lock_duration = if ix_data.is_empty() {
+        0
+    } else {
+        InitUnlockIx::try_from_bytes(ix_data)?.lock_duration
+    };
+
     check_signer(payer_info)?;
     check_program(system_program_info, &system_program::id())?;
     check_sysvar(rent_sysvar_info, &sysvar::rent::id())?;
@@ -57,11 +82,22 @@ pub fn process_init_unlock(accounts: &[AccountInfo<'_>], data: &[u8]) -> Program
         &code_vm_api::ID
     )?;
 
+    let lock_duration = if lock_duration == 0 {
+        vm.get_lock_duration()
+    } else {
+        lock_duration
+    };
+
+    let mint = match find_vm_mint(vm_info, remaining)? {
+        Some(vm_mint) => vm_mint.get_mint(),
+        None => vm.get_mint(),
+    };
+
     let (timelock_address, _) = find_virtual_timelock_address(
-        &vm.get_mint(), 
+        &mint, 
         &vm.get_authority(), 
         account_owner_info.key, 
-        vm.get_lock_duration(),
+        lock_duration,
     );
 
     let (unlock_pda, bump) = find_unlock_address(
@@ -105,7 +141,7 @@ pub fn process_init_unlock(accounts: &[AccountInfo<'_>], data: &[u8]) -> Program
 
     let now = Clock::get()?.unix_timestamp;
     let second_per_day = 86400; // 60sec * 60min * 24hrs = 86400
-    let mut unlock_at = now + (vm.lock_duration as i64 * second_per_day); 
+    let mut unlock_at = now + (lock_duration as i64 * second_per_day); 
     if unlock_at % second_per_day > 0 {
         unlock_at = unlock_at + (second_per_day - (unlock_at % second_per_day))
     }
@@ -0,0 +1,119 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+/*
+    This instruction initializes a virtual vesting account. The total amount
+    is moved from the source_ata into the VM omnibus, and is released to the
+    beneficiary linearly between the start and end of the schedule. Nothing
+    can be claimed before the cliff.
+
+    Vested tokens are moved into one of the beneficiary's virtual timelock
+    accounts using the ClaimVestedOp opcode. Until then, they are custodial
+    (see ClaimVestedOp).
+
+    Accounts expected by this instruction:
+
+    | # | R/W | Type         | PDA | Name             | Description                                   |
+    |---|-----|--------------|-----|------------------|-----------------------------------------------|
+    | 0 | mut | Signer       |     | vm_authority     | The authority of the VM.                      |
+    | 1 | mut | Vm           | PDA | vm               | The VM instance state account.                |
+    | 2 | mut | Memory       | PDA | vm_memory        | Where to create the virtual account.          |
+    | 3 | mut | Signer       |     | source_authority | The owner of the tokens to vest.              |
+    | 4 | mut | TokenAccount |     | source_ata       | The token account to pull the total from.     |
+    | 5 |     | Address      |     | beneficiary      | The owner of the vested tokens.               |
+    | 6 | mut | TokenAccount | PDA | omnibus          | A derived token account owned by vm.          |
+    | 7 |     | Program      |     | token_program    | The VM's token program.                       |
+    | 8 |     | TokenMint    |     | mint             | The VM's mint. Required for Token-2022 VMs.   |
+
+
+    Derived account seeds:
+
+    1. vm:          [ "code_vm", <mint>, <vm_authority>, <lock_duration> ]
+    2. vm_memory:   [ "code_vm", "vm_memory_account", <self.name>, <vm> ]
+    6. omnibus:     [ "code_vm", "vm_omnibus", <vm> ]
+
+    Instruction data:
+
+    0. account_index: u16 - The location in the VM's paged memory to create the account.
+    1. total: u64         - The total amount of tokens to vest.
+    2. start: i64         - The unix timestamp when vesting begins.
+    3. cliff: i64         - The unix timestamp before which nothing can be claimed.
+    4. end: i64           - The unix timestamp when the total amount has vested.
+*/
+pub fn process_init_vesting(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let args = InitVestingIx::try_from_bytes(data)?.to_struct()?;
+    let [
+        vm_authority_info,
+        vm_info,
+        vm_memory_info,
+        source_authority_info,
+        source_ata_info,
+        beneficiary_info,
+        omnibus_info,
+        token_program_info,
+        remaining @ ..
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    check_signer(vm_authority_info)?;
+    check_signer(source_authority_info)?;
+    check_mut(vm_info)?;
+    check_mut(vm_memory_info)?;
+    check_mut(source_ata_info)?;
+    check_mut(omnibus_info)?;
+
+    let vm = load_vm_checked(vm_info, vm_authority_info)?;
+
+    check_token_program(token_program_info, vm)?;
+
+    check_omnibus(omnibus_info, vm_info)?;
+    check_memory(vm_memory_info, vm_info)?;
+    check_is_empty(vm_memory_info, args.account_index)?;
+
+    let mut vesting = VirtualVestingAccount {
+        beneficiary: *beneficiary_info.key,
+        total: args.total,
+        claimed: 0,
+        start: args.start,
+        cliff: args.cliff,
+        end: args.end,
+    };
+
+    check_condition(
+        vesting.total > 0,
+        "the vesting total must be greater than zero",
+    )?;
+
+    check_condition(
+        vesting.is_valid_schedule(),
+        "the vesting schedule must satisfy start <= cliff <= end",
+    )?;
+
+    let omnibus_balance = get_token_amount(omnibus_info)?;
+
+    transfer_tokens(
+        vm,
+        source_authority_info,
+        source_ata_info,
+        omnibus_info,
+        get_optional_mint(&vm.get_mint(), remaining),
+        token_program_info,
+        args.total,
+    )?;
+
+    // Only vest what the omnibus received, see DepositFromPdaIx
+    vesting.total = get_token_amount(omnibus_info)?
+        .checked_sub(omnibus_balance)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    try_write(
+        vm_memory_info,
+        args.account_index,
+        &VirtualAccount::Vesting(vesting),
+    )?;
+
+    vm.advance_poh(CodeInstruction::InitVestingIx, accounts, data);
+
+    Ok(())
+}
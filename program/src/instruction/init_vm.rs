@@ -8,6 +8,16 @@ use steel::*;
 /*
     This instruction initializes a new VM instance owned by the given authority.
 
+    The VM uses whichever token program is passed in, which must be the SPL
+    Token program or Token-2022, and must own the mint. Token-2022 mints may
+    only use the transfer fee, interest bearing, metadata pointer, token
+    metadata and mint close authority extensions; any other extension is
+    rejected, since it could let someone else move or freeze the omnibus
+    tokens, or need accounts the VM doesn't pass to the token program.
+
+    Memo transfers are out of scope. The VM doesn't send memos, so deposits
+    from and withdrawals to token accounts that require incoming memos fail.
+
     Accounts expected by this instruction:
     
     | # | R/W | Type         | PDA | Name           | Description                              |
@@ -16,7 +26,7 @@ use steel::*;
     | 1 | mut | Vm           | PDA | vm             | The VM instance state account.           |
     | 2 | mut | TokenAccount | PDA | omnibus        | A derived token account owned by the VM. |
     | 3 |     | TokenMint    |     | mint           | The mint to use for this VM instance.    |
-    | 4 |     | Program      |     | token_program  | The SPL token program.                   |
+    | 4 |     | Program      |     | token_program  | The SPL token or Token-2022 program.     |
     | 5 |     | Program      |     | system_program | The system program.                      |
     | 6 |     | Sysvar       |     | rent_sysvar    | The rent sysvar.                         |
 
@@ -56,10 +66,21 @@ pub fn process_init_vm(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResu
     check_signer(vm_authority_info)?;
     check_mut(vm_info)?;
     check_mut(omnibus_info)?;
-    check_program(token_program_info, &spl_token::id())?;
     check_program(system_program_info, &system_program::id())?;
     check_sysvar(rent_sysvar_info, &sysvar::rent::id())?;
 
+    let token_program = TokenProgram::from_id(token_program_info.key)
+        .ok_or(ProgramError::IncorrectProgramId)?;
+
+    check_program(token_program_info, &token_program.id())?;
+
+    check_condition(
+        mint_info.owner.eq(token_program_info.key),
+        "the mint is not owned by the token program",
+    )?;
+
+    check_supported_mint(mint_info)?;
+
     check_uninitialized_pda(
         vm_info, 
         &[
@@ -108,6 +129,7 @@ pub fn process_init_vm(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResu
             &[args.vm_omnibus_bump]
         ],
         vm_authority_info,
+        token_program_info,
         system_program_info,
         rent_sysvar_info,
     )?;
@@ -118,6 +140,7 @@ pub fn process_init_vm(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResu
     vm.mint = mint_info.key.clone();
     vm.lock_duration = args.lock_duration;                                                                                                                                                      
     vm.bump = args.vm_bump;
+    vm.token_program = token_program as u8;
     vm.omnibus.vault = omnibus_info.key.clone();
     vm.omnibus.vault_bump = args.vm_omnibus_bump;
 
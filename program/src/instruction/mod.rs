@@ -1,32 +1,54 @@
+mod cancel_unlock;
+mod close_deposit_receipt;
+mod close_relay;
 mod compress;
 mod decompress;
 mod deposit;
 mod exec;
 mod init_memory;
+mod init_mint;
+mod init_multisig;
 mod init_nonce;
 mod init_relay;
+mod init_relay_nullifier;
 mod init_storage;
 mod init_timelock;
 mod init_unlock;
+mod init_vesting;
 mod init_vm;
 mod resize;
+mod set_fee_config;
+mod set_message_policy;
+mod set_signature_mode;
+mod set_velocity_limit;
 mod snapshot;
 mod swap;
 mod unlock;
 mod withdraw;
 
+pub use cancel_unlock::*;
+pub use close_deposit_receipt::*;
+pub use close_relay::*;
 pub use compress::*;
 pub use decompress::*;
 pub use deposit::*;
 pub use exec::*;
 pub use init_memory::*;
+pub use init_mint::*;
+pub use init_multisig::*;
 pub use init_nonce::*;
 pub use init_relay::*;
+pub use init_relay_nullifier::*;
 pub use init_storage::*;
 pub use init_timelock::*;
 pub use init_unlock::*;
+pub use init_vesting::*;
 pub use init_vm::*;
 pub use resize::*;
+pub use set_fee_config::*;
+pub use set_message_policy::*;
+pub use set_signature_mode::*;
+pub use set_velocity_limit::*;
 pub use snapshot::*;
 pub use swap::*;
 pub use unlock::*;
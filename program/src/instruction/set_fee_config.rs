@@ -0,0 +1,112 @@
+use code_vm_api::prelude::*;
+use solana_program::system_program;
+use steel::*;
+
+/*
+    This instruction sets the protocol fee charged by the VM on TransferOp,
+    ExternalTransferOp and AirdropOp. The fee config account is created the
+    first time this is called, and updated after that. Setting both the bps
+    and the minimum to zero turns fees off.
+
+    The fee is paid by the source on top of the transferred amount, and the
+    exact fee is part of the signed message.
+
+    Accounts expected by this instruction:
+
+    | # | R/W | Type      | PDA | Name           | Description                              |
+    |---|-----|-----------|-----|----------------|------------------------------------------|
+    | 0 | mut | Signer    |     | vm_authority   | The authority of the VM.                 |
+    | 1 | mut | Vm        | PDA | vm             | The VM instance state account.           |
+    | 2 | mut | FeeConfig | PDA | fee_config     | The fee config account to create/update. |
+    | 3 |     | Program   |     | system_program | The system program.                      |
+
+
+    Derived account seeds:
+
+    1. vm:          [ "code_vm", <mint>, <vm_authority>, <lock_duration> ]
+    2. fee_config:  [ "code_vm", "vm_fee_config", <vm> ]
+
+
+    Instruction data:
+
+    0. recipient: Pubkey      - The token address of the virtual account, or the
+                                external token account, that receives fees.
+    1. minimum: u64           - The minimum fee, in quarks.
+    2. bps: u16               - The fee in basis points of the amount.
+    3. recipient_kind: u8     - The FeeRecipient kind.
+    4. fee_config_bump: u8    - The bump seed for the fee config account.
+*/
+pub fn process_set_fee_config(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let args = SetFeeConfigIx::try_from_bytes(data)?.to_struct()?;
+    let [
+        vm_authority_info,
+        vm_info,
+        fee_config_info,
+        system_program_info,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    check_signer(vm_authority_info)?;
+    check_mut(vm_info)?;
+    check_mut(fee_config_info)?;
+    check_program(system_program_info, &system_program::id())?;
+
+    let vm = load_vm_checked(vm_info, vm_authority_info)?;
+
+    check_condition(
+        args.bps <= MAX_FEE_BPS,
+        "the fee can't be more than 10,000 bps",
+    )?;
+
+    check_condition(
+        FeeRecipient::try_from(args.recipient_kind).is_ok(),
+        "invalid fee recipient kind",
+    )?;
+
+    if fee_config_info.data_is_empty() {
+        check_uninitialized_pda(
+            fee_config_info,
+            &[
+                CODE_VM,
+                VM_FEE_CONFIG,
+                vm_info.key.as_ref(),
+            ],
+            args.fee_config_bump,
+            &code_vm_api::id(),
+        )?;
+
+        create_account::<FeeConfigAccount>(
+            fee_config_info,
+            &code_vm_api::ID,
+            &[
+                CODE_VM,
+                VM_FEE_CONFIG,
+                vm_info.key.as_ref(),
+                &[args.fee_config_bump],
+            ],
+            system_program_info,
+            vm_authority_info,
+        )?;
+
+        let fee_config = fee_config_info
+            .to_account_mut::<FeeConfigAccount>(&code_vm_api::ID)?;
+
+        fee_config.vm = *vm_info.key;
+        fee_config.bump = args.fee_config_bump;
+    }
+
+    load_fee_config(fee_config_info, vm_info)?;
+
+    let fee_config = fee_config_info
+        .to_account_mut::<FeeConfigAccount>(&code_vm_api::ID)?;
+
+    fee_config.recipient = args.recipient;
+    fee_config.minimum = args.minimum;
+    fee_config.bps = args.bps;
+    fee_config.recipient_kind = args.recipient_kind;
+
+    vm.advance_poh(CodeInstruction::SetFeeConfigIx, accounts, data);
+
+    Ok(())
+}
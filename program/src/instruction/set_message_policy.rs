@@ -0,0 +1,49 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+/*
+    This instruction sets which signed message versions the VM accepts for
+    its opcodes. VMs start out accepting v1 messages only; moving to
+    V1AndV2 lets wallets upgrade to the domain-separated v2 format while
+    older wallets keep working, and V2Only turns v1 messages off.
+
+    Accounts expected by this instruction:
+
+    | # | R/W | Type    | PDA | Name         | Description                              |
+    |---|-----|---------|-----|--------------|------------------------------------------|
+    | 0 | mut | Signer  |     | vm_authority | The authority of the VM.                 |
+    | 1 | mut | Vm      | PDA | vm           | The VM instance state account.           |
+
+
+    Derived account seeds:
+
+    1. vm:         [ "code_vm", <mint>, <vm_authority>, <lock_duration> ]
+
+
+    Instruction data:
+
+    0. message_policy: u8  - The MessagePolicy to use for the VM.
+*/
+pub fn process_set_message_policy(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let args = SetMessagePolicyIx::try_from_bytes(data)?;
+    let [
+        vm_authority_info,
+        vm_info,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    check_signer(vm_authority_info)?;
+    check_mut(vm_info)?;
+
+    let vm = load_vm_checked(vm_info, vm_authority_info)?;
+
+    let policy = MessagePolicy::try_from(args.message_policy)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    vm.message_policy = policy as u8;
+
+    vm.advance_poh(CodeInstruction::SetMessagePolicyIx, accounts, data);
+
+    Ok(())
+}
@@ -0,0 +1,50 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+/*
+    This instruction sets how the VM verifies opcode signatures. VMs start
+    out verifying signatures with the curve25519 syscalls. Switching to the
+    Precompile mode requires every signed opcode to be accompanied by an
+    Ed25519 program instruction in the same transaction, which trades
+    transaction size for compute.
+
+    Accounts expected by this instruction:
+
+    | # | R/W | Type    | PDA | Name         | Description                              |
+    |---|-----|---------|-----|--------------|------------------------------------------|
+    | 0 | mut | Signer  |     | vm_authority | The authority of the VM.                 |
+    | 1 | mut | Vm      | PDA | vm           | The VM instance state account.           |
+
+
+    Derived account seeds:
+
+    1. vm:         [ "code_vm", <mint>, <vm_authority>, <lock_duration> ]
+
+
+    Instruction data:
+
+    0. signature_mode: u8  - The SignatureMode to use for the VM.
+*/
+pub fn process_set_signature_mode(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let args = SetSignatureModeIx::try_from_bytes(data)?;
+    let [
+        vm_authority_info,
+        vm_info,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    check_signer(vm_authority_info)?;
+    check_mut(vm_info)?;
+
+    let vm = load_vm_checked(vm_info, vm_authority_info)?;
+
+    let mode = SignatureMode::try_from(args.signature_mode)
+        .map_err(|_| ProgramError::InvalidInstructionData)?;
+
+    vm.signature_mode = mode as u8;
+
+    vm.advance_poh(CodeInstruction::SetSignatureModeIx, accounts, data);
+
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use code_vm_api::prelude::*;
+use solana_program::system_program;
+use steel::*;
+
+/*
+    This instruction sets how much an owner's virtual accounts can send per
+    window of time. The velocity limit account is created the first time this
+    is called, and updated after that.
+
+    Using Pubkey::default() as the owner sets the VM default, which applies
+    to owners whose own record has a zero window. A zero window on the VM
+    default turns the default limit off.
+
+    Once any limit has been set, every opcode that moves funds out of a
+    virtual timelock account requires the velocity limit record of the
+    source owner, so each owner needs a record, even if it only uses the
+    default (a zero window).
+
+    Updating a limit keeps the usage of the current window.
+
+    Limits are amounts of the VM mint, unless a mint registered with
+    InitMintIx is given. Each registered mint has its own records, in units
+    of that mint, and no VM default.
+
+    Accounts expected by this instruction:
+
+    | # | R/W | Type          | PDA | Name           | Description                                  |
+    |---|-----|---------------|-----|----------------|----------------------------------------------|
+    | 0 | mut | Signer        |     | vm_authority   | The authority of the VM.                     |
+    | 1 | mut | Vm            | PDA | vm             | The VM instance state account.               |
+    | 2 | mut | VelocityLimit | PDA | velocity_limit | The velocity limit account to create/update. |
+    | 3 |     | Program       |     | system_program | The system program.                          |
+
+
+    Derived account seeds:
+
+    1. vm:              [ "code_vm", <mint>, <vm_authority>, <lock_duration> ]
+    2. velocity_limit:  [ "code_vm", "vm_velocity_limit", <vm>, <owner> ]
+                        [ "code_vm", "vm_velocity_limit", <vm>, <owner>, <mint> ]
+
+
+    Instruction data:
+
+    0. owner: Pubkey              - The owner to limit, or Pubkey::default().
+    1. mint: Pubkey               - A registered mint, or Pubkey::default().
+    2. amount: u64                - The most that can be sent per window.
+    3. window: i64                - The window length, in seconds.
+    4. velocity_limit_bump: u8    - The bump seed for the velocity limit account.
+*/
+pub fn process_set_velocity_limit(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
+    let args = SetVelocityLimitIx::try_from_bytes(data)?.to_struct()?;
+    let [
+        vm_authority_info,
+        vm_info,
+        velocity_limit_info,
+        system_program_info,
+    ] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    check_signer(vm_authority_info)?;
+    check_mut(vm_info)?;
+    check_mut(velocity_limit_info)?;
+    check_program(system_program_info, &system_program::id())?;
+
+    let vm = load_vm_checked(vm_info, vm_authority_info)?;
+
+    check_condition(
+        args.window >= 0,
+        "the velocity window can't be negative",
+    )?;
+
+    let has_mint = args.mint.ne(&Pubkey::default());
+
+    check_condition(
+        !has_mint || args.owner.ne(&Pubkey::default()),
+        "registered mints don't have a VM default",
+    )?;
+
+    if velocity_limit_info.data_is_empty() {
+        let bump = [args.velocity_limit_bump];
+        let mut seeds = vec![
+            CODE_VM,
+            VM_VELOCITY_LIMIT,
+            vm_info.key.as_ref(),
+            args.owner.as_ref(),
+        ];
+        if has_mint {
+            seeds.push(args.mint.as_ref());
+        }
+
+        check_uninitialized_pda(
+            velocity_limit_info,
+            &seeds,
+            args.velocity_limit_bump,
+            &code_vm_api::id(),
+        )?;
+
+        seeds.push(&bump);
+
+        create_account::<VelocityLimitAccount>(
+            velocity_limit_info,
+            &code_vm_api::ID,
+            &seeds,
+            system_program_info,
+            vm_authority_info,
+        )?;
+
+        let velocity_limit = velocity_limit_info
+            .to_account_mut::<VelocityLimitAccount>(&code_vm_api::ID)?;
+
+        velocity_limit.vm = *vm_info.key;
+        velocity_limit.owner = args.owner;
+        velocity_limit.mint = args.mint;
+        velocity_limit.bump = args.velocity_limit_bump;
+    }
+
+    let velocity_limit = load_velocity_limit(velocity_limit_info, vm_info)?;
+
+    check_condition(
+        velocity_limit.owner.eq(&args.owner),
+        "owner does not match the velocity limit account",
+    )?;
+
+    check_condition(
+        velocity_limit.mint.eq(&args.mint),
+        "mint does not match the velocity limit account",
+    )?;
+
+    let velocity_limit = velocity_limit_info
+        .to_account_mut::<VelocityLimitAccount>(&code_vm_api::ID)?;
+
+    velocity_limit.amount = args.amount;
+    velocity_limit.window = args.window;
+
+    if velocity_limit.is_default() {
+        vm.velocity_default = velocity_limit.has_limit() as u8;
+    }
+
+    vm.velocity_limits = 1;
+
+    vm.advance_poh(CodeInstruction::SetVelocityLimitIx, accounts, data);
+
+    Ok(())
+}
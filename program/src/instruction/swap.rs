@@ -12,6 +12,7 @@ pub fn process_transfer_for_swap(accounts: &[AccountInfo<'_>], data: &[u8]) -> P
         swap_ata_info,
         destination_info,
         token_program_info,
+        remaining @ ..
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
@@ -21,14 +22,17 @@ pub fn process_transfer_for_swap(accounts: &[AccountInfo<'_>], data: &[u8]) -> P
     check_mut(vm_info)?;
     check_mut(swap_ata_info)?;
     check_mut(destination_info)?;
-    check_program(token_program_info, &spl_token::id())?;
 
     let vm = load_vm_checked(vm_info, vm_authority_info)?;
 
-    transfer_signed(
+    check_token_program(token_program_info, vm)?;
+
+    transfer_tokens_signed(
+        vm,
         swap_pda_info,
         swap_ata_info,
         destination_info,
+        get_optional_mint(&vm.get_mint(), remaining),
         token_program_info,
         args.amount,
         &[&[
@@ -57,6 +61,7 @@ pub fn process_cancel_swap(accounts: &[AccountInfo<'_>], data: &[u8]) -> Program
         swap_ata_info,
         omnibus_info,
         token_program_info,
+        remaining @ ..
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
@@ -66,25 +71,33 @@ pub fn process_cancel_swap(accounts: &[AccountInfo<'_>], data: &[u8]) -> Program
     check_mut(vm_memory_info)?;
     check_mut(swap_ata_info)?;
     check_mut(omnibus_info)?;
-    check_program(token_program_info, &spl_token::id())?;
 
     let vm = load_vm_checked(vm_info, vm_authority_info)?;
 
+    check_token_program(token_program_info, vm)?;
+
     check_omnibus(omnibus_info, vm_info)?;
     check_memory(vm_memory_info, vm_info)?;
 
     let va = try_read(&vm_memory_info, args.account_index)?;
     let mut vta = va.into_inner_timelock().unwrap();
 
+    // Swaps only support the VM mint
+    check_holds_vm_mint(&vta)?;
+
     check_condition(
         vta.owner.eq(swapper_info.key),
         "The swapper does not own this account",
     )?;
 
-    transfer_signed(
+    let omnibus_balance = get_token_amount(omnibus_info)?;
+
+    transfer_tokens_signed(
+        vm,
         swap_pda_info,
         swap_ata_info,
         omnibus_info,
+        get_optional_mint(&vm.get_mint(), remaining),
         token_program_info,
         args.amount,
         &[&[
@@ -96,9 +109,14 @@ pub fn process_cancel_swap(accounts: &[AccountInfo<'_>], data: &[u8]) -> Program
         ]],
     )?;
 
+    // Only credit what the omnibus received, see DepositFromPdaIx
+    let received = get_token_amount(omnibus_info)?
+        .checked_sub(omnibus_balance)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
     vta.balance = vta
         .balance
-        .checked_add(args.amount)
+        .checked_add(received)
         .ok_or(ProgramError::ArithmeticOverflow)?;
 
     try_write(
@@ -130,18 +148,17 @@ pub fn process_close_swap_account_if_empty(accounts: &[AccountInfo<'_>], data: &
     check_signer(vm_authority_info)?;
     check_mut(vm_info)?;
     check_mut(swap_ata_info)?;
-    check_program(token_program_info, &spl_token::id())?;
 
     let vm = load_vm_checked(vm_info, vm_authority_info)?;
 
-    let token_account = swap_ata_info.to_token_account()?;
+    check_token_program(token_program_info, vm)?;
 
-    if token_account.amount > 0 {
+    if get_token_amount(swap_ata_info)? > 0 {
         return Ok(())
     }
 
     solana_program::program::invoke_signed(
-            &spl_token::instruction::close_account(
+            &spl_token_2022::instruction::close_account(
             token_program_info.key,
             swap_ata_info.key,
             destination_info.key,
@@ -7,6 +7,10 @@ use solana_program::msg;
     owner to issue a non-custodial withdraw instruction to claim the balance of
     any linked virtual account or deposit.
 
+    An owner that is a multisig address doesn't sign. Instead, the multisig
+    signer keys are given as the last accounts, and `threshold` of them sign.
+    The threshold and number of signers are appended to the instruction data.
+
     Accounts expected by this instruction:
     
     | # | R/W | Type        | PDA | Name           | Description                       |
@@ -15,6 +19,7 @@ use solana_program::msg;
     | 1 | mut | Signer      |     | payer          | The transaction fee payer.        |
     | 2 | mut | Vm          | PDA | vm             | The VM instance state account.    |
     | 3 | mut | UnlockState | PDA | unlock_pda     | Account to create.                |
+    |...|     | Signer      |     | multisig_signers | (optional) For a multisig owner.  |
 
 
     Derived account seeds:
@@ -30,11 +35,12 @@ pub fn process_unlock(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResul
         payer_info,
         vm_info,
         unlock_pda_info,
+        remaining @ ..
     ] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);        
     };
 
-    check_signer(account_owner_info)?;
+    check_owner_signer(account_owner_info, vm_info, remaining, data)?;
     check_signer(payer_info)?;
 
     let vm = vm_info.to_account_mut::<CodeVmAccount>(&code_vm_api::ID)?;
@@ -6,9 +6,18 @@ use steel::*;
     virtual accounts or deposit addresses. The tokens can be withdrawn from the
     VM's memory, storage, or from a deposit account.
 
+    Withdrawing part of the balance is possible from memory. The virtual
+    account is left in place with a reduced balance, and the withdraw receipt
+    records the cumulative amount withdrawn from it.
+
     The requirement for this instruction is that the owner's timelock account is
     in the unlocked state.
 
+    Virtual accounts that hold a mint registered with InitMintIx are withdrawn
+    from the omnibus of that mint, and need the vm_mint account. Deposit and
+    swap withdraws use the registered mint when vm_mint is given, otherwise
+    the VM mint.
+
     Accounts expected by this instruction:
 
     | # | R/W | Req | PDA | Type         | Name                | Description                             |
@@ -21,23 +30,33 @@ use steel::*;
     | 5 |     |     | Yes | Storage      | vm_storage          | If withdrawing from storage (cold).     |
     | 6 |     |     | Yes | Address      | swap_or_deposit_pda | If withdrawing from swap/deposit (ata). |
     | 7 | mut |     | Yes | ATA          | swap_or_deposit_ata | If withdrawing from swap/deposit.       |
-    | 8 |     | Yes | Yes | UnlockState  | unlock_pda          | Timelock unlock state account.          |
+    | 8 | mut*| Yes | Yes | UnlockState  | unlock_pda          | Timelock unlock state account.          |
     | 9 |     |     | Yes | Receipt      | withdraw_receipt    | If withdrawing from memory or storage.  |
     |10 | mut | Yes |     | Address      | external_address    | External address to send tokens to.     |
-    |11 |     | Yes |     | Token        | token_program       | SPL token program account.              |
+    |11 |     | Yes |     | Token        | token_program       | The token program of the mint.          |
     |12 |     |     |     | System       | system_program      | System program account.                 |
     |13 |     |     |     | Rent         | rent_sysvar         | Rent sysvar account (for receipt).      |
+    |14 |     |     |     | TokenMint    | mint                | The mint, for Token-2022 mints.         |
+    |15 |     |     | Yes | VmMint       | vm_mint             | If withdrawing a registered mint.       |
+    |...|     |     |     | Signer       | multisig_signers    | If the depositor is a multisig.         |
+
+    * The unlock_pda is marked as having a receipt when it's writable. It may
+      be read-only, as it was before CancelUnlockIx existed.
+
+    A depositor that is a multisig address doesn't sign. Instead, the multisig
+    signer keys are given as the last accounts, and `threshold` of them sign.
+    The threshold and number of signers are appended to the instruction data.
 
 */
 pub fn process_withdraw(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramResult {
-    let args = WithdrawIx::try_from_slice(data)?;
     let ctx = WithdrawContext::try_from(accounts)?;
 
-    check_signer(ctx.depositor_info)?;
+    let ix_data = check_owner_signer(ctx.depositor_info, ctx.vm_info, ctx.remaining, data)?;
+    let args = WithdrawIx::try_from_slice(ix_data)?;
+
     check_signer(ctx.payer_info)?;
     check_mut(ctx.vm_info)?;
     check_mut(ctx.external_address_info)?;
-    check_program(ctx.token_program_info, &spl_token::id())?;
 
     if let Some(vm_omnibus) = ctx.vm_omnibus {
         check_mut(vm_omnibus)?;
@@ -63,11 +82,8 @@ pub fn process_withdraw(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramRes
 
     if let Some(withdraw_receipt_info) = ctx.withdraw_receipt_info {
         check_mut(withdraw_receipt_info)?;
-        withdraw_receipt_info.is_empty()?;
     }
 
-    ctx.check_unlock_state()?;
-
     match args {
 
         WithdrawIxData::FromDeposit { .. } => 
@@ -79,6 +95,9 @@ pub fn process_withdraw(accounts: &[AccountInfo<'_>], data: &[u8]) -> ProgramRes
         WithdrawIxData::FromMemory { .. } => 
             process_withdraw_from_memory(&ctx, &args),
 
+        WithdrawIxData::PartialFromMemory { .. } => 
+            process_partial_withdraw_from_memory(&ctx, &args),
+
         WithdrawIxData::FromStorage { .. } => 
             process_withdraw_from_storage(&ctx, &args),
 
@@ -123,23 +142,87 @@ fn process_withdraw_from_memory(
         "depositor does not match the owner of the timelock account",
     )?;
 
-    transfer_signed(
-        vm_omnibus,
+    let pool = ctx.get_pool(vm, &vta.mint)?;
+    ctx.check_pool(vm_omnibus, &pool)?;
+    ctx.check_unlock_state(&pool.mint, vta.get_lock_duration(vm.get_lock_duration()))?;
+
+    transfer_from_omnibus(
+        &pool,
+        vm_info,
         vm_omnibus,
         ctx.external_address_info,
+        ctx.get_mint(&pool),
         ctx.token_program_info,
         vta.balance,
-        &[&[
-            CODE_VM,
-            VM_OMNIBUS,
-            vm_info.key.as_ref(),
-            &[vm.get_omnibus_bump()],
-        ]],
     )?;
 
     try_delete(vm_memory_info, account_index)?;
 
-    ctx.create_receipt(&vta.instance)?;
+    // There may already be a receipt if part of the balance was withdrawn
+    ctx.update_receipt(&vta.instance, vta.balance)?;
+
+    Ok(())
+}
+
+fn process_partial_withdraw_from_memory(
+    ctx: &WithdrawContext,
+    data: &WithdrawIxData,
+) -> ProgramResult {
+    let (account_index, amount) = match data {
+        WithdrawIxData::PartialFromMemory { account_index, amount } => 
+            Ok((*account_index, *amount)),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }?;
+
+    check_condition(
+        ctx.vm_memory_info.is_some(),
+        "vm_memory account is required for memory withdraw",
+    )?;
+
+    check_condition(
+        ctx.vm_omnibus.is_some(),
+        "vm_omnibus account is required for memory withdraw",
+    )?;
+
+    let vm_info = ctx.vm_info;
+    let vm = load_vm(vm_info)?;
+
+    let vm_omnibus = ctx.vm_omnibus.unwrap();
+    let vm_memory_info = ctx.vm_memory_info.unwrap();
+    let va = try_read(vm_memory_info, account_index)?;
+    let mut vta = va.into_inner_timelock().unwrap();
+
+    check_condition(
+        vta.owner.eq(ctx.depositor_info.key),
+        "depositor does not match the owner of the timelock account",
+    )?;
+
+    let pool = ctx.get_pool(vm, &vta.mint)?;
+    ctx.check_pool(vm_omnibus, &pool)?;
+    ctx.check_unlock_state(&pool.mint, vta.get_lock_duration(vm.get_lock_duration()))?;
+
+    check_condition(
+        amount > 0,
+        "the withdraw amount must be greater than zero",
+    )?;
+
+    vta.balance = vta.balance
+        .checked_sub(amount)
+        .ok_or(ProgramError::InsufficientFunds)?;
+
+    transfer_from_omnibus(
+        &pool,
+        vm_info,
+        vm_omnibus,
+        ctx.external_address_info,
+        ctx.get_mint(&pool),
+        ctx.token_program_info,
+        amount,
+    )?;
+
+    try_write(vm_memory_info, account_index, &VirtualAccount::Timelock(vta))?;
+
+    ctx.update_receipt(&vta.instance, amount)?;
 
     Ok(())
 }
@@ -188,21 +271,23 @@ fn process_withdraw_from_storage(
         "depositor does not match the owner of the timelock account",
     )?;
 
-    transfer_signed(
-        vm_omnibus,
+    let pool = ctx.get_pool(vm, &vta.mint)?;
+    ctx.check_pool(vm_omnibus, &pool)?;
+    ctx.check_unlock_state(&pool.mint, vta.get_lock_duration(vm.get_lock_duration()))?;
+
+    transfer_from_omnibus(
+        &pool,
+        vm_info,
         vm_omnibus,
         ctx.external_address_info,
+        ctx.get_mint(&pool),
         ctx.token_program_info,
         vta.balance,
-        &[&[
-            CODE_VM,
-            VM_OMNIBUS,
-            vm_info.key.as_ref(),
-            &[vm.get_omnibus_bump()],
-        ]],
     )?;
 
-    ctx.create_receipt(&vta.instance)?;
+    // The account may have been compressed after part of its balance was
+    // withdrawn, in which case the receipt already exists
+    ctx.update_receipt(&vta.instance, vta.balance)?;
 
     Ok(())
 }
@@ -226,16 +311,26 @@ fn process_withdraw_from_deposit(
         "deposit_ata account is required for deposit withdraw",
     )?;
 
+    // Deposits and swaps aren't tied to a specific virtual account, so the
+    // VM lock duration applies
+    let vm = load_vm(ctx.vm_info)?;
+    let pool = ctx.get_deposit_pool(vm)?;
+
+    check_program(ctx.token_program_info, &pool.token_program.id())?;
+    ctx.check_unlock_state(&pool.mint, vm.get_lock_duration())?;
+
     let deposit_ata_info = ctx.swap_or_deposit_ata_info.unwrap();
     let deposit_pda_info = ctx.swap_or_deposit_pda_info.unwrap();
-    let token_account = deposit_ata_info.to_token_account()?;
+    let amount = get_token_amount(deposit_ata_info)?;
 
-    transfer_signed(
+    transfer_pool_tokens_signed(
+        &pool,
         deposit_pda_info,
         deposit_ata_info,
         ctx.external_address_info,
+        ctx.get_mint(&pool),
         ctx.token_program_info,
-        token_account.amount,
+        amount,
         &[&[
             CODE_VM,
             VM_DEPOSIT_PDA,
@@ -269,16 +364,25 @@ fn process_withdraw_from_swap(
         "swap_ata account is required for deposit withdraw",
     )?;
 
+    // Same as for deposits, the VM lock duration applies
+    let vm = load_vm(ctx.vm_info)?;
+    let pool = ctx.get_deposit_pool(vm)?;
+
+    check_program(ctx.token_program_info, &pool.token_program.id())?;
+    ctx.check_unlock_state(&pool.mint, vm.get_lock_duration())?;
+
     let swap_ata_info = ctx.swap_or_deposit_ata_info.unwrap();
     let swap_pda_info = ctx.swap_or_deposit_pda_info.unwrap();
-    let token_account = swap_ata_info.to_token_account()?;
+    let amount = get_token_amount(swap_ata_info)?;
 
-    transfer_signed(
+    transfer_pool_tokens_signed(
+        &pool,
         swap_pda_info,
         swap_ata_info,
         ctx.external_address_info,
+        ctx.get_mint(&pool),
         ctx.token_program_info,
-        token_account.amount,
+        amount,
         &[&[
             CODE_VM,
             VM_SWAP_PDA,
@@ -309,6 +413,7 @@ pub struct WithdrawContext<'a, 'b> {
     pub token_program_info: &'a AccountInfo<'b>,
     pub system_program_info: Option<&'a AccountInfo<'b>>,
     pub rent_sysvar_info: Option<&'a AccountInfo<'b>>,
+    pub remaining: &'a [AccountInfo<'b>],
 }
 
 impl<'a, 'b> WithdrawContext<'a, 'b> {
@@ -328,8 +433,9 @@ impl<'a, 'b> WithdrawContext<'a, 'b> {
             token_program_info,
             system_program_info,
             rent_sysvar_info,
+            remaining,
         ) = match accounts {
-            [ a0, a1, a2, a3, a4, a5, a6, a7, a8, a9, a10, a11, a12, a13 ] => (
+            [ a0, a1, a2, a3, a4, a5, a6, a7, a8, a9, a10, a11, a12, a13, remaining @ .. ] => (
                 a0, a1, a2, 
                 get_optional(a3),
                 get_optional(a4),
@@ -342,6 +448,7 @@ impl<'a, 'b> WithdrawContext<'a, 'b> {
                 a11,
                 get_optional(a12),
                 get_optional(a13),
+                remaining,
             ),
             _ => return Err(ProgramError::NotEnoughAccountKeys),
         };
@@ -361,21 +468,49 @@ impl<'a, 'b> WithdrawContext<'a, 'b> {
             token_program_info,
             system_program_info,
             rent_sysvar_info,
+            remaining,
         })
     }
 
-    pub fn check_unlock_state(&self) -> Result<(Pubkey, u8), ProgramError> {
+    /// The mint account, which is only needed by Token-2022 mints, so older
+    /// clients can leave it out.
+    pub fn get_mint(&self, pool: &MintPool) -> Option<&'a AccountInfo<'b>> {
+        get_optional_mint(&pool.mint, self.remaining)
+    }
+
+    /// The pool backing virtual accounts that hold `mint`.
+    pub fn get_pool(&self, vm: &CodeVmAccount, mint: &Pubkey) -> Result<MintPool, ProgramError> {
+        get_mint_pool(vm, find_vm_mint(self.vm_info, self.remaining)?, mint)
+    }
+
+    /// The pool for deposit and swap withdraws, which is the registered mint
+    /// if one was given.
+    pub fn get_deposit_pool(&self, vm: &CodeVmAccount) -> Result<MintPool, ProgramError> {
+        match find_vm_mint(self.vm_info, self.remaining)? {
+            Some(vm_mint) => Ok(MintPool::from_vm_mint(vm_mint)),
+            None => Ok(MintPool::from_vm(vm)),
+        }
+    }
+
+    pub fn check_pool(&self, vm_omnibus: &AccountInfo<'_>, pool: &MintPool) -> ProgramResult {
+        check_program(self.token_program_info, &pool.token_program.id())?;
+        check_pool_omnibus(vm_omnibus, pool)
+    }
+
+    pub fn check_unlock_state(&self, mint: &Pubkey, lock_duration: u8) -> Result<(Pubkey, u8), ProgramError> {
         // Here we're going to derive the unlock address from the owner pubkey
-        // and check that it has the correct unlocked state.
+        // and check that it has the correct unlocked state. The mint and lock
+        // duration are part of the timelock address, so they must match the
+        // account being withdrawn from.
 
         let owner = self.depositor_info.key;
         let vm = load_vm(self.vm_info)?;
 
         let (timelock_address, _) = find_virtual_timelock_address(
-            &vm.get_mint(),
+            mint,
             &vm.get_authority(),
             &owner,
-            vm.get_lock_duration(),
+            lock_duration,
         );
 
         let (unlock_address, bump) =
@@ -408,7 +543,7 @@ impl<'a, 'b> WithdrawContext<'a, 'b> {
         Ok((unlock_address, bump))
     }
 
-    pub fn create_receipt(&self, nonce: &Hash) -> ProgramResult {
+    pub fn create_receipt(&self, nonce: &Hash, amount: u64) -> ProgramResult {
         // The assumption is that we have already checked that the unlock state
         // is both valid and that the address is correct.
 
@@ -441,6 +576,60 @@ impl<'a, 'b> WithdrawContext<'a, 'b> {
             self.payer_info,
         )?;
 
+        let receipt = withdraw_receipt_info
+            .to_account_mut::<WithdrawReceiptAccount>(&code_vm_api::ID)?;
+
+        receipt.unlock_pda = *unlock_pda_info.key;
+        receipt.nonce = *nonce;
+        receipt.amount = amount;
+        receipt.bump = bump;
+
+        // Once a receipt exists, the unlock can no longer be cancelled. Older
+        // clients pass the unlock_pda as read-only, in which case the flag
+        // isn't set and CancelUnlockIx relies on the receipt account instead.
+        if unlock_pda_info.is_writable {
+            let unlock_state = unlock_pda_info
+                .to_account_mut::<UnlockStateAccount>(&code_vm_api::ID)?;
+            unlock_state.has_receipt = 1;
+        }
+
+        Ok(())
+    }
+
+    /// Creates the receipt for `nonce` if it doesn't exist yet, otherwise adds
+    /// `amount` to the amount already withdrawn.
+    pub fn update_receipt(&self, nonce: &Hash, amount: u64) -> ProgramResult {
+        check_condition(
+            self.withdraw_receipt_info.is_some(),
+            "withdraw_receipt account is required for memory withdraw",
+        )?;
+
+        let withdraw_receipt_info = self.withdraw_receipt_info.unwrap();
+
+        if withdraw_receipt_info.data_is_empty() {
+            return self.create_receipt(nonce, amount);
+        }
+
+        let receipt = withdraw_receipt_info
+            .to_account_mut::<WithdrawReceiptAccount>(&code_vm_api::ID)?;
+
+        check_seeds(
+            withdraw_receipt_info,
+            &[
+                CODE_VM,
+                VM_WITHDRAW_RECEIPT,
+                self.unlock_pda_info.key.as_ref(),
+                nonce.as_ref(),
+                self.vm_info.key.as_ref(),
+            ],
+            receipt.bump,
+            &code_vm_api::ID,
+        )?;
+
+        receipt.amount = receipt.amount
+            .checked_add(amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
         Ok(())
     }
 }
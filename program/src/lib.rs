@@ -34,6 +34,19 @@ pub fn process_instruction(
         CodeInstruction::TransferForSwapIx         => process_transfer_for_swap(accounts, data)?,
         CodeInstruction::CancelSwapIx              => process_cancel_swap(accounts, data)?,
         CodeInstruction::CloseSwapAccountIfEmptyIx => process_close_swap_account_if_empty(accounts, data)?,
+        CodeInstruction::CloseRelayIx              => process_close_relay(accounts, data)?,
+        CodeInstruction::InitRelayNullifierIx      => process_init_relay_nullifier(accounts, data)?,
+        CodeInstruction::CancelUnlockIx            => process_cancel_unlock(accounts, data)?,
+        CodeInstruction::InitVestingIx             => process_init_vesting(accounts, data)?,
+        CodeInstruction::InitMultisigIx            => process_init_multisig(accounts, data)?,
+        CodeInstruction::SetMessagePolicyIx        => process_set_message_policy(accounts, data)?,
+        CodeInstruction::SetSignatureModeIx        => process_set_signature_mode(accounts, data)?,
+        CodeInstruction::SetFeeConfigIx            => process_set_fee_config(accounts, data)?,
+        CodeInstruction::SetVelocityLimitIx        => process_set_velocity_limit(accounts, data)?,
+        CodeInstruction::InitMintIx                => process_init_mint(accounts, data)?,
+        CodeInstruction::DepositWithIntentIx       => process_deposit_with_intent(accounts, data)?,
+        CodeInstruction::SweepDepositIx            => process_sweep_deposit(accounts, data)?,
+        CodeInstruction::CloseDepositReceiptIx     => process_close_deposit_receipt(accounts, data)?,
     }
 
     Ok(())
@@ -20,6 +20,12 @@ use crate::ExecContext;
     | 9 |     | <None>       |     |     |        |              |
     |10 |     | <None>       |     |     |        |              |
 
+    When the VM charges a fee, the source also pays the fee on the total
+    amount. The omnibus and token program are then required for external fee
+    recipients, and a virtual fee recipient is passed as the last memory
+    index, after the destinations.
+
+    The total amount counts towards the source owner's velocity limit.
 
     Instruction data:
 
@@ -37,7 +43,8 @@ pub fn process_airdrop(
 
     let mem_indicies = &data.mem_indicies;
     let mem_banks = &data.mem_banks;
-    let num_accounts = 2 + (args.count as usize);
+    let fee_config = ctx.get_fee_config()?;
+    let num_accounts = 2 + (args.count as usize) + ctx.get_fee_slots(&fee_config);
 
     check_condition(
         mem_indicies.len() == num_accounts,
@@ -80,12 +87,29 @@ pub fn process_airdrop(
         .checked_mul(args.count as u64)
         .ok_or(ProgramError::ArithmeticOverflow)?;
 
-    if src_vta.balance < total_amount {
+    // Protocol fees are charged in the VM mint
+    if fee_config.is_some() {
+        check_holds_vm_mint(&src_vta)?;
+    }
+
+    let fee = match &fee_config {
+        Some(fee_config) => fee_config.get_fee(total_amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?,
+        None => 0,
+    };
+
+    let total_debit = total_amount
+        .checked_add(fee)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    if src_vta.balance < total_debit {
         return Err(ProgramError::InsufficientFunds);
     }
 
+    ctx.spend_velocity(vm, &src_vta, total_amount)?;
+
     src_vta.balance = src_vta.balance
-        .checked_sub(total_amount)
+        .checked_sub(total_debit)
         .ok_or(ProgramError::ArithmeticOverflow)?;
 
     let mut dst_pubkeys = Vec::new();
@@ -103,6 +127,8 @@ pub fn process_airdrop(
         let va = try_read(&dst_mem_info, dst_index)?;
         let mut dst_vta = va.into_inner_timelock().unwrap();
 
+        check_same_mint(&src_vta, &dst_vta)?;
+
         // Check if this destination is actually the source.
         let is_same_account = (src_mem == dst_mem) && (src_index == dst_index);
         if is_same_account {
@@ -128,18 +154,23 @@ pub fn process_airdrop(
         dst_pubkeys.push(dst_vta.owner);
     }
 
-    let hash = create_airdrop_message(
+    let hash = create_airdrop_message_with_fee(
         &vm,
         &src_vta,
         &dst_pubkeys,
         args.amount,
+        fee,
         &vdn,
     );
 
-    sig_verify(
+    sig_verify_message(
+        vm,
+        ctx.vm_info.key,
+        ctx.instructions_info,
+        Opcode::AirdropOp,
         src_vta.owner.as_ref(),
         args.signature.as_ref(),
-        hash.as_ref(),
+        &hash,
     )?;
 
     vdn.value = vm.get_current_poh();
@@ -158,5 +189,9 @@ pub fn process_airdrop(
         &VirtualAccount::Nonce(vdn)
     )?;
 
+    if let Some(fee_config) = &fee_config {
+        ctx.pay_fee(vm, fee_config, fee, data)?;
+    }
+
     Ok(())
 }
\ No newline at end of file
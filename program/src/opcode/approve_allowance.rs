@@ -0,0 +1,146 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+use crate::ExecContext;
+
+/*
+    This instruction is used to let a delegate key spend up to a given amount
+    from a virtual account, using the DelegatedTransferOp opcode. The
+    signature of the source account owner is required to approve the
+    allowance.
+
+    The allowance can be revoked by the owner at any time using the
+    RevokeAllowanceOp opcode.
+
+    Extra accounts required by this instruction:
+    
+    | # | R/W | Type         | Req | PDA | Name   | Description  |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    |...| The same as the vm_exec instruction.                   |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    | 6 |     | <None>       |     |     |        |              |
+    | 7 |     | <None>       |     |     |        |              |
+    | 8 |     | <None>       |     |     |        |              |
+    | 9 |     | <None>       |     |     |        |              |
+    |10 |     | <None>       |     |     |        |              |
+
+
+    Instruction data:
+
+    0. signature: [u8;64]  - The signature of the source account owner.
+    1. delegate: [u8;32]   - The key allowed to spend from the source account.
+    2. amount: [u64]       - The amount the delegate can spend.
+    3. expires_at: [i64]   - The unix timestamp when the allowance expires, 0 for never.
+*/
+pub fn process_approve_allowance(
+    ctx: &ExecContext,
+    data: &ExecIxData,
+) -> ProgramResult {
+
+    let vm = load_vm(ctx.vm_info)?;
+    let args = ApproveAllowanceOp::try_from_bytes(&data.data)?.to_struct()?;
+
+    let mem_indicies = &data.mem_indicies;
+    let mem_banks = &data.mem_banks;
+
+    check_condition(
+        mem_indicies.len() == 3,
+        "the number of memory indicies must be 3",
+    )?;
+
+    check_condition(
+        mem_banks.len() == 3,
+        "the number of memory banks must be 3",
+    )?;
+
+    let nonce_index = mem_indicies[0];
+    let nonce_mem = mem_banks[0];
+
+    let src_index = mem_indicies[1];
+    let src_mem = mem_banks[1];
+
+    let allowance_index = mem_indicies[2];
+    let allowance_mem = mem_banks[2];
+
+    let vm_mem = ctx.get_banks();
+
+    check_condition(
+        vm_mem[nonce_mem as usize].is_some(),
+        "the nonce memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[src_mem as usize].is_some(),
+        "the source memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[allowance_mem as usize].is_some(),
+        "the allowance memory account must be provided",
+    )?;
+
+    let nonce_mem_info = vm_mem[nonce_mem as usize].unwrap();
+    let src_mem_info = vm_mem[src_mem as usize].unwrap();
+    let allowance_mem_info = vm_mem[allowance_mem as usize].unwrap();
+
+    check_is_empty(allowance_mem_info, allowance_index)?;
+
+    let va = try_read(nonce_mem_info, nonce_index)?;
+    let mut vdn = va.into_inner_nonce().unwrap();
+
+    let va = try_read(src_mem_info, src_index)?;
+    let src_vta = va.into_inner_timelock().unwrap();
+
+    let hash = create_approve_allowance_message(
+        &vm,
+        &src_vta,
+        &args.delegate,
+        &vdn,
+        args.amount,
+        args.expires_at,
+    );
+
+    sig_verify_message(
+        vm,
+        ctx.vm_info.key,
+        ctx.instructions_info,
+        Opcode::ApproveAllowanceOp,
+        src_vta.owner.as_ref(),
+        args.signature.as_ref(),
+        &hash,
+    )?;
+
+    check_condition(
+        args.amount > 0,
+        "the allowance amount must be greater than zero",
+    )?;
+
+    let src_timelock_address = src_vta.get_timelock_address(
+        &src_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        src_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    let allowance = VirtualAllowanceAccount {
+        source: src_vta.get_token_address(&src_timelock_address),
+        delegate: args.delegate,
+        remaining: args.amount,
+        expires_at: args.expires_at,
+    };
+
+    vdn.value = vm.get_current_poh();
+
+    try_write(
+        allowance_mem_info,
+        allowance_index,
+        &VirtualAccount::Allowance(allowance)
+    )?;
+
+    try_write(
+        nonce_mem_info,
+        nonce_index,
+        &VirtualAccount::Nonce(vdn)
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,137 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+use crate::ExecContext;
+
+/*
+    This instruction is used to cancel a scheduled transfer before it is
+    settled. The amount held by the scheduled transfer is returned to the
+    source account, and the record is deleted. The signature of the source
+    account is required to authorize the cancellation.
+
+    Extra accounts required by this instruction:
+    
+    | # | R/W | Type         | Req | PDA | Name   | Description  |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    |...| The same as the vm_exec instruction.                   |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    | 6 |     | <None>       |     |     |        |              |
+    | 7 |     | <None>       |     |     |        |              |
+    | 8 |     | <None>       |     |     |        |              |
+    | 9 |     | <None>       |     |     |        |              |
+    |10 |     | <None>       |     |     |        |              |
+
+
+    Instruction data:
+
+    0. signature: [u8;64]  - The signature of the source account owner.
+*/
+pub fn process_cancel_scheduled_transfer(
+    ctx: &ExecContext,
+    data: &ExecIxData,
+) -> ProgramResult {
+
+    let vm = load_vm(ctx.vm_info)?;
+    let args = CancelScheduledTransferOp::try_from_bytes(&data.data)?;
+
+    let mem_indicies = &data.mem_indicies;
+    let mem_banks = &data.mem_banks;
+
+    check_condition(
+        mem_indicies.len() == 3,
+        "the number of memory indicies must be 3",
+    )?;
+
+    check_condition(
+        mem_banks.len() == 3,
+        "the number of memory banks must be 3",
+    )?;
+
+    let nonce_index = mem_indicies[0];
+    let nonce_mem = mem_banks[0];
+
+    let record_index = mem_indicies[1];
+    let record_mem = mem_banks[1];
+
+    let src_index = mem_indicies[2];
+    let src_mem = mem_banks[2];
+
+    let vm_mem = ctx.get_banks();
+
+    check_condition(
+        vm_mem[nonce_mem as usize].is_some(),
+        "the nonce memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[record_mem as usize].is_some(),
+        "the scheduled transfer memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[src_mem as usize].is_some(),
+        "the source memory account must be provided",
+    )?;
+
+    let nonce_mem_info = vm_mem[nonce_mem as usize].unwrap();
+    let record_mem_info = vm_mem[record_mem as usize].unwrap();
+    let src_mem_info = vm_mem[src_mem as usize].unwrap();
+
+    let va = try_read(nonce_mem_info, nonce_index)?;
+    let mut vdn = va.into_inner_nonce().unwrap();
+
+    let va = try_read(record_mem_info, record_index)?;
+    let scheduled = va.into_inner_scheduled_transfer().ok_or(ProgramError::InvalidAccountData)?;
+
+    let va = try_read(src_mem_info, src_index)?;
+    let mut src_vta = va.into_inner_timelock().ok_or(ProgramError::InvalidAccountData)?;
+
+    let src_timelock_address = src_vta.get_timelock_address(
+        &src_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        src_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    check_condition(
+        src_vta.get_token_address(&src_timelock_address).eq(&scheduled.source),
+        "the source does not match the scheduled transfer",
+    )?;
+
+    let hash = create_cancel_scheduled_transfer_message(
+        ctx.vm_info.key,
+        &scheduled,
+        &vdn,
+    );
+
+    sig_verify_message(
+        vm,
+        ctx.vm_info.key,
+        ctx.instructions_info,
+        Opcode::CancelScheduledTransferOp,
+        src_vta.owner.as_ref(),
+        args.signature.as_ref(),
+        &hash,
+    )?;
+
+    src_vta.balance = src_vta.balance
+        .checked_add(scheduled.amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    vdn.value = vm.get_current_poh();
+
+    try_write(
+        src_mem_info,
+        src_index,
+        &VirtualAccount::Timelock(src_vta)
+    )?;
+
+    try_delete(record_mem_info, record_index)?;
+
+    try_write(
+        nonce_mem_info,
+        nonce_index,
+        &VirtualAccount::Nonce(vdn)
+    )?;
+
+    Ok(())
+}
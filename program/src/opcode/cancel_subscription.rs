@@ -0,0 +1,128 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+use crate::ExecContext;
+
+/*
+    This instruction is used to cancel a subscription, so that no further
+    payments can be collected from the payer account. Payments that were
+    already collected are not affected, and the record is deleted. The
+    signature of the payer account is required to authorize the
+    cancellation.
+
+    Extra accounts required by this instruction:
+
+    | # | R/W | Type         | Req | PDA | Name   | Description  |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    |...| The same as the vm_exec instruction.                   |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    | 6 |     | <None>       |     |     |        |              |
+    | 7 |     | <None>       |     |     |        |              |
+    | 8 |     | <None>       |     |     |        |              |
+    | 9 |     | <None>       |     |     |        |              |
+    |10 |     | <None>       |     |     |        |              |
+
+
+    Instruction data:
+
+    0. signature: [u8;64]  - The signature of the payer account owner.
+*/
+pub fn process_cancel_subscription(
+    ctx: &ExecContext,
+    data: &ExecIxData,
+) -> ProgramResult {
+
+    let vm = load_vm(ctx.vm_info)?;
+    let args = CancelSubscriptionOp::try_from_bytes(&data.data)?;
+
+    let mem_indicies = &data.mem_indicies;
+    let mem_banks = &data.mem_banks;
+
+    check_condition(
+        mem_indicies.len() == 3,
+        "the number of memory indicies must be 3",
+    )?;
+
+    check_condition(
+        mem_banks.len() == 3,
+        "the number of memory banks must be 3",
+    )?;
+
+    let nonce_index = mem_indicies[0];
+    let nonce_mem = mem_banks[0];
+
+    let subscription_index = mem_indicies[1];
+    let subscription_mem = mem_banks[1];
+
+    let payer_index = mem_indicies[2];
+    let payer_mem = mem_banks[2];
+
+    let vm_mem = ctx.get_banks();
+
+    check_condition(
+        vm_mem[nonce_mem as usize].is_some(),
+        "the nonce memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[subscription_mem as usize].is_some(),
+        "the subscription memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[payer_mem as usize].is_some(),
+        "the payer memory account must be provided",
+    )?;
+
+    let nonce_mem_info = vm_mem[nonce_mem as usize].unwrap();
+    let subscription_mem_info = vm_mem[subscription_mem as usize].unwrap();
+    let payer_mem_info = vm_mem[payer_mem as usize].unwrap();
+
+    let va = try_read(nonce_mem_info, nonce_index)?;
+    let mut vdn = va.into_inner_nonce().unwrap();
+
+    let va = try_read(subscription_mem_info, subscription_index)?;
+    let subscription = va.into_inner_subscription().ok_or(ProgramError::InvalidAccountData)?;
+
+    let va = try_read(payer_mem_info, payer_index)?;
+    let payer_vta = va.into_inner_timelock().ok_or(ProgramError::InvalidAccountData)?;
+
+    let payer_timelock_address = payer_vta.get_timelock_address(
+        &payer_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        payer_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    check_condition(
+        payer_vta.get_token_address(&payer_timelock_address).eq(&subscription.payer),
+        "the payer does not match the subscription",
+    )?;
+
+    let hash = create_cancel_subscription_message(
+        ctx.vm_info.key,
+        &subscription,
+        &vdn,
+    );
+
+    sig_verify_message(
+        vm,
+        ctx.vm_info.key,
+        ctx.instructions_info,
+        Opcode::CancelSubscriptionOp,
+        payer_vta.owner.as_ref(),
+        args.signature.as_ref(),
+        &hash,
+    )?;
+
+    vdn.value = vm.get_current_poh();
+
+    try_delete(subscription_mem_info, subscription_index)?;
+
+    try_write(
+        nonce_mem_info,
+        nonce_index,
+        &VirtualAccount::Nonce(vdn)
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,124 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+use crate::ExecContext;
+
+/*
+    This instruction is used to move the vested but unclaimed tokens of a
+    virtual vesting account into a virtual timelock account owned by the
+    beneficiary. No signature is required, since the tokens can only be
+    claimed by the beneficiary's own account.
+
+    Once everything has been claimed, the vesting account is deleted.
+
+    Note, this opcode runs through vm_exec, which only the VM authority can
+    sign. Until vested tokens are claimed into a timelock account, they are
+    held custodially by the VM authority: the beneficiary has no way to claim
+    them on their own, and they can't be reached with the unlock and withdraw
+    instructions either. Once claimed, they can be withdrawn non-custodially
+    like any other timelock balance.
+
+    Extra accounts required by this instruction:
+
+    | # | R/W | Type         | Req | PDA | Name   | Description  |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    |...| The same as the vm_exec instruction.                   |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    | 6 |     | <None>       |     |     |        |              |
+    | 7 |     | <None>       |     |     |        |              |
+    | 8 |     | <None>       |     |     |        |              |
+    | 9 |     | <None>       |     |     |        |              |
+    |10 |     | <None>       |     |     |        |              |
+
+
+    Instruction data:
+
+    <None>
+*/
+pub fn process_claim_vested(
+    ctx: &ExecContext,
+    data: &ExecIxData,
+) -> ProgramResult {
+
+    let mem_indicies = &data.mem_indicies;
+    let mem_banks = &data.mem_banks;
+
+    check_condition(
+        mem_indicies.len() == 2,
+        "the number of memory indicies must be 2",
+    )?;
+
+    check_condition(
+        mem_banks.len() == 2,
+        "the number of memory banks must be 2",
+    )?;
+
+    let vesting_index = mem_indicies[0];
+    let vesting_mem = mem_banks[0];
+
+    let dst_index = mem_indicies[1];
+    let dst_mem = mem_banks[1];
+
+    let vm_mem = ctx.get_banks();
+
+    check_condition(
+        vm_mem[vesting_mem as usize].is_some(),
+        "the vesting memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[dst_mem as usize].is_some(),
+        "the destination memory account must be provided",
+    )?;
+
+    let vesting_mem_info = vm_mem[vesting_mem as usize].unwrap();
+    let dst_mem_info = vm_mem[dst_mem as usize].unwrap();
+
+    let va = try_read(vesting_mem_info, vesting_index)?;
+    let mut vesting = va.into_inner_vesting().ok_or(ProgramError::InvalidAccountData)?;
+
+    let va = try_read(dst_mem_info, dst_index)?;
+    let mut dst_vta = va.into_inner_timelock().ok_or(ProgramError::InvalidAccountData)?;
+
+    // Vesting schedules only hold the VM mint
+    check_holds_vm_mint(&dst_vta)?;
+
+    check_condition(
+        dst_vta.owner.eq(&vesting.beneficiary),
+        "the destination is not owned by the beneficiary",
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let amount = vesting.get_claimable_amount(now);
+
+    check_condition(
+        amount > 0,
+        "there are no vested tokens to claim",
+    )?;
+
+    vesting.claimed = vesting.claimed
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    dst_vta.balance = dst_vta.balance
+        .checked_add(amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    try_write(
+        dst_mem_info,
+        dst_index,
+        &VirtualAccount::Timelock(dst_vta)
+    )?;
+
+    if vesting.is_fully_claimed() {
+        try_delete(vesting_mem_info, vesting_index)?;
+    } else {
+        try_write(
+            vesting_mem_info,
+            vesting_index,
+            &VirtualAccount::Vesting(vesting)
+        )?;
+    }
+
+    Ok(())
+}
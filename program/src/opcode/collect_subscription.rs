@@ -0,0 +1,161 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+use crate::ExecContext;
+
+/*
+    This instruction is used to collect one payment of a subscription once
+    it is due. The amount is moved from the payer account into the payee
+    account, and the subscription moves on to the next period.
+
+    No signature is required, since the payments were already authorized by
+    the payer account owner when the subscription was created. Once every
+    payment has been collected, the subscription is deleted.
+
+    Extra accounts required by this instruction:
+    
+    | # | R/W | Type         | Req | PDA | Name   | Description  |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    |...| The same as the vm_exec instruction.                   |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    | 6 |     | <None>       |     |     |        |              |
+    | 7 |     | <None>       |     |     |        |              |
+    | 8 |     | <None>       |     |     |        |              |
+    | 9 |     | <None>       |     |     |        |              |
+    |10 |     | <None>       |     |     |        |              |
+
+    Each payment counts towards the payer's velocity limit when it is
+    collected.
+
+
+    Instruction data:
+
+    <None>
+*/
+pub fn process_collect_subscription(
+    ctx: &ExecContext,
+    data: &ExecIxData,
+) -> ProgramResult {
+
+    let vm = load_vm(ctx.vm_info)?;
+
+    let mem_indicies = &data.mem_indicies;
+    let mem_banks = &data.mem_banks;
+
+    check_condition(
+        mem_indicies.len() == 3,
+        "the number of memory indicies must be 3",
+    )?;
+
+    check_condition(
+        mem_banks.len() == 3,
+        "the number of memory banks must be 3",
+    )?;
+
+    let subscription_index = mem_indicies[0];
+    let subscription_mem = mem_banks[0];
+
+    let payer_index = mem_indicies[1];
+    let payer_mem = mem_banks[1];
+
+    let payee_index = mem_indicies[2];
+    let payee_mem = mem_banks[2];
+
+    let vm_mem = ctx.get_banks();
+
+    check_condition(
+        vm_mem[subscription_mem as usize].is_some(),
+        "the subscription memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[payer_mem as usize].is_some(),
+        "the payer memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[payee_mem as usize].is_some(),
+        "the payee memory account must be provided",
+    )?;
+
+    let subscription_mem_info = vm_mem[subscription_mem as usize].unwrap();
+    let payer_mem_info = vm_mem[payer_mem as usize].unwrap();
+    let payee_mem_info = vm_mem[payee_mem as usize].unwrap();
+
+    let va = try_read(subscription_mem_info, subscription_index)?;
+    let mut subscription = va.into_inner_subscription().ok_or(ProgramError::InvalidAccountData)?;
+
+    let va = try_read(payer_mem_info, payer_index)?;
+    let mut payer_vta = va.into_inner_timelock().ok_or(ProgramError::InvalidAccountData)?;
+
+    let va = try_read(payee_mem_info, payee_index)?;
+    let mut payee_vta = va.into_inner_timelock().ok_or(ProgramError::InvalidAccountData)?;
+
+    check_same_mint(&payer_vta, &payee_vta)?;
+
+    let now = Clock::get()?.unix_timestamp;
+
+    check_condition(
+        subscription.is_due(now),
+        "the subscription payment is not due yet",
+    )?;
+
+    let payer_timelock_address = payer_vta.get_timelock_address(
+        &payer_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        payer_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    let payee_timelock_address = payee_vta.get_timelock_address(
+        &payee_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        payee_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    check_condition(
+        payer_vta.get_token_address(&payer_timelock_address).eq(&subscription.payer),
+        "the payer does not match the subscription",
+    )?;
+
+    check_condition(
+        payee_vta.get_token_address(&payee_timelock_address).eq(&subscription.payee),
+        "the payee does not match the subscription",
+    )?;
+
+    ctx.spend_velocity(vm, &payer_vta, subscription.amount)?;
+
+    payer_vta.balance = payer_vta.balance
+        .checked_sub(subscription.amount)
+        .ok_or(ProgramError::InsufficientFunds)?;
+
+    payee_vta.balance = payee_vta.balance
+        .checked_add(subscription.amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    subscription.advance()
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    try_write(
+        payer_mem_info,
+        payer_index,
+        &VirtualAccount::Timelock(payer_vta)
+    )?;
+
+    try_write(
+        payee_mem_info,
+        payee_index,
+        &VirtualAccount::Timelock(payee_vta)
+    )?;
+
+    if subscription.is_complete() {
+        try_delete(subscription_mem_info, subscription_index)?;
+    } else {
+        try_write(
+            subscription_mem_info,
+            subscription_index,
+            &VirtualAccount::Subscription(subscription)
+        )?;
+    }
+
+    Ok(())
+}
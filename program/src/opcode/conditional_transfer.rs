@@ -24,6 +24,8 @@ use crate::ExecContext;
     | 9 | mut | TokenAccount |     |     | external_address | Required when making external transfers.     |
     | 10|     | Program      | Yes |     | token_program    | Required when making token transfers.        |
 
+    The amount counts towards the source owner's velocity limit.
+
     Instruction data:
 
     0. signature: [u8;64]  - The opcode to execute.
@@ -54,7 +56,7 @@ pub fn process_conditional_transfer(ctx: &ExecContext, data: &ExecIxData) -> Pro
 
     check_mut(omnibus_info)?;
     check_mut(external_address_info)?;
-    check_program(token_program_info, &spl_token::id())?;
+    check_token_program(token_program_info, vm)?;
     check_omnibus(omnibus_info, ctx.vm_info)?;
 
     let mem_indicies = &data.mem_indicies;
@@ -106,6 +108,9 @@ pub fn process_conditional_transfer(ctx: &ExecContext, data: &ExecIxData) -> Pro
     let va = try_read(&src_mem_info, src_index)?;
     let mut src_vta = va.into_inner_timelock().unwrap();
 
+    // Relays only hold the VM mint
+    check_holds_vm_mint(&src_vta)?;
+
     let va = try_read(&vra_mem_info, vra_index)?;
     let vra = va.into_inner_relay().unwrap();
 
@@ -122,16 +127,22 @@ pub fn process_conditional_transfer(ctx: &ExecContext, data: &ExecIxData) -> Pro
         args.amount
     );
 
-    sig_verify(
+    sig_verify_message(
+        vm,
+        ctx.vm_info.key,
+        ctx.instructions_info,
+        Opcode::ConditionalTransferOp,
         src_vta.owner.as_ref(),
         args.signature.as_ref(),
-        hash.as_ref(),
+        &hash,
     )?;
 
-    transfer_signed(
+    transfer_tokens_signed(
+        vm,
         omnibus_info,
         omnibus_info,
         external_address_info,
+        ctx.mint_info,
         token_program_info,
         args.amount,
         &[&[
@@ -142,6 +153,8 @@ pub fn process_conditional_transfer(ctx: &ExecContext, data: &ExecIxData) -> Pro
         ]],
     )?;
 
+    ctx.spend_velocity(vm, &src_vta, args.amount)?;
+
     src_vta.balance = src_vta
         .balance
         .checked_sub(args.amount)
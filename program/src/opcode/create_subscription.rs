@@ -0,0 +1,187 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+use crate::ExecContext;
+
+/*
+    This instruction is used to create a subscription, which lets the VM
+    authority pull a fixed amount from one virtual account into another
+    virtual account once every period, without a new signature each time.
+
+    The signature of the payer account is required to authorize the
+    subscription. Payments are collected using the CollectSubscriptionOp
+    opcode, and the payer can stop them with the CancelSubscriptionOp
+    opcode.
+
+    Extra accounts required by this instruction:
+    
+    | # | R/W | Type         | Req | PDA | Name   | Description  |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    |...| The same as the vm_exec instruction.                   |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    | 6 |     | <None>       |     |     |        |              |
+    | 7 |     | <None>       |     |     |        |              |
+    | 8 |     | <None>       |     |     |        |              |
+    | 9 |     | <None>       |     |     |        |              |
+    |10 |     | <None>       |     |     |        |              |
+
+
+    Instruction data:
+
+    0. signature: [u8;64]  - The signature of the payer account owner.
+    1. amount: [u64]       - The amount to collect each period.
+    2. period: [i64]       - The length of a period in seconds.
+    3. first_due: [i64]    - The unix timestamp when the first payment can be collected.
+    4. count: [u64]        - The number of payments that can be collected.
+*/
+pub fn process_create_subscription(
+    ctx: &ExecContext,
+    data: &ExecIxData,
+) -> ProgramResult {
+
+    let vm = load_vm(ctx.vm_info)?;
+    let args = CreateSubscriptionOp::try_from_bytes(&data.data)?.to_struct()?;
+
+    let mem_indicies = &data.mem_indicies;
+    let mem_banks = &data.mem_banks;
+
+    check_condition(
+        mem_indicies.len() == 4,
+        "the number of memory indicies must be 4",
+    )?;
+
+    check_condition(
+        mem_banks.len() == 4,
+        "the number of memory banks must be 4",
+    )?;
+
+    let nonce_index = mem_indicies[0];
+    let nonce_mem = mem_banks[0];
+
+    let payer_index = mem_indicies[1];
+    let payer_mem = mem_banks[1];
+
+    let payee_index = mem_indicies[2];
+    let payee_mem = mem_banks[2];
+
+    let subscription_index = mem_indicies[3];
+    let subscription_mem = mem_banks[3];
+
+    let vm_mem = ctx.get_banks();
+
+    check_condition(
+        vm_mem[nonce_mem as usize].is_some(),
+        "the nonce memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[payer_mem as usize].is_some(),
+        "the payer memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[payee_mem as usize].is_some(),
+        "the payee memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[subscription_mem as usize].is_some(),
+        "the subscription memory account must be provided",
+    )?;
+
+    let nonce_mem_info = vm_mem[nonce_mem as usize].unwrap();
+    let payer_mem_info = vm_mem[payer_mem as usize].unwrap();
+    let payee_mem_info = vm_mem[payee_mem as usize].unwrap();
+    let subscription_mem_info = vm_mem[subscription_mem as usize].unwrap();
+
+    check_is_empty(subscription_mem_info, subscription_index)?;
+
+    let va = try_read(nonce_mem_info, nonce_index)?;
+    let mut vdn = va.into_inner_nonce().unwrap();
+
+    let va = try_read(payer_mem_info, payer_index)?;
+    let payer_vta = va.into_inner_timelock().unwrap();
+
+    let va = try_read(payee_mem_info, payee_index)?;
+    let payee_vta = va.into_inner_timelock().unwrap();
+
+    check_same_mint(&payer_vta, &payee_vta)?;
+
+    let hash = create_subscription_message(
+        &vm,
+        &payer_vta,
+        &payee_vta,
+        &vdn,
+        args.amount,
+        args.period,
+        args.first_due,
+        args.count,
+    );
+
+    sig_verify_message(
+        vm,
+        ctx.vm_info.key,
+        ctx.instructions_info,
+        Opcode::CreateSubscriptionOp,
+        payer_vta.owner.as_ref(),
+        args.signature.as_ref(),
+        &hash,
+    )?;
+
+    check_condition(
+        args.amount > 0,
+        "the subscription amount must be greater than zero",
+    )?;
+
+    check_condition(
+        args.period > 0,
+        "the subscription period must be greater than zero",
+    )?;
+
+    check_condition(
+        args.count > 0,
+        "the subscription count must be greater than zero",
+    )?;
+
+    let payer_timelock_address = payer_vta.get_timelock_address(
+        &payer_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        payer_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    let payee_timelock_address = payee_vta.get_timelock_address(
+        &payee_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        payee_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    let subscription = VirtualSubscriptionAccount {
+        payer: payer_vta.get_token_address(&payer_timelock_address),
+        payee: payee_vta.get_token_address(&payee_timelock_address),
+        amount: args.amount,
+        period: args.period,
+        next_due: args.first_due,
+        remaining: args.count,
+    };
+
+    check_condition(
+        subscription.payer.ne(&subscription.payee),
+        "the payer and payee must be different accounts",
+    )?;
+
+    vdn.value = vm.get_current_poh();
+
+    try_write(
+        subscription_mem_info,
+        subscription_index,
+        &VirtualAccount::Subscription(subscription)
+    )?;
+
+    try_write(
+        nonce_mem_info,
+        nonce_index,
+        &VirtualAccount::Nonce(vdn)
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,205 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+use crate::ExecContext;
+
+/*
+    This instruction is used by a delegate to transfer from a virtual account
+    to another virtual account, within the bounds of an allowance approved by
+    the source account owner. The signature of the delegate is required.
+
+    The allowance is decremented by the amount transferred, and is deleted
+    once it has been fully spent.
+
+    Extra accounts required by this instruction:
+    
+    | # | R/W | Type         | Req | PDA | Name   | Description  |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    |...| The same as the vm_exec instruction.                   |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    | 6 |     | <None>       |     |     |        |              |
+    | 7 |     | <None>       |     |     |        |              |
+    | 8 |     | <None>       |     |     |        |              |
+    | 9 |     | <None>       |     |     |        |              |
+    |10 |     | <None>       |     |     |        |              |
+
+    The amount counts towards the source owner's velocity limit. Transfers to
+    the same account don't.
+
+
+    Instruction data:
+
+    0. signature: [u8;64]  - The signature of the delegate.
+    1. amount: [u64]       - The amount to transfer.
+*/
+pub fn process_delegated_transfer(
+    ctx: &ExecContext,
+    data: &ExecIxData,
+) -> ProgramResult {
+
+    let vm = load_vm(ctx.vm_info)?;
+    let args = DelegatedTransferOp::try_from_bytes(&data.data)?.to_struct()?;
+
+    let mem_indicies = &data.mem_indicies;
+    let mem_banks = &data.mem_banks;
+
+    check_condition(
+        mem_indicies.len() == 4,
+        "the number of memory indicies must be 4",
+    )?;
+
+    check_condition(
+        mem_banks.len() == 4,
+        "the number of memory banks must be 4",
+    )?;
+
+    let nonce_index = mem_indicies[0];
+    let nonce_mem = mem_banks[0];
+
+    let allowance_index = mem_indicies[1];
+    let allowance_mem = mem_banks[1];
+
+    let src_index = mem_indicies[2];
+    let src_mem = mem_banks[2];
+
+    let dst_index = mem_indicies[3];
+    let dst_mem = mem_banks[3];
+
+    let vm_mem = ctx.get_banks();
+
+    check_condition(
+        vm_mem[nonce_mem as usize].is_some(),
+        "the nonce memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[allowance_mem as usize].is_some(),
+        "the allowance memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[src_mem as usize].is_some(),
+        "the source memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[dst_mem as usize].is_some(),
+        "the destination memory account must be provided",
+    )?;
+
+    let nonce_mem_info = vm_mem[nonce_mem as usize].unwrap();
+    let allowance_mem_info = vm_mem[allowance_mem as usize].unwrap();
+    let src_mem_info = vm_mem[src_mem as usize].unwrap();
+    let dst_mem_info = vm_mem[dst_mem as usize].unwrap();
+
+    let va = try_read(nonce_mem_info, nonce_index)?;
+    let mut vdn = va.into_inner_nonce().unwrap();
+
+    let va = try_read(allowance_mem_info, allowance_index)?;
+    let mut allowance = va.into_inner_allowance().ok_or(ProgramError::InvalidAccountData)?;
+
+    let va = try_read(src_mem_info, src_index)?;
+    let mut src_vta = va.into_inner_timelock().ok_or(ProgramError::InvalidAccountData)?;
+
+    let va = try_read(dst_mem_info, dst_index)?;
+    let mut dst_vta = va.into_inner_timelock().ok_or(ProgramError::InvalidAccountData)?;
+
+    check_same_mint(&src_vta, &dst_vta)?;
+
+    let src_timelock_address = src_vta.get_timelock_address(
+        &src_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        src_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    check_condition(
+        src_vta.get_token_address(&src_timelock_address).eq(&allowance.source),
+        "the source does not match the allowance",
+    )?;
+
+    let hash = create_delegated_transfer_message(
+        &vm,
+        &allowance,
+        &dst_vta,
+        &vdn,
+        args.amount,
+    );
+
+    sig_verify_message(
+        vm,
+        ctx.vm_info.key,
+        ctx.instructions_info,
+        Opcode::DelegatedTransferOp,
+        allowance.delegate.as_ref(),
+        args.signature.as_ref(),
+        &hash,
+    )?;
+
+    let now = Clock::get()?.unix_timestamp;
+
+    check_condition(
+        !allowance.is_expired(now),
+        "the allowance has expired",
+    )?;
+
+    check_condition(
+        args.amount > 0,
+        "the transfer amount must be greater than zero",
+    )?;
+
+    allowance.remaining = allowance.remaining
+        .checked_sub(args.amount)
+        .ok_or(ProgramError::InsufficientFunds)?;
+
+    if src_vta.balance < args.amount {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    // If the source and destination accounts are the same, then we don't need
+    // to move any tokens.
+
+    let is_same_account = src_mem == dst_mem && src_index == dst_index;
+    if !is_same_account {
+        ctx.spend_velocity(vm, &src_vta, args.amount)?;
+
+        src_vta.balance = src_vta.balance
+            .checked_sub(args.amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        dst_vta.balance = dst_vta.balance
+            .checked_add(args.amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+    }
+
+    vdn.value = vm.get_current_poh();
+
+    try_write(
+        src_mem_info,
+        src_index,
+        &VirtualAccount::Timelock(src_vta)
+    )?;
+
+    try_write(
+        dst_mem_info,
+        dst_index,
+        &VirtualAccount::Timelock(dst_vta)
+    )?;
+
+    if allowance.remaining == 0 {
+        try_delete(allowance_mem_info, allowance_index)?;
+    } else {
+        try_write(
+            allowance_mem_info,
+            allowance_index,
+            &VirtualAccount::Allowance(allowance)
+        )?;
+    }
+
+    try_write(
+        nonce_mem_info,
+        nonce_index,
+        &VirtualAccount::Nonce(vdn)
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,184 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+use crate::ExecContext;
+
+/*
+    This instruction is used to move funds from a virtual account into a
+    virtual escrow account, where they are held until an arbiter releases
+    them to the destination account, or until the deadline passes and they
+    are refunded to the source account. The signature of the source account
+    owner is required.
+
+    The funds never leave the omnibus, so all escrow opcodes are balance
+    neutral with respect to the VM's token accounts.
+
+    Extra accounts required by this instruction:
+    
+    | # | R/W | Type         | Req | PDA | Name   | Description  |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    |...| The same as the vm_exec instruction.                   |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    | 6 |     | <None>       |     |     |        |              |
+    | 7 |     | <None>       |     |     |        |              |
+    | 8 |     | <None>       |     |     |        |              |
+    | 9 |     | <None>       |     |     |        |              |
+    |10 |     | <None>       |     |     |        |              |
+
+    The amount counts towards the source owner's velocity limit when it is
+    deposited, not when the escrow is released.
+
+
+    Instruction data:
+
+    0. signature: [u8;64]  - The signature of the source account owner.
+    1. arbiter: [u8;32]    - The key allowed to release the escrow.
+    2. amount: [u64]       - The amount to hold in escrow.
+    3. deadline: [i64]     - The unix timestamp after which the escrow can be refunded.
+*/
+pub fn process_escrow_deposit(
+    ctx: &ExecContext,
+    data: &ExecIxData,
+) -> ProgramResult {
+
+    let vm = load_vm(ctx.vm_info)?;
+    let args = EscrowDepositOp::try_from_bytes(&data.data)?.to_struct()?;
+
+    let mem_indicies = &data.mem_indicies;
+    let mem_banks = &data.mem_banks;
+
+    check_condition(
+        mem_indicies.len() == 4,
+        "the number of memory indicies must be 4",
+    )?;
+
+    check_condition(
+        mem_banks.len() == 4,
+        "the number of memory banks must be 4",
+    )?;
+
+    let nonce_index = mem_indicies[0];
+    let nonce_mem = mem_banks[0];
+
+    let src_index = mem_indicies[1];
+    let src_mem = mem_banks[1];
+
+    let dst_index = mem_indicies[2];
+    let dst_mem = mem_banks[2];
+
+    let escrow_index = mem_indicies[3];
+    let escrow_mem = mem_banks[3];
+
+    let vm_mem = ctx.get_banks();
+
+    check_condition(
+        vm_mem[nonce_mem as usize].is_some(),
+        "the nonce memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[src_mem as usize].is_some(),
+        "the source memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[dst_mem as usize].is_some(),
+        "the destination memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[escrow_mem as usize].is_some(),
+        "the escrow memory account must be provided",
+    )?;
+
+    let nonce_mem_info = vm_mem[nonce_mem as usize].unwrap();
+    let src_mem_info = vm_mem[src_mem as usize].unwrap();
+    let dst_mem_info = vm_mem[dst_mem as usize].unwrap();
+    let escrow_mem_info = vm_mem[escrow_mem as usize].unwrap();
+
+    check_is_empty(escrow_mem_info, escrow_index)?;
+
+    let va = try_read(nonce_mem_info, nonce_index)?;
+    let mut vdn = va.into_inner_nonce().unwrap();
+
+    let va = try_read(src_mem_info, src_index)?;
+    let mut src_vta = va.into_inner_timelock().unwrap();
+
+    let va = try_read(dst_mem_info, dst_index)?;
+    let dst_vta = va.into_inner_timelock().unwrap();
+
+    check_same_mint(&src_vta, &dst_vta)?;
+
+    let hash = create_escrow_deposit_message(
+        &vm,
+        &src_vta,
+        &dst_vta,
+        &args.arbiter,
+        &vdn,
+        args.amount,
+        args.deadline,
+    );
+
+    sig_verify_message(
+        vm,
+        ctx.vm_info.key,
+        ctx.instructions_info,
+        Opcode::EscrowDepositOp,
+        src_vta.owner.as_ref(),
+        args.signature.as_ref(),
+        &hash,
+    )?;
+
+    check_condition(
+        args.amount > 0,
+        "the escrow amount must be greater than zero",
+    )?;
+
+    ctx.spend_velocity(vm, &src_vta, args.amount)?;
+
+    src_vta.balance = src_vta.balance
+        .checked_sub(args.amount)
+        .ok_or(ProgramError::InsufficientFunds)?;
+
+    let src_timelock_address = src_vta.get_timelock_address(
+        &src_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        src_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    let dst_timelock_address = dst_vta.get_timelock_address(
+        &dst_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        dst_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    let escrow = VirtualEscrowAccount {
+        source: src_vta.get_token_address(&src_timelock_address),
+        destination: dst_vta.get_token_address(&dst_timelock_address),
+        arbiter: args.arbiter,
+        amount: args.amount,
+        deadline: args.deadline,
+    };
+
+    vdn.value = vm.get_current_poh();
+
+    try_write(
+        src_mem_info,
+        src_index,
+        &VirtualAccount::Timelock(src_vta)
+    )?;
+
+    try_write(
+        escrow_mem_info,
+        escrow_index,
+        &VirtualAccount::Escrow(escrow)
+    )?;
+
+    try_write(
+        nonce_mem_info,
+        nonce_index,
+        &VirtualAccount::Nonce(vdn)
+    )?;
+
+    Ok(())
+}
@@ -0,0 +1,107 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+use crate::ExecContext;
+
+/*
+    This instruction is used to refund the funds held by an escrow to the
+    source account, once the deadline has passed without a release. No
+    signature is required, since the funds can only go back to the account
+    that deposited them. The escrow account is deleted.
+
+    Extra accounts required by this instruction:
+    
+    | # | R/W | Type         | Req | PDA | Name   | Description  |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    |...| The same as the vm_exec instruction.                   |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    | 6 |     | <None>       |     |     |        |              |
+    | 7 |     | <None>       |     |     |        |              |
+    | 8 |     | <None>       |     |     |        |              |
+    | 9 |     | <None>       |     |     |        |              |
+    |10 |     | <None>       |     |     |        |              |
+
+
+    Instruction data:
+
+    <None>
+*/
+pub fn process_escrow_refund(
+    ctx: &ExecContext,
+    data: &ExecIxData,
+) -> ProgramResult {
+
+    let vm = load_vm(ctx.vm_info)?;
+
+    let mem_indicies = &data.mem_indicies;
+    let mem_banks = &data.mem_banks;
+
+    check_condition(
+        mem_indicies.len() == 2,
+        "the number of memory indicies must be 2",
+    )?;
+
+    check_condition(
+        mem_banks.len() == 2,
+        "the number of memory banks must be 2",
+    )?;
+
+    let escrow_index = mem_indicies[0];
+    let escrow_mem = mem_banks[0];
+
+    let src_index = mem_indicies[1];
+    let src_mem = mem_banks[1];
+
+    let vm_mem = ctx.get_banks();
+
+    check_condition(
+        vm_mem[escrow_mem as usize].is_some(),
+        "the escrow memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[src_mem as usize].is_some(),
+        "the source memory account must be provided",
+    )?;
+
+    let escrow_mem_info = vm_mem[escrow_mem as usize].unwrap();
+    let src_mem_info = vm_mem[src_mem as usize].unwrap();
+
+    let va = try_read(escrow_mem_info, escrow_index)?;
+    let escrow = va.into_inner_escrow().ok_or(ProgramError::InvalidAccountData)?;
+
+    let va = try_read(src_mem_info, src_index)?;
+    let mut src_vta = va.into_inner_timelock().ok_or(ProgramError::InvalidAccountData)?;
+
+    let now = Clock::get()?.unix_timestamp;
+
+    check_condition(
+        escrow.is_refundable(now),
+        "the escrow deadline has not passed yet",
+    )?;
+
+    let src_timelock_address = src_vta.get_timelock_address(
+        &src_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        src_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    check_condition(
+        src_vta.get_token_address(&src_timelock_address).eq(&escrow.source),
+        "the source does not match the escrow",
+    )?;
+
+    src_vta.balance = src_vta.balance
+        .checked_add(escrow.amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    try_write(
+        src_mem_info,
+        src_index,
+        &VirtualAccount::Timelock(src_vta)
+    )?;
+
+    try_delete(escrow_mem_info, escrow_index)?;
+
+    Ok(())
+}
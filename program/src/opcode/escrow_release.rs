@@ -0,0 +1,135 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+use crate::ExecContext;
+
+/*
+    This instruction is used by the arbiter of an escrow to release the held
+    funds to the destination account. The signature of the arbiter is
+    required, and the escrow account is deleted.
+
+    Extra accounts required by this instruction:
+    
+    | # | R/W | Type         | Req | PDA | Name   | Description  |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    |...| The same as the vm_exec instruction.                   |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    | 6 |     | <None>       |     |     |        |              |
+    | 7 |     | <None>       |     |     |        |              |
+    | 8 |     | <None>       |     |     |        |              |
+    | 9 |     | <None>       |     |     |        |              |
+    |10 |     | <None>       |     |     |        |              |
+
+
+    Instruction data:
+
+    0. signature: [u8;64]  - The signature of the arbiter.
+*/
+pub fn process_escrow_release(
+    ctx: &ExecContext,
+    data: &ExecIxData,
+) -> ProgramResult {
+
+    let vm = load_vm(ctx.vm_info)?;
+    let args = EscrowReleaseOp::try_from_bytes(&data.data)?;
+
+    let mem_indicies = &data.mem_indicies;
+    let mem_banks = &data.mem_banks;
+
+    check_condition(
+        mem_indicies.len() == 3,
+        "the number of memory indicies must be 3",
+    )?;
+
+    check_condition(
+        mem_banks.len() == 3,
+        "the number of memory banks must be 3",
+    )?;
+
+    let nonce_index = mem_indicies[0];
+    let nonce_mem = mem_banks[0];
+
+    let escrow_index = mem_indicies[1];
+    let escrow_mem = mem_banks[1];
+
+    let dst_index = mem_indicies[2];
+    let dst_mem = mem_banks[2];
+
+    let vm_mem = ctx.get_banks();
+
+    check_condition(
+        vm_mem[nonce_mem as usize].is_some(),
+        "the nonce memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[escrow_mem as usize].is_some(),
+        "the escrow memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[dst_mem as usize].is_some(),
+        "the destination memory account must be provided",
+    )?;
+
+    let nonce_mem_info = vm_mem[nonce_mem as usize].unwrap();
+    let escrow_mem_info = vm_mem[escrow_mem as usize].unwrap();
+    let dst_mem_info = vm_mem[dst_mem as usize].unwrap();
+
+    let va = try_read(nonce_mem_info, nonce_index)?;
+    let mut vdn = va.into_inner_nonce().unwrap();
+
+    let va = try_read(escrow_mem_info, escrow_index)?;
+    let escrow = va.into_inner_escrow().ok_or(ProgramError::InvalidAccountData)?;
+
+    let va = try_read(dst_mem_info, dst_index)?;
+    let mut dst_vta = va.into_inner_timelock().ok_or(ProgramError::InvalidAccountData)?;
+
+    let dst_timelock_address = dst_vta.get_timelock_address(
+        &dst_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        dst_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    check_condition(
+        dst_vta.get_token_address(&dst_timelock_address).eq(&escrow.destination),
+        "the destination does not match the escrow",
+    )?;
+
+    let hash = create_escrow_release_message(
+        &escrow,
+        &vdn,
+    );
+
+    sig_verify_message(
+        vm,
+        ctx.vm_info.key,
+        ctx.instructions_info,
+        Opcode::EscrowReleaseOp,
+        escrow.arbiter.as_ref(),
+        args.signature.as_ref(),
+        &hash,
+    )?;
+
+    dst_vta.balance = dst_vta.balance
+        .checked_add(escrow.amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    vdn.value = vm.get_current_poh();
+
+    try_write(
+        dst_mem_info,
+        dst_index,
+        &VirtualAccount::Timelock(dst_vta)
+    )?;
+
+    try_delete(escrow_mem_info, escrow_index)?;
+
+    try_write(
+        nonce_mem_info,
+        nonce_index,
+        &VirtualAccount::Nonce(vdn)
+    )?;
+
+    Ok(())
+}
@@ -18,6 +18,14 @@ use crate::ExecContext;
     | 8 | mut | TokenAccount |     | PDA | relay_vault      | A derived token account owned by the relay.  |
     | 9 | mut | TokenAccount |     |     | external_address | Required when making external transfers.     |
     | 10|     | Program      |     |     | token_program    | Required when making token transfers.        |
+    | 11| mut | Nullifier    |     | PDA | relay_nullifier  | The used commitments of the relay.           |
+
+    Note, the relay, relay_vault and relay_nullifier are taken from the exec
+    relay set using the relay_index, so they may also be one of the extra
+    relays.
+
+    The nullifier rotates once it's full. After a rotation, recent roots saved
+    before the previous rotation are refused (see RelayNullifierAccount).
 
 
     Instruction data:
@@ -26,6 +34,10 @@ use crate::ExecContext;
     1. transcript: [u8;32]     - The transcript to verify.
     2. recent_root: [u8;32]    - The recent root to use.
     3. commitment: [u8;32]     - The commitment to use.
+    4. relay_index: [u8]       - The relay to use from the exec relay set.
+
+    Note, the relay_index was added after the fact. Data without it (the
+    original 104 byte layout) is still accepted and uses relay_index 0.
 
 */
 pub fn process_external_relay(
@@ -33,38 +45,26 @@ pub fn process_external_relay(
     data: &ExecIxData,
 ) -> ProgramResult {
 
-    let args = ExternalRelayOp::try_from_bytes(&data.data)?.to_struct()?;
+    let vm = load_vm(ctx.vm_info)?;
+    let args = ExternalRelayOp::unpack(&data.data)?;
 
     check_condition(
         ctx.external_address_info.is_some(),
         "the external_address_info account must be provided",
     )?;
 
-    check_condition(
-        ctx.relay_info.is_some(),
-        "the relay account must be provided",
-    )?;
-
-    check_condition(
-        ctx.relay_vault_info.is_some(),
-        "the relay_vault account must be provided",
-    )?;
-
     check_condition(
         ctx.token_program_info.is_some(),
         "the token program account must be provided",
     )?;
 
     let external_address_info = ctx.external_address_info.unwrap();
-    let relay_info = ctx.relay_info.unwrap();
-    let relay_vault_info = ctx.relay_vault_info.unwrap();
+    let (relay_info, relay_vault_info, relay_nullifier_info) =
+        ctx.get_relay(args.relay_index)?;
     let token_program_info = ctx.token_program_info.unwrap();
 
     check_mut(external_address_info)?;
-    check_mut(relay_info)?;
-    check_mut(relay_vault_info)?;
-    check_program(token_program_info, &spl_token::id())?;
-    check_relay(relay_info, ctx.vm_info)?;
+    check_token_program(token_program_info, vm)?;
 
     let mem_indicies = &data.mem_indicies;
     let mem_banks = &data.mem_banks;
@@ -95,10 +95,12 @@ pub fn process_external_relay(
     let relay = 
         relay_info.to_account_mut::<RelayAccount>(&code_vm_api::ID)?;
 
-    transfer_signed(
+    transfer_tokens_signed(
+        vm,
         relay_vault_info,
         relay_vault_info,
         external_address_info,
+        ctx.mint_info,
         token_program_info,
         args.amount,
         &[&[
@@ -131,6 +133,11 @@ pub fn process_external_relay(
         "the provided commitment does not match the calculated commitment",
     )?;
 
+    // Reject commitments that were already used by this relay
+    let nullifier = 
+        relay_nullifier_info.to_account_mut::<RelayNullifierAccount>(&code_vm_api::ID)?;
+    nullifier.try_spend(&commitment, &args.recent_root, &relay.recent_roots)?;
+
     // Add the commitment address to the merkle tree
     relay.add_commitment(&commitment)?;
 
@@ -20,6 +20,10 @@ use crate::ExecContext;
     | 9 | mut | TokenAccount | Yes |     | external_address | Required when making external transfers.     |
     | 10|     | Program      | Yes |     | token_program    | Required when making token transfers.        |
 
+    When the VM charges a fee, the source also pays the fee. A virtual fee
+    recipient is passed as a 3rd memory index.
+
+    The amount counts towards the source owner's velocity limit.
 
     Instruction data:
 
@@ -55,21 +59,23 @@ pub fn process_external_transfer(
 
     check_mut(omnibus_info)?;
     check_mut(external_address_info)?;
-    check_program(token_program_info, &spl_token::id())?;
 
     let dst_pubkey = external_address_info.key;
 
     let mem_indicies = &data.mem_indicies;
     let mem_banks = &data.mem_banks;
 
+    let fee_config = ctx.get_fee_config()?;
+    let num_accounts = 2 + ctx.get_fee_slots(&fee_config);
+
     check_condition(
-        mem_indicies.len() == 2,
-        "the number of memory indicies must be 2",
+        mem_indicies.len() == num_accounts,
+        "invalid number of memory indicies",
     )?;
 
     check_condition(
-        mem_banks.len() == 2,
-        "the number of memory banks must be 2",
+        mem_banks.len() == num_accounts,
+        "invalid number of memory banks",
     )?;
 
     let nonce_index = mem_indicies[0];
@@ -99,38 +105,70 @@ pub fn process_external_transfer(
     let va = try_read(&src_mem_info, src_index)?;
     let mut src_vta = va.into_inner_timelock().unwrap();
 
-    let hash = create_transfer_message_to_external(
+    // Protocol fees are charged in the VM mint
+    if fee_config.is_some() {
+        check_holds_vm_mint(&src_vta)?;
+    }
+
+    let pool = ctx.get_pool(vm, &src_vta)?;
+
+    check_program(token_program_info, &pool.token_program.id())?;
+    check_pool_omnibus(omnibus_info, &pool)?;
+
+    let fee = match &fee_config {
+        Some(fee_config) => fee_config.get_fee(args.amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?,
+        None => 0,
+    };
+
+    let hash = create_transfer_message_to_external_with_fee(
         &vm,
         &src_vta, 
         &dst_pubkey, 
         &vdn, 
-        args.amount
+        args.amount,
+        fee,
     );
 
-    sig_verify(
-        src_vta.owner.as_ref(), 
-        args.signature.as_ref(), 
-        hash.as_ref(),
+    let payload = create_transfer_payload_to_external(
+        &vm,
+        ctx.vm_info.key,
+        &src_vta,
+        &dst_pubkey,
+        &vdn,
+        args.amount,
+    ).with_fee(fee);
+
+    sig_verify_message_or_payload(
+        vm,
+        ctx.vm_info.key,
+        ctx.instructions_info,
+        src_vta.owner.as_ref(),
+        args.signature.as_ref(),
+        &hash,
+        &payload,
     )?;
 
-    transfer_signed(
-        omnibus_info,
+    transfer_from_omnibus(
+        &pool,
+        ctx.vm_info,
         omnibus_info,
         external_address_info,
+        ctx.mint_info,
         token_program_info,
         args.amount,
-        &[&[
-            CODE_VM, 
-            VM_OMNIBUS,
-            ctx.vm_info.key.as_ref(),
-            &[vm.get_omnibus_bump()],
-        ]]
     )?;
 
+    let total_amount = args.amount
+        .checked_add(fee)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
     src_vta.balance = src_vta.balance
-        .checked_sub(args.amount)
+        .checked_sub(total_amount)
         .ok_or(ProgramError::ArithmeticOverflow)?;
 
+    ctx.spend_velocity(vm, &src_vta, args.amount)?;
+
     vdn.value = vm.get_current_poh();
 
     try_write(
@@ -145,5 +183,9 @@ pub fn process_external_transfer(
         &VirtualAccount::Nonce(vdn)
     )?;
 
+    if let Some(fee_config) = &fee_config {
+        ctx.pay_fee(vm, fee_config, fee, data)?;
+    }
+
     Ok(())
 }
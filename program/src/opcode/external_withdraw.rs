@@ -20,6 +20,8 @@ use crate::ExecContext;
     | 9 | mut | TokenAccount | Yes |     | external_address | Required when making external transfers.     |
     | 10|     | Program      | Yes |     | token_program    | Required when making token transfers.        |
 
+    The withdrawn balance counts towards the source owner's velocity limit.
+
 
     Instruction data:
 
@@ -55,7 +57,6 @@ pub fn process_external_withdraw(
 
     check_mut(omnibus_info)?;
     check_mut(external_address_info)?;
-    check_program(token_program_info, &spl_token::id())?;
 
     let dst_pubkey = external_address_info.key;
 
@@ -99,6 +100,11 @@ pub fn process_external_withdraw(
     let va = try_read(&src_mem_info, src_index)?;
     let mut src_vta = va.into_inner_timelock().unwrap();
 
+    let pool = ctx.get_pool(vm, &src_vta)?;
+
+    check_program(token_program_info, &pool.token_program.id())?;
+    check_pool_omnibus(omnibus_info, &pool)?;
+
     let amount = src_vta.balance;
 
     let hash = create_withdraw_message_to_external(
@@ -108,24 +114,34 @@ pub fn process_external_withdraw(
         &vdn, 
     );
 
-    sig_verify(
-        src_vta.owner.as_ref(), 
-        args.signature.as_ref(), 
-        hash.as_ref(),
+    let payload = create_withdraw_payload_to_external(
+        &vm,
+        ctx.vm_info.key,
+        &src_vta,
+        &dst_pubkey,
+        &vdn,
+    );
+
+    sig_verify_message_or_payload(
+        vm,
+        ctx.vm_info.key,
+        ctx.instructions_info,
+        src_vta.owner.as_ref(),
+        args.signature.as_ref(),
+        &hash,
+        &payload,
     )?;
 
-    transfer_signed(
-        omnibus_info,
+    ctx.spend_velocity(vm, &src_vta, amount)?;
+
+    transfer_from_omnibus(
+        &pool,
+        ctx.vm_info,
         omnibus_info,
         external_address_info,
+        ctx.mint_info,
         token_program_info,
         amount,
-        &[&[
-            CODE_VM, 
-            VM_OMNIBUS,
-            ctx.vm_info.key.as_ref(),
-            &[vm.get_omnibus_bump()],
-        ]]
     )?;
 
     src_vta.balance = src_vta.balance
@@ -0,0 +1,113 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+use crate::ExecContext;
+
+/*
+    This instruction is used to merge two virtual timelock accounts of the
+    same owner. The balance of the source account is added to the destination
+    account, and the source account is deleted. The destination account keeps
+    its instance and bumps.
+
+    No signature is required, since the tokens stay with the same owner and
+    the same lock duration.
+
+    Extra accounts required by this instruction:
+    
+    | # | R/W | Type         | Req | PDA | Name   | Description  |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    |...| The same as the vm_exec instruction.                   |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    | 6 |     | <None>       |     |     |        |              |
+    | 7 |     | <None>       |     |     |        |              |
+    | 8 |     | <None>       |     |     |        |              |
+    | 9 |     | <None>       |     |     |        |              |
+    |10 |     | <None>       |     |     |        |              |
+
+
+    Instruction data:
+
+    <None>
+*/
+pub fn process_merge(
+    ctx: &ExecContext,
+    data: &ExecIxData,
+) -> ProgramResult {
+
+    let vm = load_vm(ctx.vm_info)?;
+
+    let mem_indicies = &data.mem_indicies;
+    let mem_banks = &data.mem_banks;
+
+    check_condition(
+        mem_indicies.len() == 2,
+        "the number of memory indicies must be 2",
+    )?;
+
+    check_condition(
+        mem_banks.len() == 2,
+        "the number of memory banks must be 2",
+    )?;
+
+    let dst_index = mem_indicies[0];
+    let dst_mem = mem_banks[0];
+
+    let src_index = mem_indicies[1];
+    let src_mem = mem_banks[1];
+
+    check_condition(
+        !(src_mem == dst_mem && src_index == dst_index),
+        "the source and destination must be different accounts",
+    )?;
+
+    let vm_mem = ctx.get_banks();
+
+    check_condition(
+        vm_mem[dst_mem as usize].is_some(),
+        "the destination memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[src_mem as usize].is_some(),
+        "the source memory account must be provided",
+    )?;
+
+    let dst_mem_info = vm_mem[dst_mem as usize].unwrap();
+    let src_mem_info = vm_mem[src_mem as usize].unwrap();
+
+    let va = try_read(dst_mem_info, dst_index)?;
+    let mut dst_vta = va.into_inner_timelock().ok_or(ProgramError::InvalidAccountData)?;
+
+    let va = try_read(src_mem_info, src_index)?;
+    let src_vta = va.into_inner_timelock().ok_or(ProgramError::InvalidAccountData)?;
+
+    check_same_mint(&src_vta, &dst_vta)?;
+
+    check_condition(
+        src_vta.owner.eq(&dst_vta.owner),
+        "the accounts must have the same owner",
+    )?;
+
+    let vm_lock_duration = vm.get_lock_duration();
+    check_condition(
+        src_vta.get_lock_duration(vm_lock_duration) == dst_vta.get_lock_duration(vm_lock_duration),
+        "the accounts must have the same lock duration",
+    )?;
+
+    dst_vta.balance = dst_vta.balance
+        .checked_add(src_vta.balance)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    try_delete(
+        src_mem_info,
+        src_index
+    )?;
+
+    try_write(
+        dst_mem_info,
+        dst_index,
+        &VirtualAccount::Timelock(dst_vta)
+    )?;
+
+    Ok(())
+}
@@ -1,17 +1,47 @@
 mod airdrop;
+mod approve_allowance;
+mod cancel_scheduled_transfer;
+mod cancel_subscription;
+mod claim_vested;
+mod collect_subscription;
 mod conditional_transfer;
+mod create_subscription;
+mod delegated_transfer;
+mod escrow_deposit;
+mod escrow_refund;
+mod escrow_release;
 mod external_relay;
 mod external_transfer;
 mod external_withdraw;
+mod merge;
+mod multisig_transfer;
 mod relay;
+mod revoke_allowance;
+mod scheduled_transfer;
+mod settle_scheduled_transfer;
 mod transfer;
 mod withdraw;
 
 pub use airdrop::*;
+pub use approve_allowance::*;
+pub use cancel_scheduled_transfer::*;
+pub use cancel_subscription::*;
+pub use claim_vested::*;
+pub use collect_subscription::*;
 pub use conditional_transfer::*;
+pub use create_subscription::*;
+pub use delegated_transfer::*;
+pub use escrow_deposit::*;
+pub use escrow_refund::*;
+pub use escrow_release::*;
 pub use external_relay::*;
 pub use external_transfer::*;
 pub use external_withdraw::*;
+pub use merge::*;
+pub use multisig_transfer::*;
 pub use relay::*;
+pub use revoke_allowance::*;
+pub use scheduled_transfer::*;
+pub use settle_scheduled_transfer::*;
 pub use transfer::*;
 pub use withdraw::*;
\ No newline at end of file
@@ -0,0 +1,227 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+use crate::ExecContext;
+
+/*
+    This instruction is used to transfer tokens from a virtual account owned
+    by a multisig to another virtual account. Signatures from at least
+    `threshold` of the multisig signers are required, each over the same
+    message used by the TransferOp opcode.
+
+    The signatures are given in the same order as the multisig signers, and
+    signers that did not sign leave their slot zeroed. Every signature that is
+    provided must be valid.
+
+    Extra accounts required by this instruction:
+    
+    | # | R/W | Type         | Req | PDA | Name   | Description  |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    |...| The same as the vm_exec instruction.                   |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    | 6 |     | <None>       |     |     |        |              |
+    | 7 |     | <None>       |     |     |        |              |
+    | 8 |     | <None>       |     |     |        |              |
+    | 9 |     | <None>       |     |     |        |              |
+    |10 |     | <None>       |     |     |        |              |
+
+    When the VM charges a fee, the source also pays the fee, the same as
+    with the TransferOp opcode. A virtual fee recipient is passed as a 5th
+    memory index.
+
+    The amount counts towards the multisig's velocity limit. Transfers to the
+    same account don't.
+
+
+    Instruction data:
+
+    0. amount: [u64]          - The amount to transfer.
+    1. signatures: [[u8;64]]  - The signatures of the multisig signers.
+*/
+pub fn process_multisig_transfer(
+    ctx: &ExecContext,
+    data: &ExecIxData,
+) -> ProgramResult {
+
+    let vm = load_vm(ctx.vm_info)?;
+    let args = MultisigTransferOp::try_from_bytes(&data.data)?.to_struct()?;
+
+    let mem_indicies = &data.mem_indicies;
+    let mem_banks = &data.mem_banks;
+
+    let fee_config = ctx.get_fee_config()?;
+    let num_accounts = 4 + ctx.get_fee_slots(&fee_config);
+
+    check_condition(
+        mem_indicies.len() == num_accounts,
+        "invalid number of memory indicies",
+    )?;
+
+    check_condition(
+        mem_banks.len() == num_accounts,
+        "invalid number of memory banks",
+    )?;
+
+    let nonce_index = mem_indicies[0];
+    let nonce_mem = mem_banks[0];
+
+    let multisig_index = mem_indicies[1];
+    let multisig_mem = mem_banks[1];
+
+    let src_index = mem_indicies[2];
+    let src_mem = mem_banks[2];
+
+    let dst_index = mem_indicies[3];
+    let dst_mem = mem_banks[3];
+
+    let vm_mem = ctx.get_banks();
+
+    check_condition(
+        vm_mem[nonce_mem as usize].is_some(),
+        "the nonce memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[multisig_mem as usize].is_some(),
+        "the multisig memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[src_mem as usize].is_some(),
+        "the source memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[dst_mem as usize].is_some(),
+        "the destination memory account must be provided",
+    )?;
+
+    let nonce_mem_info = vm_mem[nonce_mem as usize].unwrap();
+    let multisig_mem_info = vm_mem[multisig_mem as usize].unwrap();
+    let src_mem_info = vm_mem[src_mem as usize].unwrap();
+    let dst_mem_info = vm_mem[dst_mem as usize].unwrap();
+
+    let va = try_read(nonce_mem_info, nonce_index)?;
+    let mut vdn = va.into_inner_nonce().unwrap();
+
+    let va = try_read(multisig_mem_info, multisig_index)?;
+    let multisig = va.into_inner_multisig().ok_or(ProgramError::InvalidAccountData)?;
+
+    let va = try_read(src_mem_info, src_index)?;
+    let mut src_vta = va.into_inner_timelock().ok_or(ProgramError::InvalidAccountData)?;
+
+    let va = try_read(dst_mem_info, dst_index)?;
+    let mut dst_vta = va.into_inner_timelock().ok_or(ProgramError::InvalidAccountData)?;
+
+    check_same_mint(&src_vta, &dst_vta)?;
+
+    check_condition(
+        src_vta.owner.eq(&multisig.address),
+        "the source is not owned by the multisig",
+    )?;
+
+    // Protocol fees are charged in the VM mint
+    if fee_config.is_some() {
+        check_holds_vm_mint(&src_vta)?;
+    }
+
+    let fee = match &fee_config {
+        Some(fee_config) => fee_config.get_fee(args.amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?,
+        None => 0,
+    };
+
+    let hash = create_transfer_message_with_fee(
+        &vm,
+        &src_vta,
+        &dst_vta,
+        &vdn,
+        args.amount,
+        fee,
+    );
+
+    let mut pubkeys: Vec<&[u8]> = Vec::with_capacity(MAX_MULTISIG_SIGNERS);
+    let mut signatures: Vec<&[u8]> = Vec::with_capacity(MAX_MULTISIG_SIGNERS);
+
+    let signers = multisig.get_signers();
+    for (signer, signature) in signers.iter().zip(args.signatures.iter()) {
+        if signature.iter().all(|b| *b == 0) {
+            continue;
+        }
+
+        pubkeys.push(signer.as_ref());
+        signatures.push(signature.as_ref());
+    }
+
+    check_condition(
+        pubkeys.len() >= multisig.threshold as usize,
+        "not enough multisig signatures",
+    )?;
+
+    sig_verify_message_batch(
+        vm,
+        ctx.vm_info.key,
+        ctx.instructions_info,
+        Opcode::MultisigTransferOp,
+        &pubkeys,
+        &signatures,
+        &hash,
+    )?;
+
+    let total_amount = args.amount
+        .checked_add(fee)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    if src_vta.balance < total_amount {
+        return Err(ProgramError::InsufficientFunds);
+    }
+
+    src_vta.balance = src_vta.balance
+        .checked_sub(fee)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    // If the source and destination accounts are the same, then we don't need
+    // to do anything.
+
+    let is_same_account = src_mem == dst_mem && src_index == dst_index;
+    if !is_same_account {
+        ctx.spend_velocity(vm, &src_vta, args.amount)?;
+
+        src_vta.balance = src_vta.balance
+            .checked_sub(args.amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+
+        dst_vta.balance = dst_vta.balance
+            .checked_add(args.amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?;
+    } else {
+        // Both copies are the same account, keep the fee debit
+        dst_vta.balance = src_vta.balance;
+    }
+
+    vdn.value = vm.get_current_poh();
+
+    try_write(
+        src_mem_info,
+        src_index,
+        &VirtualAccount::Timelock(src_vta)
+    )?;
+
+    try_write(
+        dst_mem_info,
+        dst_index,
+        &VirtualAccount::Timelock(dst_vta)
+    )?;
+
+    try_write(
+        nonce_mem_info,
+        nonce_index,
+        &VirtualAccount::Nonce(vdn)
+    )?;
+
+    if let Some(fee_config) = &fee_config {
+        ctx.pay_fee(vm, fee_config, fee, data)?;
+    }
+
+    Ok(())
+}
@@ -18,6 +18,14 @@ use crate::ExecContext;
     | 8 | mut | TokenAccount |     | PDA | relay_vault      | A derived token account owned by the relay.  |
     | 9 |     | <None>       |     |     |                  |                                              |
     | 10|     | Program      |     |     | token_program    | Required when making token transfers.        |
+    | 11| mut | Nullifier    |     | PDA | relay_nullifier  | The used commitments of the relay.           |
+
+    Note, the relay, relay_vault and relay_nullifier are taken from the exec
+    relay set using the relay_index, so they may also be one of the extra
+    relays.
+
+    The nullifier rotates once it's full. After a rotation, recent roots saved
+    before the previous rotation are refused (see RelayNullifierAccount).
 
 
     Instruction data:
@@ -26,6 +34,10 @@ use crate::ExecContext;
     1. transcript: [u8;32]     - The transcript to verify.
     2. recent_root: [u8;32]    - The recent root to use.
     3. commitment: [u8;32]     - The commitment to use.
+    4. relay_index: [u8]       - The relay to use from the exec relay set.
+
+    Note, the relay_index was added after the fact. Data without it (the
+    original 104 byte layout) is still accepted and uses relay_index 0.
 
 */
 pub fn process_relay(
@@ -34,39 +46,26 @@ pub fn process_relay(
 ) -> ProgramResult {
 
     let vm = load_vm(ctx.vm_info)?;
-    let args = RelayOp::try_from_bytes(&data.data)?.to_struct()?;
+    let args = RelayOp::unpack(&data.data)?;
 
     check_condition(
         ctx.omnibus_info.is_some(),
         "the omnibus account must be provided",
     )?;
 
-    check_condition(
-        ctx.relay_info.is_some(),
-        "the relay account must be provided",
-    )?;
-
-    check_condition(
-        ctx.relay_vault_info.is_some(),
-        "the relay_vault account must be provided",
-    )?;
-
     check_condition(
         ctx.token_program_info.is_some(),
         "the token program account must be provided",
     )?;
 
     let omnibus_info = ctx.omnibus_info.unwrap();
-    let relay_info = ctx.relay_info.unwrap();
-    let relay_vault_info = ctx.relay_vault_info.unwrap();
+    let (relay_info, relay_vault_info, relay_nullifier_info) =
+        ctx.get_relay(args.relay_index)?;
     let token_program_info = ctx.token_program_info.unwrap();
 
     check_mut(omnibus_info)?;
-    check_mut(relay_info)?;
-    check_mut(relay_vault_info)?;
-    check_program(token_program_info, &spl_token::id())?;
+    check_token_program(token_program_info, vm)?;
     check_omnibus(omnibus_info, ctx.vm_info)?;
-    check_relay(relay_info, ctx.vm_info)?;
 
     let mem_indicies = &data.mem_indicies;
     let mem_banks = &data.mem_banks;
@@ -108,10 +107,14 @@ pub fn process_relay(
     let relay = 
         relay_info.to_account_mut::<RelayAccount>(&code_vm_api::ID)?;
 
-    transfer_signed(
+    let omnibus_balance = get_token_amount(omnibus_info)?;
+
+    transfer_tokens_signed(
+        vm,
         relay_vault_info,
         relay_vault_info,
         omnibus_info,
+        ctx.mint_info,
         token_program_info,
         args.amount,
         &[&[
@@ -125,8 +128,17 @@ pub fn process_relay(
     let va = try_read(&dst_mem_info, dst_index)?;
     let mut vta = va.into_inner_timelock().unwrap();
 
+    // Relays only hold the VM mint
+    check_holds_vm_mint(&vta)?;
+
+    // Only credit what the omnibus received, which is less than the amount
+    // when the mint charges transfer fees
+    let received = get_token_amount(omnibus_info)?
+        .checked_sub(omnibus_balance)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
     vta.balance = vta.balance
-        .checked_add(args.amount)
+        .checked_add(received)
         .ok_or(ProgramError::ArithmeticOverflow)?;
 
 
@@ -137,9 +149,9 @@ pub fn process_relay(
     )?;
 
     let timelock_address = vta.get_timelock_address(
-        &vm.get_mint(), 
+        &vta.get_mint(&vm.get_mint()), 
         &vm.get_authority(), 
-        vm.get_lock_duration(),
+        vta.get_lock_duration(vm.get_lock_duration()),
     );
     let token_address = vta.get_token_address(&timelock_address);
 
@@ -157,6 +169,11 @@ pub fn process_relay(
         "the provided commitment does not match the calculated commitment",
     )?;
 
+    // Reject commitments that were already used by this relay
+    let nullifier = 
+        relay_nullifier_info.to_account_mut::<RelayNullifierAccount>(&code_vm_api::ID)?;
+    nullifier.try_spend(&commitment, &args.recent_root, &relay.recent_roots)?;
+
     // Add the commitment address to the merkle tree
     relay.add_commitment(&commitment)?;
 
@@ -0,0 +1,187 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+use crate::ExecContext;
+
+/*
+    This instruction is used to schedule a transfer from one virtual account to
+    another virtual account, that can only be settled after a given time. The
+    signature of the source account is required to authorize the transfer.
+
+    The amount is moved out of the source account right away and held by a
+    virtual scheduled transfer record, until it is either settled using the
+    SettleScheduledTransferOp opcode or cancelled by the source owner using
+    the CancelScheduledTransferOp opcode.
+
+    Note, the held amount is no longer part of the source balance, so it is
+    not covered by the unlock and withdraw instructions. If the source owner
+    unlocks their timelock, the held amount can only leave the record through
+    SettleScheduledTransferOp or CancelScheduledTransferOp, which both run
+    through vm_exec. Until then it is held custodially by the VM authority.
+
+    Extra accounts required by this instruction:
+    
+    | # | R/W | Type         | Req | PDA | Name   | Description  |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    |...| The same as the vm_exec instruction.                   |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    | 6 |     | <None>       |     |     |        |              |
+    | 7 |     | <None>       |     |     |        |              |
+    | 8 |     | <None>       |     |     |        |              |
+    | 9 |     | <None>       |     |     |        |              |
+    |10 |     | <None>       |     |     |        |              |
+
+    The amount counts towards the source owner's velocity limit when it is
+    scheduled, not when it settles.
+
+
+    Instruction data:
+
+    0. signature: [u8;64]  - The signature of the source account owner.
+    1. amount: [u64]       - The amount to transfer.
+    2. not_before: [i64]   - The unix timestamp before which the transfer can't settle.
+*/
+pub fn process_scheduled_transfer(
+    ctx: &ExecContext,
+    data: &ExecIxData,
+) -> ProgramResult {
+
+    let vm = load_vm(ctx.vm_info)?;
+    let args = ScheduledTransferOp::try_from_bytes(&data.data)?.to_struct()?;
+
+    let mem_indicies = &data.mem_indicies;
+    let mem_banks = &data.mem_banks;
+
+    check_condition(
+        mem_indicies.len() == 4,
+        "the number of memory indicies must be 4",
+    )?;
+
+    check_condition(
+        mem_banks.len() == 4,
+        "the number of memory banks must be 4",
+    )?;
+
+    let nonce_index = mem_indicies[0];
+    let nonce_mem = mem_banks[0];
+
+    let src_index = mem_indicies[1];
+    let src_mem = mem_banks[1];
+
+    let dst_index = mem_indicies[2];
+    let dst_mem = mem_banks[2];
+
+    let record_index = mem_indicies[3];
+    let record_mem = mem_banks[3];
+
+    let vm_mem = ctx.get_banks();
+
+    check_condition(
+        vm_mem[nonce_mem as usize].is_some(),
+        "the nonce memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[src_mem as usize].is_some(),
+        "the source memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[dst_mem as usize].is_some(),
+        "the destination memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[record_mem as usize].is_some(),
+        "the scheduled transfer memory account must be provided",
+    )?;
+
+    let nonce_mem_info = vm_mem[nonce_mem as usize].unwrap();
+    let src_mem_info = vm_mem[src_mem as usize].unwrap();
+    let dst_mem_info = vm_mem[dst_mem as usize].unwrap();
+    let record_mem_info = vm_mem[record_mem as usize].unwrap();
+
+    check_is_empty(record_mem_info, record_index)?;
+
+    let va = try_read(nonce_mem_info, nonce_index)?;
+    let mut vdn = va.into_inner_nonce().unwrap();
+
+    let va = try_read(src_mem_info, src_index)?;
+    let mut src_vta = va.into_inner_timelock().unwrap();
+
+    let va = try_read(dst_mem_info, dst_index)?;
+    let dst_vta = va.into_inner_timelock().unwrap();
+
+    check_same_mint(&src_vta, &dst_vta)?;
+
+    let hash = create_scheduled_transfer_message(
+        &vm,
+        &src_vta,
+        &dst_vta,
+        &vdn,
+        args.amount,
+        args.not_before,
+    );
+
+    sig_verify_message(
+        vm,
+        ctx.vm_info.key,
+        ctx.instructions_info,
+        Opcode::ScheduledTransferOp,
+        src_vta.owner.as_ref(),
+        args.signature.as_ref(),
+        &hash,
+    )?;
+
+    check_condition(
+        args.amount > 0,
+        "the scheduled amount must be greater than zero",
+    )?;
+
+    ctx.spend_velocity(vm, &src_vta, args.amount)?;
+
+    src_vta.balance = src_vta.balance
+        .checked_sub(args.amount)
+        .ok_or(ProgramError::InsufficientFunds)?;
+
+    let src_timelock_address = src_vta.get_timelock_address(
+        &src_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        src_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    let dst_timelock_address = dst_vta.get_timelock_address(
+        &dst_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        dst_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    let scheduled = VirtualScheduledTransfer {
+        source: src_vta.get_token_address(&src_timelock_address),
+        destination: dst_vta.get_token_address(&dst_timelock_address),
+        amount: args.amount,
+        not_before: args.not_before,
+    };
+
+    vdn.value = vm.get_current_poh();
+
+    try_write(
+        src_mem_info,
+        src_index,
+        &VirtualAccount::Timelock(src_vta)
+    )?;
+
+    try_write(
+        record_mem_info,
+        record_index,
+        &VirtualAccount::ScheduledTransfer(scheduled)
+    )?;
+
+    try_write(
+        nonce_mem_info,
+        nonce_index,
+        &VirtualAccount::Nonce(vdn)
+    )?;
+
+    Ok(())
+}
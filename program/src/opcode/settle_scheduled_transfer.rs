@@ -0,0 +1,109 @@
+use code_vm_api::prelude::*;
+use steel::*;
+
+use crate::ExecContext;
+
+/*
+    This instruction is used to settle a scheduled transfer once its
+    not_before time has passed. The amount held by the scheduled transfer is
+    moved into the destination account, and the record is deleted.
+
+    No signature is required, since the transfer was already authorized by
+    the source account owner when it was scheduled.
+
+    Extra accounts required by this instruction:
+    
+    | # | R/W | Type         | Req | PDA | Name   | Description  |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    |...| The same as the vm_exec instruction.                   |
+    |---|-----|------------- |-----|-----|--------|--------------|
+    | 6 |     | <None>       |     |     |        |              |
+    | 7 |     | <None>       |     |     |        |              |
+    | 8 |     | <None>       |     |     |        |              |
+    | 9 |     | <None>       |     |     |        |              |
+    |10 |     | <None>       |     |     |        |              |
+
+
+    Instruction data:
+
+    <None>
+*/
+pub fn process_settle_scheduled_transfer(
+    ctx: &ExecContext,
+    data: &ExecIxData,
+) -> ProgramResult {
+
+    let vm = load_vm(ctx.vm_info)?;
+
+    let mem_indicies = &data.mem_indicies;
+    let mem_banks = &data.mem_banks;
+
+    check_condition(
+        mem_indicies.len() == 2,
+        "the number of memory indicies must be 2",
+    )?;
+
+    check_condition(
+        mem_banks.len() == 2,
+        "the number of memory banks must be 2",
+    )?;
+
+    let record_index = mem_indicies[0];
+    let record_mem = mem_banks[0];
+
+    let dst_index = mem_indicies[1];
+    let dst_mem = mem_banks[1];
+
+    let vm_mem = ctx.get_banks();
+
+    check_condition(
+        vm_mem[record_mem as usize].is_some(),
+        "the scheduled transfer memory account must be provided",
+    )?;
+
+    check_condition(
+        vm_mem[dst_mem as usize].is_some(),
+        "the destination memory account must be provided",
+    )?;
+
+    let record_mem_info = vm_mem[record_mem as usize].unwrap();
+    let dst_mem_info = vm_mem[dst_mem as usize].unwrap();
+
+    let va = try_read(record_mem_info, record_index)?;
+    let scheduled = va.into_inner_scheduled_transfer().ok_or(ProgramError::InvalidAccountData)?;
+
+    let va = try_read(dst_mem_info, dst_index)?;
+    let mut dst_vta = va.into_inner_timelock().ok_or(ProgramError::InvalidAccountData)?;
+
+    let now = Clock::get()?.unix_timestamp;
+
+    check_condition(
+        scheduled.is_due(now),
+        "the scheduled transfer is not due yet",
+    )?;
+
+    let dst_timelock_address = dst_vta.get_timelock_address(
+        &dst_vta.get_mint(&vm.get_mint()),
+        &vm.get_authority(),
+        dst_vta.get_lock_duration(vm.get_lock_duration()),
+    );
+
+    check_condition(
+        dst_vta.get_token_address(&dst_timelock_address).eq(&scheduled.destination),
+        "the destination does not match the scheduled transfer",
+    )?;
+
+    dst_vta.balance = dst_vta.balance
+        .checked_add(scheduled.amount)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    try_write(
+        dst_mem_info,
+        dst_index,
+        &VirtualAccount::Timelock(dst_vta)
+    )?;
+
+    try_delete(record_mem_info, record_index)?;
+
+    Ok(())
+}
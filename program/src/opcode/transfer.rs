@@ -20,6 +20,13 @@ use crate::ExecContext;
     | 9 |     | <None>       |     |     |        |              |
     |10 |     | <None>       |     |     |        |              |
 
+    When the VM charges a fee, the source also pays the fee. The omnibus and
+    token program are then required for external fee recipients, and a
+    virtual fee recipient is passed as a 4th memory index.
+
+    The amount counts towards the source owner's velocity limit. Transfers to the
+    same account don't.
+
 
     Instruction data:
 
@@ -37,14 +44,17 @@ pub fn process_transfer(
     let mem_indicies = &data.mem_indicies;
     let mem_banks = &data.mem_banks;
 
+    let fee_config = ctx.get_fee_config()?;
+    let num_accounts = 3 + ctx.get_fee_slots(&fee_config);
+
     check_condition(
-        mem_indicies.len() == 3,
-        "the number of memory indicies must be 3",
+        mem_indicies.len() == num_accounts,
+        "invalid number of memory indicies",
     )?;
 
     check_condition(
-        mem_banks.len() == 3,
-        "the number of memory banks must be 3",
+        mem_banks.len() == num_accounts,
+        "invalid number of memory banks",
     )?;
 
     let nonce_index = mem_indicies[0];
@@ -86,29 +96,66 @@ pub fn process_transfer(
     let va = try_read(&dst_mem_info, dst_index)?;
     let mut dst_vta = va.into_inner_timelock().unwrap();
 
-    let hash = create_transfer_message(
+    check_same_mint(&src_vta, &dst_vta)?;
+
+    // Protocol fees are charged in the VM mint
+    if fee_config.is_some() {
+        check_holds_vm_mint(&src_vta)?;
+    }
+
+    let fee = match &fee_config {
+        Some(fee_config) => fee_config.get_fee(args.amount)
+            .ok_or(ProgramError::ArithmeticOverflow)?,
+        None => 0,
+    };
+
+    let hash = create_transfer_message_with_fee(
         &vm,
         &src_vta, 
         &dst_vta, 
         &vdn, 
-        args.amount
+        args.amount,
+        fee,
     );
 
-    sig_verify(
-        src_vta.owner.as_ref(), 
-        args.signature.as_ref(), 
-        hash.as_ref(),
+    let payload = create_transfer_payload(
+        &vm,
+        ctx.vm_info.key,
+        &src_vta,
+        &dst_vta,
+        &vdn,
+        args.amount,
+    ).with_fee(fee);
+
+    sig_verify_message_or_payload(
+        vm,
+        ctx.vm_info.key,
+        ctx.instructions_info,
+        src_vta.owner.as_ref(),
+        args.signature.as_ref(),
+        &hash,
+        &payload,
     )?;
 
-    if src_vta.balance < args.amount {
+    let total_amount = args.amount
+        .checked_add(fee)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    if src_vta.balance < total_amount {
         return Err(ProgramError::InsufficientFunds);
     }
 
+    src_vta.balance = src_vta.balance
+        .checked_sub(fee)
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
     // If the source and destination accounts are the same, then we don't need
     // to do anything.
 
     let is_same_account = src_mem == dst_mem && src_index == dst_index;
     if !is_same_account {
+        ctx.spend_velocity(vm, &src_vta, args.amount)?;
+
         src_vta.balance = src_vta.balance
             .checked_sub(args.amount)
             .ok_or(ProgramError::ArithmeticOverflow)?;
@@ -116,6 +163,9 @@ pub fn process_transfer(
         dst_vta.balance = dst_vta.balance
             .checked_add(args.amount)
             .ok_or(ProgramError::ArithmeticOverflow)?;
+    } else {
+        // Both copies are the same account, keep the fee debit
+        dst_vta.balance = src_vta.balance;
     }
 
     vdn.value = vm.get_current_poh();
@@ -138,5 +188,9 @@ pub fn process_transfer(
         &VirtualAccount::Nonce(vdn)
     )?;
 
+    if let Some(fee_config) = &fee_config {
+        ctx.pay_fee(vm, fee_config, fee, data)?;
+    }
+
     Ok(())
 }
\ No newline at end of file
@@ -21,6 +21,9 @@ use crate::ExecContext;
     | 9 |     | <None>       |     |     |        |              |
     |10 |     | <None>       |     |     |        |              |
 
+    The withdrawn balance counts towards the source owner's velocity limit.
+    Withdrawing into the same account doesn't.
+
 
     Instruction data:
 
@@ -86,6 +89,8 @@ pub fn process_withdraw(
     let va = try_read(&dst_mem_info, dst_index)?;
     let mut dst_vta = va.into_inner_timelock().unwrap();
 
+    check_same_mint(&src_vta, &dst_vta)?;
+
     let amount = src_vta.balance;
 
     let hash = create_withdraw_message(
@@ -95,10 +100,22 @@ pub fn process_withdraw(
         &vdn, 
     );
 
-    sig_verify(
-        src_vta.owner.as_ref(), 
-        args.signature.as_ref(), 
-        hash.as_ref(),
+    let payload = create_withdraw_payload(
+        &vm,
+        ctx.vm_info.key,
+        &src_vta,
+        &dst_vta,
+        &vdn,
+    );
+
+    sig_verify_message_or_payload(
+        vm,
+        ctx.vm_info.key,
+        ctx.instructions_info,
+        src_vta.owner.as_ref(),
+        args.signature.as_ref(),
+        &hash,
+        &payload,
     )?;
 
     if src_vta.balance < amount {
@@ -110,6 +127,8 @@ pub fn process_withdraw(
 
     let is_same_account = src_mem == dst_mem && src_index == dst_index;
     if !is_same_account {
+        ctx.spend_velocity(vm, &src_vta, amount)?;
+
         src_vta.balance = src_vta.balance
             .checked_sub(amount)
             .ok_or(ProgramError::ArithmeticOverflow)?;
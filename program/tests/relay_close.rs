@@ -0,0 +1,44 @@
+#![cfg(test)]
+// `Option::is_none_or` needs a newer toolchain than the one we build with
+#![allow(clippy::unnecessary_map_or)]
+pub mod utils;
+use utils::*;
+
+use code_vm_api::prelude::*;
+
+#[test]
+fn run_relay_close() {
+    let mut ctx = TestContext::new(21);
+
+    // Setup a relay and treasury (with tokens)
+    let relay_ctx = ctx.create_relay("relay_0", 10_00);
+    let relay_vault = relay_ctx.relay.treasury.vault;
+    let omnibus = ctx.vm.omnibus.vault;
+    let pool = MintPool::from_vm(&ctx.vm);
+
+    let omnibus_before = ctx.get_ata_balance(omnibus);
+
+    assert!(tx_close_relay(
+        &mut ctx.svm,
+        &ctx.payer,
+        ctx.vm_address,
+        relay_ctx.relay_address,
+        relay_vault,
+        &pool,
+    ).is_ok());
+
+    // The remaining vault balance is swept into the omnibus
+    assert_eq!(ctx.get_ata_balance(omnibus), omnibus_before + 10_00);
+
+    // Both the relay and the vault are gone
+    let relay_account = ctx.svm.get_account(&relay_ctx.relay_address);
+    assert!(relay_account.map_or(true, |a| a.lamports == 0));
+
+    let vault_account = ctx.svm.get_account(&relay_vault);
+    assert!(vault_account.map_or(true, |a| a.lamports == 0));
+
+    // The relay address can be re-used after it was closed
+    let relay_ctx = ctx.create_relay("relay_0", 0);
+    let relay = get_relay_account(&ctx.svm, relay_ctx.relay_address);
+    assert_eq!(relay.treasury.vault, relay_vault);
+}
@@ -32,4 +32,8 @@ fn run_relay_init_test() {
     assert_eq!(relay.recent_roots.num_items, 1);
     assert_eq!(relay.recent_roots.first().unwrap(), relay.history.get_root().as_ref());
 
+    let nullifier = get_relay_nullifier_account(&svm, relay_address);
+    assert_eq!(nullifier.relay, relay_address);
+    assert_eq!(nullifier.num_items, 0);
+
 }
\ No newline at end of file
@@ -2,7 +2,7 @@
 pub mod utils;
 use utils::*;
 
-use solana_sdk::signature::Signer;
+use solana_sdk::{account::Account, signature::Signer};
 use code_vm_api::prelude::*;
 
 #[test]
@@ -86,6 +86,7 @@ fn run_relay_transfer() {
         transcript,
         recent_root,
         commitment,
+        relay_index: 0,
     }).to_bytes();
 
     ctx.exec_relay_op(
@@ -132,3 +133,119 @@ fn run_relay_transfer() {
     let vta = ctx.get_virtual_timelock(mem_b, vta_b_index);
     assert_eq!(vta.balance, 100 - 42);
 }
+
+#[test]
+fn run_relay_transfer_replay() {
+    let mut ctx = TestContext::new(21);
+
+    let relay_ctx = ctx.create_relay("relay_0", 10_00);
+
+    let mem_b = ctx.create_memory(100, VirtualTimelockAccount::LEN + 1, "mem_timelock_0");
+    let mem_c = ctx.create_memory(100, VirtualRelayAccount::LEN + 1, "mem_relay_0");
+
+    let vta_a_index = 7;
+    let vta_b_index = 15;
+
+    let vta_a_ctx = ctx.create_timelock_account(mem_b, vta_a_index);
+    let vta_b_ctx = ctx.create_timelock_account(mem_b, vta_b_index);
+
+    let amount: u64 = 42;
+    let payment = create_relay_payment(
+        &ctx.vm,
+        &relay_ctx.relay_address,
+        &relay_ctx.relay,
+        &vta_b_ctx.account,
+        &vta_a_ctx.account,
+        amount,
+    );
+    let data = payment.to_relay_op(0).to_bytes();
+
+    ctx.exec_relay_op(
+        &relay_ctx,
+        [None, Some(mem_b), Some(mem_c), None],
+        vec![vta_a_index, 3],
+        vec![1, 2],
+        data.clone(),
+    )
+    .unwrap();
+
+    let nullifier = get_relay_nullifier_account(&ctx.svm, relay_ctx.relay_address);
+    assert!(nullifier.is_spent(&payment.commitment));
+    assert_eq!(nullifier.num_items, 1);
+
+    // The same commitment can't be used again, even with a new vra slot
+    assert!(ctx.exec_relay_op(
+        &relay_ctx,
+        [None, Some(mem_b), Some(mem_c), None],
+        vec![vta_a_index, 4],
+        vec![1, 2],
+        data,
+    ).is_err());
+
+    let vta = ctx.get_virtual_timelock(mem_b, vta_a_index);
+    assert_eq!(vta.balance, amount);
+    assert_eq!(ctx.get_ata_balance(relay_ctx.relay.treasury.vault), 10_00 - amount);
+}
+
+#[test]
+fn run_relay_transfer_after_nullifier_migration() {
+    let mut ctx = TestContext::new(21);
+
+    let relay_ctx = ctx.create_relay("relay_0", 10_00);
+
+    // Relays created before InitRelayIx created the nullifier don't have one
+    let (relay_nullifier, _) = find_vm_relay_nullifier_pda(&relay_ctx.relay_address);
+    ctx.svm.set_account(relay_nullifier, Account::default()).unwrap();
+
+    let mem_b = ctx.create_memory(100, VirtualTimelockAccount::LEN + 1, "mem_timelock_0");
+    let mem_c = ctx.create_memory(100, VirtualRelayAccount::LEN + 1, "mem_relay_0");
+
+    let vta_a_index = 7;
+    let vta_b_index = 15;
+
+    let vta_a_ctx = ctx.create_timelock_account(mem_b, vta_a_index);
+    let vta_b_ctx = ctx.create_timelock_account(mem_b, vta_b_index);
+
+    let amount: u64 = 42;
+    let payment = create_relay_payment(
+        &ctx.vm,
+        &relay_ctx.relay_address,
+        &relay_ctx.relay,
+        &vta_b_ctx.account,
+        &vta_a_ctx.account,
+        amount,
+    );
+    let data = payment.to_relay_op(0).to_bytes();
+
+    assert!(ctx.exec_relay_op(
+        &relay_ctx,
+        [None, Some(mem_b), Some(mem_c), None],
+        vec![vta_a_index, 3],
+        vec![1, 2],
+        data.clone(),
+    ).is_err());
+
+    // The VM authority migrates the relay
+    assert!(tx_create_relay_nullifier(
+        &mut ctx.svm,
+        &ctx.payer,
+        ctx.vm_address,
+        relay_ctx.relay_address,
+    ).is_ok());
+    ctx.svm.expire_blockhash();
+
+    ctx.exec_relay_op(
+        &relay_ctx,
+        [None, Some(mem_b), Some(mem_c), None],
+        vec![vta_a_index, 3],
+        vec![1, 2],
+        data,
+    )
+    .unwrap();
+
+    let vta = ctx.get_virtual_timelock(mem_b, vta_a_index);
+    assert_eq!(vta.balance, amount);
+
+    let nullifier = get_relay_nullifier_account(&ctx.svm, relay_ctx.relay_address);
+    assert!(nullifier.is_spent(&payment.commitment));
+}
@@ -0,0 +1,79 @@
+#![cfg(test)]
+pub mod utils;
+use utils::*;
+
+use code_vm_api::prelude::*;
+
+#[test]
+fn run_relay_transfer_multi() {
+    // Initialize the test context
+    let mut ctx = TestContext::new(21);
+
+    // Setup two relays, only the second one has tokens
+    let relay_0_ctx = ctx.create_relay("relay_0", 0);
+    let relay_1_ctx = ctx.create_relay("relay_1", 10_00);
+
+    // Create our virtual memory accounts
+    let mem_b = ctx.create_memory(100, VirtualTimelockAccount::LEN + 1, "mem_timelock_0");
+    let mem_c = ctx.create_memory(100, VirtualRelayAccount::LEN + 1, "mem_relay_0");
+
+    let vta_a_index = 7;
+    let vta_b_index = 15;
+    let vra_index = 3;
+
+    let vta_a_ctx = ctx.create_timelock_account(mem_b, vta_a_index);
+    let vta_b_ctx = ctx.create_timelock_account(mem_b, vta_b_index);
+
+    // Pay vta_a through relay_1, which is the second relay in the set
+    let amount: u64 = 42;
+    let payment = create_relay_payment(
+        &ctx.vm,
+        &relay_1_ctx.relay_address,
+        &relay_1_ctx.relay,
+        &vta_b_ctx.account,
+        &vta_a_ctx.account,
+        amount,
+    );
+
+    let mem_indices = vec![vta_a_index, vra_index]; // dst, vra
+    let mem_banks = vec![1, 2]; // mem_b, mem_c
+
+    // An index outside of the relay set is rejected
+    let data = payment.to_relay_op(2).to_bytes();
+    assert!(ctx.exec_relay_op_with_relays(
+        &[&relay_0_ctx, &relay_1_ctx],
+        [None, Some(mem_b), Some(mem_c), None],
+        mem_indices.clone(),
+        mem_banks.clone(),
+        data,
+    ).is_err());
+
+    // Using the wrong relay doesn't match the commitment
+    let data = payment.to_relay_op(0).to_bytes();
+    assert!(ctx.exec_relay_op_with_relays(
+        &[&relay_0_ctx, &relay_1_ctx],
+        [None, Some(mem_b), Some(mem_c), None],
+        mem_indices.clone(),
+        mem_banks.clone(),
+        data,
+    ).is_err());
+
+    let data = payment.to_relay_op(1).to_bytes();
+    ctx.exec_relay_op_with_relays(
+        &[&relay_0_ctx, &relay_1_ctx],
+        [None, Some(mem_b), Some(mem_c), None],
+        mem_indices,
+        mem_banks,
+        data,
+    )
+    .unwrap();
+
+    let vta = ctx.get_virtual_timelock(mem_b, vta_a_index);
+    assert_eq!(vta.balance, amount);
+
+    let vra = get_virtual_relay(&ctx.svm, mem_c, vra_index);
+    assert_eq!(vra, payment.vra);
+
+    assert_eq!(ctx.get_ata_balance(relay_1_ctx.relay.treasury.vault), 10_00 - amount);
+    assert_eq!(ctx.get_ata_balance(relay_0_ctx.relay.treasury.vault), 0);
+}
@@ -82,7 +82,7 @@ fn run_cancel_swap() {
         swapper, 
         swap_pda, 
         swap_ata, 
-        vm.omnibus.vault, 
+        &MintPool::from_vm(&vm),
         account_index, 
         amount, 
         bump
@@ -3,7 +3,7 @@ pub mod utils;
 use utils::*;
 
 use code_vm_api::prelude::*;
-use solana_sdk::signer::Signer;
+use solana_sdk::{pubkey::Pubkey, signer::Signer};
 
 #[test]
 fn run_system_timelock_init() {
@@ -70,10 +70,76 @@ fn run_system_timelock_init() {
         unlock_bump: unlock_pda_bump,
         withdraw_bump,
         balance: 0,
+        lock_duration: 0,
+        mint: Pubkey::default(),
     };
 
     assert_eq!(expected, actual);
 
 }
 
+#[test]
+fn run_system_timelock_init_with_lock_duration_needs_larger_slots() {
+    let (mut svm, payer, _mint_owner, _mint_pk, vm_address) =
+        setup_svm_with_payer_and_vm(21);
+
+    let capacity = 100;
+
+    // Standard slots only fit accounts using the VM lock duration
+    let (small_mem_address, _) = create_and_resize_memory(
+        &mut svm, &payer, vm_address, capacity, VirtualTimelockAccount::LEN+1, "small");
+    let (large_mem_address, _) = create_and_resize_memory(
+        &mut svm, &payer, vm_address, capacity, VirtualTimelockAccount::LEN_WITH_LOCK_DURATION+1, "large");
+
+    let vm = get_vm_account(&svm, vm_address);
+
+    let virtual_account_owner = create_keypair().pubkey();
+    let account_index = 0;
+    let lock_duration = 1;
+
+    let (timelock_address, virtual_timelock_bump) = find_virtual_timelock_address(
+        &vm.get_mint(), 
+        &vm.get_authority(), 
+        &virtual_account_owner, 
+        lock_duration
+    );
+
+    let (_, virtual_vault_bump) = find_virtual_timelock_vault_address(
+        &timelock_address
+    );
+
+    let (_, unlock_pda_bump)  = find_unlock_address(
+        &virtual_account_owner, 
+        &timelock_address, 
+        &vm_address, 
+    );
+
+    assert!(tx_create_virtual_timelock_with_lock_duration(
+        &mut svm, 
+        &payer, 
+        vm_address, 
+        small_mem_address, 
+        virtual_account_owner, 
+        account_index,
+        virtual_timelock_bump,
+        virtual_vault_bump,
+        unlock_pda_bump,
+        lock_duration,
+    ).is_err());
 
+    assert!(tx_create_virtual_timelock_with_lock_duration(
+        &mut svm, 
+        &payer, 
+        vm_address, 
+        large_mem_address, 
+        virtual_account_owner, 
+        account_index,
+        virtual_timelock_bump,
+        virtual_vault_bump,
+        unlock_pda_bump,
+        lock_duration,
+    ).is_ok());
+
+    let actual = get_virtual_timelock(&svm, large_mem_address, account_index);
+    assert_eq!(actual.lock_duration, lock_duration);
+}
@@ -1,9 +1,14 @@
 #![cfg(test)]
+// `Option::is_none_or` needs a newer toolchain than the one we build with
+#![allow(clippy::unnecessary_map_or)]
 pub mod utils;
-use solana_sdk::signer::Signer;
+use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer};
 use utils::*;
 
+use litesvm::LiteSVM;
+use litesvm_token::Approve;
 use code_vm_api::prelude::*;
+use steel::Clock;
 
 #[test]
 fn run_deposit_from_pda() {
@@ -38,7 +43,7 @@ fn run_deposit_from_pda() {
         depositor, 
         deposit_pda, 
         deposit_ata, 
-        vm.omnibus.vault, 
+        &MintPool::from_vm(&vm),
         account_index, 
         amount, 
         bump
@@ -83,7 +88,7 @@ fn run_deposit_with_authority() {
         vm_memory,
         source,
         destination,
-        vm.omnibus.vault,
+        &MintPool::from_vm(&vm),
         account_index,
         amount,
     ).is_ok());
@@ -92,3 +97,357 @@ fn run_deposit_with_authority() {
 
     assert_eq!(vta.balance, amount);
 }
+
+/// Signs a deposit intent and approves the deposit delegate on the source
+/// account for the amount.
+#[allow(clippy::too_many_arguments)]
+fn sign_deposit_intent(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    source_key: &Keypair,
+    mint_pk: &Pubkey,
+    vm_address: Pubkey,
+    dst_vta: &VirtualTimelockAccount,
+    account_index: u16,
+    amount: u64,
+    nonce: Hash,
+    expires_at: i64,
+) -> DepositIntent {
+    let source = create_ata(svm, payer, mint_pk, &source_key.pubkey());
+    let (deposit_delegate, _) = find_vm_deposit_delegate_pda(&vm_address);
+
+    Approve::new(svm, payer, &deposit_delegate, &source, amount)
+        .owner(source_key)
+        .send()
+        .unwrap();
+
+    let vm = get_vm_account(svm, vm_address);
+    let hash = create_deposit_intent_message(
+        &vm, &vm_address, &source, dst_vta, amount, &nonce, expires_at,
+    );
+    let signature: [u8; 64] = source_key
+        .sign_message(hash.as_ref())
+        .as_ref()
+        .try_into()
+        .unwrap();
+
+    DepositIntent {
+        source_authority: source_key.pubkey(),
+        source,
+        account_index,
+        amount,
+        nonce,
+        expires_at,
+        signature: signature.into(),
+    }
+}
+
+#[test]
+fn run_deposit_with_intent() {
+    let (mut svm, payer, mint_owner, mint_pk, vm_address) =
+        setup_svm_with_payer_and_vm(21);
+
+    let name = "test";
+    let capacity = 100;
+    let account_size = VirtualTimelockAccount::LEN+1;
+
+    let (vm_memory, _) =
+        create_and_resize_memory(&mut svm, &payer, vm_address, capacity, account_size, name);
+
+    let amount = 1000;
+    let account_index = 7;
+    let nonce = Hash::new_from_array([7; 32]);
+    let expires_at = svm.get_sysvar::<Clock>().unix_timestamp + 60;
+
+    let (dst_vta, _) =
+        create_timelock(&mut svm, &payer, vm_address, vm_memory, account_index);
+
+    let source_key = create_keypair();
+    let intent = sign_deposit_intent(
+        &mut svm, &payer, &source_key, &mint_pk, vm_address, &dst_vta,
+        account_index, amount, nonce, expires_at,
+    );
+    let source = intent.source;
+
+    mint_to(&mut svm, &payer, &mint_pk, &mint_owner, &source, 2 * amount).unwrap();
+
+    let vm = get_vm_account(&svm, vm_address);
+
+    assert!(tx_deposit_with_intent(
+        &mut svm,
+        &payer,
+        vm_address,
+        vm_memory,
+        &MintPool::from_vm(&vm),
+        &intent,
+    ).is_ok());
+
+    let vta = get_virtual_timelock(&svm, vm_memory, account_index);
+    assert_eq!(vta.balance, amount);
+    assert_eq!(get_ata_balance(&svm, &source), amount);
+
+    let (receipt, _) = find_vm_deposit_receipt_pda(&vm_address, &source_key.pubkey(), &nonce);
+    let receipt = DepositReceiptAccount::unpack(&svm.get_account(&receipt).unwrap().data);
+    assert_eq!(receipt.source, source);
+    assert_eq!(receipt.amount, amount);
+    assert_eq!(receipt.expires_at, expires_at);
+
+    // The same intent can't be used twice, even with enough allowance left
+    Approve::new(&mut svm, &payer, &find_vm_deposit_delegate_pda(&vm_address).0, &source, amount)
+        .owner(&source_key)
+        .send()
+        .unwrap();
+    svm.expire_blockhash();
+
+    assert!(tx_deposit_with_intent(
+        &mut svm,
+        &payer,
+        vm_address,
+        vm_memory,
+        &MintPool::from_vm(&vm),
+        &intent,
+    ).is_err());
+
+    let vta = get_virtual_timelock(&svm, vm_memory, account_index);
+    assert_eq!(vta.balance, amount);
+}
+
+#[test]
+fn run_deposit_with_intent_rejects_changes() {
+    let (mut svm, payer, mint_owner, mint_pk, vm_address) =
+        setup_svm_with_payer_and_vm(21);
+
+    let name = "test";
+    let capacity = 100;
+    let account_size = VirtualTimelockAccount::LEN+1;
+
+    let (vm_memory, _) =
+        create_and_resize_memory(&mut svm, &payer, vm_address, capacity, account_size, name);
+
+    let amount = 1000;
+    let nonce = Hash::new_from_array([7; 32]);
+    let expires_at = svm.get_sysvar::<Clock>().unix_timestamp + 60;
+
+    let (dst_vta, _) = create_timelock(&mut svm, &payer, vm_address, vm_memory, 0);
+    create_timelock(&mut svm, &payer, vm_address, vm_memory, 1);
+
+    let source_key = create_keypair();
+    let intent = sign_deposit_intent(
+        &mut svm, &payer, &source_key, &mint_pk, vm_address, &dst_vta,
+        0, amount, nonce, expires_at,
+    );
+
+    mint_to(&mut svm, &payer, &mint_pk, &mint_owner, &intent.source, amount).unwrap();
+
+    let vm = get_vm_account(&svm, vm_address);
+
+    // Another virtual account, another amount, another expiry or another signer
+    let attempts = [
+        DepositIntent { account_index: 1, ..intent },
+        DepositIntent { amount: amount - 1, ..intent },
+        DepositIntent { expires_at: expires_at + 60, ..intent },
+        DepositIntent { source_authority: create_keypair().pubkey(), ..intent },
+    ];
+
+    for attempt in attempts {
+        assert!(tx_deposit_with_intent(
+            &mut svm,
+            &payer,
+            vm_address,
+            vm_memory,
+            &MintPool::from_vm(&vm),
+            &attempt,
+        ).is_err());
+        svm.expire_blockhash();
+    }
+
+    assert_eq!(get_virtual_timelock(&svm, vm_memory, 0).balance, 0);
+    assert_eq!(get_virtual_timelock(&svm, vm_memory, 1).balance, 0);
+}
+
+#[test]
+fn run_deposit_with_intent_expiry() {
+    let (mut svm, payer, mint_owner, mint_pk, vm_address) =
+        setup_svm_with_payer_and_vm(21);
+
+    let name = "test";
+    let capacity = 100;
+    let account_size = VirtualTimelockAccount::LEN+1;
+
+    let (vm_memory, _) =
+        create_and_resize_memory(&mut svm, &payer, vm_address, capacity, account_size, name);
+
+    let amount = 1000;
+    let nonce = Hash::new_from_array([7; 32]);
+    let now = svm.get_sysvar::<Clock>().unix_timestamp;
+
+    let (dst_vta, _) = create_timelock(&mut svm, &payer, vm_address, vm_memory, 0);
+
+    let source_key = create_keypair();
+    let intent = sign_deposit_intent(
+        &mut svm, &payer, &source_key, &mint_pk, vm_address, &dst_vta,
+        0, amount, nonce, now + 60,
+    );
+
+    mint_to(&mut svm, &payer, &mint_pk, &mint_owner, &intent.source, amount).unwrap();
+
+    let vm = get_vm_account(&svm, vm_address);
+
+    assert!(tx_deposit_with_intent(
+        &mut svm,
+        &payer,
+        vm_address,
+        vm_memory,
+        &MintPool::from_vm(&vm),
+        &intent,
+    ).is_ok());
+
+    // The receipt can't be closed while the intent could still be replayed
+    assert!(tx_close_deposit_receipt(
+        &mut svm, &payer, vm_address, source_key.pubkey(), nonce,
+    ).is_err());
+
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = now + 60;
+    svm.set_sysvar::<Clock>(&clock);
+    svm.expire_blockhash();
+
+    let (receipt, _) = find_vm_deposit_receipt_pda(&vm_address, &source_key.pubkey(), &nonce);
+    let rent = svm.get_account(&receipt).unwrap().lamports;
+    let before = svm.get_balance(&payer.pubkey()).unwrap();
+
+    assert!(tx_close_deposit_receipt(
+        &mut svm, &payer, vm_address, source_key.pubkey(), nonce,
+    ).is_ok());
+
+    assert!(svm.get_account(&receipt).map_or(true, |account| account.lamports == 0));
+    assert!(svm.get_balance(&payer.pubkey()).unwrap() > before + rent - 10_000);
+
+    // Once expired, the intent is rejected even though its receipt is gone
+    mint_to(&mut svm, &payer, &mint_pk, &mint_owner, &intent.source, amount).unwrap();
+    Approve::new(&mut svm, &payer, &find_vm_deposit_delegate_pda(&vm_address).0, &intent.source, amount)
+        .owner(&source_key)
+        .send()
+        .unwrap();
+    svm.expire_blockhash();
+
+    assert!(tx_deposit_with_intent(
+        &mut svm,
+        &payer,
+        vm_address,
+        vm_memory,
+        &MintPool::from_vm(&vm),
+        &intent,
+    ).is_err());
+
+    assert_eq!(get_virtual_timelock(&svm, vm_memory, 0).balance, amount);
+}
+
+#[test]
+fn run_sweep_deposit() {
+    let (mut svm, payer, mint_owner, mint_pk, vm_address) =
+        setup_svm_with_payer_and_vm(21);
+
+    let name = "test";
+    let capacity = 100;
+    let account_size = VirtualTimelockAccount::LEN+1;
+
+    let (vm_memory, _) =
+        create_and_resize_memory(&mut svm, &payer, vm_address, capacity, account_size, name);
+
+    let account_index = 7;
+
+    let (_, vta_key) =
+        create_timelock(&mut svm, &payer, vm_address, vm_memory, account_index);
+    let depositor = vta_key.pubkey();
+    let (deposit_pda, bump) = find_timelock_deposit_pda(&vm_address, &depositor);
+    let deposit_ata = create_ata(&mut svm, &payer, &mint_pk, &deposit_pda);
+
+    // Several deposits are pulled in together
+    mint_to(&mut svm, &payer, &mint_pk, &mint_owner, &deposit_ata, 300).unwrap();
+    mint_to(&mut svm, &payer, &mint_pk, &mint_owner, &deposit_ata, 700).unwrap();
+
+    let vm = get_vm_account(&svm, vm_address);
+
+    assert!(tx_sweep_deposit(
+        &mut svm,
+        &payer,
+        vm_address,
+        vm_memory,
+        depositor,
+        deposit_pda,
+        deposit_ata,
+        &MintPool::from_vm(&vm),
+        account_index,
+        bump,
+        false,
+    ).is_ok());
+
+    assert_eq!(get_virtual_timelock(&svm, vm_memory, account_index).balance, 1000);
+    assert_eq!(get_ata_balance(&svm, &deposit_ata), 0);
+    assert_eq!(get_ata_balance(&svm, &vm.omnibus.vault), 1000);
+
+    // Nothing is left to sweep, but the ATA can still be closed, which
+    // returns more rent than the transaction fee
+    let payer_balance = svm.get_balance(&payer.pubkey()).unwrap();
+    svm.expire_blockhash();
+
+    assert!(tx_sweep_deposit(
+        &mut svm,
+        &payer,
+        vm_address,
+        vm_memory,
+        depositor,
+        deposit_pda,
+        deposit_ata,
+        &MintPool::from_vm(&vm),
+        account_index,
+        bump,
+        true,
+    ).is_ok());
+
+    assert_eq!(get_virtual_timelock(&svm, vm_memory, account_index).balance, 1000);
+    assert!(svm.get_account(&deposit_ata).map_or(true, |account| account.lamports == 0));
+    assert!(svm.get_balance(&payer.pubkey()).unwrap() > payer_balance);
+}
+
+#[test]
+fn run_sweep_deposit_wrong_owner() {
+    let (mut svm, payer, mint_owner, mint_pk, vm_address) =
+        setup_svm_with_payer_and_vm(21);
+
+    let name = "test";
+    let capacity = 100;
+    let account_size = VirtualTimelockAccount::LEN+1;
+
+    let (vm_memory, _) =
+        create_and_resize_memory(&mut svm, &payer, vm_address, capacity, account_size, name);
+
+    create_timelock(&mut svm, &payer, vm_address, vm_memory, 0);
+    let (_, vta_key) = create_timelock(&mut svm, &payer, vm_address, vm_memory, 1);
+
+    let depositor = vta_key.pubkey();
+    let (deposit_pda, bump) = find_timelock_deposit_pda(&vm_address, &depositor);
+    let deposit_ata = create_ata(&mut svm, &payer, &mint_pk, &deposit_pda);
+
+    mint_to(&mut svm, &payer, &mint_pk, &mint_owner, &deposit_ata, 1000).unwrap();
+
+    let vm = get_vm_account(&svm, vm_address);
+
+    // The deposit can only be credited to the depositor's own account
+    assert!(tx_sweep_deposit(
+        &mut svm,
+        &payer,
+        vm_address,
+        vm_memory,
+        depositor,
+        deposit_pda,
+        deposit_ata,
+        &MintPool::from_vm(&vm),
+        0,
+        bump,
+        true,
+    ).is_err());
+
+    assert_eq!(get_ata_balance(&svm, &deposit_ata), 1000);
+}
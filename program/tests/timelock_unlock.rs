@@ -1,6 +1,7 @@
 #![cfg(test)]
 pub mod utils;
 use steel::Clock;
+use solana_sdk::signer::Signer;
 use utils::*;
 
 use code_vm_api::prelude::*;
@@ -58,4 +59,88 @@ fn run_unlock() {
         vm_address,
         unlock_address,
     ).is_ok());
-}
\ No newline at end of file
+}
+#[test]
+fn run_unlock_with_account_lock_duration() {
+    let (mut svm, payer, _mint_owner, mint_pk, vm_address) =
+        setup_svm_with_payer_and_vm(21);
+
+    let name = "test";
+    let capacity = 100;
+    let account_size = VirtualTimelockAccount::LEN_WITH_LOCK_DURATION+1;
+
+    let (vm_memory, _) =
+        create_and_resize_memory(&mut svm, &payer, vm_address, capacity, account_size, name);
+
+    let account_index = 0;
+    let lock_duration = 1;
+
+    let (vta, vta_key) = create_timelock_with_lock_duration(
+        &mut svm, &payer, vm_address, vm_memory, account_index, lock_duration);
+
+    assert_eq!(vta.lock_duration, lock_duration);
+
+    let vm = get_vm_account(&svm, vm_address);
+    let timelock_address = vta.get_timelock_address(
+        &vm.get_mint(),
+        &vm.get_authority(),
+        vta.get_lock_duration(vm.get_lock_duration())
+    );
+
+    let unlock_address = vta.get_unlock_address(&timelock_address, &vm_address);
+    let receipt_address = vta.get_withdraw_receipt_address(&unlock_address, &vm_address);
+
+    // The VM lock duration derives a different timelock address
+    assert!(tx_unlock_init(
+        &mut svm,
+        &payer,
+        &vta_key,
+        vm_address,
+        unlock_address,
+    ).is_err());
+
+    let now = svm.get_sysvar::<Clock>().unix_timestamp;
+
+    assert!(tx_unlock_init_with_lock_duration(
+        &mut svm,
+        &payer,
+        &vta_key,
+        vm_address,
+        unlock_address,
+        lock_duration,
+    ).is_ok());
+
+    let unlock = get_unlock_state(&svm, unlock_address);
+    assert_eq!(unlock.address, timelock_address);
+    assert!(unlock.unlock_at <= now + 2 * 86400);
+
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = unlock.unlock_at + 1;
+    svm.set_sysvar::<Clock>(&clock);
+
+    assert!(tx_unlock_finalize(
+        &mut svm,
+        &payer,
+        &vta_key,
+        vm_address,
+        unlock_address,
+    ).is_ok());
+
+    let dest_key = create_keypair();
+    let destination = create_ata(&mut svm, &payer, &mint_pk, &dest_key.pubkey());
+
+    assert!(tx_withdraw_from_memory(
+        &mut svm,
+        &payer,
+        &vta_key,
+        vm_address,
+        &MintPool::from_vm(&vm),
+        vm_memory,
+        unlock_address,
+        receipt_address,
+        destination,
+        WithdrawIxData::FromMemory { account_index }
+    ).is_ok());
+
+    assert!(!has_virtual_account(&svm, vm_memory, account_index));
+}
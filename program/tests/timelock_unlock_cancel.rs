@@ -0,0 +1,380 @@
+#![cfg(test)]
+// `Option::is_none_or` needs a newer toolchain than the one we build with
+#![allow(clippy::unnecessary_map_or)]
+pub mod utils;
+use steel::Clock;
+use solana_sdk::{signer::Signer, transaction::Transaction};
+use utils::*;
+
+use code_vm_api::prelude::*;
+
+#[test]
+fn run_cancel_unlock_while_waiting() {
+    let (mut svm, payer, _mint_owner, _mint_pk, vm_address) =
+        setup_svm_with_payer_and_vm(21);
+
+    let name = "test";
+    let capacity = 100;
+    let account_size = VirtualTimelockAccount::LEN+1;
+
+    let (vm_memory, _) =
+        create_and_resize_memory(&mut svm, &payer, vm_address, capacity, account_size, name);
+
+    let account_index = 0;
+    let (vta, vta_key) = 
+        create_timelock(&mut svm, &payer, vm_address, vm_memory, account_index);
+
+    let vm = get_vm_account(&svm, vm_address);
+    let timelock_address = vta.get_timelock_address(
+        &vm.get_mint(), 
+        &vm.get_authority(), 
+        vm.get_lock_duration()
+    );
+
+    let unlock_address = vta.get_unlock_address(&timelock_address, &vm_address);
+    let receipt_address = vta.get_withdraw_receipt_address(&unlock_address, &vm_address);
+
+    assert!(tx_unlock_init(
+        &mut svm, 
+        &payer, 
+        &vta_key,
+        vm_address,
+        unlock_address,
+    ).is_ok());
+
+    let payer_before = svm.get_balance(&payer.pubkey()).unwrap();
+    let rent = svm.get_balance(&unlock_address).unwrap();
+
+    // Someone other than the owner can't cancel the unlock
+    let other_key = create_keypair();
+    assert!(tx_unlock_cancel(
+        &mut svm, 
+        &payer, 
+        &other_key,
+        vm_address,
+        unlock_address,
+        vm_memory,
+        receipt_address,
+        account_index,
+    ).is_err());
+
+    assert!(tx_unlock_cancel(
+        &mut svm, 
+        &payer, 
+        &vta_key,
+        vm_address,
+        unlock_address,
+        vm_memory,
+        receipt_address,
+        account_index,
+    ).is_ok());
+
+    // The unlock state is gone and the rent went back to the payer (minus
+    // the fees for the two transactions above)
+    let unlock_account = svm.get_account(&unlock_address);
+    assert!(unlock_account.map_or(true, |a| a.lamports == 0));
+
+    let payer_after = svm.get_balance(&payer.pubkey()).unwrap();
+    assert!(payer_after > payer_before);
+    assert!(payer_after <= payer_before + rent);
+
+    // The owner can start a new unlock
+    assert!(tx_unlock_init(
+        &mut svm, 
+        &payer, 
+        &vta_key,
+        vm_address,
+        unlock_address,
+    ).is_ok());
+}
+
+#[test]
+fn run_cancel_unlock_after_unlocked() {
+    let (mut svm, payer, _mint_owner, _mint_pk, vm_address) =
+        setup_svm_with_payer_and_vm(21);
+
+    let name = "test";
+    let capacity = 100;
+    let account_size = VirtualTimelockAccount::LEN+1;
+
+    let (vm_memory, _) =
+        create_and_resize_memory(&mut svm, &payer, vm_address, capacity, account_size, name);
+
+    let account_index = 0;
+    let (vta, vta_key) = 
+        create_timelock(&mut svm, &payer, vm_address, vm_memory, account_index);
+
+    let vm = get_vm_account(&svm, vm_address);
+    let timelock_address = vta.get_timelock_address(
+        &vm.get_mint(), 
+        &vm.get_authority(), 
+        vm.get_lock_duration()
+    );
+
+    let unlock_address = vta.get_unlock_address(&timelock_address, &vm_address);
+    let receipt_address = vta.get_withdraw_receipt_address(&unlock_address, &vm_address);
+
+    assert!(tx_unlock_init(
+        &mut svm, 
+        &payer, 
+        &vta_key,
+        vm_address,
+        unlock_address,
+    ).is_ok());
+
+    let unlock = get_unlock_state(&svm, unlock_address);
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = unlock.unlock_at + 1;
+    svm.set_sysvar::<Clock>(&clock);
+
+    assert!(tx_unlock_finalize(
+        &mut svm, 
+        &payer, 
+        &vta_key,
+        vm_address,
+        unlock_address,
+    ).is_ok());
+
+    assert!(tx_unlock_cancel(
+        &mut svm, 
+        &payer, 
+        &vta_key,
+        vm_address,
+        unlock_address,
+        vm_memory,
+        receipt_address,
+        account_index,
+    ).is_ok());
+
+    let unlock_account = svm.get_account(&unlock_address);
+    assert!(unlock_account.map_or(true, |a| a.lamports == 0));
+}
+
+/// Unlocks a funded timelock and withdraws part of its balance. Returns
+/// whether the cancel that follows succeeded.
+fn cancel_after_partial_withdraw(unlock_writable: bool) -> bool {
+    let (mut svm, payer, mint_owner, mint_pk, vm_address) =
+        setup_svm_with_payer_and_vm(21);
+
+    let name = "test";
+    let capacity = 100;
+    let account_size = VirtualTimelockAccount::LEN+1;
+
+    let (vm_memory, _) =
+        create_and_resize_memory(&mut svm, &payer, vm_address, capacity, account_size, name);
+
+    let amount = 1000;
+    let account_index = 7;
+
+    let (vta, vta_key) = 
+        create_timelock(&mut svm, &payer, vm_address, vm_memory, account_index);
+
+    let depositor = vta_key.pubkey();
+    let (deposit_pda, deposit_pda_bump) = find_timelock_deposit_pda(&vm_address, &depositor);
+    let deposit_ata = create_ata(&mut svm, &payer, &mint_pk, &deposit_pda);
+
+    mint_to(&mut svm, &payer, &mint_pk, &mint_owner, &deposit_ata, amount).unwrap();
+
+    let vm = get_vm_account(&svm, vm_address);
+
+    assert!(tx_deposit_from_pda(
+        &mut svm,
+        &payer,
+        vm_address,
+        vm_memory,
+        depositor,
+        deposit_pda,
+        deposit_ata,
+        &MintPool::from_vm(&vm),
+        account_index,
+        amount,
+        deposit_pda_bump
+    ).is_ok());
+
+    let dest_key = create_keypair();
+    let destination = create_ata(&mut svm, &payer, &mint_pk, &dest_key.pubkey());
+
+    let timelock_address = vta.get_timelock_address(
+        &vm.get_mint(), 
+        &vm.get_authority(), 
+        vm.get_lock_duration()
+    );
+
+    let unlock_address = vta.get_unlock_address(&timelock_address, &vm_address);
+    let receipt_address = vta.get_withdraw_receipt_address(&unlock_address, &vm_address);
+
+    assert!(tx_unlock_init(
+        &mut svm, 
+        &payer, 
+        &vta_key,
+        vm_address,
+        unlock_address,
+    ).is_ok());
+
+    let unlock = get_unlock_state(&svm, unlock_address);
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = unlock.unlock_at + 1;
+    svm.set_sysvar::<Clock>(&clock);
+
+    assert!(tx_unlock_finalize(
+        &mut svm, 
+        &payer, 
+        &vta_key,
+        vm_address,
+        unlock_address,
+    ).is_ok());
+
+    let mut ix = timelock_withdraw(
+        depositor,
+        payer.pubkey(),
+        vm_address,
+        &MintPool::from_vm(&vm),
+        Some(vm_memory),
+        None, // vm_storage
+        None, // deposit_pda
+        None, // deposit_ata
+        unlock_address,
+        Some(receipt_address),
+        destination,
+        WithdrawIxData::PartialFromMemory { account_index, amount: 300 },
+    );
+    ix.accounts[8].is_writable = unlock_writable;
+
+    let blockhash = svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(
+        &[ix], Some(&payer.pubkey()), &[&payer, &vta_key], blockhash);
+    assert!(send_tx(&mut svm, tx).is_ok());
+
+    let unlock = get_unlock_state(&svm, unlock_address);
+    assert_eq!(unlock.has_receipt(), unlock_writable);
+
+    tx_unlock_cancel(
+        &mut svm, 
+        &payer, 
+        &vta_key,
+        vm_address,
+        unlock_address,
+        vm_memory,
+        receipt_address,
+        account_index,
+    ).is_ok()
+}
+
+#[test]
+fn run_cancel_unlock_after_withdraw() {
+    assert!(!cancel_after_partial_withdraw(true));
+}
+
+#[test]
+fn run_cancel_unlock_after_withdraw_without_flag() {
+    // The unlock_pda was read-only, as with clients from before the flag
+    // existed, so only the receipt account shows the withdraw
+    assert!(!cancel_after_partial_withdraw(false));
+}
+
+#[test]
+fn run_cancel_unlock_from_storage() {
+    let (mut svm, payer, _mint_owner, _mint_pk, vm_address) =
+        setup_svm_with_payer_and_vm(21);
+
+    let name = "test";
+    let capacity = 100;
+    let account_size = VirtualTimelockAccount::LEN+1;
+
+    let (vm_memory, _) =
+        create_and_resize_memory(&mut svm, &payer, vm_address, capacity, account_size, name);
+    let (vm_storage, _) =
+        create_storage_account(&mut svm, &payer, vm_address, name);
+
+    let account_index = 0;
+    let (vta, vta_key) =
+        create_timelock(&mut svm, &payer, vm_address, vm_memory, account_index);
+
+    let vm = get_vm_account(&svm, vm_address);
+    let timelock_address = vta.get_timelock_address(
+        &vm.get_mint(),
+        &vm.get_authority(),
+        vm.get_lock_duration()
+    );
+
+    let unlock_address = vta.get_unlock_address(&timelock_address, &vm_address);
+    let receipt_address = vta.get_withdraw_receipt_address(&unlock_address, &vm_address);
+
+    // The account is compressed before the owner notices the unlock
+    let va = VirtualAccount::Timelock(vta);
+    let va_hash = va.get_hash();
+    let sig = Signature::new(payer.sign_message(va_hash.as_ref()).as_ref());
+    let sig_hash = hashv(&[sig.as_ref(), va_hash.as_ref()]);
+
+    assert!(tx_account_compress(
+        &mut svm,
+        &payer,
+        vm_address,
+        vm_memory,
+        vm_storage,
+        account_index,
+        sig
+    ).is_ok());
+
+    let compressed_mem = get_storage_account(&svm, vm_storage).compressed_state;
+    let proof = compressed_mem.get_merkle_proof(&[sig_hash], 0);
+
+    assert!(tx_unlock_init(
+        &mut svm,
+        &payer,
+        &vta_key,
+        vm_address,
+        unlock_address,
+    ).is_ok());
+
+    // The account is no longer in memory
+    assert!(tx_unlock_cancel(
+        &mut svm,
+        &payer,
+        &vta_key,
+        vm_address,
+        unlock_address,
+        vm_memory,
+        receipt_address,
+        account_index,
+    ).is_err());
+
+    // The account state must match what was compressed
+    let mut other_vta = vta;
+    other_vta.balance += 1;
+    assert!(tx_unlock_cancel_from_storage(
+        &mut svm,
+        &payer,
+        &vta_key,
+        vm_address,
+        unlock_address,
+        vm_storage,
+        receipt_address,
+        CancelUnlockIxData::FromStorage {
+            packed_va: VirtualAccount::Timelock(other_vta).pack(),
+            proof: proof.clone(),
+            signature: sig,
+        },
+    ).is_err());
+
+    assert!(tx_unlock_cancel_from_storage(
+        &mut svm,
+        &payer,
+        &vta_key,
+        vm_address,
+        unlock_address,
+        vm_storage,
+        receipt_address,
+        CancelUnlockIxData::FromStorage {
+            packed_va: va.pack(),
+            proof,
+            signature: sig,
+        },
+    ).is_ok());
+
+    let unlock_account = svm.get_account(&unlock_address);
+    assert!(unlock_account.map_or(true, |a| a.lamports == 0));
+
+    // The account is still in storage
+    assert_eq!(get_storage_account(&svm, vm_storage).compressed_state.get_root(), compressed_mem.get_root());
+}
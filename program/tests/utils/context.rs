@@ -77,6 +77,28 @@ impl TestContext {
         }
     }
 
+    pub fn create_timelock_account_for_owner(
+        &mut self,
+        mem_b: Pubkey,
+        index: u16,
+        owner: &Keypair,
+    ) -> TimelockAccountContext {
+        let account = create_timelock_for_owner(
+            &mut self.svm,
+            &self.payer,
+            self.vm_address,
+            mem_b,
+            index,
+            owner.pubkey(),
+            0,
+        );
+        TimelockAccountContext {
+            account,
+            key: owner.insecure_clone(),
+            index,
+        }
+    }
+
     pub fn create_durable_nonce_account(
         &mut self,
         mem_a: Pubkey,
@@ -105,7 +127,13 @@ impl TestContext {
         let depositor = vta_ctx.key.pubkey();
         let (deposit_pda, deposit_pda_bump) =
             find_timelock_deposit_pda(&self.vm_address, &depositor);
-        let deposit_ata = create_ata(&mut self.svm, &self.payer, &self.mint_pk, &deposit_pda);
+        let deposit_ata = spl_associated_token_account::get_associated_token_address(
+            &deposit_pda,
+            &self.mint_pk,
+        );
+        if self.svm.get_account(&deposit_ata).is_none() {
+            create_ata(&mut self.svm, &self.payer, &self.mint_pk, &deposit_pda);
+        }
         mint_to(
             &mut self.svm,
             &self.payer,
@@ -124,7 +152,7 @@ impl TestContext {
             depositor,
             deposit_pda,
             deposit_ata,
-            self.vm.omnibus.vault,
+            &MintPool::from_vm(&self.vm),
             vta_ctx.index,
             amount,
             deposit_pda_bump,
@@ -174,29 +202,63 @@ impl TestContext {
         mem_banks: Vec<u8>,
         data: Vec<u8>,
     ) -> TransactionResult {
-        let opcode = data[0];
-        let data = data[1..].to_vec();
-
-        tx_exec_opcode(
-            &mut self.svm,
-            &self.payer,
-            self.vm_address,
-            mems[0],
-            mems[1],
-            mems[2],
-            mems[3],
-            Some(self.vm.omnibus.vault),
-            Some(relay_ctx.relay_address),
-            Some(relay_ctx.relay.treasury.vault),
-            None,
-            Some(spl_token::id()),
-            opcode,
+        self.exec_relay_op_with_relays(
+            &[relay_ctx],
+            mems,
             mem_indices,
             mem_banks,
             data,
         )
     }
 
+    pub fn exec_relay_op_with_relays(
+        &mut self,
+        relays: &[&RelayContext],
+        mems: [Option<Pubkey>; 4],
+        mem_indices: Vec<u16>,
+        mem_banks: Vec<u8>,
+        data: Vec<u8>,
+    ) -> TransactionResult {
+        let opcode = data[0];
+        let data = data[1..].to_vec();
+
+        let extra = relays[1..]
+            .iter()
+            .map(|r| r.relay_address)
+            .collect::<Vec<_>>();
+
+        let ix = with_exec_relay_set(
+            vm_exec(
+                self.payer.pubkey(),
+                self.vm_address,
+                mems[0],
+                mems[1],
+                mems[2],
+                mems[3],
+                Some(self.vm.omnibus.vault),
+                Some(relays[0].relay_address),
+                Some(relays[0].relay.treasury.vault),
+                None,
+                Some(spl_token::id()),
+                opcode,
+                mem_indices,
+                mem_banks,
+                data,
+            ),
+            &extra,
+        );
+
+        let blockhash = self.svm.latest_blockhash();
+        let tx = Transaction::new_signed_with_payer(
+            &[ix],
+            Some(&self.payer.pubkey()),
+            &[&self.payer],
+            blockhash,
+        );
+
+        send_tx(&mut self.svm, tx)
+    }
+
     pub fn exec_conditional_transfer(
         &mut self,
         external_address: Pubkey,
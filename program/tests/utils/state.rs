@@ -2,9 +2,10 @@
 use code_vm_api::prelude::*;
 use litesvm::{types::TransactionResult, LiteSVM};
 use solana_sdk::{pubkey::Pubkey, signature::Keypair, signer::Signer, transaction::Transaction};
-use solana_program::entrypoint::MAX_PERMITTED_DATA_INCREASE;
+use solana_program::{clock::Clock, entrypoint::MAX_PERMITTED_DATA_INCREASE};
 
 use super::svm::*;
+use super::context::*;
 
 pub fn setup_svm_with_payer_and_vm(
     lock_duration: u8,
@@ -21,6 +22,57 @@ pub fn setup_svm_with_payer_and_vm(
     (svm, payer, mint_owner, mint_pk, vm_address)
 }
 
+/// A VM with a durable nonce in mem_a, a funded source and an empty
+/// destination timelock in mem_b, and an empty mem_c for the virtual account
+/// created by the opcode under test.
+pub struct OpcodeFixture {
+    pub ctx: TestContext,
+    pub mem_a: Pubkey,
+    pub mem_b: Pubkey,
+    pub mem_c: Pubkey,
+    pub src_ctx: TimelockAccountContext,
+    pub dst_ctx: TimelockAccountContext,
+    pub vdn_ctx: DurableNonceContext,
+}
+
+pub fn setup_opcode_fixture(
+    account_size: usize,
+    name: &str,
+    deposit_amount: u64,
+) -> OpcodeFixture {
+    let mut ctx = TestContext::new(21);
+
+    let mem_a = ctx.create_memory(10, VirtualDurableNonce::LEN + 1, "mem_nonce_0");
+    let mem_b = ctx.create_memory(10, VirtualTimelockAccount::LEN + 1, "mem_timelock_0");
+    let mem_c = ctx.create_memory(10, account_size + 1, name);
+
+    let src_ctx = ctx.create_timelock_account(mem_b, 0);
+    let dst_ctx = ctx.create_timelock_account(mem_b, 1);
+    let vdn_ctx = ctx.create_durable_nonce_account(mem_a, 0);
+
+    if deposit_amount > 0 {
+        ctx.deposit_tokens_to_timelock(mem_b, &src_ctx, deposit_amount)
+            .unwrap();
+    }
+
+    OpcodeFixture {
+        ctx,
+        mem_a,
+        mem_b,
+        mem_c,
+        src_ctx,
+        dst_ctx,
+        vdn_ctx,
+    }
+}
+
+pub fn set_unix_timestamp(svm: &mut LiteSVM, unix_timestamp: i64) {
+    let mut clock = svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = unix_timestamp;
+    svm.set_sysvar::<Clock>(&clock);
+    svm.expire_blockhash();
+}
+
 pub fn get_vm_account(svm: &LiteSVM, vm_address: Pubkey) -> CodeVmAccount {
     let account = svm.get_account(&vm_address).unwrap();
     CodeVmAccount::unpack(&account.data)
@@ -41,11 +93,22 @@ pub fn get_relay_account(svm: &LiteSVM, relay_address: Pubkey) -> RelayAccount {
     RelayAccount::unpack(&account.data)
 }
 
+pub fn get_relay_nullifier_account(svm: &LiteSVM, relay_address: Pubkey) -> RelayNullifierAccount {
+    let (nullifier_address, _) = find_vm_relay_nullifier_pda(&relay_address);
+    let account = svm.get_account(&nullifier_address).unwrap();
+    RelayNullifierAccount::unpack(&account.data)
+}
+
 pub fn get_unlock_state(svm: &LiteSVM, unlock_address: Pubkey) -> UnlockStateAccount {
     let account = svm.get_account(&unlock_address).unwrap();
     UnlockStateAccount::unpack(&account.data)
 }
 
+pub fn get_withdraw_receipt(svm: &LiteSVM, receipt_address: Pubkey) -> WithdrawReceiptAccount {
+    let account = svm.get_account(&receipt_address).unwrap();
+    WithdrawReceiptAccount::unpack(&account.data)
+}
+
 pub fn has_virtual_account(svm: &LiteSVM, vm_memory: Pubkey, account_index: u16) -> bool {
     let info = svm.get_account(&vm_memory).unwrap();
     let mem_account = MemoryAccount::unpack(&info.data);
@@ -90,6 +153,36 @@ pub fn get_virtual_relay(svm: &LiteSVM, vm_memory: Pubkey, account_index: u16) -
     va.into_inner_relay().unwrap()
 }
 
+pub fn get_virtual_vesting(svm: &LiteSVM, vm_memory: Pubkey, account_index: u16) -> VirtualVestingAccount {
+    let va = get_virtual_account(svm, vm_memory, account_index);
+    va.into_inner_vesting().unwrap()
+}
+
+pub fn get_virtual_scheduled_transfer(svm: &LiteSVM, vm_memory: Pubkey, account_index: u16) -> VirtualScheduledTransfer {
+    let va = get_virtual_account(svm, vm_memory, account_index);
+    va.into_inner_scheduled_transfer().unwrap()
+}
+
+pub fn get_virtual_subscription(svm: &LiteSVM, vm_memory: Pubkey, account_index: u16) -> VirtualSubscriptionAccount {
+    let va = get_virtual_account(svm, vm_memory, account_index);
+    va.into_inner_subscription().unwrap()
+}
+
+pub fn get_virtual_allowance(svm: &LiteSVM, vm_memory: Pubkey, account_index: u16) -> VirtualAllowanceAccount {
+    let va = get_virtual_account(svm, vm_memory, account_index);
+    va.into_inner_allowance().unwrap()
+}
+
+pub fn get_virtual_escrow(svm: &LiteSVM, vm_memory: Pubkey, account_index: u16) -> VirtualEscrowAccount {
+    let va = get_virtual_account(svm, vm_memory, account_index);
+    va.into_inner_escrow().unwrap()
+}
+
+pub fn get_virtual_multisig(svm: &LiteSVM, vm_memory: Pubkey, account_index: u16) -> VirtualMultisigAccount {
+    let va = get_virtual_account(svm, vm_memory, account_index);
+    va.into_inner_multisig().unwrap()
+}
+
 pub fn create_durable_nonce(
     svm: &mut LiteSVM,
     payer: &Keypair,
@@ -121,15 +214,53 @@ pub fn create_timelock(
     vm_memory: Pubkey,
     account_index: u16,
 ) -> (VirtualTimelockAccount, Keypair) {
-    let vm = get_vm_account(&svm, vm_address);
+    create_timelock_with_lock_duration(svm, payer, vm_address, vm_memory, account_index, 0)
+}
+
+pub fn create_timelock_with_lock_duration(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    vm_address: Pubkey,
+    vm_memory: Pubkey,
+    account_index: u16,
+    lock_duration: u8,
+) -> (VirtualTimelockAccount, Keypair) {
     let signer = create_keypair();
-    let owner = signer.pubkey();
+    let vta = create_timelock_for_owner(
+        svm,
+        payer,
+        vm_address,
+        vm_memory,
+        account_index,
+        signer.pubkey(),
+        lock_duration,
+    );
+
+    (vta, signer)
+}
+
+pub fn create_timelock_for_owner(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    vm_address: Pubkey,
+    vm_memory: Pubkey,
+    account_index: u16,
+    owner: Pubkey,
+    lock_duration: u8,
+) -> VirtualTimelockAccount {
+    let vm = get_vm_account(&svm, vm_address);
+
+    let effective_lock_duration = if lock_duration == 0 {
+        vm.get_lock_duration()
+    } else {
+        lock_duration
+    };
 
     let (timelock_address, virtual_timelock_bump) = find_virtual_timelock_address(
         &vm.get_mint(), 
         &vm.get_authority(), 
         &owner, 
-        vm.get_lock_duration()
+        effective_lock_duration
     );
 
     let (_, virtual_vault_bump) = find_virtual_timelock_vault_address(
@@ -143,7 +274,7 @@ pub fn create_timelock(
     );
 
     // Create the virtual timelock account
-    assert!(tx_create_virtual_timelock(
+    assert!(tx_create_virtual_timelock_with_lock_duration(
         svm, 
         &payer, 
         vm_address, 
@@ -153,12 +284,11 @@ pub fn create_timelock(
         virtual_timelock_bump,
         virtual_vault_bump,
         unlock_pda_bump,
+        lock_duration,
     ).is_ok());
 
     // Grab the virtual account data from the memory account
-    let vta = get_virtual_timelock(svm, vm_memory, account_index);
-
-    (vta, signer)
+    get_virtual_timelock(svm, vm_memory, account_index)
 }
 
 pub fn create_and_resize_memory(
@@ -238,7 +368,99 @@ pub fn tx_create_vm(
 ) -> TransactionResult {
     let payer_pk = payer.pubkey();
     let blockhash = svm.latest_blockhash();
-    let ix = vm_init(payer_pk, *mint, lock_duration);
+    let ix = vm_init(payer_pk, *mint, TokenProgram::Token, lock_duration);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
+
+    send_tx(svm, tx)
+}
+
+pub fn tx_set_message_policy(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    vm_address: Pubkey,
+    message_policy: MessagePolicy,
+) -> TransactionResult {
+    let payer_pk = payer.pubkey();
+    let blockhash = svm.latest_blockhash();
+    let ix = vm_set_message_policy(payer_pk, vm_address, message_policy);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
+
+    send_tx(svm, tx)
+}
+
+pub fn tx_set_signature_mode(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    vm_address: Pubkey,
+    signature_mode: SignatureMode,
+) -> TransactionResult {
+    let payer_pk = payer.pubkey();
+    let blockhash = svm.latest_blockhash();
+    let ix = vm_set_signature_mode(payer_pk, vm_address, signature_mode);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
+
+    send_tx(svm, tx)
+}
+
+pub fn tx_set_fee_config(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    vm_address: Pubkey,
+    recipient_kind: FeeRecipient,
+    recipient: Pubkey,
+    bps: u16,
+    minimum: u64,
+) -> TransactionResult {
+    let payer_pk = payer.pubkey();
+    let blockhash = svm.latest_blockhash();
+    let ix = vm_set_fee_config(payer_pk, vm_address, recipient_kind, recipient, bps, minimum);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
+
+    send_tx(svm, tx)
+}
+
+pub fn tx_set_velocity_limit(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    vm_address: Pubkey,
+    owner: Pubkey,
+    amount: u64,
+    window: i64,
+) -> TransactionResult {
+    let payer_pk = payer.pubkey();
+    let blockhash = svm.latest_blockhash();
+    let ix = vm_set_velocity_limit(payer_pk, vm_address, owner, amount, window);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
+
+    send_tx(svm, tx)
+}
+
+pub fn tx_set_mint_velocity_limit(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    vm_address: Pubkey,
+    owner: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+    window: i64,
+) -> TransactionResult {
+    let payer_pk = payer.pubkey();
+    let blockhash = svm.latest_blockhash();
+    let ix = vm_set_mint_velocity_limit(payer_pk, vm_address, owner, mint, amount, window);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
+
+    send_tx(svm, tx)
+}
+
+pub fn tx_init_mint(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    vm_address: Pubkey,
+    mint: Pubkey,
+) -> TransactionResult {
+    let payer_pk = payer.pubkey();
+    let blockhash = svm.latest_blockhash();
+    let ix = vm_init_mint(payer_pk, vm_address, mint, TokenProgram::Token);
     let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
 
     send_tx(svm, tx)
@@ -315,10 +537,36 @@ pub fn tx_create_virtual_timelock(
     virtual_timelock_bump: u8,
     virtual_vault_bump: u8,
     unlock_pda_bump: u8,
+) -> TransactionResult {
+    tx_create_virtual_timelock_with_lock_duration(
+        svm,
+        payer,
+        vm_address,
+        vm_memory,
+        virtual_account_owner,
+        account_index,
+        virtual_timelock_bump,
+        virtual_vault_bump,
+        unlock_pda_bump,
+        0,
+    )
+}
+
+pub fn tx_create_virtual_timelock_with_lock_duration(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    vm_address: Pubkey,
+    vm_memory: Pubkey,
+    virtual_account_owner: Pubkey,
+    account_index: u16,
+    virtual_timelock_bump: u8,
+    virtual_vault_bump: u8,
+    unlock_pda_bump: u8,
+    lock_duration: u8,
 ) -> TransactionResult {
     let payer_pk = payer.pubkey();
     let blockhash = svm.latest_blockhash();
-    let ix = system_timelock_init(
+    let ix = system_timelock_init_with_lock_duration(
         payer_pk, 
         vm_address, 
         vm_memory, 
@@ -326,7 +574,8 @@ pub fn tx_create_virtual_timelock(
         account_index, 
         virtual_timelock_bump, 
         virtual_vault_bump, 
-        unlock_pda_bump
+        unlock_pda_bump,
+        lock_duration,
     );
     let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
 
@@ -440,7 +689,22 @@ pub fn tx_create_relay(
 ) -> TransactionResult {
     let payer_pk = payer.pubkey();
     let blockhash = svm.latest_blockhash();
-    let ix = relay_init(payer_pk, vm_address, *mint, name);
+    let token_program = get_vm_account(svm, vm_address).get_token_program();
+    let ix = relay_init(payer_pk, vm_address, *mint, token_program, name);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
+
+    send_tx(svm, tx)
+}
+
+pub fn tx_create_relay_nullifier(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    vm_address: Pubkey,
+    relay: Pubkey,
+) -> TransactionResult {
+    let payer_pk = payer.pubkey();
+    let blockhash = svm.latest_blockhash();
+    let ix = relay_init_nullifier(payer_pk, vm_address, relay);
     let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
 
     send_tx(svm, tx)
@@ -460,22 +724,50 @@ pub fn tx_save_root(
     send_tx(svm, tx)
 }
 
+pub fn tx_close_relay(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    vm_address: Pubkey,
+    relay: Pubkey,
+    relay_vault: Pubkey,
+    pool: &MintPool,
+) -> TransactionResult {
+    let payer_pk = payer.pubkey();
+    let blockhash = svm.latest_blockhash();
+    let ix = relay_close(payer_pk, vm_address, relay, relay_vault, pool);
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
+
+    send_tx(svm, tx)
+}
+
 pub fn tx_unlock_init(
     svm: &mut LiteSVM,
     payer: &Keypair,
     account_owner: &Keypair,
     vm_address: Pubkey,
     unlock_pda: Pubkey,
+) -> TransactionResult {
+    tx_unlock_init_with_lock_duration(svm, payer, account_owner, vm_address, unlock_pda, 0)
+}
+
+pub fn tx_unlock_init_with_lock_duration(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    account_owner: &Keypair,
+    vm_address: Pubkey,
+    unlock_pda: Pubkey,
+    lock_duration: u8,
 ) -> TransactionResult {
     let owner = account_owner.pubkey();
     let payer_pk = payer.pubkey();
     let blockhash = svm.latest_blockhash();
 
-    let ix = timelock_unlock_init(
+    let ix = timelock_unlock_init_with_lock_duration(
         owner,
         payer_pk,
         vm_address,
         unlock_pda,
+        lock_duration,
     );
 
     let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer, account_owner], blockhash);
@@ -506,6 +798,64 @@ pub fn tx_unlock_finalize(
     send_tx(svm, tx)
 }
 
+pub fn tx_unlock_cancel(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    account_owner: &Keypair,
+    vm_address: Pubkey,
+    unlock_pda: Pubkey,
+    vm_memory: Pubkey,
+    withdraw_receipt: Pubkey,
+    account_index: u16,
+) -> TransactionResult {
+    let owner = account_owner.pubkey();
+    let payer_pk = payer.pubkey();
+    let blockhash = svm.latest_blockhash();
+
+    let ix = timelock_unlock_cancel(
+        owner,
+        payer_pk,
+        vm_address,
+        unlock_pda,
+        vm_memory,
+        withdraw_receipt,
+        CancelUnlockIxData::FromMemory { account_index },
+    );
+
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer, account_owner], blockhash);
+
+    send_tx(svm, tx)
+}
+
+pub fn tx_unlock_cancel_from_storage(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    account_owner: &Keypair,
+    vm_address: Pubkey,
+    unlock_pda: Pubkey,
+    vm_storage: Pubkey,
+    withdraw_receipt: Pubkey,
+    data: CancelUnlockIxData,
+) -> TransactionResult {
+    let owner = account_owner.pubkey();
+    let payer_pk = payer.pubkey();
+    let blockhash = svm.latest_blockhash();
+
+    let ix = timelock_unlock_cancel(
+        owner,
+        payer_pk,
+        vm_address,
+        unlock_pda,
+        vm_storage,
+        withdraw_receipt,
+        data,
+    );
+
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer, account_owner], blockhash);
+
+    send_tx(svm, tx)
+}
+
 pub fn tx_deposit_from_pda(
     svm: &mut LiteSVM,
     payer: &Keypair,
@@ -514,7 +864,7 @@ pub fn tx_deposit_from_pda(
     depositor: Pubkey,
     deposit_pda: Pubkey,
     deposit_ata: Pubkey,
-    omnibus: Pubkey,
+    pool: &MintPool,
     account_index: u16,
     amount: u64,
     bump: u8,
@@ -529,7 +879,7 @@ pub fn tx_deposit_from_pda(
         depositor,
         deposit_pda,
         deposit_ata,
-        omnibus,
+        pool,
         account_index,
         amount,
         bump,
@@ -540,6 +890,41 @@ pub fn tx_deposit_from_pda(
     send_tx(svm, tx)
 }
 
+#[allow(clippy::too_many_arguments)]
+pub fn tx_sweep_deposit(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    vm_address: Pubkey,
+    vm_memory: Pubkey,
+    depositor: Pubkey,
+    deposit_pda: Pubkey,
+    deposit_ata: Pubkey,
+    pool: &MintPool,
+    account_index: u16,
+    bump: u8,
+    close: bool,
+) -> TransactionResult {
+    let payer_pk = payer.pubkey();
+    let blockhash = svm.latest_blockhash();
+
+    let ix = timelock_sweep_deposit(
+        payer_pk,
+        vm_address,
+        vm_memory,
+        depositor,
+        deposit_pda,
+        deposit_ata,
+        pool,
+        account_index,
+        bump,
+        close,
+    );
+
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
+
+    send_tx(svm, tx)
+}
+
 pub fn tx_deposit_with_authority(
     svm: &mut LiteSVM,
     payer: &Keypair,
@@ -548,7 +933,7 @@ pub fn tx_deposit_with_authority(
     vm_memory: Pubkey,
     source_ata: Pubkey,
     destination: Pubkey,
-    omnibus: Pubkey,
+    pool: &MintPool,
     account_index: u16,
     amount: u64,
 ) -> TransactionResult {
@@ -563,7 +948,7 @@ pub fn tx_deposit_with_authority(
         source_authority_pk,
         source_ata,
         destination,
-        omnibus,
+        pool,
         account_index,
         amount,
     );
@@ -573,6 +958,117 @@ pub fn tx_deposit_with_authority(
     send_tx(svm, tx)
 }
 
+pub fn tx_deposit_with_intent(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    vm_address: Pubkey,
+    vm_memory: Pubkey,
+    pool: &MintPool,
+    intent: &DepositIntent,
+) -> TransactionResult {
+    let payer_pk = payer.pubkey();
+    let blockhash = svm.latest_blockhash();
+
+    let ix = timelock_deposit_with_intent(
+        payer_pk,
+        vm_address,
+        vm_memory,
+        pool,
+        intent,
+    );
+
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
+
+    send_tx(svm, tx)
+}
+
+pub fn tx_close_deposit_receipt(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    vm_address: Pubkey,
+    source_authority: Pubkey,
+    nonce: Hash,
+) -> TransactionResult {
+    let payer_pk = payer.pubkey();
+    let blockhash = svm.latest_blockhash();
+
+    let ix = deposit_receipt_close(
+        payer_pk,
+        vm_address,
+        source_authority,
+        nonce,
+    );
+
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
+
+    send_tx(svm, tx)
+}
+
+pub fn tx_init_vesting(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    source_authority: &Keypair,
+    vm_address: Pubkey,
+    vm_memory: Pubkey,
+    source_ata: Pubkey,
+    beneficiary: Pubkey,
+    pool: &MintPool,
+    account_index: u16,
+    total: u64,
+    start: i64,
+    cliff: i64,
+    end: i64,
+) -> TransactionResult {
+    let payer_pk = payer.pubkey();
+    let source_authority_pk = source_authority.pubkey();
+    let blockhash = svm.latest_blockhash();
+
+    let ix = system_vesting_init(
+        payer_pk,
+        vm_address,
+        vm_memory,
+        source_authority_pk,
+        source_ata,
+        beneficiary,
+        pool,
+        account_index,
+        total,
+        start,
+        cliff,
+        end,
+    );
+
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer, source_authority], blockhash);
+
+    send_tx(svm, tx)
+}
+
+pub fn tx_init_multisig(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    vm_address: Pubkey,
+    vm_memory: Pubkey,
+    signers: &[Pubkey],
+    account_index: u16,
+    threshold: u8,
+) -> TransactionResult {
+    let payer_pk = payer.pubkey();
+    let blockhash = svm.latest_blockhash();
+
+    let ix = system_multisig_init(
+        payer_pk,
+        vm_address,
+        vm_memory,
+        signers,
+        account_index,
+        threshold,
+    );
+
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[payer], blockhash);
+
+    send_tx(svm, tx)
+}
+
 pub fn tx_withdraw_from_deposit(
     svm: &mut LiteSVM,
     payer: &Keypair,
@@ -587,12 +1083,13 @@ pub fn tx_withdraw_from_deposit(
     let depositor = owner.pubkey();
     let payer_pk = payer.pubkey();
     let blockhash = svm.latest_blockhash();
+    let pool = MintPool::from_vm(&get_vm_account(svm, vm_address));
 
     let ix = timelock_withdraw(
         depositor,
         payer_pk,
         vm_address,
-        None, // vm_omnibus
+        &pool,
         None, // vm_memory
         None, // vm_storage
         Some(deposit_pda),
@@ -622,12 +1119,13 @@ pub fn tx_withdraw_from_swap(
     let depositor = owner.pubkey();
     let payer_pk = payer.pubkey();
     let blockhash = svm.latest_blockhash();
+    let pool = MintPool::from_vm(&get_vm_account(svm, vm_address));
 
     let ix = timelock_withdraw(
         depositor,
         payer_pk,
         vm_address,
-        None, // vm_omnibus
+        &pool,
         None, // vm_memory
         None, // vm_storage
         Some(swap_pda),
@@ -648,7 +1146,7 @@ pub fn tx_withdraw_from_memory(
     payer: &Keypair,
     owner: &Keypair,
     vm_address: Pubkey,
-    vm_omnibus: Pubkey,
+    pool: &MintPool,
     vm_memory: Pubkey,
     unlock_pda: Pubkey,
     withdraw_receipt: Pubkey,
@@ -663,7 +1161,7 @@ pub fn tx_withdraw_from_memory(
         depositor,
         payer_pk,
         vm_address,
-        Some(vm_omnibus),
+        pool,
         Some(vm_memory),
         None, // vm_storage
         None, // deposit_pda
@@ -684,7 +1182,7 @@ pub fn tx_withdraw_from_storage(
     payer: &Keypair,
     owner: &Keypair,
     vm_address: Pubkey,
-    vm_omnibus: Pubkey,
+    pool: &MintPool,
     vm_storage: Pubkey,
     unlock_pda: Pubkey,
     withdraw_receipt: Pubkey,
@@ -699,7 +1197,7 @@ pub fn tx_withdraw_from_storage(
         depositor,
         payer_pk,
         vm_address,
-        Some(vm_omnibus),
+        pool,
         None, // vm_memory
         Some(vm_storage),
         None, // deposit_pda
@@ -729,6 +1227,7 @@ pub fn tx_transfer_for_swap(
     let payer_pk = payer.pubkey();
     let swapper_pk = swapper.pubkey();
     let blockhash = svm.latest_blockhash();
+    let pool = MintPool::from_vm(&get_vm_account(svm, vm_address));
 
     let ix = transfer_for_swap(
         payer_pk,
@@ -737,6 +1236,7 @@ pub fn tx_transfer_for_swap(
         swap_pda,
         swap_ata,
         destination,
+        &pool,
         amount,
         bump,
     );
@@ -754,7 +1254,7 @@ pub fn tx_cancel_swap(
     swapper: Pubkey,
     swap_pda: Pubkey,
     swap_ata: Pubkey,
-    omnibus: Pubkey,
+    pool: &MintPool,
     account_index: u16,
     amount: u64,
     bump: u8,
@@ -769,7 +1269,7 @@ pub fn tx_cancel_swap(
         swapper,
         swap_pda,
         swap_ata,
-        omnibus,
+        pool,
         account_index,
         amount,
         bump,
@@ -792,6 +1292,7 @@ pub fn tx_close_swap_account_if_empty(
 ) -> TransactionResult {
     let payer_pk = payer.pubkey();
     let blockhash = svm.latest_blockhash();
+    let pool = MintPool::from_vm(&get_vm_account(svm, vm));
 
     let ix = close_swap_account_if_empty(
         payer_pk,
@@ -800,6 +1301,7 @@ pub fn tx_close_swap_account_if_empty(
         swap_pda,
         swap_ata,
         destination,
+        &pool,
         bump,
     );
 
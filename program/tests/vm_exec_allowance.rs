@@ -0,0 +1,193 @@
+#![cfg(test)]
+
+use steel::*;
+
+pub mod utils;
+use utils::*;
+
+use solana_sdk::signature::{Keypair, Signer};
+use code_vm_api::prelude::*;
+
+struct AllowanceContext {
+    ctx: TestContext,
+    mem_a: Pubkey,
+    mem_b: Pubkey,
+    mem_c: Pubkey,
+    owner_ctx: TimelockAccountContext,
+    dst_ctx: TimelockAccountContext,
+    vdn_ctx: DurableNonceContext,
+    delegate: Keypair,
+}
+
+fn setup_allowance(deposit_amount: u64, amount: u64, expires_in: i64) -> AllowanceContext {
+    let OpcodeFixture {
+        mut ctx,
+        mem_a,
+        mem_b,
+        mem_c,
+        src_ctx: owner_ctx,
+        dst_ctx,
+        vdn_ctx,
+    } = setup_opcode_fixture(VirtualAllowanceAccount::LEN, "mem_allowance_0", deposit_amount);
+
+    let delegate = create_keypair();
+
+    let expires_at = if expires_in == 0 {
+        0
+    } else {
+        ctx.svm.get_sysvar::<Clock>().unix_timestamp + expires_in
+    };
+
+    let hash = create_approve_allowance_message(
+        &ctx.vm,
+        &owner_ctx.account,
+        &delegate.pubkey(),
+        &vdn_ctx.account,
+        amount,
+        expires_at,
+    );
+    let signature = owner_ctx
+        .key
+        .sign_message(hash.as_ref())
+        .as_ref()
+        .try_into()
+        .unwrap();
+
+    let data = ApproveAllowanceOp::from_struct(ParsedApproveAllowanceOp {
+        signature,
+        delegate: delegate.pubkey(),
+        amount,
+        expires_at,
+    }).to_bytes();
+
+    ctx.exec_opcode(
+        [Some(mem_a), Some(mem_b), Some(mem_c), None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        data,
+        vec![vdn_ctx.index, owner_ctx.index, 0],
+        vec![0, 1, 2],
+    )
+    .unwrap();
+
+    AllowanceContext {
+        ctx,
+        mem_a,
+        mem_b,
+        mem_c,
+        owner_ctx,
+        dst_ctx,
+        vdn_ctx,
+        delegate,
+    }
+}
+
+fn delegated_transfer(a: &mut AllowanceContext, signer: &Keypair, amount: u64) -> bool {
+    let vdn = get_virtual_nonce(&a.ctx.svm, a.mem_a, a.vdn_ctx.index);
+    let allowance = get_virtual_allowance(&a.ctx.svm, a.mem_c, 0);
+
+    let hash = create_delegated_transfer_message(
+        &a.ctx.vm,
+        &allowance,
+        &a.dst_ctx.account,
+        &vdn,
+        amount,
+    );
+    let signature = signer
+        .sign_message(hash.as_ref())
+        .as_ref()
+        .try_into()
+        .unwrap();
+
+    let data = DelegatedTransferOp::from_struct(ParsedDelegatedTransferOp {
+        signature,
+        amount,
+    }).to_bytes();
+
+    a.ctx.svm.expire_blockhash();
+    a.ctx.exec_opcode(
+        [Some(a.mem_a), Some(a.mem_c), Some(a.mem_b), None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        data,
+        vec![a.vdn_ctx.index, 0, a.owner_ctx.index, a.dst_ctx.index],
+        vec![0, 1, 2, 2],
+    )
+    .is_ok()
+}
+
+#[test]
+fn run_delegated_transfer() {
+    let mut a = setup_allowance(100, 50, 0);
+    let delegate = a.delegate.insecure_clone();
+
+    let allowance = get_virtual_allowance(&a.ctx.svm, a.mem_c, 0);
+    assert_eq!(allowance.delegate, delegate.pubkey());
+    assert_eq!(allowance.remaining, 50);
+
+    // Only the delegate can spend the allowance
+    let other = create_keypair();
+    assert!(!delegated_transfer(&mut a, &other, 10));
+
+    assert!(delegated_transfer(&mut a, &delegate, 30));
+    assert_eq!(get_virtual_allowance(&a.ctx.svm, a.mem_c, 0).remaining, 20);
+    assert_eq!(a.ctx.get_virtual_timelock(a.mem_b, a.owner_ctx.index).balance, 70);
+    assert_eq!(a.ctx.get_virtual_timelock(a.mem_b, a.dst_ctx.index).balance, 30);
+
+    // The delegate can't spend more than what is left
+    assert!(!delegated_transfer(&mut a, &delegate, 21));
+
+    // Spending the rest deletes the allowance
+    assert!(delegated_transfer(&mut a, &delegate, 20));
+    assert_eq!(a.ctx.get_virtual_timelock(a.mem_b, a.dst_ctx.index).balance, 50);
+    assert!(!a.ctx.has_virtual_account(a.mem_c, 0));
+}
+
+#[test]
+fn run_delegated_transfer_expired() {
+    let mut a = setup_allowance(100, 50, 60);
+    let delegate = a.delegate.insecure_clone();
+
+    let expires_at = get_virtual_allowance(&a.ctx.svm, a.mem_c, 0).expires_at;
+    set_unix_timestamp(&mut a.ctx.svm, expires_at);
+
+    assert!(!delegated_transfer(&mut a, &delegate, 10));
+    assert_eq!(a.ctx.get_virtual_timelock(a.mem_b, a.owner_ctx.index).balance, 100);
+}
+
+#[test]
+fn run_revoke_allowance() {
+    let mut a = setup_allowance(100, 50, 0);
+
+    let vdn = get_virtual_nonce(&a.ctx.svm, a.mem_a, a.vdn_ctx.index);
+    let allowance = get_virtual_allowance(&a.ctx.svm, a.mem_c, 0);
+
+    let hash = create_revoke_allowance_message(&allowance, &vdn);
+    let signature = a.owner_ctx
+        .key
+        .sign_message(hash.as_ref())
+        .as_ref()
+        .try_into()
+        .unwrap();
+
+    a.ctx.exec_opcode(
+        [Some(a.mem_a), Some(a.mem_c), Some(a.mem_b), None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        RevokeAllowanceOp { signature }.to_bytes(),
+        vec![a.vdn_ctx.index, 0, a.owner_ctx.index],
+        vec![0, 1, 2],
+    )
+    .unwrap();
+
+    assert!(!a.ctx.has_virtual_account(a.mem_c, 0));
+}
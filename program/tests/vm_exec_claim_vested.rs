@@ -0,0 +1,210 @@
+#![cfg(test)]
+
+use steel::*;
+
+pub mod utils;
+use utils::*;
+
+use solana_sdk::signature::{Keypair, Signer};
+use code_vm_api::prelude::*;
+
+struct VestingContext {
+    ctx: TestContext,
+    mem_b: Pubkey,
+    mem_c: Pubkey,
+    vta_ctx: TimelockAccountContext,
+    other_vta_ctx: TimelockAccountContext,
+    source_key: Keypair,
+    source_ata: Pubkey,
+    start: i64,
+}
+
+fn setup_vesting(total: u64) -> VestingContext {
+    let OpcodeFixture {
+        mut ctx,
+        mem_b,
+        mem_c,
+        src_ctx: vta_ctx,
+        dst_ctx: other_vta_ctx,
+        ..
+    } = setup_opcode_fixture(VirtualVestingAccount::LEN, "mem_vesting_0", 0);
+
+    let source_key = create_keypair();
+    let source_ata = create_ata(&mut ctx.svm, &ctx.payer, &ctx.mint_pk, &source_key.pubkey());
+    mint_to(&mut ctx.svm, &ctx.payer, &ctx.mint_pk, &ctx.mint_owner, &source_ata, total).unwrap();
+
+    let start = ctx.svm.get_sysvar::<Clock>().unix_timestamp;
+
+    VestingContext {
+        ctx,
+        mem_b,
+        mem_c,
+        vta_ctx,
+        other_vta_ctx,
+        source_key,
+        source_ata,
+        start,
+    }
+}
+
+fn claim_vested(
+    ctx: &mut TestContext,
+    mem_c: Pubkey,
+    mem_b: Pubkey,
+    vta_index: u16,
+) -> bool {
+    ctx.exec_opcode(
+        [Some(mem_c), Some(mem_b), None, None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        create_claim_vested_op(),
+        vec![0, vta_index],
+        vec![0, 1],
+    )
+    .is_ok()
+}
+
+#[test]
+fn run_claim_vested() {
+    let total = 1_000;
+    let VestingContext {
+        mut ctx,
+        mem_b,
+        mem_c,
+        vta_ctx,
+        source_key,
+        source_ata,
+        start,
+        ..
+    } = setup_vesting(total);
+
+    let cliff = start + 250;
+    let end = start + 1_000;
+
+    let vm_address = ctx.vm_address;
+    let omnibus = ctx.vm.omnibus.vault;
+    let pool = MintPool::from_vm(&ctx.vm);
+
+    assert!(tx_init_vesting(
+        &mut ctx.svm,
+        &ctx.payer,
+        &source_key,
+        vm_address,
+        mem_c,
+        source_ata,
+        vta_ctx.account.owner,
+        &pool,
+        0,
+        total,
+        start,
+        cliff,
+        end,
+    ).is_ok());
+
+    assert_eq!(ctx.get_ata_balance(source_ata), 0);
+    assert_eq!(ctx.get_ata_balance(omnibus), total);
+
+    let vesting = get_virtual_vesting(&ctx.svm, mem_c, 0);
+    assert_eq!(vesting.beneficiary, vta_ctx.account.owner);
+    assert_eq!(vesting.total, total);
+    assert_eq!(vesting.claimed, 0);
+
+    // Nothing is claimable before the cliff
+    set_unix_timestamp(&mut ctx.svm, cliff - 1);
+    assert!(!claim_vested(&mut ctx, mem_c, mem_b, vta_ctx.index));
+
+    set_unix_timestamp(&mut ctx.svm, start + 500);
+    assert!(claim_vested(&mut ctx, mem_c, mem_b, vta_ctx.index));
+
+    assert_eq!(ctx.get_virtual_timelock(mem_b, vta_ctx.index).balance, 500);
+    assert_eq!(get_virtual_vesting(&ctx.svm, mem_c, 0).claimed, 500);
+
+    // Claiming again at the same time has nothing to move
+    ctx.svm.expire_blockhash();
+    assert!(!claim_vested(&mut ctx, mem_c, mem_b, vta_ctx.index));
+
+    // Once fully claimed, the vesting account is deleted
+    set_unix_timestamp(&mut ctx.svm, end);
+    assert!(claim_vested(&mut ctx, mem_c, mem_b, vta_ctx.index));
+
+    assert_eq!(ctx.get_virtual_timelock(mem_b, vta_ctx.index).balance, total);
+    assert!(!ctx.has_virtual_account(mem_c, 0));
+}
+
+#[test]
+fn run_claim_vested_wrong_beneficiary() {
+    let total = 1_000;
+    let VestingContext {
+        mut ctx,
+        mem_b,
+        mem_c,
+        vta_ctx,
+        other_vta_ctx,
+        source_key,
+        source_ata,
+        start,
+    } = setup_vesting(total);
+
+    let vm_address = ctx.vm_address;
+    let pool = MintPool::from_vm(&ctx.vm);
+
+    assert!(tx_init_vesting(
+        &mut ctx.svm,
+        &ctx.payer,
+        &source_key,
+        vm_address,
+        mem_c,
+        source_ata,
+        vta_ctx.account.owner,
+        &pool,
+        0,
+        total,
+        start,
+        start,
+        start + 1_000,
+    ).is_ok());
+
+    set_unix_timestamp(&mut ctx.svm, start + 1_000);
+    assert!(!claim_vested(&mut ctx, mem_c, mem_b, other_vta_ctx.index));
+    assert!(claim_vested(&mut ctx, mem_c, mem_b, vta_ctx.index));
+}
+
+#[test]
+fn run_init_vesting_invalid_schedule() {
+    let total = 1_000;
+    let VestingContext {
+        mut ctx,
+        mem_c,
+        vta_ctx,
+        source_key,
+        source_ata,
+        start,
+        ..
+    } = setup_vesting(total);
+
+    let vm_address = ctx.vm_address;
+    let pool = MintPool::from_vm(&ctx.vm);
+
+    // The cliff is after the end of the schedule
+    assert!(tx_init_vesting(
+        &mut ctx.svm,
+        &ctx.payer,
+        &source_key,
+        vm_address,
+        mem_c,
+        source_ata,
+        vta_ctx.account.owner,
+        &pool,
+        0,
+        total,
+        start,
+        start + 2_000,
+        start + 1_000,
+    ).is_err());
+
+    assert!(!ctx.has_virtual_account(mem_c, 0));
+    assert_eq!(ctx.get_ata_balance(source_ata), total);
+}
@@ -0,0 +1,162 @@
+#![cfg(test)]
+
+use steel::*;
+
+pub mod utils;
+use utils::*;
+
+use solana_sdk::signature::{Keypair, Signer};
+use code_vm_api::prelude::*;
+
+struct EscrowContext {
+    ctx: TestContext,
+    mem_a: Pubkey,
+    mem_b: Pubkey,
+    mem_c: Pubkey,
+    src_ctx: TimelockAccountContext,
+    dst_ctx: TimelockAccountContext,
+    vdn_ctx: DurableNonceContext,
+    arbiter: Keypair,
+    deadline: i64,
+}
+
+fn setup_escrow(deposit_amount: u64, amount: u64) -> EscrowContext {
+    let OpcodeFixture {
+        mut ctx,
+        mem_a,
+        mem_b,
+        mem_c,
+        src_ctx,
+        dst_ctx,
+        vdn_ctx,
+    } = setup_opcode_fixture(VirtualEscrowAccount::LEN, "mem_escrow_0", deposit_amount);
+
+    let arbiter = create_keypair();
+
+    let deadline = ctx.svm.get_sysvar::<Clock>().unix_timestamp + 3_600;
+
+    let hash = create_escrow_deposit_message(
+        &ctx.vm,
+        &src_ctx.account,
+        &dst_ctx.account,
+        &arbiter.pubkey(),
+        &vdn_ctx.account,
+        amount,
+        deadline,
+    );
+    let signature = src_ctx
+        .key
+        .sign_message(hash.as_ref())
+        .as_ref()
+        .try_into()
+        .unwrap();
+
+    let data = EscrowDepositOp::from_struct(ParsedEscrowDepositOp {
+        signature,
+        arbiter: arbiter.pubkey(),
+        amount,
+        deadline,
+    }).to_bytes();
+
+    ctx.exec_opcode(
+        [Some(mem_a), Some(mem_b), Some(mem_c), None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        data,
+        vec![vdn_ctx.index, src_ctx.index, dst_ctx.index, 0],
+        vec![0, 1, 1, 2],
+    )
+    .unwrap();
+
+    EscrowContext {
+        ctx,
+        mem_a,
+        mem_b,
+        mem_c,
+        src_ctx,
+        dst_ctx,
+        vdn_ctx,
+        arbiter,
+        deadline,
+    }
+}
+
+fn escrow_release(e: &mut EscrowContext, signer: &Keypair) -> bool {
+    let vdn = get_virtual_nonce(&e.ctx.svm, e.mem_a, e.vdn_ctx.index);
+    let escrow = get_virtual_escrow(&e.ctx.svm, e.mem_c, 0);
+
+    let hash = create_escrow_release_message(&escrow, &vdn);
+    let signature = signer
+        .sign_message(hash.as_ref())
+        .as_ref()
+        .try_into()
+        .unwrap();
+
+    e.ctx.svm.expire_blockhash();
+    e.ctx.exec_opcode(
+        [Some(e.mem_a), Some(e.mem_c), Some(e.mem_b), None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        EscrowReleaseOp { signature }.to_bytes(),
+        vec![e.vdn_ctx.index, 0, e.dst_ctx.index],
+        vec![0, 1, 2],
+    )
+    .is_ok()
+}
+
+fn escrow_refund(e: &mut EscrowContext) -> bool {
+    e.ctx.svm.expire_blockhash();
+    e.ctx.exec_opcode(
+        [Some(e.mem_c), Some(e.mem_b), None, None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        EscrowRefundOp {}.to_bytes(),
+        vec![0, e.src_ctx.index],
+        vec![0, 1],
+    )
+    .is_ok()
+}
+
+#[test]
+fn run_escrow_release() {
+    let mut e = setup_escrow(100, 40);
+    let arbiter = e.arbiter.insecure_clone();
+
+    assert_eq!(e.ctx.get_virtual_timelock(e.mem_b, e.src_ctx.index).balance, 60);
+
+    let escrow = get_virtual_escrow(&e.ctx.svm, e.mem_c, 0);
+    assert_eq!(escrow.arbiter, arbiter.pubkey());
+    assert_eq!(escrow.amount, 40);
+
+    // Only the arbiter can release the escrow
+    let other = create_keypair();
+    assert!(!escrow_release(&mut e, &other));
+
+    // The escrow can't be refunded before the deadline
+    assert!(!escrow_refund(&mut e));
+
+    assert!(escrow_release(&mut e, &arbiter));
+    assert_eq!(e.ctx.get_virtual_timelock(e.mem_b, e.dst_ctx.index).balance, 40);
+    assert!(!e.ctx.has_virtual_account(e.mem_c, 0));
+}
+
+#[test]
+fn run_escrow_refund() {
+    let mut e = setup_escrow(100, 40);
+
+    set_unix_timestamp(&mut e.ctx.svm, e.deadline);
+
+    assert!(escrow_refund(&mut e));
+    assert_eq!(e.ctx.get_virtual_timelock(e.mem_b, e.src_ctx.index).balance, 100);
+    assert_eq!(e.ctx.get_virtual_timelock(e.mem_b, e.dst_ctx.index).balance, 0);
+    assert!(!e.ctx.has_virtual_account(e.mem_c, 0));
+}
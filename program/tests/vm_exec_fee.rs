@@ -0,0 +1,204 @@
+#![cfg(test)]
+
+use steel::*;
+
+pub mod utils;
+use utils::*;
+
+use solana_sdk::{
+    signature::Signer,
+    transaction::Transaction,
+};
+use code_vm_api::prelude::*;
+
+struct FeeContext {
+    ctx: TestContext,
+    mem_a: Pubkey,
+    mem_b: Pubkey,
+    src_ctx: TimelockAccountContext,
+    dst_ctx: TimelockAccountContext,
+    fee_ctx: TimelockAccountContext,
+}
+
+fn setup_fee() -> FeeContext {
+    let mut ctx = TestContext::new(21);
+
+    let mem_a = ctx.create_memory(10, VirtualDurableNonce::LEN + 1, "mem_nonce_0");
+    let mem_b = ctx.create_memory(10, VirtualTimelockAccount::LEN + 1, "mem_timelock_0");
+
+    let src_ctx = ctx.create_timelock_account(mem_b, 0);
+    let dst_ctx = ctx.create_timelock_account(mem_b, 1);
+    let fee_ctx = ctx.create_timelock_account(mem_b, 2);
+    ctx.create_durable_nonce_account(mem_a, 0);
+
+    ctx.deposit_tokens_to_timelock(mem_b, &src_ctx, 100_000)
+        .unwrap();
+
+    // Fees go to the token address of a virtual account
+    let fee_vta = ctx.get_virtual_timelock(mem_b, fee_ctx.index);
+    let fee_timelock_address = fee_vta.get_timelock_address(
+        &ctx.vm.get_mint(),
+        &ctx.vm.get_authority(),
+        fee_vta.get_lock_duration(ctx.vm.get_lock_duration()),
+    );
+    let recipient = fee_vta.get_token_address(&fee_timelock_address);
+
+    let vm_address = ctx.vm_address;
+    assert!(tx_set_fee_config(
+        &mut ctx.svm,
+        &ctx.payer,
+        vm_address,
+        FeeRecipient::Virtual,
+        recipient,
+        25, // 0.25%
+        10,
+    )
+    .is_ok());
+    ctx.svm.expire_blockhash();
+
+    FeeContext {
+        ctx,
+        mem_a,
+        mem_b,
+        src_ctx,
+        dst_ctx,
+        fee_ctx,
+    }
+}
+
+/// Sends a transfer of `amount` with the fee config attached, signed over a
+/// message that includes `signed_fee`.
+fn transfer(f: &mut FeeContext, amount: u64, signed_fee: u64) -> bool {
+    let fee_index = f.fee_ctx.index;
+    send_transfer(f, amount, signed_fee, Some(fee_index))
+}
+
+fn send_transfer(f: &mut FeeContext, amount: u64, signed_fee: u64, fee_index: Option<u16>) -> bool {
+    let src_vta = f.ctx.get_virtual_timelock(f.mem_b, f.src_ctx.index);
+    let dst_vta = f.ctx.get_virtual_timelock(f.mem_b, f.dst_ctx.index);
+    let vdn = get_virtual_nonce(&f.ctx.svm, f.mem_a, 0);
+
+    let hash = create_transfer_message_with_fee(
+        &f.ctx.vm,
+        &src_vta,
+        &dst_vta,
+        &vdn,
+        amount,
+        signed_fee,
+    );
+
+    let signature = f.src_ctx
+        .key
+        .sign_message(hash.as_ref())
+        .as_ref()
+        .try_into()
+        .unwrap();
+
+    let data = TransferOp::from_struct(ParsedTransferOp { amount, signature }).to_bytes();
+
+    let ix = vm_exec(
+        f.ctx.payer.pubkey(),
+        f.ctx.vm_address,
+        Some(f.mem_a),
+        Some(f.mem_b),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        data[0],
+        [vec![0, f.src_ctx.index, f.dst_ctx.index], fee_index.into_iter().collect()].concat(),
+        [vec![0, 1, 1], fee_index.map(|_| 1).into_iter().collect()].concat(),
+        data[1..].to_vec(),
+    );
+    let ix = with_exec_fee_config(ix, f.ctx.vm_address, None);
+
+    f.ctx.svm.expire_blockhash();
+    let payer_pk = f.ctx.payer.pubkey();
+    let blockhash = f.ctx.svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[&f.ctx.payer], blockhash);
+
+    send_tx(&mut f.ctx.svm, tx).is_ok()
+}
+
+#[test]
+fn run_exec_fee_config() {
+    let f = setup_fee();
+
+    let (fee_config_address, _) = find_vm_fee_config_pda(&f.ctx.vm_address);
+    let account = f.ctx.svm.get_account(&fee_config_address).unwrap();
+    let fee_config = FeeConfigAccount::unpack(&account.data);
+
+    assert_eq!(fee_config.vm, f.ctx.vm_address);
+    assert_eq!(fee_config.get_recipient_kind(), Some(FeeRecipient::Virtual));
+    assert_eq!(fee_config.bps, 25);
+    assert_eq!(fee_config.minimum, 10);
+}
+
+#[test]
+fn run_exec_fee_transfer() {
+    let mut f = setup_fee();
+
+    assert!(transfer(&mut f, 10_000, 25));
+
+    assert_eq!(f.ctx.get_virtual_timelock(f.mem_b, f.src_ctx.index).balance, 89_975);
+    assert_eq!(f.ctx.get_virtual_timelock(f.mem_b, f.dst_ctx.index).balance, 10_000);
+    assert_eq!(f.ctx.get_virtual_timelock(f.mem_b, f.fee_ctx.index).balance, 25);
+
+    // Small transfers pay the minimum
+    assert!(transfer(&mut f, 100, 10));
+    assert_eq!(f.ctx.get_virtual_timelock(f.mem_b, f.fee_ctx.index).balance, 35);
+}
+
+#[test]
+fn run_exec_fee_requires_signed_fee() {
+    let mut f = setup_fee();
+
+    // The owner has to agree to the exact fee
+    assert!(!transfer(&mut f, 10_000, 0));
+    assert!(!transfer(&mut f, 10_000, 24));
+
+    assert_eq!(f.ctx.get_virtual_timelock(f.mem_b, f.src_ctx.index).balance, 100_000);
+    assert_eq!(f.ctx.get_virtual_timelock(f.mem_b, f.fee_ctx.index).balance, 0);
+}
+
+#[test]
+fn run_exec_fee_insufficient_funds() {
+    let mut f = setup_fee();
+
+    // The fee comes on top of the amount
+    assert!(!transfer(&mut f, 100_000, 250));
+    assert!(transfer(&mut f, 99_750, 249));
+    assert_eq!(f.ctx.get_virtual_timelock(f.mem_b, f.src_ctx.index).balance, 1);
+}
+
+#[test]
+fn run_exec_fee_disabled() {
+    let mut f = setup_fee();
+
+    let vm_address = f.ctx.vm_address;
+    let (fee_config_address, _) = find_vm_fee_config_pda(&vm_address);
+    let account = f.ctx.svm.get_account(&fee_config_address).unwrap();
+    let recipient = FeeConfigAccount::unpack(&account.data).recipient;
+
+    assert!(tx_set_fee_config(
+        &mut f.ctx.svm,
+        &f.ctx.payer,
+        vm_address,
+        FeeRecipient::Virtual,
+        recipient,
+        0,
+        0,
+    )
+    .is_ok());
+
+    // With fees turned off, the fee recipient isn't needed
+    let fee_index = f.fee_ctx.index;
+    assert!(!send_transfer(&mut f, 10_000, 0, Some(fee_index)));
+    assert!(send_transfer(&mut f, 10_000, 0, None));
+
+    assert_eq!(f.ctx.get_virtual_timelock(f.mem_b, f.src_ctx.index).balance, 90_000);
+    assert_eq!(f.ctx.get_virtual_timelock(f.mem_b, f.fee_ctx.index).balance, 0);
+}
@@ -0,0 +1,69 @@
+#![cfg(test)]
+
+use steel::*;
+
+pub mod utils;
+use utils::*;
+
+use code_vm_api::prelude::*;
+
+fn merge(
+    ctx: &mut TestContext,
+    mems: [Option<Pubkey>; 4],
+    mem_indices: Vec<u16>,
+    mem_banks: Vec<u8>,
+) -> bool {
+    ctx.svm.expire_blockhash();
+    ctx.exec_opcode(
+        mems,
+        None,
+        None,
+        None,
+        None,
+        None,
+        MergeOp {}.to_bytes(),
+        mem_indices,
+        mem_banks,
+    )
+    .is_ok()
+}
+
+#[test]
+fn run_merge() {
+    let mut ctx = TestContext::new(21);
+
+    let mem_a = ctx.create_memory(10, VirtualTimelockAccount::LEN + 1, "mem_timelock_0");
+    let mem_b = ctx.create_memory(10, VirtualTimelockAccount::LEN + 1, "mem_timelock_1");
+
+    let vta_ctx = ctx.create_timelock_account(mem_a, 0);
+    ctx.deposit_tokens_to_timelock(mem_a, &vta_ctx, 60).unwrap();
+
+    // A second account for the same owner in another bank
+    let other_ctx = ctx.create_timelock_account_for_owner(mem_b, 3, &vta_ctx.key);
+    ctx.deposit_tokens_to_timelock(mem_b, &other_ctx, 40).unwrap();
+
+    let before = ctx.get_virtual_timelock(mem_a, 0);
+
+    assert!(merge(&mut ctx, [Some(mem_a), Some(mem_b), None, None], vec![0, 3], vec![0, 1]));
+
+    let merged = ctx.get_virtual_timelock(mem_a, 0);
+    assert_eq!(merged.balance, 100);
+    assert_eq!(merged.instance, before.instance);
+    assert!(!ctx.has_virtual_account(mem_b, 3));
+}
+
+#[test]
+fn run_merge_different_owner() {
+    let mut ctx = TestContext::new(21);
+
+    let mem_a = ctx.create_memory(10, VirtualTimelockAccount::LEN + 1, "mem_timelock_0");
+
+    let vta_a = ctx.create_timelock_account(mem_a, 0);
+    let vta_b = ctx.create_timelock_account(mem_a, 1);
+    ctx.deposit_tokens_to_timelock(mem_a, &vta_b, 40).unwrap();
+
+    assert!(!merge(&mut ctx, [Some(mem_a), None, None, None], vec![vta_a.index, vta_b.index], vec![0, 0]));
+    assert!(!merge(&mut ctx, [Some(mem_a), None, None, None], vec![vta_b.index, vta_b.index], vec![0, 0]));
+
+    assert_eq!(ctx.get_virtual_timelock(mem_a, vta_b.index).balance, 40);
+}
@@ -0,0 +1,332 @@
+#![cfg(test)]
+
+use steel::*;
+
+pub mod utils;
+use utils::*;
+
+use solana_sdk::{signature::{Keypair, Signer}, transaction::Transaction};
+use code_vm_api::prelude::*;
+
+struct MultisigContext {
+    ctx: TestContext,
+    mem_a: Pubkey,
+    mem_b: Pubkey,
+    mem_c: Pubkey,
+    signers: Vec<Keypair>,
+    src: VirtualTimelockAccount,
+    dst_ctx: TimelockAccountContext,
+    vdn_ctx: DurableNonceContext,
+}
+
+fn setup_multisig(signer_count: usize, threshold: u8, deposit_amount: u64) -> MultisigContext {
+    let mut ctx = TestContext::new(21);
+
+    let mem_a = ctx.create_memory(10, VirtualDurableNonce::LEN + 1, "mem_nonce_0");
+    let mem_b = ctx.create_memory(10, VirtualTimelockAccount::LEN + 1, "mem_timelock_0");
+    let mem_c = ctx.create_memory(10, VirtualMultisigAccount::LEN + 1, "mem_multisig_0");
+
+    let signers: Vec<Keypair> = (0..signer_count).map(|_| create_keypair()).collect();
+    let keys: Vec<Pubkey> = signers.iter().map(|s| s.pubkey()).collect();
+
+    let vm_address = ctx.vm_address;
+    assert!(tx_init_multisig(&mut ctx.svm, &ctx.payer, vm_address, mem_c, &keys, 0, threshold).is_ok());
+
+    let multisig = get_virtual_multisig(&ctx.svm, mem_c, 0);
+    assert_eq!(multisig.threshold, threshold);
+    assert_eq!(multisig.get_signers(), &keys[..]);
+
+    // The source timelock account is owned by the multisig address
+    let src = create_timelock_for_owner(
+        &mut ctx.svm,
+        &ctx.payer,
+        vm_address,
+        mem_b,
+        0,
+        multisig.address,
+        0,
+    );
+
+    let dst_ctx = ctx.create_timelock_account(mem_b, 1);
+    let vdn_ctx = ctx.create_durable_nonce_account(mem_a, 0);
+
+    let (deposit_pda, deposit_pda_bump) =
+        find_timelock_deposit_pda(&vm_address, &multisig.address);
+    let deposit_ata = create_ata(&mut ctx.svm, &ctx.payer, &ctx.mint_pk, &deposit_pda);
+    mint_to(&mut ctx.svm, &ctx.payer, &ctx.mint_pk, &ctx.mint_owner, &deposit_ata, deposit_amount)
+        .unwrap();
+
+    let pool = MintPool::from_vm(&ctx.vm);
+    assert!(tx_deposit_from_pda(
+        &mut ctx.svm,
+        &ctx.payer,
+        vm_address,
+        mem_b,
+        multisig.address,
+        deposit_pda,
+        deposit_ata,
+        &pool,
+        0,
+        deposit_amount,
+        deposit_pda_bump,
+    ).is_ok());
+
+    let src = VirtualTimelockAccount {
+        balance: deposit_amount,
+        ..src
+    };
+
+    MultisigContext {
+        ctx,
+        mem_a,
+        mem_b,
+        mem_c,
+        signers,
+        src,
+        dst_ctx,
+        vdn_ctx,
+    }
+}
+
+fn multisig_transfer(m: &mut MultisigContext, signed_by: &[usize], amount: u64) -> bool {
+    let vdn = get_virtual_nonce(&m.ctx.svm, m.mem_a, m.vdn_ctx.index);
+
+    let hash = create_transfer_message(
+        &m.ctx.vm,
+        &m.src,
+        &m.dst_ctx.account,
+        &vdn,
+        amount,
+    );
+
+    let mut signatures = [[0u8; 64]; MAX_MULTISIG_SIGNERS];
+    for i in signed_by {
+        signatures[*i] = m.signers[*i]
+            .sign_message(hash.as_ref())
+            .as_ref()
+            .try_into()
+            .unwrap();
+    }
+
+    let data = MultisigTransferOp::from_struct(ParsedMultisigTransferOp {
+        amount,
+        signatures,
+    }).to_bytes();
+
+    m.ctx.svm.expire_blockhash();
+    m.ctx.exec_opcode(
+        [Some(m.mem_a), Some(m.mem_c), Some(m.mem_b), None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        data,
+        vec![m.vdn_ctx.index, 0, 0, m.dst_ctx.index],
+        vec![0, 1, 2, 2],
+    )
+    .is_ok()
+}
+
+/// Sends an unlock or withdraw instruction for the multisig, approved by the
+/// signers in `signed_by`.
+fn multisig_send(m: &mut MultisigContext, ix: Instruction, signed_by: &[usize]) -> bool {
+    let multisig = get_virtual_multisig(&m.ctx.svm, m.mem_c, 0);
+    let approvers: Vec<Pubkey> = signed_by.iter().map(|i| m.signers[*i].pubkey()).collect();
+
+    let ix = with_multisig_signers(
+        ix,
+        m.ctx.vm_address,
+        multisig.threshold,
+        multisig.get_signers(),
+        &approvers,
+    );
+
+    let mut keypairs: Vec<&Keypair> = vec![&m.ctx.payer];
+    keypairs.extend(signed_by.iter().map(|i| &m.signers[*i]));
+
+    let payer_pk = m.ctx.payer.pubkey();
+    let blockhash = m.ctx.svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &keypairs, blockhash);
+
+    let ok = send_tx(&mut m.ctx.svm, tx).is_ok();
+    m.ctx.svm.expire_blockhash();
+    ok
+}
+
+#[test]
+fn run_multisig_transfer() {
+    let mut m = setup_multisig(3, 2, 100);
+
+    // One signature is below the threshold
+    assert!(!multisig_transfer(&mut m, &[0], 40));
+
+    assert!(multisig_transfer(&mut m, &[0, 2], 40));
+    assert_eq!(m.ctx.get_virtual_timelock(m.mem_b, 0).balance, 60);
+    assert_eq!(m.ctx.get_virtual_timelock(m.mem_b, m.dst_ctx.index).balance, 40);
+
+    // All signers can sign too
+    assert!(multisig_transfer(&mut m, &[0, 1, 2], 10));
+    assert_eq!(m.ctx.get_virtual_timelock(m.mem_b, m.dst_ctx.index).balance, 50);
+}
+
+#[test]
+fn run_multisig_transfer_invalid_signature() {
+    let mut m = setup_multisig(3, 2, 100);
+
+    // Swap in a signature from a key that is not a signer
+    m.signers[1] = create_keypair();
+    assert!(!multisig_transfer(&mut m, &[0, 1], 40));
+    assert_eq!(m.ctx.get_virtual_timelock(m.mem_b, 0).balance, 100);
+}
+
+#[test]
+fn run_multisig_transfer_with_fee() {
+    let mut m = setup_multisig(3, 2, 100_000);
+    let fee_ctx = m.ctx.create_timelock_account(m.mem_b, 2);
+
+    let fee_timelock_address = fee_ctx.account.get_timelock_address(
+        &m.ctx.vm.get_mint(),
+        &m.ctx.vm.get_authority(),
+        fee_ctx.account.get_lock_duration(m.ctx.vm.get_lock_duration()),
+    );
+    let recipient = fee_ctx.account.get_token_address(&fee_timelock_address);
+
+    let vm_address = m.ctx.vm_address;
+    assert!(tx_set_fee_config(
+        &mut m.ctx.svm,
+        &m.ctx.payer,
+        vm_address,
+        FeeRecipient::Virtual,
+        recipient,
+        25, // 0.25%
+        10,
+    )
+    .is_ok());
+
+    // The signers agree to the fee, the same as with a TransferOp
+    let amount = 10_000;
+    let vdn = get_virtual_nonce(&m.ctx.svm, m.mem_a, m.vdn_ctx.index);
+    let hash = create_transfer_message_with_fee(
+        &m.ctx.vm,
+        &m.src,
+        &m.dst_ctx.account,
+        &vdn,
+        amount,
+        25,
+    );
+
+    let mut signatures = [[0u8; 64]; MAX_MULTISIG_SIGNERS];
+    for i in [0, 1] {
+        signatures[i] = m.signers[i]
+            .sign_message(hash.as_ref())
+            .as_ref()
+            .try_into()
+            .unwrap();
+    }
+
+    let data = MultisigTransferOp::from_struct(ParsedMultisigTransferOp {
+        amount,
+        signatures,
+    }).to_bytes();
+
+    let ix = vm_exec(
+        m.ctx.payer.pubkey(),
+        vm_address,
+        Some(m.mem_a),
+        Some(m.mem_c),
+        Some(m.mem_b),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        data[0],
+        vec![m.vdn_ctx.index, 0, 0, m.dst_ctx.index, fee_ctx.index],
+        vec![0, 1, 2, 2, 2],
+        data[1..].to_vec(),
+    );
+    let ix = with_exec_fee_config(ix, vm_address, None);
+
+    m.ctx.svm.expire_blockhash();
+    let payer_pk = m.ctx.payer.pubkey();
+    let blockhash = m.ctx.svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[&m.ctx.payer], blockhash);
+    assert!(send_tx(&mut m.ctx.svm, tx).is_ok());
+
+    assert_eq!(m.ctx.get_virtual_timelock(m.mem_b, 0).balance, 89_975);
+    assert_eq!(m.ctx.get_virtual_timelock(m.mem_b, m.dst_ctx.index).balance, amount);
+    assert_eq!(m.ctx.get_virtual_timelock(m.mem_b, fee_ctx.index).balance, 25);
+}
+
+#[test]
+fn run_init_multisig_invalid_config() {
+    let mut ctx = TestContext::new(21);
+    let mem = ctx.create_memory(10, VirtualMultisigAccount::LEN + 1, "mem_multisig_0");
+    let vm_address = ctx.vm_address;
+
+    let a = create_keypair().pubkey();
+    let b = create_keypair().pubkey();
+
+    assert!(tx_init_multisig(&mut ctx.svm, &ctx.payer, vm_address, mem, &[a, b], 0, 3).is_err());
+    assert!(tx_init_multisig(&mut ctx.svm, &ctx.payer, vm_address, mem, &[a, a], 0, 1).is_err());
+    assert!(!ctx.has_virtual_account(mem, 0));
+}
+
+#[test]
+fn run_multisig_unlock_withdraw() {
+    let mut m = setup_multisig(3, 2, 100);
+    let multisig = get_virtual_multisig(&m.ctx.svm, m.mem_c, 0);
+
+    let vm = m.ctx.vm;
+    let vm_address = m.ctx.vm_address;
+    let payer_pk = m.ctx.payer.pubkey();
+
+    let timelock_address = m.src.get_timelock_address(
+        &vm.get_mint(),
+        &vm.get_authority(),
+        vm.get_lock_duration(),
+    );
+    let unlock_address = m.src.get_unlock_address(&timelock_address, &vm_address);
+    let receipt_address = m.src.get_withdraw_receipt_address(&unlock_address, &vm_address);
+
+    let init_ix = timelock_unlock_init(multisig.address, payer_pk, vm_address, unlock_address);
+
+    // One signature is below the threshold
+    assert!(!multisig_send(&mut m, init_ix.clone(), &[1]));
+    assert!(multisig_send(&mut m, init_ix, &[0, 2]));
+
+    let unlock = get_unlock_state(&m.ctx.svm, unlock_address);
+    assert_eq!(unlock.owner, multisig.address);
+
+    let mut clock = m.ctx.svm.get_sysvar::<Clock>();
+    clock.unix_timestamp = unlock.unlock_at + 1;
+    m.ctx.svm.set_sysvar::<Clock>(&clock);
+
+    let finalize_ix = timelock_unlock_finalize(multisig.address, payer_pk, vm_address, unlock_address);
+    assert!(multisig_send(&mut m, finalize_ix, &[1, 2]));
+
+    let dest_key = create_keypair();
+    let mint_pk = m.ctx.mint_pk;
+    let destination = create_ata(&mut m.ctx.svm, &m.ctx.payer, &mint_pk, &dest_key.pubkey());
+
+    let withdraw_ix = timelock_withdraw(
+        multisig.address,
+        payer_pk,
+        vm_address,
+        &MintPool::from_vm(&vm),
+        Some(m.mem_b),
+        None, // vm_storage
+        None, // deposit_pda
+        None, // deposit_ata
+        unlock_address,
+        Some(receipt_address),
+        destination,
+        WithdrawIxData::FromMemory { account_index: 0 },
+    );
+    assert!(!multisig_send(&mut m, withdraw_ix.clone(), &[0]));
+    assert!(multisig_send(&mut m, withdraw_ix, &[0, 1]));
+
+    assert_eq!(m.ctx.get_ata_balance(destination), 100);
+}
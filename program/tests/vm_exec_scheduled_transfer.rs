@@ -0,0 +1,138 @@
+#![cfg(test)]
+
+use steel::*;
+
+pub mod utils;
+use utils::*;
+
+use solana_sdk::signature::Signer;
+use code_vm_api::prelude::*;
+
+fn setup_scheduled(deposit_amount: u64) -> OpcodeFixture {
+    setup_opcode_fixture(VirtualScheduledTransfer::LEN, "mem_scheduled_0", deposit_amount)
+}
+
+fn schedule_transfer(s: &mut OpcodeFixture, amount: u64, not_before: i64) {
+    let hash = create_scheduled_transfer_message(
+        &s.ctx.vm,
+        &s.src_ctx.account,
+        &s.dst_ctx.account,
+        &s.vdn_ctx.account,
+        amount,
+        not_before,
+    );
+    let signature = s.src_ctx
+        .key
+        .sign_message(hash.as_ref())
+        .as_ref()
+        .try_into()
+        .unwrap();
+
+    let data = ScheduledTransferOp::from_struct(
+        ParsedScheduledTransferOp { signature, amount, not_before }
+    ).to_bytes();
+
+    s.ctx.exec_opcode(
+        [Some(s.mem_a), Some(s.mem_b), Some(s.mem_c), None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        data,
+        vec![s.vdn_ctx.index, s.src_ctx.index, s.dst_ctx.index, 0],
+        vec![0, 1, 1, 2],
+    )
+    .unwrap();
+}
+
+fn settle_scheduled_transfer(s: &mut OpcodeFixture) -> bool {
+    s.ctx.exec_opcode(
+        [Some(s.mem_c), Some(s.mem_b), None, None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        SettleScheduledTransferOp {}.to_bytes(),
+        vec![0, s.dst_ctx.index],
+        vec![0, 1],
+    )
+    .is_ok()
+}
+
+#[test]
+fn run_scheduled_transfer_settle() {
+    let deposit_amount = 100;
+    let amount = 42;
+
+    let mut s = setup_scheduled(deposit_amount);
+
+    let now = s.ctx.svm.get_sysvar::<Clock>().unix_timestamp;
+    let not_before = now + 3_600;
+
+    schedule_transfer(&mut s, amount, not_before);
+
+    // The funds are held by the scheduled transfer
+    let src_vta = s.ctx.get_virtual_timelock(s.mem_b, s.src_ctx.index);
+    assert_eq!(src_vta.balance, deposit_amount - amount);
+
+    let scheduled = get_virtual_scheduled_transfer(&s.ctx.svm, s.mem_c, 0);
+    assert_eq!(scheduled.amount, amount);
+    assert_eq!(scheduled.not_before, not_before);
+
+    // Settling before the not_before time fails
+    set_unix_timestamp(&mut s.ctx.svm, not_before - 1);
+    assert!(!settle_scheduled_transfer(&mut s));
+
+    set_unix_timestamp(&mut s.ctx.svm, not_before);
+    assert!(settle_scheduled_transfer(&mut s));
+
+    let dst_vta = s.ctx.get_virtual_timelock(s.mem_b, s.dst_ctx.index);
+    assert_eq!(dst_vta.balance, amount);
+    assert!(!s.ctx.has_virtual_account(s.mem_c, 0));
+}
+
+#[test]
+fn run_scheduled_transfer_cancel() {
+    let deposit_amount = 100;
+    let amount = 42;
+
+    let mut s = setup_scheduled(deposit_amount);
+
+    let now = s.ctx.svm.get_sysvar::<Clock>().unix_timestamp;
+    schedule_transfer(&mut s, amount, now + 3_600);
+
+    // The nonce was advanced by the schedule, so sign over the new value
+    let vdn = get_virtual_nonce(&s.ctx.svm, s.mem_a, s.vdn_ctx.index);
+    let scheduled = get_virtual_scheduled_transfer(&s.ctx.svm, s.mem_c, 0);
+
+    let hash = create_cancel_scheduled_transfer_message(&s.ctx.vm_address, &scheduled, &vdn);
+    let signature = s.src_ctx
+        .key
+        .sign_message(hash.as_ref())
+        .as_ref()
+        .try_into()
+        .unwrap();
+
+    s.ctx.exec_opcode(
+        [Some(s.mem_a), Some(s.mem_c), Some(s.mem_b), None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        CancelScheduledTransferOp { signature }.to_bytes(),
+        vec![s.vdn_ctx.index, 0, s.src_ctx.index],
+        vec![0, 1, 2],
+    )
+    .unwrap();
+
+    let src_vta = s.ctx.get_virtual_timelock(s.mem_b, s.src_ctx.index);
+    assert_eq!(src_vta.balance, deposit_amount);
+    assert!(!s.ctx.has_virtual_account(s.mem_c, 0));
+
+    // A cancelled transfer can't be settled
+    set_unix_timestamp(&mut s.ctx.svm, now + 3_600);
+    assert!(!settle_scheduled_transfer(&mut s));
+}
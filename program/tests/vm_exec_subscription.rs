@@ -0,0 +1,202 @@
+#![cfg(test)]
+
+use steel::*;
+
+pub mod utils;
+use utils::*;
+
+use solana_sdk::signature::Signer;
+use code_vm_api::prelude::*;
+
+fn collect_subscription(
+    ctx: &mut TestContext,
+    mem_b: Pubkey,
+    mem_c: Pubkey,
+    payer_index: u16,
+    payee_index: u16,
+) -> bool {
+    ctx.exec_opcode(
+        [Some(mem_c), Some(mem_b), None, None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        CollectSubscriptionOp {}.to_bytes(),
+        vec![0, payer_index, payee_index],
+        vec![0, 1, 1],
+    )
+    .is_ok()
+}
+
+fn setup_subscription(
+    deposit_amount: u64,
+    amount: u64,
+    period: i64,
+    count: u64,
+) -> (OpcodeFixture, i64) {
+    let mut f = setup_opcode_fixture(
+        VirtualSubscriptionAccount::LEN,
+        "mem_subscription_0",
+        deposit_amount,
+    );
+
+    let first_due = f.ctx.svm.get_sysvar::<Clock>().unix_timestamp + period;
+
+    let hash = create_subscription_message(
+        &f.ctx.vm,
+        &f.src_ctx.account,
+        &f.dst_ctx.account,
+        &f.vdn_ctx.account,
+        amount,
+        period,
+        first_due,
+        count,
+    );
+    let signature = f.src_ctx
+        .key
+        .sign_message(hash.as_ref())
+        .as_ref()
+        .try_into()
+        .unwrap();
+
+    let data = CreateSubscriptionOp::from_struct(ParsedCreateSubscriptionOp {
+        signature,
+        amount,
+        period,
+        first_due,
+        count,
+    }).to_bytes();
+
+    f.ctx.exec_opcode(
+        [Some(f.mem_a), Some(f.mem_b), Some(f.mem_c), None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        data,
+        vec![f.vdn_ctx.index, f.src_ctx.index, f.dst_ctx.index, 0],
+        vec![0, 1, 1, 2],
+    )
+    .unwrap();
+
+    (f, first_due)
+}
+
+#[test]
+fn run_subscription() {
+    let deposit_amount = 100;
+    let amount = 30;
+    let period = 86_400;
+    let count = 2;
+
+    let (fixture, first_due) = setup_subscription(deposit_amount, amount, period, count);
+    let OpcodeFixture {
+        mut ctx,
+        mem_b,
+        mem_c,
+        src_ctx: payer_ctx,
+        dst_ctx: payee_ctx,
+        ..
+    } = fixture;
+
+    let other_ctx = ctx.create_timelock_account(mem_b, 2);
+
+    let subscription = get_virtual_subscription(&ctx.svm, mem_c, 0);
+    assert_eq!(subscription.amount, amount);
+    assert_eq!(subscription.next_due, first_due);
+    assert_eq!(subscription.remaining, count);
+
+    // Nothing can be collected before the first payment is due
+    assert!(!collect_subscription(&mut ctx, mem_b, mem_c, payer_ctx.index, payee_ctx.index));
+
+    set_unix_timestamp(&mut ctx.svm, first_due);
+
+    // Payments can only go to the payee
+    assert!(!collect_subscription(&mut ctx, mem_b, mem_c, payer_ctx.index, other_ctx.index));
+
+    assert!(collect_subscription(&mut ctx, mem_b, mem_c, payer_ctx.index, payee_ctx.index));
+    assert_eq!(ctx.get_virtual_timelock(mem_b, payer_ctx.index).balance, deposit_amount - amount);
+    assert_eq!(ctx.get_virtual_timelock(mem_b, payee_ctx.index).balance, amount);
+
+    // The same period can't be collected twice
+    ctx.svm.expire_blockhash();
+    assert!(!collect_subscription(&mut ctx, mem_b, mem_c, payer_ctx.index, payee_ctx.index));
+
+    // The last payment deletes the subscription
+    set_unix_timestamp(&mut ctx.svm, first_due + period);
+    assert!(collect_subscription(&mut ctx, mem_b, mem_c, payer_ctx.index, payee_ctx.index));
+    assert_eq!(ctx.get_virtual_timelock(mem_b, payee_ctx.index).balance, amount * 2);
+    assert!(!ctx.has_virtual_account(mem_c, 0));
+}
+
+#[test]
+fn run_subscription_cancel() {
+    let deposit_amount = 100;
+
+    let (fixture, first_due) = setup_subscription(deposit_amount, 30, 86_400, 2);
+    let OpcodeFixture {
+        mut ctx,
+        mem_a,
+        mem_b,
+        mem_c,
+        src_ctx: payer_ctx,
+        dst_ctx: payee_ctx,
+        vdn_ctx,
+    } = fixture;
+
+    // The nonce was advanced by the subscription, so sign over the new value
+    let vdn = get_virtual_nonce(&ctx.svm, mem_a, vdn_ctx.index);
+    let subscription = get_virtual_subscription(&ctx.svm, mem_c, 0);
+    let hash = create_cancel_subscription_message(&ctx.vm_address, &subscription, &vdn);
+
+    // Only the payer can cancel the subscription
+    let signature = payee_ctx
+        .key
+        .sign_message(hash.as_ref())
+        .as_ref()
+        .try_into()
+        .unwrap();
+
+    assert!(ctx.exec_opcode(
+        [Some(mem_a), Some(mem_c), Some(mem_b), None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        CancelSubscriptionOp { signature }.to_bytes(),
+        vec![vdn_ctx.index, 0, payer_ctx.index],
+        vec![0, 1, 2],
+    )
+    .is_err());
+
+    let signature = payer_ctx
+        .key
+        .sign_message(hash.as_ref())
+        .as_ref()
+        .try_into()
+        .unwrap();
+
+    ctx.svm.expire_blockhash();
+    ctx.exec_opcode(
+        [Some(mem_a), Some(mem_c), Some(mem_b), None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        CancelSubscriptionOp { signature }.to_bytes(),
+        vec![vdn_ctx.index, 0, payer_ctx.index],
+        vec![0, 1, 2],
+    )
+    .unwrap();
+
+    assert!(!ctx.has_virtual_account(mem_c, 0));
+
+    // Nothing can be collected once the subscription is cancelled
+    set_unix_timestamp(&mut ctx.svm, first_due);
+    assert!(!collect_subscription(&mut ctx, mem_b, mem_c, payer_ctx.index, payee_ctx.index));
+    assert_eq!(ctx.get_virtual_timelock(mem_b, payer_ctx.index).balance, deposit_amount);
+}
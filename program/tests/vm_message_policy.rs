@@ -0,0 +1,119 @@
+#![cfg(test)]
+
+use steel::*;
+
+pub mod utils;
+use utils::*;
+
+use solana_sdk::signature::Signer;
+use code_vm_api::prelude::*;
+
+struct PolicyContext {
+    ctx: TestContext,
+    mem_a: Pubkey,
+    mem_b: Pubkey,
+    src_ctx: TimelockAccountContext,
+    dst_ctx: TimelockAccountContext,
+}
+
+fn setup_policy() -> PolicyContext {
+    let mut ctx = TestContext::new(21);
+
+    let mem_a = ctx.create_memory(10, VirtualDurableNonce::LEN + 1, "mem_nonce_0");
+    let mem_b = ctx.create_memory(10, VirtualTimelockAccount::LEN + 1, "mem_timelock_0");
+
+    let src_ctx = ctx.create_timelock_account(mem_b, 0);
+    let dst_ctx = ctx.create_timelock_account(mem_b, 1);
+    ctx.create_durable_nonce_account(mem_a, 0);
+
+    ctx.deposit_tokens_to_timelock(mem_b, &src_ctx, 100)
+        .unwrap();
+
+    PolicyContext {
+        ctx,
+        mem_a,
+        mem_b,
+        src_ctx,
+        dst_ctx,
+    }
+}
+
+fn set_policy(p: &mut PolicyContext, policy: MessagePolicy) {
+    let vm_address = p.ctx.vm_address;
+    assert!(tx_set_message_policy(&mut p.ctx.svm, &p.ctx.payer, vm_address, policy).is_ok());
+    p.ctx.svm.expire_blockhash();
+
+    let vm = get_vm_account(&p.ctx.svm, vm_address);
+    assert_eq!(vm.get_message_policy(), policy);
+}
+
+/// Sends a 1 token transfer, signed over either the v1 message or its v2
+/// wrapper.
+fn transfer(p: &mut PolicyContext, version: MessageVersion) -> bool {
+    let vdn = get_virtual_nonce(&p.ctx.svm, p.mem_a, 0);
+
+    let mut hash = create_transfer_message(
+        &p.ctx.vm,
+        &p.src_ctx.account,
+        &p.dst_ctx.account,
+        &vdn,
+        1,
+    );
+    if version == MessageVersion::V2 {
+        hash = create_v2_message(&p.ctx.vm_address, Opcode::TransferOp, &hash);
+    }
+
+    let signature = p.src_ctx
+        .key
+        .sign_message(hash.as_ref())
+        .as_ref()
+        .try_into()
+        .unwrap();
+
+    let data = TransferOp::from_struct(ParsedTransferOp { amount: 1, signature }).to_bytes();
+
+    p.ctx.svm.expire_blockhash();
+    p.ctx.exec_opcode(
+        [Some(p.mem_a), Some(p.mem_b), None, None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        data,
+        vec![0, p.src_ctx.index, p.dst_ctx.index],
+        vec![0, 1, 1],
+    )
+    .is_ok()
+}
+
+#[test]
+fn run_message_policy_v1_only() {
+    let mut p = setup_policy();
+
+    // New VMs only accept v1 messages
+    let vm = get_vm_account(&p.ctx.svm, p.ctx.vm_address);
+    assert_eq!(vm.get_message_policy(), MessagePolicy::V1Only);
+
+    assert!(!transfer(&mut p, MessageVersion::V2));
+    assert!(transfer(&mut p, MessageVersion::V1));
+}
+
+#[test]
+fn run_message_policy_v1_and_v2() {
+    let mut p = setup_policy();
+    set_policy(&mut p, MessagePolicy::V1AndV2);
+
+    assert!(transfer(&mut p, MessageVersion::V1));
+    assert!(transfer(&mut p, MessageVersion::V2));
+    assert_eq!(p.ctx.get_virtual_timelock(p.mem_b, p.dst_ctx.index).balance, 2);
+}
+
+#[test]
+fn run_message_policy_v2_only() {
+    let mut p = setup_policy();
+    set_policy(&mut p, MessagePolicy::V2Only);
+
+    assert!(!transfer(&mut p, MessageVersion::V1));
+    assert!(transfer(&mut p, MessageVersion::V2));
+}
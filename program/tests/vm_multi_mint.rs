@@ -0,0 +1,281 @@
+#![cfg(test)]
+
+use steel::*;
+
+pub mod utils;
+use utils::*;
+
+use solana_sdk::{
+    instruction::Instruction,
+    signature::{Keypair, Signer},
+};
+use code_vm_api::prelude::*;
+
+struct MultiMintContext {
+    ctx: TestContext,
+    mint_pk: Pubkey,
+    omnibus: Pubkey,
+    mem_a: Pubkey,
+    mem_b: Pubkey,
+}
+
+fn setup_multi_mint() -> MultiMintContext {
+    let mut ctx = TestContext::new(21);
+    let mint_owner = ctx.mint_owner.pubkey();
+    let mint_pk = create_mint(&mut ctx.svm, &ctx.payer, &mint_owner);
+
+    assert!(tx_init_mint(&mut ctx.svm, &ctx.payer, ctx.vm_address, mint_pk).is_ok());
+    ctx.svm.expire_blockhash();
+
+    let (omnibus, _) = find_vm_mint_omnibus_pda(&ctx.vm_address, &mint_pk);
+
+    let mem_a = ctx.create_memory(10, VirtualDurableNonce::LEN + 1, "mem_nonce_0");
+    let mem_b = ctx.create_memory(10, VirtualTimelockAccount::LEN_WITH_MINT + 1, "mem_timelock_0");
+    ctx.create_durable_nonce_account(mem_a, 0);
+
+    MultiMintContext {
+        ctx,
+        mint_pk,
+        omnibus,
+        mem_a,
+        mem_b,
+    }
+}
+
+/// Creates a virtual account that holds the registered mint.
+fn create_mint_timelock(m: &mut MultiMintContext, account_index: u16) -> Keypair {
+    let owner = create_keypair();
+    let vm = m.ctx.vm;
+
+    let (timelock_address, virtual_timelock_bump) = find_virtual_timelock_address(
+        &m.mint_pk,
+        &vm.get_authority(),
+        &owner.pubkey(),
+        vm.get_lock_duration(),
+    );
+    let (_, virtual_vault_bump) = find_virtual_timelock_vault_address(&timelock_address);
+    let (_, unlock_pda_bump) = find_unlock_address(&owner.pubkey(), &timelock_address, &m.ctx.vm_address);
+
+    let ix = system_timelock_init(
+        m.ctx.payer.pubkey(),
+        m.ctx.vm_address,
+        m.mem_b,
+        owner.pubkey(),
+        account_index,
+        virtual_timelock_bump,
+        virtual_vault_bump,
+        unlock_pda_bump,
+    );
+    let ix = with_vm_mint(ix, m.ctx.vm_address, m.mint_pk);
+
+    assert!(m.ctx.ix_send(&[ix]).is_ok());
+    m.ctx.svm.expire_blockhash();
+
+    owner
+}
+
+/// Deposits `amount` of the registered mint into a virtual account.
+fn deposit(m: &mut MultiMintContext, owner: &Keypair, account_index: u16, amount: u64) -> bool {
+    let depositor = owner.pubkey();
+    let (deposit_pda, bump) = find_timelock_deposit_pda(&m.ctx.vm_address, &depositor);
+    let deposit_ata = create_ata(&mut m.ctx.svm, &m.ctx.payer, &m.mint_pk, &deposit_pda);
+    mint_to(
+        &mut m.ctx.svm,
+        &m.ctx.payer,
+        &m.mint_pk,
+        &m.ctx.mint_owner,
+        &deposit_ata,
+        amount,
+    )
+    .unwrap();
+
+    let (vm_mint_address, _) = find_vm_mint_pda(&m.ctx.vm_address, &m.mint_pk);
+    let account = m.ctx.svm.get_account(&vm_mint_address).unwrap();
+    let pool = MintPool::from_vm_mint(&VmMintAccount::unpack(&account.data));
+
+    let ix = timelock_deposit_from_pda(
+        m.ctx.payer.pubkey(),
+        m.ctx.vm_address,
+        m.mem_b,
+        depositor,
+        deposit_pda,
+        deposit_ata,
+        &pool,
+        account_index,
+        amount,
+        bump,
+    );
+
+    let ok = m.ctx.ix_send(&[ix]).is_ok();
+    m.ctx.svm.expire_blockhash();
+    ok
+}
+
+fn transfer(m: &mut MultiMintContext, src: &Keypair, src_index: u16, dst_index: u16, amount: u64) -> bool {
+    let ix = transfer_ix(m, src, src_index, dst_index, amount);
+    send(m, ix)
+}
+
+/// Sends a transfer that passes the velocity limit of `src` for the
+/// registered mint.
+fn transfer_limited(m: &mut MultiMintContext, src: &Keypair, src_index: u16, dst_index: u16, amount: u64) -> bool {
+    let ix = transfer_ix(m, src, src_index, dst_index, amount);
+    let ix = with_exec_mint_velocity_limit(ix, m.ctx.vm_address, src.pubkey(), m.mint_pk);
+    send(m, ix)
+}
+
+fn transfer_ix(m: &MultiMintContext, src: &Keypair, src_index: u16, dst_index: u16, amount: u64) -> Instruction {
+    let src_vta = m.ctx.get_virtual_timelock(m.mem_b, src_index);
+    let dst_vta = m.ctx.get_virtual_timelock(m.mem_b, dst_index);
+    let vdn = get_virtual_nonce(&m.ctx.svm, m.mem_a, 0);
+
+    let hash = create_transfer_message(&m.ctx.vm, &src_vta, &dst_vta, &vdn, amount);
+    let signature = src
+        .sign_message(hash.as_ref())
+        .as_ref()
+        .try_into()
+        .unwrap();
+
+    let data = TransferOp::from_struct(ParsedTransferOp { amount, signature }).to_bytes();
+
+    vm_exec(
+        m.ctx.payer.pubkey(),
+        m.ctx.vm_address,
+        Some(m.mem_a),
+        Some(m.mem_b),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        data[0],
+        vec![0, src_index, dst_index],
+        vec![0, 1, 1],
+        data[1..].to_vec(),
+    )
+}
+
+fn send(m: &mut MultiMintContext, ix: Instruction) -> bool {
+    let ok = m.ctx.ix_send(&[ix]).is_ok();
+    m.ctx.svm.expire_blockhash();
+    ok
+}
+
+#[test]
+fn run_init_mint() {
+    let m = setup_multi_mint();
+
+    let (vm_mint_address, _) = find_vm_mint_pda(&m.ctx.vm_address, &m.mint_pk);
+    let account = m.ctx.svm.get_account(&vm_mint_address).unwrap();
+    let vm_mint = VmMintAccount::unpack(&account.data);
+
+    assert_eq!(vm_mint.vm, m.ctx.vm_address);
+    assert_eq!(vm_mint.get_mint(), m.mint_pk);
+    assert_eq!(vm_mint.omnibus.vault, m.omnibus);
+    assert_eq!(vm_mint.get_token_program(), TokenProgram::Token);
+    assert_eq!(m.ctx.get_ata_balance(m.omnibus), 0);
+}
+
+#[test]
+fn run_init_mint_rejects_vm_mint() {
+    let mut m = setup_multi_mint();
+    let vm_mint = m.ctx.mint_pk;
+
+    assert!(tx_init_mint(&mut m.ctx.svm, &m.ctx.payer, m.ctx.vm_address, vm_mint).is_err());
+}
+
+#[test]
+fn run_multi_mint_deposit() {
+    let mut m = setup_multi_mint();
+    let owner = create_mint_timelock(&mut m, 0);
+
+    let vta = m.ctx.get_virtual_timelock(m.mem_b, 0);
+    assert_eq!(vta.mint, m.mint_pk);
+
+    assert!(deposit(&mut m, &owner, 0, 1_000));
+    assert_eq!(m.ctx.get_virtual_timelock(m.mem_b, 0).balance, 1_000);
+
+    // The tokens are held by the omnibus of the registered mint
+    assert_eq!(m.ctx.get_ata_balance(m.omnibus), 1_000);
+    assert_eq!(m.ctx.get_ata_balance(m.ctx.vm.omnibus.vault), 0);
+}
+
+#[test]
+fn run_multi_mint_transfer() {
+    let mut m = setup_multi_mint();
+    let src = create_mint_timelock(&mut m, 0);
+    create_mint_timelock(&mut m, 1);
+
+    assert!(deposit(&mut m, &src, 0, 1_000));
+    assert!(transfer(&mut m, &src, 0, 1, 400));
+
+    assert_eq!(m.ctx.get_virtual_timelock(m.mem_b, 0).balance, 600);
+    assert_eq!(m.ctx.get_virtual_timelock(m.mem_b, 1).balance, 400);
+}
+
+#[test]
+fn run_multi_mint_transfer_across_mints() {
+    let mut m = setup_multi_mint();
+    let src = create_mint_timelock(&mut m, 0);
+    let mem_b = m.mem_b;
+    m.ctx.create_timelock_account(mem_b, 1);
+
+    assert!(deposit(&mut m, &src, 0, 1_000));
+
+    // Balances of different mints can't be mixed
+    assert!(!transfer(&mut m, &src, 0, 1, 400));
+    assert_eq!(m.ctx.get_virtual_timelock(m.mem_b, 0).balance, 1_000);
+}
+
+#[test]
+fn run_multi_mint_transfer_velocity_limited() {
+    let mut m = setup_multi_mint();
+    let src = create_mint_timelock(&mut m, 0);
+    create_mint_timelock(&mut m, 1);
+
+    assert!(deposit(&mut m, &src, 0, 1_000));
+
+    // The VM default is in VM mint units, so it doesn't apply to other mints,
+    // but once a limit is set the owner's record for the mint is required
+    assert!(tx_set_velocity_limit(
+        &mut m.ctx.svm,
+        &m.ctx.payer,
+        m.ctx.vm_address,
+        Pubkey::default(),
+        100,
+        3_600,
+    ).is_ok());
+    m.ctx.svm.expire_blockhash();
+
+    assert!(tx_set_mint_velocity_limit(
+        &mut m.ctx.svm,
+        &m.ctx.payer,
+        m.ctx.vm_address,
+        src.pubkey(),
+        m.mint_pk,
+        0,
+        0,
+    ).is_ok());
+    m.ctx.svm.expire_blockhash();
+
+    assert!(!transfer(&mut m, &src, 0, 1, 400));
+    assert!(transfer_limited(&mut m, &src, 0, 1, 400));
+
+    // The owner can be limited in units of the registered mint
+    assert!(tx_set_mint_velocity_limit(
+        &mut m.ctx.svm,
+        &m.ctx.payer,
+        m.ctx.vm_address,
+        src.pubkey(),
+        m.mint_pk,
+        500,
+        3_600,
+    ).is_ok());
+    m.ctx.svm.expire_blockhash();
+
+    assert!(!transfer_limited(&mut m, &src, 0, 1, 501));
+    assert!(transfer_limited(&mut m, &src, 0, 1, 500));
+    assert_eq!(m.ctx.get_virtual_timelock(m.mem_b, 1).balance, 900);
+}
@@ -0,0 +1,154 @@
+#![cfg(test)]
+
+use steel::*;
+
+pub mod utils;
+use utils::*;
+
+use solana_sdk::{
+    ed25519_instruction::new_ed25519_instruction,
+    signature::{Keypair, Signer},
+    transaction::Transaction,
+};
+use code_vm_api::prelude::*;
+
+struct ModeContext {
+    ctx: TestContext,
+    mem_a: Pubkey,
+    mem_b: Pubkey,
+    src_ctx: TimelockAccountContext,
+    dst_ctx: TimelockAccountContext,
+}
+
+fn setup_mode() -> ModeContext {
+    let mut ctx = TestContext::new(21);
+
+    let mem_a = ctx.create_memory(10, VirtualDurableNonce::LEN + 1, "mem_nonce_0");
+    let mem_b = ctx.create_memory(10, VirtualTimelockAccount::LEN + 1, "mem_timelock_0");
+
+    let src_ctx = ctx.create_timelock_account(mem_b, 0);
+    let dst_ctx = ctx.create_timelock_account(mem_b, 1);
+    ctx.create_durable_nonce_account(mem_a, 0);
+
+    ctx.deposit_tokens_to_timelock(mem_b, &src_ctx, 100)
+        .unwrap();
+
+    ModeContext {
+        ctx,
+        mem_a,
+        mem_b,
+        src_ctx,
+        dst_ctx,
+    }
+}
+
+fn set_mode(m: &mut ModeContext, mode: SignatureMode) {
+    let vm_address = m.ctx.vm_address;
+    assert!(tx_set_signature_mode(&mut m.ctx.svm, &m.ctx.payer, vm_address, mode).is_ok());
+    m.ctx.svm.expire_blockhash();
+
+    let vm = get_vm_account(&m.ctx.svm, vm_address);
+    assert_eq!(vm.get_signature_mode(), mode);
+}
+
+/// Sends a 1 token transfer, optionally preceded by an Ed25519 program
+/// instruction over `signed_by`'s signature, and optionally passing the
+/// instructions sysvar.
+fn transfer(m: &mut ModeContext, signed_by: &Keypair, precompile: bool, sysvar: bool) -> bool {
+    let vdn = get_virtual_nonce(&m.ctx.svm, m.mem_a, 0);
+
+    let hash = create_transfer_message(
+        &m.ctx.vm,
+        &m.src_ctx.account,
+        &m.dst_ctx.account,
+        &vdn,
+        1,
+    );
+
+    let signature = m.src_ctx
+        .key
+        .sign_message(hash.as_ref())
+        .as_ref()
+        .try_into()
+        .unwrap();
+
+    let data = TransferOp::from_struct(ParsedTransferOp { amount: 1, signature }).to_bytes();
+
+    let mut ix = vm_exec(
+        m.ctx.payer.pubkey(),
+        m.ctx.vm_address,
+        Some(m.mem_a),
+        Some(m.mem_b),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        data[0],
+        vec![0, m.src_ctx.index, m.dst_ctx.index],
+        vec![0, 1, 1],
+        data[1..].to_vec(),
+    );
+    if sysvar {
+        ix = with_exec_instructions_sysvar(ix);
+    }
+
+    let mut ixs = vec![];
+    if precompile {
+        let keypair = ed25519_dalek::Keypair::from_bytes(&signed_by.to_bytes()).unwrap();
+        ixs.push(new_ed25519_instruction(&keypair, hash.as_ref()));
+    }
+    ixs.push(ix);
+
+    m.ctx.svm.expire_blockhash();
+    let payer_pk = m.ctx.payer.pubkey();
+    let blockhash = m.ctx.svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&ixs, Some(&payer_pk), &[&m.ctx.payer], blockhash);
+
+    send_tx(&mut m.ctx.svm, tx).is_ok()
+}
+
+#[test]
+fn run_signature_mode_syscall() {
+    let mut m = setup_mode();
+    let owner = m.src_ctx.key.insecure_clone();
+
+    // New VMs verify signatures with the curve25519 syscalls, and don't care
+    // about precompile instructions
+    let vm = get_vm_account(&m.ctx.svm, m.ctx.vm_address);
+    assert_eq!(vm.get_signature_mode(), SignatureMode::Syscall);
+
+    assert!(transfer(&mut m, &owner, false, false));
+    assert!(transfer(&mut m, &owner, true, true));
+    assert_eq!(m.ctx.get_virtual_timelock(m.mem_b, m.dst_ctx.index).balance, 2);
+}
+
+#[test]
+fn run_signature_mode_precompile() {
+    let mut m = setup_mode();
+    let owner = m.src_ctx.key.insecure_clone();
+    set_mode(&mut m, SignatureMode::Precompile);
+
+    // Needs both the precompile instruction and the instructions sysvar
+    assert!(!transfer(&mut m, &owner, false, true));
+    assert!(!transfer(&mut m, &owner, true, false));
+
+    assert!(transfer(&mut m, &owner, true, true));
+    assert_eq!(m.ctx.get_virtual_timelock(m.mem_b, m.dst_ctx.index).balance, 1);
+}
+
+#[test]
+fn run_signature_mode_precompile_wrong_signer() {
+    let mut m = setup_mode();
+    set_mode(&mut m, SignatureMode::Precompile);
+
+    // A valid Ed25519 instruction from someone else doesn't authorize the
+    // owner's transfer
+    assert!(!transfer(&mut m, &Keypair::new(), true, true));
+
+    set_mode(&mut m, SignatureMode::Syscall);
+    let owner = m.src_ctx.key.insecure_clone();
+    assert!(transfer(&mut m, &owner, false, false));
+}
@@ -0,0 +1,152 @@
+#![cfg(test)]
+
+use steel::*;
+
+pub mod utils;
+use utils::*;
+
+use solana_sdk::signature::Signer;
+use code_vm_api::prelude::*;
+
+struct PayloadContext {
+    ctx: TestContext,
+    mem_a: Pubkey,
+    mem_b: Pubkey,
+    src_ctx: TimelockAccountContext,
+    dst_ctx: TimelockAccountContext,
+}
+
+fn setup_payload() -> PayloadContext {
+    let mut ctx = TestContext::new(21);
+
+    let mem_a = ctx.create_memory(10, VirtualDurableNonce::LEN + 1, "mem_nonce_0");
+    let mem_b = ctx.create_memory(10, VirtualTimelockAccount::LEN + 1, "mem_timelock_0");
+
+    let src_ctx = ctx.create_timelock_account(mem_b, 0);
+    let dst_ctx = ctx.create_timelock_account(mem_b, 1);
+    ctx.create_durable_nonce_account(mem_a, 0);
+
+    ctx.deposit_tokens_to_timelock(mem_b, &src_ctx, 100)
+        .unwrap();
+
+    PayloadContext {
+        ctx,
+        mem_a,
+        mem_b,
+        src_ctx,
+        dst_ctx,
+    }
+}
+
+fn set_policy(p: &mut PayloadContext, policy: MessagePolicy) {
+    let vm_address = p.ctx.vm_address;
+    assert!(tx_set_message_policy(&mut p.ctx.svm, &p.ctx.payer, vm_address, policy).is_ok());
+    p.ctx.svm.expire_blockhash();
+}
+
+fn sign_payload(p: &PayloadContext, payload: &SigningPayload) -> [u8; 64] {
+    // This is what a wallet would show the user before signing
+    let encoded = payload.encode();
+    assert_eq!(SigningPayload::decode(&encoded).as_ref(), Some(payload));
+
+    p.src_ctx
+        .key
+        .sign_message(&encoded)
+        .as_ref()
+        .try_into()
+        .unwrap()
+}
+
+/// Sends a transfer signed over a structured payload that claims
+/// `signed_amount`, while the opcode requests `amount`.
+fn transfer(p: &mut PayloadContext, signed_amount: u64, amount: u64) -> bool {
+    let src_vta = p.ctx.get_virtual_timelock(p.mem_b, p.src_ctx.index);
+    let dst_vta = p.ctx.get_virtual_timelock(p.mem_b, p.dst_ctx.index);
+    let vdn = get_virtual_nonce(&p.ctx.svm, p.mem_a, 0);
+
+    let payload = create_transfer_payload(
+        &p.ctx.vm,
+        &p.ctx.vm_address,
+        &src_vta,
+        &dst_vta,
+        &vdn,
+        signed_amount,
+    );
+    let signature = sign_payload(p, &payload);
+
+    let data = TransferOp::from_struct(ParsedTransferOp { amount, signature }).to_bytes();
+
+    p.ctx.svm.expire_blockhash();
+    p.ctx.exec_opcode(
+        [Some(p.mem_a), Some(p.mem_b), None, None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        data,
+        vec![0, p.src_ctx.index, p.dst_ctx.index],
+        vec![0, 1, 1],
+    )
+    .is_ok()
+}
+
+#[test]
+fn run_signing_payload_requires_v2() {
+    let mut p = setup_payload();
+
+    // New VMs only accept v1 messages, which rules out payloads
+    assert!(!transfer(&mut p, 1, 1));
+
+    set_policy(&mut p, MessagePolicy::V1AndV2);
+    assert!(transfer(&mut p, 1, 1));
+    assert_eq!(p.ctx.get_virtual_timelock(p.mem_b, p.dst_ctx.index).balance, 1);
+}
+
+#[test]
+fn run_signing_payload_rejects_mismatch() {
+    let mut p = setup_payload();
+    set_policy(&mut p, MessagePolicy::V2Only);
+
+    assert!(!transfer(&mut p, 1, 50));
+    assert!(transfer(&mut p, 50, 50));
+    assert_eq!(p.ctx.get_virtual_timelock(p.mem_b, p.src_ctx.index).balance, 50);
+}
+
+#[test]
+fn run_signing_payload_withdraw() {
+    let mut p = setup_payload();
+    set_policy(&mut p, MessagePolicy::V2Only);
+
+    let src_vta = p.ctx.get_virtual_timelock(p.mem_b, p.src_ctx.index);
+    let dst_vta = p.ctx.get_virtual_timelock(p.mem_b, p.dst_ctx.index);
+    let vdn = get_virtual_nonce(&p.ctx.svm, p.mem_a, 0);
+
+    let payload = create_withdraw_payload(
+        &p.ctx.vm,
+        &p.ctx.vm_address,
+        &src_vta,
+        &dst_vta,
+        &vdn,
+    );
+    assert_eq!(payload.action, PayloadAction::Withdraw);
+    assert_eq!(payload.amount, 100);
+
+    let signature = sign_payload(&p, &payload);
+    let data = WithdrawOp { signature }.to_bytes();
+
+    assert!(p.ctx.exec_opcode(
+        [Some(p.mem_a), Some(p.mem_b), None, None],
+        None,
+        None,
+        None,
+        None,
+        None,
+        data,
+        vec![0, p.src_ctx.index, p.dst_ctx.index],
+        vec![0, 1, 1],
+    )
+    .is_ok());
+
+    assert_eq!(p.ctx.get_virtual_timelock(p.mem_b, p.dst_ctx.index).balance, 100);
+}
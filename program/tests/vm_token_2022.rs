@@ -0,0 +1,277 @@
+#![cfg(test)]
+
+use steel::*;
+
+pub mod utils;
+use utils::*;
+
+use litesvm::LiteSVM;
+use litesvm_token::{CreateAssociatedTokenAccount, MintTo};
+use solana_sdk::{
+    signature::{Keypair, Signer},
+    system_instruction,
+    transaction::Transaction,
+};
+use spl_token_2022::extension::{transfer_fee, transfer_hook, ExtensionType};
+use code_vm_api::prelude::*;
+
+const FEE_BPS: u16 = 100; // 1%
+
+struct Token2022Context {
+    svm: LiteSVM,
+    payer: Keypair,
+    mint_owner: Keypair,
+    mint_pk: Pubkey,
+    vm_address: Pubkey,
+    vm_memory: Pubkey,
+}
+
+/// Creates a Token-2022 mint with one extension, which `init_extension`
+/// initializes.
+fn create_mint_with_extension(
+    svm: &mut LiteSVM,
+    payer: &Keypair,
+    owner: &Pubkey,
+    extension: ExtensionType,
+    init_extension: impl FnOnce(&Pubkey) -> Instruction,
+) -> Pubkey {
+    let mint = Keypair::new();
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(
+        &[extension],
+    )
+    .unwrap();
+
+    let ixs = [
+        system_instruction::create_account(
+            &payer.pubkey(),
+            &mint.pubkey(),
+            svm.minimum_balance_for_rent_exemption(space),
+            space as u64,
+            &spl_token_2022::id(),
+        ),
+        init_extension(&mint.pubkey()),
+        spl_token_2022::instruction::initialize_mint2(
+            &spl_token_2022::id(),
+            &mint.pubkey(),
+            owner,
+            None,
+            6,
+        )
+        .unwrap(),
+    ];
+
+    let blockhash = svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&ixs, Some(&payer.pubkey()), &[payer, &mint], blockhash);
+    assert!(send_tx(svm, tx).is_ok());
+
+    mint.pubkey()
+}
+
+/// Creates a Token-2022 mint that charges a transfer fee.
+fn create_fee_mint(svm: &mut LiteSVM, payer: &Keypair, owner: &Pubkey) -> Pubkey {
+    create_mint_with_extension(svm, payer, owner, ExtensionType::TransferFeeConfig, |mint| {
+        transfer_fee::instruction::initialize_transfer_fee_config(
+            &spl_token_2022::id(),
+            mint,
+            Some(owner),
+            Some(owner),
+            FEE_BPS,
+            u64::MAX,
+        )
+        .unwrap()
+    })
+}
+
+/// Creates a Token-2022 mint with a transfer hook.
+fn create_hook_mint(svm: &mut LiteSVM, payer: &Keypair, owner: &Pubkey) -> Pubkey {
+    create_mint_with_extension(svm, payer, owner, ExtensionType::TransferHook, |mint| {
+        transfer_hook::instruction::initialize(
+            &spl_token_2022::id(),
+            mint,
+            Some(*owner),
+            Some(Pubkey::new_unique()),
+        )
+        .unwrap()
+    })
+}
+
+/// Creates a Token-2022 mint with a permanent delegate.
+fn create_delegate_mint(svm: &mut LiteSVM, payer: &Keypair, owner: &Pubkey) -> Pubkey {
+    create_mint_with_extension(svm, payer, owner, ExtensionType::PermanentDelegate, |mint| {
+        spl_token_2022::instruction::initialize_permanent_delegate(
+            &spl_token_2022::id(),
+            mint,
+            owner,
+        )
+        .unwrap()
+    })
+}
+
+/// Creates a Token-2022 mint that can be closed by `owner`.
+fn create_closable_mint(svm: &mut LiteSVM, payer: &Keypair, owner: &Pubkey) -> Pubkey {
+    create_mint_with_extension(svm, payer, owner, ExtensionType::MintCloseAuthority, |mint| {
+        spl_token_2022::instruction::initialize_mint_close_authority(
+            &spl_token_2022::id(),
+            mint,
+            Some(owner),
+        )
+        .unwrap()
+    })
+}
+
+fn init_vm(svm: &mut LiteSVM, payer: &Keypair, mint_pk: Pubkey) -> bool {
+    let ix = vm_init(payer.pubkey(), mint_pk, TokenProgram::Token2022, 21);
+    let blockhash = svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[payer], blockhash);
+    send_tx(svm, tx).is_ok()
+}
+
+fn setup_token_2022() -> Token2022Context {
+    let mut svm = setup_svm();
+    let payer = create_payer(&mut svm);
+    let mint_owner = create_keypair();
+    let mint_pk = create_fee_mint(&mut svm, &payer, &mint_owner.pubkey());
+
+    let ix = vm_init(payer.pubkey(), mint_pk, TokenProgram::Token2022, 21);
+    let blockhash = svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer.pubkey()), &[&payer], blockhash);
+    assert!(send_tx(&mut svm, tx).is_ok());
+
+    let (vm_address, _) = find_vm_pda(&mint_pk, &payer.pubkey(), 21);
+    let (vm_memory, _) = create_and_resize_memory(
+        &mut svm,
+        &payer,
+        vm_address,
+        10,
+        VirtualTimelockAccount::LEN + 1,
+        "mem_timelock_0",
+    );
+
+    Token2022Context {
+        svm,
+        payer,
+        mint_owner,
+        mint_pk,
+        vm_address,
+        vm_memory,
+    }
+}
+
+/// Mints `amount` to the deposit address of a new virtual account and
+/// deposits it, optionally leaving out the mint.
+fn deposit(t: &mut Token2022Context, account_index: u16, amount: u64, with_mint_account: bool) -> bool {
+    let (_, vta_key) = create_timelock(&mut t.svm, &t.payer, t.vm_address, t.vm_memory, account_index);
+    let depositor = vta_key.pubkey();
+    let (deposit_pda, bump) = find_timelock_deposit_pda(&t.vm_address, &depositor);
+
+    let deposit_ata = CreateAssociatedTokenAccount::new(&mut t.svm, &t.payer, &t.mint_pk)
+        .owner(&deposit_pda)
+        .token_program_id(&spl_token_2022::id())
+        .send()
+        .unwrap();
+
+    MintTo::new(&mut t.svm, &t.payer, &t.mint_pk, &deposit_ata, amount)
+        .owner(&t.mint_owner)
+        .token_program_id(&spl_token_2022::id())
+        .send()
+        .unwrap();
+
+    let vm = get_vm_account(&t.svm, t.vm_address);
+    let mut ix = timelock_deposit_from_pda(
+        t.payer.pubkey(),
+        t.vm_address,
+        t.vm_memory,
+        depositor,
+        deposit_pda,
+        deposit_ata,
+        &MintPool::from_vm(&vm),
+        account_index,
+        amount,
+        bump,
+    );
+    if !with_mint_account {
+        ix.accounts.retain(|account| account.pubkey.ne(&t.mint_pk));
+    }
+
+    t.svm.expire_blockhash();
+    let payer_pk = t.payer.pubkey();
+    let blockhash = t.svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[&t.payer], blockhash);
+
+    send_tx(&mut t.svm, tx).is_ok()
+}
+
+#[test]
+fn run_token_2022_init() {
+    let t = setup_token_2022();
+
+    let vm = get_vm_account(&t.svm, t.vm_address);
+    assert_eq!(vm.get_mint(), t.mint_pk);
+    assert_eq!(vm.get_token_program(), TokenProgram::Token2022);
+
+    let omnibus = t.svm.get_account(&vm.omnibus.vault).unwrap();
+    assert_eq!(omnibus.owner, spl_token_2022::id());
+
+    // VMs for SPL Token mints are unchanged
+    let ctx = TestContext::new(21);
+    assert_eq!(ctx.vm.get_token_program(), TokenProgram::Token);
+}
+
+#[test]
+fn run_token_2022_deposit() {
+    let mut t = setup_token_2022();
+
+    // The virtual account is credited with what the omnibus received
+    assert!(deposit(&mut t, 0, 10_000, true));
+    assert_eq!(get_virtual_timelock(&t.svm, t.vm_memory, 0).balance, 9_900);
+}
+
+#[test]
+fn run_token_2022_requires_mint() {
+    let mut t = setup_token_2022();
+
+    assert!(!deposit(&mut t, 0, 10_000, false));
+}
+
+#[test]
+fn run_token_2022_rejects_transfer_hook() {
+    let mut svm = setup_svm();
+    let payer = create_payer(&mut svm);
+    let mint_owner = create_keypair();
+    let mint_pk = create_hook_mint(&mut svm, &payer, &mint_owner.pubkey());
+
+    // The VM can't pass the extra accounts a transfer hook needs
+    assert!(!init_vm(&mut svm, &payer, mint_pk));
+
+    // Nor can it be registered with an existing VM
+    let mut ctx = TestContext::new(21);
+    let mint_pk = create_hook_mint(&mut ctx.svm, &ctx.payer, &mint_owner.pubkey());
+    let ix = vm_init_mint(ctx.payer.pubkey(), ctx.vm_address, mint_pk, TokenProgram::Token2022);
+    assert!(ctx.ix_send(&[ix]).is_err());
+}
+
+#[test]
+fn run_token_2022_rejects_permanent_delegate() {
+    let mut svm = setup_svm();
+    let payer = create_payer(&mut svm);
+    let mint_owner = create_keypair();
+    let mint_pk = create_delegate_mint(&mut svm, &payer, &mint_owner.pubkey());
+
+    // A permanent delegate could move tokens out of the omnibus
+    assert!(!init_vm(&mut svm, &payer, mint_pk));
+
+    let mut ctx = TestContext::new(21);
+    let mint_pk = create_delegate_mint(&mut ctx.svm, &ctx.payer, &mint_owner.pubkey());
+    let ix = vm_init_mint(ctx.payer.pubkey(), ctx.vm_address, mint_pk, TokenProgram::Token2022);
+    assert!(ctx.ix_send(&[ix]).is_err());
+}
+
+#[test]
+fn run_token_2022_allows_mint_close_authority() {
+    let mut svm = setup_svm();
+    let payer = create_payer(&mut svm);
+    let mint_owner = create_keypair();
+    let mint_pk = create_closable_mint(&mut svm, &payer, &mint_owner.pubkey());
+
+    assert!(init_vm(&mut svm, &payer, mint_pk));
+}
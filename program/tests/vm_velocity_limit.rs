@@ -0,0 +1,282 @@
+#![cfg(test)]
+
+use steel::*;
+
+pub mod utils;
+use utils::*;
+
+use solana_sdk::{
+    instruction::Instruction,
+    signature::Signer,
+    transaction::Transaction,
+};
+use code_vm_api::prelude::*;
+
+struct VelocityContext {
+    ctx: TestContext,
+    mem_a: Pubkey,
+    mem_b: Pubkey,
+    src_ctx: TimelockAccountContext,
+    dst_ctx: TimelockAccountContext,
+}
+
+fn setup_velocity() -> VelocityContext {
+    let mut ctx = TestContext::new(21);
+
+    let mem_a = ctx.create_memory(10, VirtualDurableNonce::LEN + 1, "mem_nonce_0");
+    let mem_b = ctx.create_memory(10, VirtualTimelockAccount::LEN + 1, "mem_timelock_0");
+
+    let src_ctx = ctx.create_timelock_account(mem_b, 0);
+    let dst_ctx = ctx.create_timelock_account(mem_b, 1);
+    ctx.create_durable_nonce_account(mem_a, 0);
+
+    ctx.deposit_tokens_to_timelock(mem_b, &src_ctx, 1_000)
+        .unwrap();
+
+    VelocityContext {
+        ctx,
+        mem_a,
+        mem_b,
+        src_ctx,
+        dst_ctx,
+    }
+}
+
+fn set_limit(v: &mut VelocityContext, owner: Pubkey, amount: u64, window: i64) {
+    let vm_address = v.ctx.vm_address;
+    assert!(tx_set_velocity_limit(&mut v.ctx.svm, &v.ctx.payer, vm_address, owner, amount, window).is_ok());
+    v.ctx.svm.expire_blockhash();
+}
+
+fn warp(v: &mut VelocityContext, seconds: i64) {
+    let mut clock = v.ctx.svm.get_sysvar::<Clock>();
+    clock.unix_timestamp += seconds;
+    v.ctx.svm.set_sysvar::<Clock>(&clock);
+}
+
+/// Sends a transfer of `amount`, passing the velocity limit of `limit_owner`
+/// (and the VM default when `use_default` is set), if any.
+fn transfer(v: &mut VelocityContext, amount: u64, limit_owner: Option<Pubkey>, use_default: bool) -> bool {
+    let src_vta = v.ctx.get_virtual_timelock(v.mem_b, v.src_ctx.index);
+    let dst_vta = v.ctx.get_virtual_timelock(v.mem_b, v.dst_ctx.index);
+    let vdn = get_virtual_nonce(&v.ctx.svm, v.mem_a, 0);
+
+    let hash = create_transfer_message(
+        &v.ctx.vm,
+        &src_vta,
+        &dst_vta,
+        &vdn,
+        amount,
+    );
+
+    let signature = v.src_ctx
+        .key
+        .sign_message(hash.as_ref())
+        .as_ref()
+        .try_into()
+        .unwrap();
+
+    let data = TransferOp::from_struct(ParsedTransferOp { amount, signature }).to_bytes();
+
+    let ix = vm_exec(
+        v.ctx.payer.pubkey(),
+        v.ctx.vm_address,
+        Some(v.mem_a),
+        Some(v.mem_b),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        data[0],
+        vec![0, v.src_ctx.index, v.dst_ctx.index],
+        vec![0, 1, 1],
+        data[1..].to_vec(),
+    );
+
+    send(v, ix, limit_owner, use_default)
+}
+
+/// Moves `amount` into an escrow that the source owner arbitrates, passing
+/// the velocity limit of `limit_owner`, if any.
+fn escrow_deposit(v: &mut VelocityContext, mem_c: Pubkey, amount: u64, limit_owner: Option<Pubkey>) -> bool {
+    let src_vta = v.ctx.get_virtual_timelock(v.mem_b, v.src_ctx.index);
+    let vdn = get_virtual_nonce(&v.ctx.svm, v.mem_a, 0);
+    let arbiter = v.src_ctx.key.pubkey();
+    let deadline = v.ctx.svm.get_sysvar::<Clock>().unix_timestamp + 3_600;
+
+    let hash = create_escrow_deposit_message(
+        &v.ctx.vm,
+        &src_vta,
+        &src_vta,
+        &arbiter,
+        &vdn,
+        amount,
+        deadline,
+    );
+
+    let signature = v.src_ctx
+        .key
+        .sign_message(hash.as_ref())
+        .as_ref()
+        .try_into()
+        .unwrap();
+
+    let data = EscrowDepositOp::from_struct(ParsedEscrowDepositOp {
+        signature,
+        arbiter,
+        amount,
+        deadline,
+    }).to_bytes();
+
+    let ix = vm_exec(
+        v.ctx.payer.pubkey(),
+        v.ctx.vm_address,
+        Some(v.mem_a),
+        Some(v.mem_b),
+        Some(mem_c),
+        None,
+        None,
+        None,
+        None,
+        None,
+        None,
+        data[0],
+        vec![0, v.src_ctx.index, v.src_ctx.index, 0],
+        vec![0, 1, 1, 2],
+        data[1..].to_vec(),
+    );
+
+    send(v, ix, limit_owner, false)
+}
+
+fn send(v: &mut VelocityContext, mut ix: Instruction, limit_owner: Option<Pubkey>, use_default: bool) -> bool {
+    if let Some(limit_owner) = limit_owner {
+        ix = with_exec_velocity_limit(ix, v.ctx.vm_address, limit_owner, use_default);
+    }
+
+    v.ctx.svm.expire_blockhash();
+    let payer_pk = v.ctx.payer.pubkey();
+    let blockhash = v.ctx.svm.latest_blockhash();
+    let tx = Transaction::new_signed_with_payer(&[ix], Some(&payer_pk), &[&v.ctx.payer], blockhash);
+
+    send_tx(&mut v.ctx.svm, tx).is_ok()
+}
+
+fn get_velocity_limit(v: &VelocityContext, owner: &Pubkey) -> VelocityLimitAccount {
+    let (address, _) = find_vm_velocity_limit_pda(&v.ctx.vm_address, owner);
+    let account = v.ctx.svm.get_account(&address).unwrap();
+    VelocityLimitAccount::unpack(&account.data)
+}
+
+/// The amount `owner` sent in the last `window` seconds.
+fn get_used(v: &VelocityContext, owner: &Pubkey, window: i64) -> u64 {
+    let now = v.ctx.svm.get_sysvar::<Clock>().unix_timestamp;
+    get_velocity_limit(v, owner).get_used(window, now)
+}
+
+#[test]
+fn run_velocity_limit_owner() {
+    let mut v = setup_velocity();
+    let owner = v.src_ctx.key.pubkey();
+    set_limit(&mut v, owner, 100, 60);
+
+    assert!(transfer(&mut v, 60, Some(owner), false));
+    assert!(!transfer(&mut v, 41, Some(owner), false));
+    assert!(transfer(&mut v, 40, Some(owner), false));
+    assert_eq!(get_used(&v, &owner, 60), 100);
+
+    // The window is a rolling one, so the usage isn't reset at a fixed time
+    warp(&mut v, 30);
+    assert!(!transfer(&mut v, 1, Some(owner), false));
+
+    // The usage is freed once the window (and a bucket) have passed
+    warp(&mut v, 40);
+    assert!(transfer(&mut v, 100, Some(owner), false));
+
+    assert_eq!(v.ctx.get_virtual_timelock(v.mem_b, v.dst_ctx.index).balance, 200);
+}
+
+#[test]
+fn run_velocity_limit_default() {
+    let mut v = setup_velocity();
+    let owner = v.src_ctx.key.pubkey();
+
+    // A zero window on the owner record uses the VM default
+    set_limit(&mut v, Pubkey::default(), 50, 3_600);
+    set_limit(&mut v, owner, 0, 0);
+
+    assert!(!transfer(&mut v, 10, Some(owner), false));
+    assert!(!transfer(&mut v, 51, Some(owner), true));
+    assert!(transfer(&mut v, 50, Some(owner), true));
+    assert!(!transfer(&mut v, 1, Some(owner), true));
+
+    // Owners can be given their own limit
+    set_limit(&mut v, owner, 200, 3_600);
+    assert!(transfer(&mut v, 150, Some(owner), false));
+    assert_eq!(get_used(&v, &owner, 3_600), 200);
+}
+
+#[test]
+fn run_velocity_limit_wrong_owner() {
+    let mut v = setup_velocity();
+    let owner = v.src_ctx.key.pubkey();
+    let other = v.dst_ctx.key.pubkey();
+    set_limit(&mut v, owner, 10, 60);
+    set_limit(&mut v, other, 1_000, 60);
+
+    // Another owner's record can't be used to get around the limit
+    assert!(!transfer(&mut v, 11, Some(other), false));
+    assert!(!transfer(&mut v, 11, Some(owner), false));
+
+    assert!(transfer(&mut v, 10, Some(owner), false));
+    assert_eq!(get_used(&v, &other, 60), 0);
+}
+
+#[test]
+fn run_velocity_limit_not_given() {
+    let mut v = setup_velocity();
+    let owner = v.src_ctx.key.pubkey();
+    set_limit(&mut v, owner, 10, 60);
+
+    // Once a limit is set, the owner's record can't be left out
+    assert!(!transfer(&mut v, 100, None, false));
+    assert!(transfer(&mut v, 10, Some(owner), false));
+    assert_eq!(get_used(&v, &owner, 60), 10);
+}
+
+#[test]
+fn run_velocity_limit_default_required() {
+    let mut v = setup_velocity();
+    let owner = v.src_ctx.key.pubkey();
+    set_limit(&mut v, Pubkey::default(), 50, 3_600);
+
+    // While the VM default is on, the owner's record is required
+    assert!(!transfer(&mut v, 10, None, false));
+
+    set_limit(&mut v, owner, 0, 0);
+    assert!(transfer(&mut v, 10, Some(owner), true));
+
+    // Turning the VM default off leaves owners without a limit unlimited,
+    // but their record is still required
+    set_limit(&mut v, Pubkey::default(), 0, 0);
+    assert!(!transfer(&mut v, 100, None, false));
+    assert!(transfer(&mut v, 100, Some(owner), false));
+}
+
+#[test]
+fn run_velocity_limit_escrow_deposit() {
+    let mut v = setup_velocity();
+    let owner = v.src_ctx.key.pubkey();
+    let mem_c = v.ctx.create_memory(10, VirtualEscrowAccount::LEN + 1, "mem_escrow_0");
+    set_limit(&mut v, owner, 100, 60);
+
+    // Escrowing to themselves doesn't let the owner get around their limit
+    assert!(!escrow_deposit(&mut v, mem_c, 101, Some(owner)));
+    assert!(!escrow_deposit(&mut v, mem_c, 50, None));
+    assert!(escrow_deposit(&mut v, mem_c, 60, Some(owner)));
+    assert!(!transfer(&mut v, 41, Some(owner), false));
+    assert_eq!(get_used(&v, &owner, 60), 60);
+}